@@ -1,6 +1,6 @@
 use crate::{
     program::{AsmCommand, AsmProgramState, AsmProgramUpdateType},
-    Data,
+    AsmParseError, Data,
 };
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
@@ -44,14 +44,66 @@ impl AsmComponent {
     /// Parses a component from a asmhdl file
     pub fn from_file(path: &str) -> Self {
         let text = std::fs::read_to_string(path).expect("Failed to read file");
-        Self::parse(&text)
+        Self::from_code(&text)
     }
 
     /// Parses a component from an asmhdl code
+    ///
+    /// Panics if the code is not valid, see [`AsmComponent::try_from_code`].
     pub fn from_code(code: &str) -> Self {
+        Self::try_from_code(code).expect("Invalid asmhdl code")
+    }
+
+    /// Parses a component from an asmhdl code, reporting malformed code
+    pub fn try_from_code(code: &str) -> Result<Self, AsmParseError> {
         Self::parse(code)
     }
 
+    /// Writes the component as AsmHDL code
+    ///
+    /// The result can be parsed back with [`AsmComponent::from_code`].
+    pub fn to_code(&self) -> String {
+        let mut lines = vec!["_info:".to_string(), format!("name {}", self.name)];
+        if let Some(desc) = &self.description {
+            lines.push(format!("description {}", desc));
+        }
+        lines.push(match self.update_type {
+            AsmProgramUpdateType::InputChanges => "update_type input_changes".to_string(),
+            AsmProgramUpdateType::Always => "update_type always".to_string(),
+        });
+
+        lines.push("\n_inputs:".to_string());
+        lines.extend(
+            self.inputs
+                .iter()
+                .map(|(name, size)| format!("{} {}", name, size)),
+        );
+
+        lines.push("\n_outputs:".to_string());
+        lines.extend(
+            self.outputs
+                .iter()
+                .map(|(name, size)| format!("{} {}", name, size)),
+        );
+
+        let mut defaults: Vec<_> = self.defaults.iter().collect();
+        defaults.sort_by(|a, b| a.0.cmp(b.0));
+        lines.push("\n_defaults:".to_string());
+        lines.extend(defaults.into_iter().map(|(name, value)| {
+            format!(
+                "{} {:0width$b}",
+                name,
+                value.value,
+                width = value.size.max(1)
+            )
+        }));
+
+        lines.push("\n_start:".to_string());
+        lines.extend(self.cmds.iter().map(|cmd| cmd.to_string()));
+
+        lines.join("\n") + "\n"
+    }
+
     /// Generates an [`AsmProgram`] from the component information
     pub fn new_program_state(&self) -> AsmProgramState {
         AsmProgramState::new(self.cmds.clone()).with_default_vars(self.defaults.clone())
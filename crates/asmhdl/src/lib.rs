@@ -19,4 +19,5 @@ mod program;
 
 pub use component::AsmComponent;
 pub use data::Data;
+pub use parser::AsmParseError;
 pub use program::{AsmCommand, AsmExpr, AsmProgramState, AsmProgramUpdateType};
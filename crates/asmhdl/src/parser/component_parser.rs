@@ -1,12 +1,33 @@
 use log::debug;
+use thiserror::Error;
 
 use crate::{
     parser::grammar_mod_builder::grammar::CommandParser,
     pcmd, pexp,
     program::{AsmCommand, AsmExpr, AsmProgramUpdateType},
-    AsmComponent,
+    AsmComponent, Data,
 };
 
+/// Error found while parsing AsmHDL code, with the offending line.
+#[derive(Debug, Clone, Error)]
+pub enum AsmParseError {
+    /// Unknown `update_type` in the info section.
+    #[error("Invalid update type: {0}")]
+    InvalidUpdateType(String),
+
+    /// Input or output not declared as `name size`.
+    #[error("Invalid port declaration: {0}")]
+    InvalidPort(String),
+
+    /// Default not declared as `name bits`.
+    #[error("Invalid default value: {0}")]
+    InvalidDefault(String),
+
+    /// Command that does not parse or has the wrong arguments.
+    #[error("Invalid command {0}: {1}")]
+    InvalidCommand(String, String),
+}
+
 #[derive(Debug)]
 pub enum AsmCommandArg {
     Var(String),
@@ -55,8 +76,30 @@ impl AsmComponent {
         line.trim().to_string()
     }
 
+    fn parse_port(line: &str) -> Result<(String, usize), AsmParseError> {
+        match line.split(" ").collect::<Vec<_>>()[..] {
+            [name, size] => match size.parse() {
+                Ok(size) => Ok((name.to_string(), size)),
+                Err(_) => Err(AsmParseError::InvalidPort(line.to_string())),
+            },
+            _ => Err(AsmParseError::InvalidPort(line.to_string())),
+        }
+    }
+
+    fn parse_default(line: &str) -> Result<(String, Data), AsmParseError> {
+        match line.split(" ").collect::<Vec<_>>()[..] {
+            [name, bits]
+                if bits.len() <= usize::BITS as usize
+                    && bits.chars().all(|c| c == '0' || c == '1') =>
+            {
+                Ok((name.to_string(), bits.into()))
+            }
+            _ => Err(AsmParseError::InvalidDefault(line.to_string())),
+        }
+    }
+
     /// Parse an ASM component from a code source string
-    pub fn parse(code: &str) -> AsmComponent {
+    pub fn parse(code: &str) -> Result<AsmComponent, AsmParseError> {
         let mut state = ParseState::Info;
         let mut ast = AsmComponent::default();
 
@@ -115,123 +158,121 @@ impl AsmComponent {
                         } else if update_type == "always" {
                             ast.update_type = AsmProgramUpdateType::Always;
                         } else {
-                            panic!("Invalid update type");
+                            return Err(AsmParseError::InvalidUpdateType(update_type));
                         }
                     }
                 }
                 ParseState::Inputs => {
-                    let parts: Vec<&str> = line.split(" ").collect();
-                    ast.inputs
-                        .insert(parts[0].to_string(), parts[1].parse().unwrap());
+                    let (name, size) = Self::parse_port(&line)?;
+                    ast.inputs.insert(name, size);
                 }
                 ParseState::Outputs => {
-                    let parts: Vec<&str> = line.split(" ").collect();
-                    ast.outputs
-                        .insert(parts[0].to_string(), parts[1].parse().unwrap());
+                    let (name, size) = Self::parse_port(&line)?;
+                    ast.outputs.insert(name, size);
                 }
                 ParseState::Defaults => {
-                    let parts: Vec<&str> = line.split(" ").collect();
-                    let name = parts[0].to_string();
-                    let value = parts[1].into();
+                    let (name, value) = Self::parse_default(&line)?;
                     ast.defaults.insert(name, value);
                 }
                 ParseState::Commands => {
-                    ast.cmds
-                        .push(CommandParser::new().parse(&line).expect("Invalid command"));
+                    let cmd = CommandParser::new().parse(&line).map_err(|err| {
+                        AsmParseError::InvalidCommand(line.clone(), err.to_string())
+                    })?;
+                    ast.cmds.push(cmd);
                 }
             }
         }
 
-        ast
+        Ok(ast)
     }
 }
 
-pub fn cmd_from_args(cmd: AsmCmdDecl, args: Vec<AsmCommandArg>) -> AsmCommand {
+pub fn cmd_from_args(cmd: AsmCmdDecl, args: Vec<AsmCommandArg>) -> Result<AsmCommand, String> {
     match cmd {
         AsmCmdDecl::Mov => {
             let name = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
             let expr = match args.get(1) {
                 Some(arg) => arg.get_expr(),
-                _ => panic!("Expected argument expr"),
+                _ => return Err("expected argument expr".to_string()),
             };
-            pcmd!(mov, name, expr)
+            Ok(pcmd!(mov, name, expr))
         }
         AsmCmdDecl::Label => {
             let name = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(label, name)
+            Ok(pcmd!(label, name))
         }
         AsmCmdDecl::Goto => {
             let name = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(goto, name)
+            Ok(pcmd!(goto, name))
         }
         AsmCmdDecl::Cmp => {
             let v1 = match args.first() {
                 Some(arg) => arg.get_expr(),
-                _ => panic!("Expected argument expr"),
+                _ => return Err("expected argument expr".to_string()),
             };
             let v2 = match args.get(1) {
                 Some(arg) => arg.get_expr(),
-                _ => panic!("Expected argument expr"),
+                _ => return Err("expected argument expr".to_string()),
             };
-            pcmd!(cmp, v1, v2)
+            Ok(pcmd!(cmp, v1, v2))
         }
         AsmCmdDecl::Je => {
             let label = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(je, label)
+            Ok(pcmd!(je, label))
         }
         AsmCmdDecl::Jne => {
             let label = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(jne, label)
+            Ok(pcmd!(jne, label))
         }
         AsmCmdDecl::Jg => {
             let label = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(jg, label)
+            Ok(pcmd!(jg, label))
         }
         AsmCmdDecl::Jge => {
             let label = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(jge, label)
+            Ok(pcmd!(jge, label))
         }
         AsmCmdDecl::Jl => {
             let label = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(jl, label)
+            Ok(pcmd!(jl, label))
         }
         AsmCmdDecl::Jle => {
             let label = match args.first() {
                 Some(AsmCommandArg::Var(name)) => name,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(jle, label)
+            Ok(pcmd!(jle, label))
         }
         AsmCmdDecl::Wait => {
             let time = match args.first() {
                 Some(AsmCommandArg::Num(time)) => time,
-                _ => panic!("Invalid argument"),
+                _ => return Err("invalid argument".to_string()),
             };
-            pcmd!(wait, *time as u128)
+            Ok(pcmd!(wait, *time as u128))
         }
     }
 }
@@ -260,4 +301,65 @@ mod tests {
             AsmCommand::Wait { .. } => AsmCmdDecl::Wait,
         };
     }
+
+    #[test]
+    fn test_to_code_roundtrip() {
+        let code = "_info:
+name TFF
+description T flip flop
+update_type input_changes
+
+_inputs:
+T 1
+CLK 1
+
+_outputs:
+Q 1
+nQ 1
+
+_defaults:
+last_clk 0
+
+_start:
+mov edge ((CLK and !last_clk) and T)
+mov last_clk CLK
+mov TQ [T Q]
+cmp edge 0b1
+jne end
+mov Q !Q
+end:
+mov nQ !Q
+";
+        let comp = AsmComponent::from_code(code);
+        let parsed = AsmComponent::from_code(&comp.to_code());
+
+        assert_eq!(parsed.name, comp.name);
+        assert_eq!(parsed.description, comp.description);
+        assert_eq!(parsed.inputs, comp.inputs);
+        assert_eq!(parsed.outputs, comp.outputs);
+        assert_eq!(parsed.defaults, comp.defaults);
+        assert_eq!(parsed.to_code(), comp.to_code());
+    }
+
+    #[test]
+    fn test_malformed_code() {
+        for (code, expected) in [
+            ("update_type sometimes", "Invalid update type: sometimes"),
+            ("_inputs:\nT", "Invalid port declaration: T"),
+            ("_outputs:\nQ one", "Invalid port declaration: Q one"),
+            ("_defaults:\nlast 012", "Invalid default value: last 012"),
+            (
+                "_start:\nmov x",
+                "Invalid command mov x: expected argument expr",
+            ),
+            ("_start:\njmp end", "Invalid command jmp end"),
+            (
+                "_start:\nwait 99999999999999999999",
+                "number 99999999999999999999 is too large",
+            ),
+        ] {
+            let err = AsmComponent::try_from_code(code).unwrap_err();
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+    }
 }
@@ -1,2 +1,4 @@
 mod component_parser;
 mod grammar_mod_builder;
+
+pub use component_parser::AsmParseError;
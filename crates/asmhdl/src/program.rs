@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
@@ -399,3 +399,54 @@ impl AsmProgramState {
         }
     }
 }
+
+impl Display for AsmExpr {
+    /// Writes the expression in AsmHDL syntax
+    ///
+    /// Logical operations are always wrapped in parentheses so the result can be used as a
+    /// command argument.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |f: &mut std::fmt::Formatter<'_>, op: &str, exprs: &[AsmExpr]| {
+            let items: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+            write!(f, "({})", items.join(&format!(" {} ", op)))
+        };
+        match self {
+            AsmExpr::Not(expr) => match expr.as_ref() {
+                AsmExpr::Not(_) => write!(f, "!({})", expr),
+                _ => write!(f, "!{}", expr),
+            },
+            AsmExpr::And(exprs) => join(f, "and", exprs),
+            AsmExpr::Or(exprs) => join(f, "or", exprs),
+            AsmExpr::Nand(exprs) => join(f, "nand", exprs),
+            AsmExpr::Nor(exprs) => join(f, "nor", exprs),
+            AsmExpr::Xor(exprs) => join(f, "xor", exprs),
+            AsmExpr::BitVec(exprs) => {
+                let items: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+                write!(f, "[{}]", items.join(" "))
+            }
+            AsmExpr::Var(name) => write!(f, "{}", name),
+            AsmExpr::Const(value) => {
+                write!(f, "0b{:0width$b}", value.value, width = value.size.max(1))
+            }
+        }
+    }
+}
+
+impl Display for AsmCommand {
+    /// Writes the command as a line of AsmHDL code
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsmCommand::Mov { name, value } => write!(f, "mov {} {}", name, value),
+            AsmCommand::Label { name } => write!(f, "{}:", name),
+            AsmCommand::Goto { label } => write!(f, "goto {}", label),
+            AsmCommand::Cmp { v1, v2 } => write!(f, "cmp {} {}", v1, v2),
+            AsmCommand::Je { label } => write!(f, "je {}", label),
+            AsmCommand::Jne { label } => write!(f, "jne {}", label),
+            AsmCommand::Jg { label } => write!(f, "jg {}", label),
+            AsmCommand::Jl { label } => write!(f, "jl {}", label),
+            AsmCommand::Jge { label } => write!(f, "jge {}", label),
+            AsmCommand::Jle { label } => write!(f, "jle {}", label),
+            AsmCommand::Wait { time } => write!(f, "wait {}", time),
+        }
+    }
+}
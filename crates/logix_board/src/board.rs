@@ -2,21 +2,20 @@ use std::path::{Path, PathBuf};
 
 use egui::Pos2;
 use logix_core::component::{Component, Conn, SubComponent};
-use logix_sim::primitives::primitive::{ExtraInfo, Primitive};
+use logix_sim::primitives::primitive::ExtraInfo;
 use serde::{Deserialize, Serialize};
 
-use crate::app_ui::id_map::IdMap;
-
-use super::{
-    super::errors::{
-        BoardBuildError, LoadBoardError, LoadComponentError, OpenBoardError, ReloadComponentsError,
-        SaveBoardError,
-    },
+use crate::{
     board_actions::BoardAction,
     board_comp::BoardComponent,
     board_conn::BoardConnection,
     board_io::BoardIO,
     comp_info::{ComponentInfo, IOInfo},
+    errors::{
+        BoardBuildError, LoadBoardError, LoadComponentError, OpenBoardError, ReloadComponentsError,
+        SaveBoardError,
+    },
+    id_map::IdMap,
     CompSource,
 };
 
@@ -57,31 +56,18 @@ impl Board {
 
         let id = *last_id;
         *last_id += 1;
+        let (input_ports, output_ports) = self.board_comp(id, source.clone()).info.port_descs();
         let id_map = IdMap::from_children(id, self.name.clone(), source, ids);
 
+        // The input and output primitives are kept, so inputs wired straight
+        // to outputs still pass their values
         let in_addrs = self
-            .conns
-            .iter()
-            .filter_map(|conn| match self.components[conn.conn.from.0].info.source {
-                CompSource::Prim(Primitive::Input { .. }) => {
-                    let pos = self
-                        .inputs
-                        .iter()
-                        .position(|io| io.idx == conn.conn.from.0)?;
-                    Some((pos, conn.conn.to))
-                }
-                _ => None,
-            })
-            .collect();
-
-        let out_addrs = self
-            .conns
+            .inputs
             .iter()
-            .filter_map(|conn| match self.components[conn.conn.to.0].info.source {
-                CompSource::Prim(Primitive::Output { .. }) => Some(conn.conn.from),
-                _ => None,
-            })
+            .enumerate()
+            .map(|(pos, io)| (pos, (io.idx, 0)))
             .collect();
+        let out_addrs = self.outputs.iter().map(|io| (io.idx, 0)).collect();
 
         let sub = SubComponent {
             components: sub_comps,
@@ -95,8 +81,11 @@ impl Board {
             Component {
                 id,
                 name: Some(self.name.clone()),
+                instance: None,
                 inputs: self.inputs.len(),
                 outputs: self.outputs.len(),
+                input_ports,
+                output_ports,
                 sub: Some(sub),
                 extra: ExtraInfo {
                     id: 0,
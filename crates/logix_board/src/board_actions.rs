@@ -1,7 +1,7 @@
 use egui::Pos2;
 use logix_sim::primitives::prelude::Primitive;
 
-use super::{board_comp::BoardComponent, board_conn::BoardConnection, board_io::BoardIO, Board};
+use crate::{board_comp::BoardComponent, board_conn::BoardConnection, board_io::BoardIO, Board};
 
 #[derive(Debug, Clone)]
 pub enum BoardAction {
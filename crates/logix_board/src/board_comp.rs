@@ -4,9 +4,7 @@ use logix_core::prelude::Component;
 use logix_sim::primitives::prelude::{ExtraInfo, Primitive};
 use serde::{Deserialize, Serialize};
 
-use crate::app_ui::{errors::BoardBuildError, id_map::IdMap};
-
-use super::{comp_info::ComponentInfo, Board, CompSource};
+use crate::{comp_info::ComponentInfo, errors::BoardBuildError, id_map::IdMap, Board, CompSource};
 
 #[derive(Debug, Clone)]
 pub enum UserInteraction {
@@ -107,6 +105,7 @@ impl BoardComponent {
         *last_id += 1;
         self.id = id;
 
+        let (input_ports, output_ports) = self.info.port_descs();
         Ok((
             IdMap::new(
                 id,
@@ -116,8 +115,11 @@ impl BoardComponent {
             Component {
                 id,
                 name: Some(self.info.name.clone()),
+                instance: None,
                 inputs: self.input_count(),
                 outputs: self.output_count(),
+                input_ports,
+                output_ports,
                 sub: None,
                 extra: ExtraInfo {
                     id: self.id,
@@ -144,6 +146,8 @@ impl BoardComponent {
 
         let mut board = Board::load(&source)?;
 
-        board.build_component(self.info.source.clone(), last_id)
+        let (id_map, comp) = board.build_component(self.info.source.clone(), last_id)?;
+        self.id = comp.id;
+        Ok((id_map, comp))
     }
 }
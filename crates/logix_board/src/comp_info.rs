@@ -1,8 +1,9 @@
 use asmhdl::{pcmd, pexp, AsmComponent, Data};
-use logix_sim::primitives::primitive::Primitive;
+use logix_core::component::PortDesc;
+use logix_sim::primitives::{clock::ClockConfig, primitive::Primitive};
 use serde::{Deserialize, Serialize};
 
-use super::CompSource;
+use crate::CompSource;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IOInfo {
@@ -54,6 +55,30 @@ pub struct ComponentInfo {
 }
 
 impl ComponentInfo {
+    /// Descriptors of the ports of the component. Unnamed ports are named
+    /// `in{i}` and `out{i}`.
+    pub fn port_descs(&self) -> (Vec<PortDesc>, Vec<PortDesc>) {
+        let name = |io: &IOInfo, prefix: &str, i: usize| {
+            if io.name.is_empty() {
+                format!("{prefix}{i}")
+            } else {
+                io.name.clone()
+            }
+        };
+        (
+            self.inputs
+                .iter()
+                .enumerate()
+                .map(|(i, io)| PortDesc::input(name(io, "in", i), io.size))
+                .collect(),
+            self.outputs
+                .iter()
+                .enumerate()
+                .map(|(i, io)| PortDesc::output(name(io, "out", i), io.size))
+                .collect(),
+        )
+    }
+
     pub fn custom(comp: AsmComponent) -> Self {
         let name = comp.name.clone();
         let state = comp.new_program_state();
@@ -158,13 +183,29 @@ impl ComponentInfo {
         }
     }
 
+    pub fn const_gate(value: Data) -> Self {
+        Self {
+            name: "CONST".to_string(),
+            source: CompSource::Prim(Primitive::Const { value }),
+            inputs: vec![],
+            outputs: vec![IOInfo::new("", value.size)],
+            description: None,
+        }
+    }
+
     pub fn clock_gate() -> Self {
+        Self::clock(ClockConfig::new(1_000_000_000))
+    }
+
+    pub fn clock(clock: ClockConfig) -> Self {
         Self {
             name: "CLK".to_string(),
-            source: CompSource::Prim(Primitive::Clock {
-                period: 1_000_000_000,
-            }),
-            inputs: vec![],
+            source: CompSource::Prim(Primitive::Clock(clock)),
+            inputs: if clock.enable {
+                vec![IOInfo::single("EN")]
+            } else {
+                vec![]
+            },
             outputs: vec![IOInfo::default()],
             description: None,
         }
@@ -220,6 +261,29 @@ impl ComponentInfo {
         }
     }
 
+    /// Returns the info of a primitive, `in_count` is the number of inputs of
+    /// the gates.
+    pub fn from_primitive(prim: Primitive, in_count: usize) -> Self {
+        match prim {
+            Primitive::AndGate => Self::and_gate(in_count),
+            Primitive::OrGate => Self::or_gate(in_count),
+            Primitive::NotGate => Self::not_gate(),
+            Primitive::NandGate => Self::nand_gate(in_count),
+            Primitive::NorGate => Self::nor_gate(in_count),
+            Primitive::XorGate => Self::xor_gate(in_count),
+            Primitive::Switch => Self::switch(),
+            Primitive::Input { bits } => Self::input(bits),
+            Primitive::Output { bits } => Self::output(bits),
+            Primitive::Splitter { bits } => Self::splitter(bits),
+            Primitive::Joiner { bits } => Self::joiner(bits),
+            Primitive::Clock(clock) => Self::clock(clock),
+            Primitive::Const { value } if value == Data::high() => Self::const_high_gate(),
+            Primitive::Const { value } if value == Data::low() => Self::const_low_gate(),
+            Primitive::Const { value } => Self::const_gate(value),
+            Primitive::Custom { comp, .. } => Self::custom(comp),
+        }
+    }
+
     pub fn multiplexer(in_size: usize, size: usize) -> Self {
         let mut asm = AsmComponent::new("MUX").with_input("SEL", size);
         let in_count = 1 << size;
@@ -0,0 +1,114 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LoadBoardError {
+    #[error("Failed to read board file.\n{0}")]
+    ReadBoardFile(#[from] std::io::Error),
+    #[error("Failed to parse board file.\n{0}")]
+    ParseBoardFile(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum SaveBoardError {
+    #[error("Failed to write board file.\n{0}")]
+    WriteBoardFile(#[from] std::io::Error),
+    #[error("Failed to serialize board.\n{0}")]
+    SerializeBoard(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum BoardBuildError {
+    #[error("Primitive not specified")]
+    PrimitiveNotSpecified,
+    #[error("Source not specified")]
+    SourceNotSpecified,
+    #[error("Failed to load board.\n{0}")]
+    LoadBoard(#[from] LoadBoardError),
+}
+
+#[derive(Debug, Error)]
+#[error("Failed to load component.\n{0}")]
+pub struct LoadComponentError(#[from] LoadBoardError);
+
+#[derive(Debug, Error)]
+pub enum ReloadComponentsError {
+    #[error("Failed to load component.\n{0}")]
+    LoadError(#[from] LoadComponentError),
+}
+
+#[derive(Debug, Error)]
+pub enum OpenBoardError {
+    #[error("Failed to load board.\n{0}")]
+    LoadBoard(#[from] LoadBoardError),
+    #[error("Failed to reload component.\n{0}")]
+    ReloadComponents(#[from] ReloadComponentsError),
+}
+
+#[derive(Debug, Error)]
+pub enum ImportLgxError {
+    #[error("Failed to build lgx file.\n{0}")]
+    Build(#[from] logix_lang::BuildError),
+    #[error("Failed to save board.\n{0}")]
+    SaveBoard(#[from] SaveBoardError),
+}
+
+#[derive(Debug, Error)]
+pub enum ImportNetlistError {
+    #[error("Unsupported file type: {0:?}. Expected a .v or .blif file")]
+    UnsupportedFile(std::path::PathBuf),
+    #[error("Failed to read netlist.\n{0}")]
+    Read(#[from] std::io::Error),
+    #[error("Failed to build netlist.\n{0}")]
+    Build(#[from] logix_lang::NetlistError),
+    #[error("Failed to synthesize logic.\n{0}")]
+    Synth(#[from] logix_lang::SynthError),
+    #[error("Failed to build state machine.\n{0}")]
+    Fsm(#[from] logix_lang::FsmError),
+    #[error("Failed to save board.\n{0}")]
+    SaveBoard(#[from] SaveBoardError),
+}
+
+#[derive(Debug, Error)]
+pub enum ImportLogisimError {
+    #[error("Failed to read circuit file.\n{0}")]
+    Read(#[from] std::io::Error),
+    #[error("Failed to parse circuit file.\n{0}")]
+    Parse(#[from] roxmltree::Error),
+    #[error("The file has no circuits")]
+    NoCircuits,
+    #[error("Invalid location: {0:?}")]
+    InvalidLocation(String),
+    #[error("Circuit not found: {0}")]
+    CircuitNotFound(String),
+    #[error("Circuit {0} contains itself")]
+    RecursiveCircuit(String),
+    #[error("Failed to save board.\n{0}")]
+    SaveBoard(#[from] SaveBoardError),
+}
+
+#[derive(Debug, Error)]
+pub enum ExportLgxError {
+    #[error("Failed to load component.\n{0}")]
+    LoadComponent(#[from] LoadComponentError),
+    #[error("Failed to write lgx file.\n{0}")]
+    WriteFile(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum ExportVerilogError {
+    #[error("Failed to build board.\n{0}")]
+    Build(#[from] BoardBuildError),
+    #[error("{0}")]
+    Verilog(#[from] logix_sim::errors::VerilogError),
+    #[error("Failed to write Verilog file.\n{0}")]
+    WriteFile(#[from] std::io::Error),
+}
+
+#[cfg(feature = "png")]
+#[derive(Debug, Error)]
+pub enum RenderPngError {
+    #[error("Invalid image size: {0}x{1}")]
+    InvalidSize(u32, u32),
+    #[error("Failed to encode PNG image.\n{0}")]
+    Encode(#[from] image::ImageError),
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::board::CompSource;
+use crate::CompSource;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct IdMap {
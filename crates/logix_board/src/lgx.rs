@@ -0,0 +1,591 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use asmhdl::{AsmComponent, Data};
+use egui::{Pos2, Vec2};
+use logix_core::component::{Component, Conn, PortDesc};
+use logix_sim::primitives::{
+    clock::ClockConfig,
+    primitive::{ExtraInfo, Primitive},
+};
+
+use crate::{
+    board_comp::BoardComponent,
+    board_conn::BoardConnection,
+    board_io::BoardIO,
+    comp_info::{ComponentInfo, IOInfo},
+    errors::{ExportLgxError, ImportLgxError, LoadComponentError},
+    Board, CompSource,
+};
+
+// Approximated sizes of the components drawn in the canvas, used for the
+// automatic layout
+const PIN_HEIGHT: f32 = 20.0;
+const CHAR_WIDTH: f32 = 12.0;
+const COL_SPACING: f32 = 120.0;
+const ROW_SPACING: f32 = 40.0;
+const WIRE_MARGIN: f32 = 20.0;
+
+/// Names that can not be used as identifiers in `.lgx` files.
+const RESERVED_NAMES: [&str; 24] = [
+    "And", "Or", "Not", "Xor", "Nand", "Nor", "Clock", "High", "Low", "Const", "Switch", "In",
+    "Out", "Splitter", "Joiner", "subc", "design", "ins", "outs", "use", "asm", "as", "for", "in",
+];
+
+impl Board {
+    /// Converts a `.lgx` file into boards.
+    ///
+    /// The `Main` component and every composite component it uses become a
+    /// board that is expected to be saved in `out_dir`. Boards are returned
+    /// with their paths, dependencies first and the main board last.
+    pub fn from_lgx(
+        lgx_path: &Path,
+        out_dir: &Path,
+    ) -> Result<Vec<(PathBuf, Self)>, ImportLgxError> {
+        let comp = logix_lang::build_from_file(&lgx_path.to_string_lossy())?;
+        let mut boards = vec![];
+        Self::from_comp(&comp, out_dir, &mut boards);
+        Ok(boards)
+    }
+
+    /// Converts a `.lgx` file into boards and saves them in `out_dir`.
+    ///
+    /// Returns the path of the main board.
+    pub fn import_lgx(lgx_path: &Path, out_dir: &Path) -> Result<PathBuf, ImportLgxError> {
+        let mut boards = Self::from_lgx(lgx_path, out_dir)?;
+        for (path, board) in &mut boards {
+            board.save(path)?;
+        }
+        Ok(boards.pop().map(|(path, _)| path).unwrap_or_default())
+    }
+
+    /// Converts a composite component and the composite components it uses
+    /// into boards, see [`Board::from_lgx`].
+    ///
+    /// Returns the index of the board of `comp` in `boards`.
+    pub(crate) fn from_comp(
+        comp: &Component<ExtraInfo>,
+        out_dir: &Path,
+        boards: &mut Vec<(PathBuf, Self)>,
+    ) -> usize {
+        let name = comp.name.clone().unwrap_or_default();
+        if let Some(idx) = boards.iter().position(|(_, board)| board.name == name) {
+            return idx;
+        }
+
+        let sub = comp
+            .sub
+            .as_ref()
+            .expect("Composite component without sub-components");
+        let mut board = Self {
+            name: name.clone(),
+            ..Default::default()
+        };
+
+        for (i, sub_comp) in sub.components.iter().enumerate() {
+            let board_comp = sub_comp.extra.primitive.as_ref().map_or_else(
+                || {
+                    let idx = Self::from_comp(sub_comp, out_dir, boards);
+                    let (path, sub_board) = &boards[idx];
+                    sub_board.board_comp(i, CompSource::Local(path.clone()))
+                },
+                |prim| {
+                    let info = ComponentInfo::from_primitive(prim.clone(), sub_comp.inputs);
+                    BoardComponent::from_comp_info(info).with_id(i)
+                },
+            );
+            board.components.push(board_comp);
+        }
+
+        // Pins are named after the ports of the component
+        let io_name = |ports: &[PortDesc], pos: usize| {
+            ports
+                .get(pos)
+                .map(|port| port.name.clone())
+                .unwrap_or_default()
+        };
+        let mut in_addrs = sub.in_addrs.clone();
+        in_addrs.sort_by_key(|(in_idx, _)| *in_idx);
+        board.inputs = in_addrs
+            .iter()
+            .map(|(pos, (idx, _))| BoardIO::new(*idx, io_name(&comp.input_ports, *pos)))
+            .collect();
+        board.outputs = sub
+            .out_addrs
+            .iter()
+            .enumerate()
+            .map(|(pos, (idx, _))| BoardIO::new(*idx, io_name(&comp.output_ports, pos)))
+            .collect();
+
+        board.conns = sub
+            .connections
+            .iter()
+            .map(|conn| BoardConnection {
+                conn: *conn,
+                points: vec![],
+            })
+            .collect();
+        board.auto_layout();
+
+        let mut deps: Vec<PathBuf> = board
+            .components
+            .iter()
+            .filter_map(|bc| bc.info.source.local().cloned())
+            .collect();
+        deps.sort();
+        deps.dedup();
+        board.deps = deps;
+
+        let path = out_dir.join(format!("{}.lgxb", ident(&name)));
+        boards.push((path, board));
+        boards.len() - 1
+    }
+
+    /// Places the components in columns following the signal flow and routes
+    /// all the connections.
+    ///
+    /// Inputs are placed in the first column and outputs in the last one.
+    pub fn auto_layout(&mut self) {
+        let levels = self.signal_levels();
+        let last_level = levels.iter().max().copied().unwrap_or_default() + 1;
+
+        let mut columns: Vec<Vec<usize>> = vec![vec![]; last_level + 1];
+        for (idx, level) in levels.iter().enumerate() {
+            let level = if self.components[idx].is_output() {
+                last_level
+            } else {
+                *level
+            };
+            columns[level].push(idx);
+        }
+
+        let mut x = 0.0;
+        for column in &columns {
+            let mut y = 0.0;
+            let mut width: f32 = 0.0;
+            for idx in column {
+                let size = estimated_size(&self.components[*idx]);
+                self.components[*idx].pos = Pos2::new(x, y);
+                y += size.y + ROW_SPACING;
+                width = width.max(size.x);
+            }
+            x += width + COL_SPACING;
+        }
+
+        for i in 0..self.conns.len() {
+            self.conns[i].points = self.route(&self.conns[i].conn);
+        }
+    }
+
+    /// Longest distance (in components) from a source to each component.
+    ///
+    /// Loops are broken at the component with the lowest index among the ones
+    /// that can not be reached otherwise.
+    fn signal_levels(&self) -> Vec<usize> {
+        let count = self.components.len();
+        let mut pending = vec![0; count];
+        for info in &self.conns {
+            pending[info.conn.to.0] += 1;
+        }
+
+        let mut levels = vec![0; count];
+        let mut done = vec![false; count];
+        let mut queue: VecDeque<usize> = (0..count).filter(|i| pending[*i] == 0).collect();
+        loop {
+            while let Some(idx) = queue.pop_front() {
+                if done[idx] {
+                    continue;
+                }
+                done[idx] = true;
+                for info in self.conns.iter().filter(|info| info.conn.from.0 == idx) {
+                    let to = info.conn.to.0;
+                    levels[to] = levels[to].max(levels[idx] + 1);
+                    pending[to] -= 1;
+                    if pending[to] == 0 {
+                        queue.push_back(to);
+                    }
+                }
+            }
+            match done.iter().position(|d| !d) {
+                Some(idx) => queue.push_back(idx),
+                None => break,
+            }
+        }
+        levels
+    }
+
+    fn route(&self, conn: &Conn) -> Vec<Pos2> {
+        let from_comp = &self.components[conn.from.0];
+        let to_comp = &self.components[conn.to.0];
+        let from = pin_pos(from_comp, conn.from.1, false);
+        let to = pin_pos(to_comp, conn.to.1, true);
+
+        if to.x - from.x > 2.0 * WIRE_MARGIN {
+            #[allow(clippy::cast_precision_loss)]
+            let shift = (conn.to.1 % 4) as f32 * 4.0;
+            let mid_x = f32::midpoint(from.x, to.x) + shift;
+            return vec![from, Pos2::new(mid_x, from.y), Pos2::new(mid_x, to.y), to];
+        }
+
+        // Wires going backwards go around the components
+        let bottom = (from_comp.pos.y + estimated_size(from_comp).y)
+            .max(to_comp.pos.y + estimated_size(to_comp).y)
+            + ROW_SPACING / 2.0;
+        let (x1, x2) = (from.x + WIRE_MARGIN, to.x - WIRE_MARGIN);
+        vec![
+            from,
+            Pos2::new(x1, from.y),
+            Pos2::new(x1, bottom),
+            Pos2::new(x2, bottom),
+            Pos2::new(x2, to.y),
+            to,
+        ]
+    }
+
+    /// Writes the board as `.lgx` code.
+    ///
+    /// Boards used as components are written in the same code, before the
+    /// board using them. Returns the code and the `.asmhdl` files (name and
+    /// code) of the custom components, which are imported with `use asm`.
+    pub fn to_lgx(&self) -> Result<(String, Vec<(String, String)>), ExportLgxError> {
+        let mut writer = LgxWriter::default();
+        writer.write_board(self)?;
+        let mut code: Vec<String> = writer
+            .asm
+            .iter()
+            .map(|(name, _)| format!("use asm {} as {};", name.to_lowercase(), name))
+            .collect();
+        if !code.is_empty() {
+            code.push(String::new());
+        }
+        code.push(writer.decls.join("\n"));
+        let asm_files = writer
+            .asm
+            .into_iter()
+            .map(|(name, comp)| (name.to_lowercase(), comp.to_code()))
+            .collect();
+        Ok((code.join("\n"), asm_files))
+    }
+
+    /// Writes the board as a `.lgx` file, see [`Board::to_lgx`].
+    ///
+    /// The `.asmhdl` files of custom components are written in the same
+    /// folder.
+    pub fn export_lgx(&self, path: &Path) -> Result<(), ExportLgxError> {
+        let (code, asm_files) = self.to_lgx()?;
+        let folder = path.parent().unwrap_or_else(|| Path::new(""));
+        for (name, asm_code) in asm_files {
+            std::fs::write(folder.join(format!("{name}.asmhdl")), asm_code)?;
+        }
+        std::fs::write(path, code)?;
+        Ok(())
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn estimated_size(comp: &BoardComponent) -> Vec2 {
+    let max_len = |ios: &[IOInfo]| {
+        ios.iter()
+            .map(|io| io.name.chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let chars =
+        max_len(&comp.info.inputs) + comp.info.name.chars().count() + max_len(&comp.info.outputs);
+    let pins = comp.input_count().max(comp.output_count()).max(1);
+    Vec2::new(
+        (chars + 2) as f32 * CHAR_WIDTH,
+        (pins as f32).mul_add(PIN_HEIGHT, PIN_HEIGHT / 2.0),
+    )
+}
+
+fn pin_pos(comp: &BoardComponent, idx: usize, is_input: bool) -> Pos2 {
+    let size = estimated_size(comp);
+    let count = if is_input {
+        comp.input_count()
+    } else {
+        comp.output_count()
+    };
+
+    #[allow(clippy::cast_precision_loss)]
+    let offset = (count as f32).mul_add(-PIN_HEIGHT, size.y) / 2.0;
+
+    #[allow(clippy::cast_precision_loss)]
+    let y = (idx as f32).mul_add(PIN_HEIGHT, comp.pos.y + offset + PIN_HEIGHT / 2.0);
+    let x = if is_input {
+        comp.pos.x
+    } else {
+        comp.pos.x + size.x
+    };
+    Pos2::new(x, y)
+}
+
+/// Turns a name into a valid `.lgx` identifier.
+pub(crate) fn ident(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    if RESERVED_NAMES.contains(&id.as_str()) {
+        id.push('_');
+    }
+    id
+}
+
+fn unique(name: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.to_string();
+    let mut i = 1;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{name}_{i}");
+        i += 1;
+    }
+    candidate
+}
+
+/// Instantiation of a clock, leaving out the options with default values.
+fn clock_decl(clock: &ClockConfig) -> String {
+    let mut args = vec![format!("{}ns", clock.period)];
+    if clock.duty != 50 {
+        args.push(format!("duty = {}", clock.duty));
+    }
+    if clock.phase != 0 {
+        args.push(format!("phase = {}ns", clock.phase));
+    }
+    if clock.delay != 0 {
+        args.push(format!("delay = {}ns", clock.delay));
+    }
+    if clock.enable {
+        args.push("enable".to_string());
+    }
+    format!("Clock({})", args.join(", "))
+}
+
+#[derive(Default)]
+struct LgxWriter {
+    decls: Vec<String>,
+    decl_names: HashSet<String>,
+    boards: HashMap<PathBuf, String>,
+    asm: Vec<(String, AsmComponent)>,
+}
+
+impl LgxWriter {
+    fn write_board(&mut self, board: &Board) -> Result<String, ExportLgxError> {
+        let name = match board.name.as_str() {
+            "" => "Main".to_string(),
+            name => ident(name),
+        };
+        let name = unique(&name, &mut self.decl_names);
+        self.write_decl(board, &name)?;
+        Ok(name)
+    }
+
+    fn write_dep(&mut self, path: &PathBuf) -> Result<String, ExportLgxError> {
+        if let Some(name) = self.boards.get(path) {
+            return Ok(name.clone());
+        }
+        let board = Board::load(path).map_err(LoadComponentError::from)?;
+        let name = unique(&ident(&board.name), &mut self.decl_names);
+        self.boards.insert(path.clone(), name.clone());
+        self.write_decl(&board, &name)?;
+        Ok(name)
+    }
+
+    fn asm_name(&mut self, comp: &AsmComponent) -> String {
+        let code = comp.to_code();
+        if let Some((name, _)) = self.asm.iter().find(|(_, c)| c.to_code() == code) {
+            return name.clone();
+        }
+        let mut used = self.decl_names.clone();
+        used.extend(self.asm.iter().map(|(name, _)| name.clone()));
+        let name = unique(&ident(&comp.name), &mut used);
+        self.decl_names.insert(name.clone());
+        self.asm.push((name.clone(), comp.clone()));
+        name
+    }
+
+    fn comp_type(&mut self, comp: &BoardComponent) -> Result<String, ExportLgxError> {
+        let prim = match &comp.info.source {
+            CompSource::Local(path) => return self.write_dep(path),
+            CompSource::Prim(prim) => prim,
+        };
+        let in_count = comp.input_count();
+        Ok(match prim {
+            Primitive::AndGate => format!("And({in_count})"),
+            Primitive::OrGate => format!("Or({in_count})"),
+            Primitive::NotGate => "Not".to_string(),
+            Primitive::NandGate => format!("Nand({in_count})"),
+            Primitive::NorGate => format!("Nor({in_count})"),
+            Primitive::XorGate => format!("Xor({in_count})"),
+            Primitive::Switch => "Switch".to_string(),
+            Primitive::Input { bits } => format!("In({bits})"),
+            Primitive::Output { bits } => format!("Out({bits})"),
+            Primitive::Splitter { bits } => format!("Splitter({bits})"),
+            Primitive::Joiner { bits } => format!("Joiner({bits})"),
+            Primitive::Clock(clock) => clock_decl(clock),
+            Primitive::Const { value } if *value == Data::high() => "High".to_string(),
+            Primitive::Const { value } if *value == Data::low() => "Low".to_string(),
+            Primitive::Const { value } => format!("Const({}, {})", value.value, value.size),
+            Primitive::Custom { comp, .. } => self.asm_name(comp),
+        })
+    }
+
+    fn write_decl(&mut self, board: &Board, name: &str) -> Result<(), ExportLgxError> {
+        let mut used = HashSet::new();
+        let mut inst_names = vec![String::new(); board.components.len()];
+        for (prefix, ios) in [("in", &board.inputs), ("out", &board.outputs)] {
+            for (i, io) in ios.iter().enumerate() {
+                let io_name = match io.name.as_str() {
+                    "" => format!("{prefix}{i}"),
+                    io_name => ident(io_name),
+                };
+                inst_names[io.idx] = unique(&io_name, &mut used);
+            }
+        }
+
+        let mut subc = vec![];
+        for (i, comp) in board.components.iter().enumerate() {
+            if comp.is_input() || comp.is_output() {
+                continue;
+            }
+            let comp_type = self.comp_type(comp)?;
+            let prefix = ident(&comp.info.name).to_lowercase();
+            inst_names[i] = unique(&format!("{}{}", prefix.trim_end_matches('_'), i), &mut used);
+            subc.push(format!("{} = {}", inst_names[i], comp_type));
+        }
+
+        let io_decl = |io: &BoardIO, size: usize| match size {
+            1 => inst_names[io.idx].clone(),
+            _ => format!("{}:{}", inst_names[io.idx], size),
+        };
+        let ins: Vec<String> = board
+            .inputs
+            .iter()
+            .map(|io| io_decl(io, board.components[io.idx].info.outputs[0].size))
+            .collect();
+        let outs: Vec<String> = board
+            .outputs
+            .iter()
+            .map(|io| io_decl(io, board.components[io.idx].info.inputs[0].size))
+            .collect();
+
+        let design: Vec<String> = board
+            .conns
+            .iter()
+            .map(|info| {
+                let (from, to) = (info.conn.from, info.conn.to);
+                let src = if board.components[from.0].is_input() {
+                    inst_names[from.0].clone()
+                } else {
+                    format!("{}.{}", inst_names[from.0], from.1)
+                };
+                let dest = if board.components[to.0].is_output() {
+                    inst_names[to.0].clone()
+                } else {
+                    format!("{}.{}", inst_names[to.0], to.1)
+                };
+                format!("{src} -> {dest}")
+            })
+            .collect();
+
+        let mut lines = vec![format!("{name} (")];
+        if !subc.is_empty() {
+            lines.push(format!(
+                "    subc (\n        {}\n    )\n",
+                subc.join(",\n        ")
+            ));
+        }
+        if !ins.is_empty() {
+            lines.push(format!("    ins ({})", ins.join(", ")));
+        }
+        if !outs.is_empty() {
+            lines.push(format!("    outs ({})", outs.join(", ")));
+        }
+        if !ins.is_empty() || !outs.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!(
+            "    design (\n        {}\n    )",
+            design.join(",\n        ")
+        ));
+        lines.push(")\n".to_string());
+        self.decls.push(lines.join("\n"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lgx_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../logix_lang/examples")
+    }
+
+    fn import(code: &str, file: &str) -> Vec<(PathBuf, Board)> {
+        let path = std::env::temp_dir().join(file);
+        std::fs::write(&path, code).unwrap();
+        Board::from_lgx(&path, Path::new("boards")).unwrap()
+    }
+
+    #[test]
+    fn test_lgx_to_board() {
+        let code = format!(
+            "{}\nMain ( subc ( add = Adder<2> ) ins (a:2, b:2) outs (s:2, c) design (
+                a -> add.a, b -> add.b, add.res -> s, add.carr -> c
+            ))",
+            std::fs::read_to_string(lgx_dir().join("adders.lgx")).unwrap()
+        );
+        let boards = import(&code, "logix_lgx_to_board.lgx");
+        let names: Vec<&str> = boards.iter().map(|(_, b)| b.name.as_str()).collect();
+        assert_eq!(names, vec!["FullAdder", "Adder<2>", "Main"]);
+        assert_eq!(boards[1].0, Path::new("boards/Adder_2_.lgxb"));
+
+        let (_, main) = &boards[2];
+        let io_names: Vec<&str> = main.inputs.iter().map(|io| io.name.as_str()).collect();
+        assert_eq!(io_names, vec!["a", "b"]);
+        assert_eq!(main.deps, vec![boards[1].0.clone()]);
+
+        // Every wire starts and ends horizontally
+        let adder = &boards[1].1;
+        for info in &adder.conns {
+            let points = &info.points;
+            assert!(points.len() % 2 == 0);
+            assert!((points[0].y - points[1].y).abs() < f32::EPSILON);
+        }
+        // Outputs are placed in the last column
+        let max_x = adder.components.iter().map(|c| c.pos.x).fold(0.0, f32::max);
+        assert!(adder
+            .outputs
+            .iter()
+            .all(|io| (adder.components[io.idx].pos.x - max_x).abs() < f32::EPSILON));
+    }
+
+    #[test]
+    fn test_board_to_lgx() {
+        let code = "Main (
+            subc ( x = Xor(2), k = Const(5, 3), sw = Switch )
+            ins (a, b) outs (o, v:3, s)
+            design ( a -> x.0, b -> x.1, x.0 -> o, k.0 -> v, sw.0 -> s )
+        )";
+        let (_, main) = import(code, "logix_board_to_lgx.lgx").pop().unwrap();
+        let (lgx, asm_files) = main.to_lgx().unwrap();
+        assert!(asm_files.is_empty());
+        assert!(lgx.contains("xor0 = Xor(2)"));
+        assert!(lgx.contains("const1 = Const(5, 3)"));
+        assert!(lgx.contains("ins (a, b)"));
+        assert!(lgx.contains("outs (o, v:3, s)"));
+        assert!(lgx.contains("a -> xor0.0"));
+
+        // The exported code builds the same circuit
+        let boards = import(&lgx, "logix_board_to_lgx_2.lgx");
+        let (_, again) = boards.last().unwrap();
+        assert_eq!(again.components.len(), main.components.len());
+        assert_eq!(again.conns.len(), main.conns.len());
+        assert_eq!(again.to_lgx().unwrap().0, lgx);
+    }
+}
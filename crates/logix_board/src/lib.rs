@@ -1,4 +1,5 @@
 #![allow(clippy::module_inception)]
+
 mod board;
 mod board_actions;
 mod board_comp;
@@ -6,10 +7,21 @@ mod board_conn;
 mod board_io;
 mod comp_info;
 mod comp_source;
+mod id_map;
+mod lgx;
+mod logisim;
+mod netlist;
+mod render;
+mod verilog;
+
+pub mod errors;
 
 pub use board::Board;
 pub use board_actions::BoardAction;
 pub use board_comp::BoardComponent;
 pub use board_comp::UserInteraction;
-pub use comp_info::ComponentInfo;
+pub use board_conn::BoardConnection;
+pub use board_io::BoardIO;
+pub use comp_info::{ComponentInfo, IOInfo};
 pub use comp_source::CompSource;
+pub use id_map::IdMap;
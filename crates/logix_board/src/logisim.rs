@@ -0,0 +1,1315 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use asmhdl::Data;
+use egui::Pos2;
+use logix_core::component::Conn;
+use logix_sim::primitives::{clock::ClockConfig, primitive::Primitive};
+use roxmltree::Node;
+
+use crate::{
+    board_comp::BoardComponent, board_conn::BoardConnection, board_io::BoardIO,
+    comp_info::ComponentInfo, errors::ImportLogisimError, lgx::ident, Board, CompSource,
+};
+
+/// Duration of a clock tick with the default Logisim tick frequency (1 Hz),
+/// in nanoseconds.
+const TICK_NS: u128 = 1_000_000_000;
+
+/// Approximated width of a character in the labels of the default Logisim
+/// appearance of a circuit.
+const LABEL_CHAR_WIDTH: i32 = 8;
+
+/// Spacing between the ports of the default Logisim-evolution appearance of a
+/// circuit.
+const EVOLUTION_PORT_SPACING: i32 = 20;
+
+/// Location in a Logisim circuit.
+type Loc = (i32, i32);
+
+impl Board {
+    /// Converts a Logisim (or Logisim-evolution) `.circ` file into boards.
+    ///
+    /// The main circuit and every circuit it uses become a board that is
+    /// expected to be saved in `out_dir`, keeping the positions of the
+    /// components and the points of the wires. Gates, pins, splitters,
+    /// clocks, constants, tunnels and subcircuits are supported; the other
+    /// elements are left out and described in the returned messages. XOR and
+    /// XNOR gates with more than two inputs are only supported when they
+    /// compute the odd parity of their inputs.
+    ///
+    /// Boards are returned with their paths, dependencies first and the main
+    /// board last.
+    #[allow(clippy::type_complexity)]
+    pub fn from_logisim(
+        circ_path: &Path,
+        out_dir: &Path,
+    ) -> Result<(Vec<(PathBuf, Self)>, Vec<String>), ImportLogisimError> {
+        let xml = std::fs::read_to_string(circ_path)?;
+        let project = Project::parse(&xml)?;
+        let mut importer = LogisimImporter {
+            circuits: &project.circuits,
+            out_dir,
+            boards: vec![],
+            stack: vec![],
+            unsupported: vec![],
+        };
+        importer.import(&project.main)?;
+        Ok((importer.boards, importer.unsupported))
+    }
+
+    /// Converts a `.circ` file into boards and saves them in `out_dir`, see
+    /// [`Board::from_logisim`].
+    ///
+    /// Returns the path of the main board and the elements that could not be
+    /// imported.
+    pub fn import_logisim(
+        circ_path: &Path,
+        out_dir: &Path,
+    ) -> Result<(PathBuf, Vec<String>), ImportLogisimError> {
+        let (mut boards, unsupported) = Self::from_logisim(circ_path, out_dir)?;
+        for (path, board) in &mut boards {
+            board.save(path)?;
+        }
+        let main = boards.pop().map(|(path, _)| path).unwrap_or_default();
+        Ok((main, unsupported))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Facing {
+    East,
+    North,
+    West,
+    South,
+}
+
+impl Facing {
+    fn parse(val: Option<&str>) -> Option<Self> {
+        match val? {
+            "east" => Some(Self::East),
+            "north" => Some(Self::North),
+            "west" => Some(Self::West),
+            "south" => Some(Self::South),
+            _ => None,
+        }
+    }
+
+    /// Counterclockwise quarter turns from east.
+    const fn turns(self) -> i32 {
+        match self {
+            Self::East => 0,
+            Self::North => 1,
+            Self::West => 2,
+            Self::South => 3,
+        }
+    }
+
+    const fn reverse(self) -> Self {
+        match self {
+            Self::East => Self::West,
+            Self::North => Self::South,
+            Self::West => Self::East,
+            Self::South => Self::North,
+        }
+    }
+}
+
+/// Rotates the offset of an element drawn facing `from` so it faces `to`.
+const fn rotate((dx, dy): Loc, from: Facing, to: Facing) -> Loc {
+    match (to.turns() - from.turns()).rem_euclid(4) {
+        1 => (dy, -dx),
+        2 => (-dx, -dy),
+        3 => (-dy, dx),
+        _ => (dx, dy),
+    }
+}
+
+const fn add((x, y): Loc, (dx, dy): Loc) -> Loc {
+    (x + dx, y + dy)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn pos((x, y): Loc) -> Pos2 {
+    Pos2::new(x as f32, y as f32)
+}
+
+fn parse_loc(val: &str) -> Option<Loc> {
+    let (x, y) = val
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Values of the `<a name=".." val=".."/>` children of a node.
+fn attributes(node: Node) -> HashMap<String, String> {
+    node.children()
+        .filter(|child| child.has_tag_name("a"))
+        .filter_map(|a| {
+            let val = a.attribute("val").or_else(|| a.text()).unwrap_or_default();
+            Some((a.attribute("name")?.to_string(), val.to_string()))
+        })
+        .collect()
+}
+
+struct Project {
+    circuits: Vec<Circuit>,
+    main: String,
+}
+
+impl Project {
+    fn parse(xml: &str) -> Result<Self, ImportLogisimError> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let root = doc.root_element();
+        let libs: HashMap<&str, &str> = root
+            .children()
+            .filter(|node| node.has_tag_name("lib"))
+            .filter_map(|node| Some((node.attribute("name")?, node.attribute("desc")?)))
+            .collect();
+        // Files of the original Logisim (up to 2.7) use different defaults
+        let legacy = root
+            .attribute("source")
+            .and_then(|source| source.split('.').next()?.parse::<u32>().ok())
+            .is_some_and(|major| major < 3);
+
+        let circuits = root
+            .children()
+            .filter(|node| node.has_tag_name("circuit"))
+            .map(|node| Circuit::parse(node, &libs, legacy))
+            .collect::<Result<Vec<_>, _>>()?;
+        let main = root
+            .children()
+            .find(|node| node.has_tag_name("main"))
+            .and_then(|node| node.attribute("name"))
+            .map(str::to_string)
+            .or_else(|| circuits.first().map(|circuit| circuit.name.clone()))
+            .ok_or(ImportLogisimError::NoCircuits)?;
+        Ok(Self { circuits, main })
+    }
+}
+
+/// Component placed in a Logisim circuit.
+struct CircComp {
+    /// Description of the library of the component (e.g. `#Gates`), `None`
+    /// for subcircuits.
+    lib: Option<String>,
+    name: String,
+    loc: Loc,
+    attrs: HashMap<String, String>,
+}
+
+impl CircComp {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(String::as_str)
+    }
+
+    fn num(&self, name: &str, default: usize) -> usize {
+        self.attr(name)
+            .and_then(|val| val.trim().parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn facing(&self) -> Facing {
+        Facing::parse(self.attr("facing")).unwrap_or(Facing::East)
+    }
+
+    fn label(&self) -> String {
+        self.attr("label").unwrap_or_default().to_string()
+    }
+
+    fn is_pin(&self) -> bool {
+        self.lib.is_some() && self.name == "Pin"
+    }
+
+    fn is_output_pin(&self) -> bool {
+        self.attr("output") == Some("true") || self.attr("type") == Some("output")
+    }
+}
+
+/// How the instances of a circuit are drawn, which gives the location of
+/// their ports.
+enum Appearance {
+    Classic,
+    Evolution,
+    /// Appearance drawn by the user, with the offsets of the ports from the
+    /// anchor by location of the pin in the circuit.
+    Custom {
+        facing: Facing,
+        ports: HashMap<Loc, Loc>,
+    },
+}
+
+impl Appearance {
+    fn parse_custom(appear: Node) -> Option<Self> {
+        let center = |node: Node| -> Option<Loc> {
+            let num = |name: &str| node.attribute(name)?.parse::<i32>().ok();
+            Some((
+                num("x")? + num("width")? / 2,
+                num("y")? + num("height")? / 2,
+            ))
+        };
+        let anchor = appear
+            .children()
+            .find(|node| node.has_tag_name("circ-anchor"))?;
+        let anchor_loc = center(anchor)?;
+        let ports = appear
+            .children()
+            .filter(|node| node.has_tag_name("circ-port"))
+            .filter_map(|port| {
+                let pin = parse_loc(&format!("({})", port.attribute("pin")?))?;
+                let (x, y) = center(port)?;
+                Some((pin, (x - anchor_loc.0, y - anchor_loc.1)))
+            })
+            .collect();
+        Some(Self::Custom {
+            facing: Facing::parse(anchor.attribute("facing")).unwrap_or(Facing::East),
+            ports,
+        })
+    }
+}
+
+/// Offset of a port from the location of an instance facing east.
+#[derive(Clone, Copy)]
+struct PortOffset {
+    offset: Loc,
+    /// Whether the horizontal offset is only an estimation, as it depends on
+    /// the width of the labels.
+    estimated: bool,
+}
+
+impl PortOffset {
+    const fn exact(offset: Loc) -> Self {
+        Self {
+            offset,
+            estimated: false,
+        }
+    }
+}
+
+struct Circuit {
+    name: String,
+    comps: Vec<CircComp>,
+    wires: Vec<(Loc, Loc)>,
+    appearance: Appearance,
+    legacy: bool,
+}
+
+impl Circuit {
+    fn parse(
+        node: Node,
+        libs: &HashMap<&str, &str>,
+        legacy: bool,
+    ) -> Result<Self, ImportLogisimError> {
+        let loc = |node: Node, name: &str| {
+            let val = node.attribute(name).unwrap_or_default();
+            parse_loc(val).ok_or_else(|| ImportLogisimError::InvalidLocation(val.to_string()))
+        };
+
+        let mut comps = vec![];
+        let mut wires = vec![];
+        for child in node.children() {
+            match child.tag_name().name() {
+                "comp" => comps.push(CircComp {
+                    lib: child
+                        .attribute("lib")
+                        .map(|lib| libs.get(lib).copied().unwrap_or(lib).to_string()),
+                    name: child.attribute("name").unwrap_or_default().to_string(),
+                    loc: loc(child, "loc")?,
+                    attrs: attributes(child),
+                }),
+                "wire" => wires.push((loc(child, "from")?, loc(child, "to")?)),
+                _ => (),
+            }
+        }
+
+        let custom = node
+            .children()
+            .find(|child| child.has_tag_name("appear"))
+            .and_then(Appearance::parse_custom);
+        let appearance = custom.unwrap_or_else(|| {
+            match attributes(node).get("appearance").map(String::as_str) {
+                Some("classic") => Appearance::Classic,
+                Some(_) => Appearance::Evolution,
+                None if legacy => Appearance::Classic,
+                None => Appearance::Evolution,
+            }
+        });
+
+        Ok(Self {
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            comps,
+            wires,
+            appearance,
+            legacy,
+        })
+    }
+
+    /// Input and output pins, sorted by location. They are the inputs and
+    /// outputs of the board, in the same order.
+    fn pins(&self) -> (Vec<&CircComp>, Vec<&CircComp>) {
+        let mut pins: Vec<&CircComp> = self.comps.iter().filter(|c| c.is_pin()).collect();
+        pins.sort_by_key(|pin| (pin.loc.1, pin.loc.0));
+        pins.into_iter().partition(|pin| !pin.is_output_pin())
+    }
+
+    /// Facing of the appearance and offsets of the ports of the instances
+    /// (inputs first), as Logisim places them.
+    fn port_offsets(&self) -> (Facing, Vec<Option<PortOffset>>) {
+        let (inputs, outputs) = self.pins();
+        let pins: Vec<&CircComp> = inputs.iter().chain(&outputs).copied().collect();
+        match &self.appearance {
+            Appearance::Custom { facing, ports } => (
+                *facing,
+                pins.iter()
+                    .map(|pin| ports.get(&pin.loc).copied().map(PortOffset::exact))
+                    .collect(),
+            ),
+            Appearance::Evolution => {
+                let label_width = |pins: &[&CircComp]| {
+                    pins.iter()
+                        .map(|pin| pin.label().chars().count())
+                        .max()
+                        .unwrap_or_default() as i32
+                        * LABEL_CHAR_WIDTH
+                };
+                let title_width = self.name.chars().count() as i32 * LABEL_CHAR_WIDTH;
+                let text_width =
+                    (label_width(&inputs) + label_width(&outputs) + 35).max(title_width + 15);
+                let width = text_width / 10 * 10 + 20;
+
+                // The anchor is the first output, or the first input
+                let in_x = if outputs.is_empty() { 0 } else { -width };
+                let column = |x: i32, count: usize, estimated: bool| {
+                    (0..count as i32).map(move |i| {
+                        Some(PortOffset {
+                            offset: (x, i * EVOLUTION_PORT_SPACING),
+                            estimated,
+                        })
+                    })
+                };
+                let offsets = column(in_x, inputs.len(), !outputs.is_empty())
+                    .chain(column(0, outputs.len(), false))
+                    .collect();
+                (Facing::East, offsets)
+            }
+            Appearance::Classic => (Facing::East, classic_offsets(&pins)),
+        }
+    }
+}
+
+/// Offsets of the ports of the default appearance of the original Logisim,
+/// where each pin is placed in the side opposite to its facing.
+fn classic_offsets(pins: &[&CircComp]) -> Vec<Option<PortOffset>> {
+    // Pins of each side, indexed by the quarter turns of the side from east
+    let mut sides: [Vec<usize>; 4] = Default::default();
+    for (i, pin) in pins.iter().enumerate() {
+        sides[pin.facing().reverse().turns() as usize].push(i);
+    }
+    for (turns, side) in sides.iter_mut().enumerate() {
+        if turns % 2 == 1 {
+            side.sort_by_key(|i| pins[*i].loc);
+        } else {
+            side.sort_by_key(|i| (pins[*i].loc.1, pins[*i].loc.0));
+        }
+    }
+
+    let count = |facing: Facing| sides[facing.turns() as usize].len() as i32;
+    let (east, north, west, south) = (
+        count(Facing::East),
+        count(Facing::North),
+        count(Facing::West),
+        count(Facing::South),
+    );
+    let (max_vert, max_horz) = (north.max(south), east.max(west));
+    let offset = |facing: i32, opposite: i32, max_others: i32| {
+        let max_this = facing.max(opposite);
+        let max_offset = match max_this {
+            0 | 1 if max_others == 0 => 15,
+            0..=2 => 10,
+            _ if max_others == 0 => 5,
+            _ => 10,
+        };
+        max_offset + 10 * ((max_this - facing) / 2)
+    };
+    let dimension = |max_this: i32, max_others: i32| match (max_this, max_others) {
+        (0..=2, _) => 30,
+        (_, 0) => 10 * max_this,
+        _ => 10 * max_this + 10,
+    };
+    let offs_east = offset(east, west, max_vert);
+    let offs_north = offset(north, south, max_horz);
+    let offs_west = offset(west, east, max_vert);
+    let offs_south = offset(south, north, max_horz);
+    let width = dimension(max_vert, max_horz);
+    let height = dimension(max_horz, max_vert);
+
+    // Location of the anchor from the top left corner of the box
+    let anchor = if east > 0 {
+        (width, offs_east)
+    } else if north > 0 {
+        (offs_north, 0)
+    } else if west > 0 {
+        (0, offs_west)
+    } else if south > 0 {
+        (offs_south, height)
+    } else {
+        (0, 0)
+    };
+
+    let mut offsets = vec![None; pins.len()];
+    for (turns, side) in sides.iter().enumerate() {
+        for (i, pin) in side.iter().enumerate() {
+            let i = i as i32 * 10;
+            let (x, y) = match turns {
+                0 => (width, offs_east + i),
+                1 => (offs_north + i, 0),
+                2 => (0, offs_west + i),
+                _ => (offs_south + i, height),
+            };
+            offsets[*pin] = Some(PortOffset::exact((x - anchor.0, y - anchor.1)));
+        }
+    }
+    offsets
+}
+
+/// Location and bits of each end of a splitter.
+fn splitter_ends(comp: &CircComp) -> Vec<(Loc, Vec<usize>)> {
+    let fanout = comp.num("fanout", 2).max(1);
+    let bits = comp.num("incoming", 2);
+    let spacing = comp.num("spacing", 1) as i32 * 10;
+    let justify = match comp.attr("appear") {
+        Some("center" | "legacy") => 0,
+        Some("right") => 1,
+        _ => -1,
+    };
+
+    let count = fanout as i32;
+    let (start, step) = match comp.facing() {
+        facing @ (Facing::North | Facing::South) => {
+            let m = if facing == Facing::North { 1 } else { -1 };
+            let dx = match justify {
+                0 => spacing * ((count + 1) / 2 - 1),
+                _ if m * justify < 0 => -spacing,
+                _ => spacing * count,
+            };
+            ((dx, -m * 20), (-spacing, 0))
+        }
+        facing => {
+            let m = if facing == Facing::West { -1 } else { 1 };
+            let dy = match justify {
+                0 => -spacing * (count / 2),
+                _ if m * justify > 0 => -spacing * count,
+                _ => spacing,
+            };
+            ((m * 20, dy), (0, spacing))
+        }
+    };
+
+    // By default the bits are spread evenly, in order
+    let (per_end, with_extra) = (bits / fanout, bits % fanout);
+    let default_end = |bit: usize| {
+        if fanout >= bits {
+            bit
+        } else if bit < with_extra * (per_end + 1) {
+            bit / (per_end + 1)
+        } else {
+            with_extra + (bit - with_extra * (per_end + 1)) / per_end
+        }
+    };
+    let mut ends = vec![vec![]; fanout];
+    for bit in 0..bits {
+        let end = match comp.attr(&format!("bit{bit}")) {
+            Some("none") => None,
+            Some(val) => val.parse().ok(),
+            None => Some(default_end(bit)),
+        };
+        if let Some(end) = end.filter(|end| *end < fanout) {
+            ends[end].push(bit);
+        }
+    }
+    ends.into_iter()
+        .enumerate()
+        .map(|(i, bits)| {
+            let i = i as i32;
+            let offset = (start.0 + step.0 * i, start.1 + step.1 * i);
+            (add(comp.loc, offset), bits)
+        })
+        .collect()
+}
+
+/// Offset of an input of a gate from its output, as Logisim places them.
+fn gate_input_offset(inputs: usize, index: usize, size: i32, axis: i32, facing: Facing) -> Loc {
+    let (inputs, index) = (inputs as i32, index as i32);
+    let (skip_start, skip_dist, skip_lower_even) = if inputs <= 3 {
+        if size < 40 {
+            (-5, 10, 10)
+        } else if size < 60 || inputs <= 2 {
+            (-10, 20, 20)
+        } else {
+            (-15, 30, 30)
+        }
+    } else if inputs == 4 && size >= 60 {
+        (-5, 20, 0)
+    } else {
+        (-5, 10, 10)
+    };
+    let dy = if inputs % 2 == 1 {
+        skip_start * (inputs - 1) + skip_dist * index
+    } else if index >= inputs / 2 {
+        skip_start * inputs + skip_dist * index + skip_lower_even
+    } else {
+        skip_start * inputs + skip_dist * index
+    };
+    // Inputs keep their order from top to bottom (or left to right)
+    match facing {
+        Facing::North => (dy, axis),
+        Facing::South => (dy, -axis),
+        Facing::West => (axis, dy),
+        Facing::East => (-axis, dy),
+    }
+}
+
+/// Pin of a board component at a location of the circuit.
+struct Pin {
+    loc: Loc,
+    comp: usize,
+    port: usize,
+    output: bool,
+}
+
+struct LogisimImporter<'a> {
+    circuits: &'a [Circuit],
+    out_dir: &'a Path,
+    boards: Vec<(PathBuf, Board)>,
+    /// Circuits being imported, to detect recursive circuits
+    stack: Vec<String>,
+    unsupported: Vec<String>,
+}
+
+impl LogisimImporter<'_> {
+    /// Imports a circuit and the circuits it uses, returning the index of its
+    /// board.
+    fn import(&mut self, name: &str) -> Result<usize, ImportLogisimError> {
+        if let Some(idx) = self.boards.iter().position(|(_, board)| board.name == name) {
+            return Ok(idx);
+        }
+        if self.stack.iter().any(|other| other == name) {
+            return Err(ImportLogisimError::RecursiveCircuit(name.to_string()));
+        }
+        let circuit = self
+            .circuits
+            .iter()
+            .find(|circuit| circuit.name == name)
+            .ok_or_else(|| ImportLogisimError::CircuitNotFound(name.to_string()))?;
+
+        self.stack.push(name.to_string());
+        let board = self.import_circuit(circuit)?;
+        self.stack.pop();
+
+        let path = self.out_dir.join(format!("{}.lgxb", ident(name)));
+        self.boards.push((path, board));
+        Ok(self.boards.len() - 1)
+    }
+
+    fn import_circuit(&mut self, circuit: &Circuit) -> Result<Board, ImportLogisimError> {
+        let mut builder = CircuitBuilder {
+            circuit,
+            board: Board {
+                name: circuit.name.clone(),
+                ..Default::default()
+            },
+            pins: vec![],
+            estimated: vec![],
+            parent: HashMap::new(),
+            unsupported: vec![],
+        };
+        for (from, to) in &circuit.wires {
+            builder.union(*from, *to);
+        }
+
+        let (inputs, outputs) = circuit.pins();
+        for pin in inputs {
+            let width = pin.num("width", 1);
+            let idx = builder.add(ComponentInfo::input(width), vec![(pin.loc, 0, true)]);
+            builder.board.inputs.push(BoardIO::new(idx, pin.label()));
+        }
+        for pin in outputs {
+            let width = pin.num("width", 1);
+            let idx = builder.add(ComponentInfo::output(width), vec![(pin.loc, 0, false)]);
+            builder.board.outputs.push(BoardIO::new(idx, pin.label()));
+        }
+
+        let mut tunnels: HashMap<String, Loc> = HashMap::new();
+        let mut splitters = vec![];
+        for comp in &circuit.comps {
+            if comp.lib.is_none() {
+                self.add_subcircuit(&mut builder, comp)?;
+                continue;
+            }
+            match comp.name.as_str() {
+                "Pin" | "Text" | "Probe" => (),
+                "Tunnel" => {
+                    let first = *tunnels.entry(comp.label()).or_insert(comp.loc);
+                    builder.union(first, comp.loc);
+                }
+                "Splitter" => splitters.push(comp),
+                "Buffer" => {
+                    let input = rotate((-20, 0), Facing::East, comp.facing());
+                    builder.union(comp.loc, add(comp.loc, input));
+                }
+                "NOT Gate" => builder.add_not(comp),
+                "AND Gate" | "OR Gate" | "NAND Gate" | "NOR Gate" | "XOR Gate" | "XNOR Gate" => {
+                    builder.add_gate(comp);
+                }
+                "Clock" => builder.add_clock(comp),
+                "Constant" | "Power" | "Ground" => builder.add_const(comp),
+                _ => builder.report(comp, "is not supported"),
+            }
+        }
+
+        builder.place_estimated_pins();
+        builder.add_splitters(splitters);
+        builder.connect_nets();
+        self.unsupported.append(&mut builder.unsupported);
+
+        let mut board = builder.board;
+        let mut deps: Vec<PathBuf> = board
+            .components
+            .iter()
+            .filter_map(|bc| bc.info.source.local().cloned())
+            .collect();
+        deps.sort();
+        deps.dedup();
+        board.deps = deps;
+        Ok(board)
+    }
+
+    fn add_subcircuit(
+        &mut self,
+        builder: &mut CircuitBuilder,
+        comp: &CircComp,
+    ) -> Result<(), ImportLogisimError> {
+        let Some(sub) = self.circuits.iter().find(|c| c.name == comp.name) else {
+            builder.report(comp, "is not a circuit of the file");
+            return Ok(());
+        };
+        let idx = self.import(&sub.name)?;
+        let (path, sub_board) = &self.boards[idx];
+        let board_comp = sub_board.board_comp(0, CompSource::Local(path.clone()));
+
+        let (appearance_facing, offsets) = sub.port_offsets();
+        let in_count = sub_board.inputs.len();
+        let mut pins = vec![];
+        for (i, offset) in offsets.into_iter().enumerate() {
+            let (port, output) = if i < in_count {
+                (i, false)
+            } else {
+                (i - in_count, true)
+            };
+            let Some(offset) = offset else {
+                builder.report(
+                    comp,
+                    &format!("has no location for port {i} of {}", sub.name),
+                );
+                continue;
+            };
+            let loc = add(
+                comp.loc,
+                rotate(offset.offset, appearance_facing, comp.facing()),
+            );
+            if offset.estimated {
+                builder
+                    .estimated
+                    .push((builder.pins.len() + pins.len(), comp.loc, comp.facing()));
+            }
+            pins.push((loc, port, output));
+        }
+        builder.add_comp(board_comp, pins);
+        Ok(())
+    }
+}
+
+/// Board of a circuit being imported, with the pins of its components by
+/// location.
+struct CircuitBuilder<'a> {
+    circuit: &'a Circuit,
+    board: Board,
+    pins: Vec<Pin>,
+    /// Pins of subcircuits whose location is estimated: index of the pin and
+    /// location and facing of the instance.
+    estimated: Vec<(usize, Loc, Facing)>,
+    /// Union-find of the locations connected by wires and tunnels
+    parent: HashMap<Loc, Loc>,
+    unsupported: Vec<String>,
+}
+
+impl CircuitBuilder<'_> {
+    fn report(&mut self, comp: &CircComp, what: &str) {
+        self.unsupported.push(format!(
+            "{}: {} at ({}, {}) {what}",
+            self.circuit.name, comp.name, comp.loc.0, comp.loc.1
+        ));
+    }
+
+    fn find(&self, mut loc: Loc) -> Loc {
+        while let Some(next) = self.parent.get(&loc) {
+            loc = *next;
+        }
+        loc
+    }
+
+    fn union(&mut self, a: Loc, b: Loc) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent.insert(a, b);
+        }
+    }
+
+    /// Whether an output drives the net at a location.
+    fn driven(&self, loc: Loc) -> bool {
+        let net = self.find(loc);
+        self.pins
+            .iter()
+            .any(|pin| pin.output && self.find(pin.loc) == net)
+    }
+
+    /// Adds a component with its pins (location, port and whether it is an
+    /// output), placed at the top left of its pins.
+    ///
+    /// Returns the index of the component.
+    fn add_comp(&mut self, comp: BoardComponent, pins: Vec<(Loc, usize, bool)>) -> usize {
+        let idx = self.board.components.len();
+        let min_x = pins.iter().map(|(loc, ..)| loc.0).min().unwrap_or_default();
+        let min_y = pins.iter().map(|(loc, ..)| loc.1).min().unwrap_or_default();
+        self.board
+            .components
+            .push(comp.with_id(idx).with_pos(pos((min_x, min_y - 10))));
+        self.pins
+            .extend(pins.into_iter().map(|(loc, port, output)| Pin {
+                loc,
+                comp: idx,
+                port,
+                output,
+            }));
+        idx
+    }
+
+    fn add(&mut self, info: ComponentInfo, pins: Vec<(Loc, usize, bool)>) -> usize {
+        self.add_comp(BoardComponent::from_comp_info(info), pins)
+    }
+
+    /// Connects two ports of components that have no wire between them in the
+    /// circuit.
+    fn connect(&mut self, from: (usize, usize), to: (usize, usize), (a, b): (Loc, Loc)) {
+        self.board.conns.push(BoardConnection {
+            conn: Conn::new(from.0, from.1, to.0, to.1),
+            points: vec![pos(a), pos(b)],
+        });
+    }
+
+    fn add_not(&mut self, comp: &CircComp) {
+        if comp.num("width", 1) > 1 {
+            return self.report(comp, "has more than one bit, which is not supported");
+        }
+        let size = comp.num("size", 30) as i32;
+        let input = add(comp.loc, rotate((-size, 0), Facing::East, comp.facing()));
+        self.add(
+            ComponentInfo::not_gate(),
+            vec![(input, 0, false), (comp.loc, 0, true)],
+        );
+    }
+
+    fn add_gate(&mut self, comp: &CircComp) {
+        if comp.num("width", 1) > 1 {
+            return self.report(comp, "has more than one bit, which is not supported");
+        }
+        // Primitive, extra width of the body and whether the output is negated
+        let (prim, bonus_width, negated) = match comp.name.as_str() {
+            "AND Gate" => (Primitive::AndGate, 0, false),
+            "OR Gate" => (Primitive::OrGate, 0, false),
+            "NAND Gate" => (Primitive::NandGate, 0, true),
+            "NOR Gate" => (Primitive::NorGate, 0, true),
+            "XOR Gate" => (Primitive::XorGate, 10, false),
+            _ => (Primitive::XorGate, 10, true),
+        };
+        let xnor = comp.name == "XNOR Gate";
+        let inputs = comp.num("inputs", if self.circuit.legacy { 5 } else { 2 });
+        // Unless set to odd parity, Logisim XOR gates are high when exactly
+        // one input is, which only matches a XOR gate for two inputs
+        if matches!(prim, Primitive::XorGate) && inputs > 2 && comp.attr("xor") != Some("odd") {
+            return self.report(
+                comp,
+                "checks for exactly one high input, which is not supported",
+            );
+        }
+        let size = comp.num("size", 50) as i32;
+        let facing = comp.facing();
+        let axis = size + bonus_width + if negated { 10 } else { 0 };
+
+        let mut pins = vec![];
+        let mut negated_inputs = vec![];
+        for i in 0..inputs {
+            let negate = comp.attr(&format!("negate{i}")) == Some("true");
+            let axis = if negate { axis + 10 } else { axis };
+            let loc = add(comp.loc, gate_input_offset(inputs, i, size, axis, facing));
+            if negate {
+                negated_inputs.push((i, loc));
+            } else {
+                pins.push((loc, i, false));
+            }
+        }
+        if !xnor {
+            pins.push((comp.loc, 0, true));
+        }
+        let gate = self.add(ComponentInfo::from_primitive(prim, inputs), pins);
+
+        if xnor {
+            let not = self.add(ComponentInfo::not_gate(), vec![(comp.loc, 0, true)]);
+            let gate_out = add(comp.loc, rotate((-10, 0), Facing::East, facing));
+            self.connect((gate, 0), (not, 0), (gate_out, comp.loc));
+        }
+        for (i, loc) in negated_inputs {
+            let not = self.add(ComponentInfo::not_gate(), vec![(loc, 0, false)]);
+            self.connect((not, 0), (gate, i), (loc, loc));
+        }
+    }
+
+    fn add_clock(&mut self, comp: &CircComp) {
+        let (high, low) = (comp.num("highDuration", 1), comp.num("lowDuration", 1));
+        let duty = (high * 100 / (high + low).max(1)).clamp(1, 99);
+        if duty * (high + low) != high * 100 {
+            self.report(comp, "has a duty cycle rounded to a whole percentage");
+        }
+        let clock = ClockConfig {
+            duty: duty as u8,
+            ..ClockConfig::new(TICK_NS * (high + low) as u128)
+        };
+        self.add(ComponentInfo::clock(clock), vec![(comp.loc, 0, true)]);
+    }
+
+    fn add_const(&mut self, comp: &CircComp) {
+        let width = comp.num("width", 1);
+        let mask = 1_usize
+            .checked_shl(width as u32)
+            .map_or(usize::MAX, |m| m - 1);
+        let value = match comp.name.as_str() {
+            "Power" => Some(mask),
+            "Ground" => Some(0),
+            _ => comp.attr("value").map_or(Some(1), |val| {
+                val.strip_prefix("0x").map_or_else(
+                    || val.parse().ok(),
+                    |hex| usize::from_str_radix(hex, 16).ok(),
+                )
+            }),
+        };
+        let Some(value) = value else {
+            return self.report(comp, "has an invalid value");
+        };
+        let value = Data::new(value & mask, width);
+        self.add(
+            ComponentInfo::from_primitive(Primitive::Const { value }, 0),
+            vec![(comp.loc, 0, true)],
+        );
+    }
+
+    /// Moves the pins with an estimated location to the closest wire end or
+    /// pin in the same row, at the side of the instance.
+    fn place_estimated_pins(&mut self) {
+        let estimated = std::mem::take(&mut self.estimated);
+        let mut candidates: Vec<Loc> = self
+            .circuit
+            .wires
+            .iter()
+            .flat_map(|(from, to)| [*from, *to])
+            .collect();
+        candidates.extend(
+            self.pins
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !estimated.iter().any(|(pin, ..)| pin == i))
+                .map(|(_, pin)| pin.loc),
+        );
+
+        for (pin, inst_loc, facing) in estimated {
+            let local = |loc: Loc| {
+                let offset = (loc.0 - inst_loc.0, loc.1 - inst_loc.1);
+                rotate(offset, facing, Facing::East)
+            };
+            let (x, y) = local(self.pins[pin].loc);
+            let closest = candidates
+                .iter()
+                .filter(|loc| {
+                    let (cx, cy) = local(**loc);
+                    cy == y && cx < 0
+                })
+                .min_by_key(|loc| (local(**loc).0 - x).abs());
+            if let Some(loc) = closest {
+                self.pins[pin].loc = *loc;
+            }
+        }
+    }
+
+    /// Adds the splitters, as splitters or joiners depending on the side they
+    /// are driven from.
+    fn add_splitters(&mut self, splitters: Vec<&CircComp>) {
+        let mut pending: Vec<_> = splitters
+            .into_iter()
+            .map(|comp| (comp, splitter_ends(comp)))
+            .collect();
+        loop {
+            let count = pending.len();
+            pending.retain(|(comp, ends)| {
+                if self.driven(comp.loc) {
+                    self.add_splitter(comp, ends, true);
+                } else if ends.iter().any(|(loc, _)| self.driven(*loc)) {
+                    self.add_splitter(comp, ends, false);
+                } else {
+                    return true;
+                }
+                false
+            });
+            if pending.len() == count {
+                break;
+            }
+        }
+        for (comp, ends) in pending {
+            self.add_splitter(comp, &ends, true);
+        }
+    }
+
+    /// Adds a splitter as a splitter of its bits (or a joiner if `split` is
+    /// false), with joiners (or splitters) for the ends of several bits.
+    fn add_splitter(&mut self, comp: &CircComp, ends: &[(Loc, Vec<usize>)], split: bool) {
+        let bits = comp.num("incoming", 2);
+        let mut pins = vec![(comp.loc, 0, !split)];
+        pins.extend(ends.iter().filter_map(|(loc, bits)| match bits.as_slice() {
+            [bit] => Some((*loc, *bit, split)),
+            _ => None,
+        }));
+
+        if split {
+            let splitter = self.add(ComponentInfo::splitter(bits), pins);
+            for (loc, bits) in ends.iter().filter(|(_, bits)| bits.len() > 1) {
+                let joiner = self.add(ComponentInfo::joiner(bits.len()), vec![(*loc, 0, true)]);
+                for (i, bit) in bits.iter().enumerate() {
+                    self.connect((splitter, *bit), (joiner, i), (comp.loc, *loc));
+                }
+            }
+        } else {
+            let joiner = self.add(ComponentInfo::joiner(bits), pins);
+            for (loc, bits) in ends.iter().filter(|(_, bits)| bits.len() > 1) {
+                let splitter =
+                    self.add(ComponentInfo::splitter(bits.len()), vec![(*loc, 0, false)]);
+                for (i, bit) in bits.iter().enumerate() {
+                    self.connect((splitter, i), (joiner, *bit), (*loc, comp.loc));
+                }
+            }
+        }
+    }
+
+    /// Connects the output driving each net to the inputs in it, following
+    /// the wires of the circuit.
+    fn connect_nets(&mut self) {
+        let mut adjacent: HashMap<Loc, Vec<Loc>> = HashMap::new();
+        for (from, to) in &self.circuit.wires {
+            adjacent.entry(*from).or_default().push(*to);
+            adjacent.entry(*to).or_default().push(*from);
+        }
+
+        // Outputs and inputs of each net, in order of appearance
+        let mut nets: Vec<(Vec<usize>, Vec<usize>)> = vec![];
+        let mut net_idx: HashMap<Loc, usize> = HashMap::new();
+        for (i, pin) in self.pins.iter().enumerate() {
+            let idx = *net_idx.entry(self.find(pin.loc)).or_insert_with(|| {
+                nets.push(Default::default());
+                nets.len() - 1
+            });
+            if pin.output {
+                nets[idx].0.push(i);
+            } else {
+                nets[idx].1.push(i);
+            }
+        }
+
+        for (outputs, inputs) in nets {
+            let Some(from) = outputs.first().map(|i| &self.pins[*i]) else {
+                continue;
+            };
+            if outputs.len() > 1 {
+                self.unsupported.push(format!(
+                    "{}: the wire at ({}, {}) is driven by {} outputs, only the first one is kept",
+                    self.circuit.name,
+                    from.loc.0,
+                    from.loc.1,
+                    outputs.len()
+                ));
+            }
+            for to in inputs.iter().map(|i| &self.pins[*i]) {
+                self.board.conns.push(BoardConnection {
+                    conn: Conn::new(from.comp, from.port, to.comp, to.port),
+                    points: wire_points(&adjacent, from.loc, to.loc),
+                });
+            }
+        }
+    }
+}
+
+/// Points of the wires going from one location to another, starting and
+/// ending with horizontal segments as in the boards.
+fn wire_points(adjacent: &HashMap<Loc, Vec<Loc>>, from: Loc, to: Loc) -> Vec<Pos2> {
+    let mut prev: HashMap<Loc, Loc> = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(loc) = queue.pop_front() {
+        if loc == to {
+            break;
+        }
+        for next in adjacent.get(&loc).into_iter().flatten() {
+            if !prev.contains_key(next) {
+                prev.insert(*next, loc);
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    let mut path = if prev.contains_key(&to) {
+        let mut path = vec![to];
+        let mut loc = to;
+        while loc != from {
+            loc = prev[&loc];
+            path.push(loc);
+        }
+        path.reverse();
+        path
+    } else {
+        // Locations connected through tunnels
+        let mid_x = (from.0 + to.0) / 2;
+        vec![from, (mid_x, from.1), (mid_x, to.1), to]
+    };
+
+    path.dedup();
+    let mut i = 1;
+    while i + 1 < path.len() {
+        let (a, b, c) = (path[i - 1], path[i], path[i + 1]);
+        if (a.0 == b.0 && b.0 == c.0) || (a.1 == b.1 && b.1 == c.1) {
+            path.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    if path.len() == 1 || path[0].1 != path[1].1 {
+        path.insert(1, path[0]);
+    }
+    let last = path.len() - 1;
+    if path[last - 1].1 != path[last].1 {
+        path.insert(last, path[last]);
+    }
+    path.into_iter().map(pos).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use logix_sim::{flatten::FlattenComponent, HeadlessSimulator};
+
+    use super::*;
+
+    const CIRC: &str = r##"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<project source="3.8.0" version="1.0">
+  <lib desc="#Wiring" name="0"/>
+  <lib desc="#Gates" name="1"/>
+  <lib desc="#Memory" name="4"/>
+  <main name="main"/>
+  <circuit name="main">
+    <a name="circuit" val="main"/>
+    <comp lib="0" loc="(100,100)" name="Pin"><a name="label" val="a"/></comp>
+    <comp lib="0" loc="(100,140)" name="Pin"><a name="label" val="b"/></comp>
+    <comp lib="1" loc="(200,120)" name="AND Gate"/>
+    <comp lib="1" loc="(260,120)" name="NOT Gate"/>
+    <comp lib="0" loc="(280,120)" name="Tunnel"><a name="label" val="t"/></comp>
+    <comp lib="0" loc="(300,200)" name="Tunnel"><a name="label" val="t"/></comp>
+    <comp loc="(400,200)" name="inv"/>
+    <comp lib="0" loc="(450,240)" name="Pin">
+      <a name="facing" val="west"/>
+      <a name="output" val="true"/>
+      <a name="label" val="q"/>
+    </comp>
+    <comp lib="0" loc="(100,300)" name="Pin">
+      <a name="width" val="2"/>
+      <a name="label" val="d"/>
+    </comp>
+    <comp lib="0" loc="(120,300)" name="Splitter"/>
+    <comp lib="0" loc="(280,300)" name="Splitter">
+      <a name="facing" val="west"/>
+      <a name="appear" val="right"/>
+      <a name="bit0" val="1"/>
+      <a name="bit1" val="0"/>
+    </comp>
+    <comp lib="0" loc="(300,300)" name="Pin">
+      <a name="facing" val="west"/>
+      <a name="output" val="true"/>
+      <a name="width" val="2"/>
+      <a name="label" val="dd"/>
+    </comp>
+    <comp lib="0" loc="(100,400)" name="Constant"/>
+    <comp lib="0" loc="(150,400)" name="Pin">
+      <a name="output" val="true"/>
+      <a name="label" val="one"/>
+    </comp>
+    <comp lib="4" loc="(500,500)" name="Register"/>
+    <wire from="(100,100)" to="(150,100)"/>
+    <wire from="(100,140)" to="(150,140)"/>
+    <wire from="(200,120)" to="(230,120)"/>
+    <wire from="(260,120)" to="(280,120)"/>
+    <wire from="(300,200)" to="(330,200)"/>
+    <wire from="(400,200)" to="(420,200)"/>
+    <wire from="(420,200)" to="(420,240)"/>
+    <wire from="(420,240)" to="(450,240)"/>
+    <wire from="(100,300)" to="(120,300)"/>
+    <wire from="(140,310)" to="(260,310)"/>
+    <wire from="(140,320)" to="(260,320)"/>
+    <wire from="(280,300)" to="(300,300)"/>
+    <wire from="(100,400)" to="(150,400)"/>
+  </circuit>
+  <circuit name="inv">
+    <comp lib="0" loc="(100,100)" name="Pin"><a name="label" val="x"/></comp>
+    <comp lib="1" loc="(160,100)" name="NOT Gate"/>
+    <comp lib="0" loc="(200,100)" name="Pin">
+      <a name="output" val="true"/>
+      <a name="label" val="y"/>
+    </comp>
+    <wire from="(100,100)" to="(130,100)"/>
+    <wire from="(160,100)" to="(200,100)"/>
+  </circuit>
+</project>
+"##;
+
+    #[test]
+    fn test_logisim_to_board() {
+        let dir = std::env::temp_dir().join("logix_logisim_to_board");
+        std::fs::create_dir_all(&dir).unwrap();
+        let circ_path = dir.join("lab.circ");
+        std::fs::write(&circ_path, CIRC).unwrap();
+
+        let (main_path, unsupported) = Board::import_logisim(&circ_path, &dir).unwrap();
+        assert_eq!(
+            unsupported,
+            vec!["main: Register at (500, 500) is not supported"]
+        );
+        let mut main = Board::open(&main_path).unwrap();
+        let names = |ios: &[BoardIO]| ios.iter().map(|io| io.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&main.inputs), vec!["a", "b", "d"]);
+        assert_eq!(names(&main.outputs), vec!["q", "dd", "one"]);
+        assert_eq!(main.deps, vec![dir.join("inv.lgxb")]);
+
+        // Positions and wire points are kept
+        let inv = main
+            .components
+            .iter()
+            .position(|c| c.info.source.local().is_some())
+            .unwrap();
+        assert_eq!(main.components[inv].pos, Pos2::new(330.0, 190.0));
+        let to_q = main
+            .conns
+            .iter()
+            .find(|info| info.conn.from.0 == inv)
+            .unwrap();
+        let points: Vec<(f32, f32)> = to_q.points.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(
+            points,
+            vec![
+                (400.0, 200.0),
+                (420.0, 200.0),
+                (420.0, 240.0),
+                (450.0, 240.0)
+            ]
+        );
+
+        let (_, comp) = main
+            .build_component(CompSource::Local(main_path.clone()), &mut 0)
+            .unwrap();
+        let ids = |ios: &[BoardIO]| {
+            let sub = &comp.sub.as_ref().unwrap().components;
+            ios.iter().map(|io| sub[io.idx].id).collect::<Vec<_>>()
+        };
+        let (ins, outs) = (ids(&main.inputs), ids(&main.outputs));
+        let mut sim = HeadlessSimulator::new(FlattenComponent::new(comp).unwrap());
+        for (a, b, d) in [(0, 1, 1), (1, 1, 2), (1, 0, 3)] {
+            sim.set_input(ins[0], Data::new(a, 1)).unwrap();
+            sim.set_input(ins[1], Data::new(b, 1)).unwrap();
+            sim.set_input(ins[2], Data::new(d, 2)).unwrap();
+            sim.settle().unwrap();
+            let out = |i: usize| sim.output(outs[i]).unwrap().value;
+            assert_eq!(out(0), a & b);
+            assert_eq!(out(1), (d >> 1) | ((d & 1) << 1));
+            assert_eq!(out(2), 1);
+        }
+    }
+
+    #[test]
+    fn test_multi_input_xor() {
+        // Pins placed on the inputs and outputs of three-input gates: odd
+        // parity XOR and XNOR gates, and a XOR gate with the default
+        // one-hot behavior
+        let circ = r##"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<project source="3.8.0" version="1.0">
+  <lib desc="#Wiring" name="0"/>
+  <lib desc="#Gates" name="1"/>
+  <main name="main"/>
+  <circuit name="main">
+    <comp lib="0" loc="(140,100)" name="Pin"/>
+    <comp lib="0" loc="(140,120)" name="Pin"/>
+    <comp lib="0" loc="(140,140)" name="Pin"/>
+    <comp lib="1" loc="(200,120)" name="XOR Gate">
+      <a name="inputs" val="3"/>
+      <a name="xor" val="odd"/>
+    </comp>
+    <comp lib="0" loc="(200,120)" name="Pin"><a name="output" val="true"/></comp>
+    <comp lib="0" loc="(130,200)" name="Pin"/>
+    <comp lib="0" loc="(130,220)" name="Pin"/>
+    <comp lib="0" loc="(130,240)" name="Pin"/>
+    <comp lib="1" loc="(200,220)" name="XNOR Gate">
+      <a name="inputs" val="3"/>
+      <a name="xor" val="odd"/>
+    </comp>
+    <comp lib="0" loc="(200,220)" name="Pin"><a name="output" val="true"/></comp>
+    <comp lib="1" loc="(200,320)" name="XOR Gate"><a name="inputs" val="3"/></comp>
+  </circuit>
+</project>
+"##;
+        let dir = std::env::temp_dir().join("logix_logisim_multi_input_xor");
+        std::fs::create_dir_all(&dir).unwrap();
+        let circ_path = dir.join("xor.circ");
+        std::fs::write(&circ_path, circ).unwrap();
+
+        let (main_path, unsupported) = Board::import_logisim(&circ_path, &dir).unwrap();
+        assert_eq!(
+            unsupported,
+            vec![
+                "main: XOR Gate at (200, 320) checks for exactly one high input, which is not supported"
+            ]
+        );
+        let mut main = Board::open(&main_path).unwrap();
+        let (_, comp) = main
+            .build_component(CompSource::Local(main_path.clone()), &mut 0)
+            .unwrap();
+        let ids = |ios: &[BoardIO]| {
+            let sub = &comp.sub.as_ref().unwrap().components;
+            ios.iter().map(|io| sub[io.idx].id).collect::<Vec<_>>()
+        };
+        let (ins, outs) = (ids(&main.inputs), ids(&main.outputs));
+        let mut sim = HeadlessSimulator::new(FlattenComponent::new(comp).unwrap());
+        for value in 0..8 {
+            for (i, id) in ins.iter().enumerate() {
+                sim.set_input(*id, Data::new((value >> (i % 3)) & 1, 1))
+                    .unwrap();
+            }
+            sim.settle().unwrap();
+            let parity = (value as u32).count_ones() as usize % 2;
+            assert_eq!(sim.output(outs[0]).unwrap().value, parity);
+            assert_eq!(sim.output(outs[1]).unwrap().value, 1 - parity);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use logix_lang::{Fsm, FsmTarget, GateStyle, Logic};
+
+use crate::{errors::ImportNetlistError, Board};
+
+impl Board {
+    /// Converts a structural Verilog (`.v`) or BLIF (`.blif`) file into
+    /// boards, as [`Board::from_lgx`].
+    pub fn from_netlist(
+        path: &Path,
+        out_dir: &Path,
+    ) -> Result<Vec<(PathBuf, Self)>, ImportNetlistError> {
+        let code = std::fs::read_to_string(path)?;
+        let comp = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("v") => logix_lang::build_from_verilog(&code)?,
+            Some("blif") => logix_lang::build_from_blif(&code)?,
+            _ => return Err(ImportNetlistError::UnsupportedFile(path.to_path_buf())),
+        };
+        let mut boards = vec![];
+        Self::from_comp(&comp, out_dir, &mut boards);
+        Ok(boards)
+    }
+
+    /// Converts a netlist into boards and saves them in `out_dir`.
+    ///
+    /// Returns the path of the main board.
+    pub fn import_netlist(path: &Path, out_dir: &Path) -> Result<PathBuf, ImportNetlistError> {
+        let mut boards = Self::from_netlist(path, out_dir)?;
+        for (path, board) in &mut boards {
+            board.save(path)?;
+        }
+        Ok(boards.pop().map(|(path, _)| path).unwrap_or_default())
+    }
+
+    /// Builds minimized boolean functions into a board named `name` and
+    /// saves it in `out_dir`.
+    ///
+    /// Returns the path of the board.
+    pub fn import_logic(
+        logic: &Logic,
+        name: &str,
+        style: GateStyle,
+        out_dir: &Path,
+    ) -> Result<PathBuf, ImportNetlistError> {
+        let comp = logic.build(name, style)?;
+        let mut boards = vec![];
+        let idx = Self::from_comp(&comp, out_dir, &mut boards);
+        let (path, board) = &mut boards[idx];
+        board.save(path)?;
+        Ok(path.clone())
+    }
+
+    /// Builds a state machine into boards saved in `out_dir`.
+    ///
+    /// Returns the path of the main board.
+    pub fn import_fsm(
+        fsm: &Fsm,
+        target: FsmTarget,
+        out_dir: &Path,
+    ) -> Result<PathBuf, ImportNetlistError> {
+        let comp = fsm.build(target)?;
+        let mut boards = vec![];
+        let idx = Self::from_comp(&comp, out_dir, &mut boards);
+        for (path, board) in &mut boards {
+            board.save(path)?;
+        }
+        Ok(boards[idx].0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use asmhdl::Data;
+    use logix_sim::{flatten::FlattenComponent, HeadlessSimulator};
+
+    use super::*;
+    use crate::{BoardIO, CompSource};
+
+    #[test]
+    fn test_verilog_to_board() {
+        let dir = std::env::temp_dir().join("logix_verilog_to_board");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mux.v");
+        std::fs::write(
+            &path,
+            "module inv (input a, output y); not (y, a); endmodule
+             module mux (input a, b, s, output y);
+                 wire ns;
+                 inv i0 (.a(s), .y(ns));
+                 assign y = (a & ns) | (b & s);
+             endmodule",
+        )
+        .unwrap();
+
+        let boards = Board::from_netlist(&path, &dir).unwrap();
+        let names: Vec<&str> = boards.iter().map(|(_, b)| b.name.as_str()).collect();
+        assert_eq!(names, vec!["inv", "mux"]);
+        let (_, mux) = &boards[1];
+        let io_names: Vec<&str> = mux.inputs.iter().map(|io| io.name.as_str()).collect();
+        assert_eq!(io_names, vec!["a", "b", "s"]);
+        assert_eq!(mux.deps, vec![dir.join("inv.lgxb")]);
+        // Gates are laid out between the inputs and the outputs
+        assert!(mux.components.iter().any(|c| c.pos.x > 0.0));
+    }
+
+    #[test]
+    fn test_logic_to_board() {
+        let dir = std::env::temp_dir().join("logix_logic_to_board");
+        std::fs::create_dir_all(&dir).unwrap();
+        let logic = Logic::from_equations("f = (a & b) | !c").unwrap();
+        let path = Board::import_logic(&logic, "F", GateStyle::Nand, &dir).unwrap();
+        assert_eq!(path, dir.join("F.lgxb"));
+
+        let board = Board::load(&path).unwrap();
+        let io_names: Vec<&str> = board.inputs.iter().map(|io| io.name.as_str()).collect();
+        assert_eq!(io_names, vec!["a", "b", "c"]);
+        assert_eq!(board.outputs[0].name, "f");
+    }
+
+    #[test]
+    fn test_fsm_to_board() {
+        let dir = std::env::temp_dir().join("logix_fsm_to_board");
+        std::fs::create_dir_all(&dir).unwrap();
+        let fsm = Fsm::parse(
+            "name Toggle\ninputs t\noutputs q, n:2\n\
+             state off / n=1\nstate on / q=1, n=2\noff -> on when t\non -> off when t",
+        )
+        .unwrap();
+        let path = Board::import_fsm(&fsm, FsmTarget::Gates(GateStyle::AndOrNot), &dir).unwrap();
+        assert_eq!(path, dir.join("Toggle.lgxb"));
+
+        let mut board = Board::load(&path).unwrap();
+        let io_names: Vec<&str> = board.inputs.iter().map(|io| io.name.as_str()).collect();
+        assert_eq!(io_names, vec!["clk", "rst", "t"]);
+        assert_eq!(board.deps, vec![dir.join("Toggle_logic.lgxb")]);
+
+        let (_, comp) = board
+            .build_component(CompSource::Local(path), &mut 0)
+            .unwrap();
+        let sub = &comp.sub.as_ref().unwrap().components;
+        let ids = |ios: &[BoardIO]| ios.iter().map(|io| sub[io.idx].id).collect::<Vec<_>>();
+        let (ins, outs) = (ids(&board.inputs), ids(&board.outputs));
+        let mut sim = HeadlessSimulator::new(FlattenComponent::new(comp).unwrap());
+        sim.set_input(ins[2], Data::high()).unwrap();
+        sim.settle().unwrap();
+        for (q, n) in [(1, 2), (0, 1), (1, 2)] {
+            sim.set_input(ins[0], Data::high()).unwrap();
+            sim.settle().unwrap();
+            assert_eq!(sim.output(outs[0]).unwrap().value, q);
+            assert_eq!(sim.output(outs[1]).unwrap().value, n);
+            sim.set_input(ins[0], Data::low()).unwrap();
+            sim.settle().unwrap();
+        }
+    }
+}
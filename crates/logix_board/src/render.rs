@@ -0,0 +1,641 @@
+use asmhdl::Data;
+use egui::{Color32, Pos2, Rect, Vec2};
+use logix_sim::{errors::DataRequestError, flatten::FlattenComponent};
+
+use crate::Board;
+
+// Same look as the Logix App canvas
+const BACKGROUND: Color32 = Color32::from_rgb(35, 35, 35);
+const COMP_FILL: Color32 = Color32::from_rgb(70, 70, 70);
+const PORT_TEXT: Color32 = Color32::from_gray(140);
+const HIGH_COLOR: Color32 = Color32::LIGHT_GREEN;
+const LOW_COLOR: Color32 = Color32::GRAY;
+const FONT_SIZE: f32 = 20.0;
+const VALUE_FONT_SIZE: f32 = 12.0;
+const PIN_SIZE: f32 = 8.0;
+
+/// Advance of the monospace font, relative to its size.
+const CHAR_WIDTH: f32 = 0.6;
+/// Spacing between the columns and the rows of a component.
+const SPACING: Vec2 = Vec2::new(8.0, 3.0);
+const MARGIN: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Anchor {
+    Start,
+    End,
+}
+
+/// Drawing primitives a board is rendered with.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Shape {
+    Rect {
+        rect: Rect,
+        rounding: f32,
+        fill: Color32,
+    },
+    Line {
+        points: Vec<Pos2>,
+        width: f32,
+        color: Color32,
+    },
+    Circle {
+        center: Pos2,
+        radius: f32,
+        color: Color32,
+    },
+    /// Text vertically centered at `pos`.
+    Text {
+        pos: Pos2,
+        text: String,
+        size: f32,
+        color: Color32,
+        anchor: Anchor,
+    },
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * CHAR_WIDTH
+}
+
+fn column_height(rows: usize) -> f32 {
+    rows as f32 * FONT_SIZE + rows.saturating_sub(1) as f32 * SPACING.y
+}
+
+const fn data_color(data: Data) -> Color32 {
+    match data.value {
+        0 => LOW_COLOR,
+        _ => HIGH_COLOR,
+    }
+}
+
+/// Position of the pins and frame of a component, laid out as in the canvas.
+struct CompLayout {
+    rect: Rect,
+    inputs: Vec<Pos2>,
+    outputs: Vec<Pos2>,
+    name_pos: Pos2,
+}
+
+impl Board {
+    /// Sets the data of the components to the values they have in `comp`,
+    /// the flattened component of this board (e.g. a simulator snapshot).
+    ///
+    /// The ids of the components must be the ones given to them by
+    /// [`Board::build_component`].
+    pub fn load_values(&mut self, comp: &FlattenComponent) -> Result<(), DataRequestError> {
+        for board_comp in &mut self.components {
+            let (inputs, outputs) = comp.get_status(&[], Some(board_comp.id))?;
+            board_comp.inputs_data = inputs;
+            board_comp.outputs_data = outputs;
+        }
+        Ok(())
+    }
+
+    /// Renders the board as an SVG image, drawn as in the Logix App.
+    ///
+    /// If `values` is set, wires and pins are colored by the data of the
+    /// components (see [`Board::load_values`]) and buses are annotated with
+    /// their value.
+    pub fn to_svg(&self, values: bool) -> String {
+        let (bounds, shapes) = self.shapes(values);
+        let mut svg = vec![format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">",
+            bounds.width(),
+            bounds.height(),
+            bounds.min.x,
+            bounds.min.y,
+            bounds.width(),
+            bounds.height()
+        )];
+        for shape in shapes {
+            svg.push(match shape {
+                Shape::Rect {
+                    rect,
+                    rounding,
+                    fill,
+                } => format!(
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{rounding}\" fill=\"{}\"/>",
+                    rect.min.x,
+                    rect.min.y,
+                    rect.width(),
+                    rect.height(),
+                    svg_color(fill)
+                ),
+                Shape::Line {
+                    points,
+                    width,
+                    color,
+                } => format!(
+                    "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{width}\"/>",
+                    points
+                        .iter()
+                        .map(|p| format!("{},{}", p.x, p.y))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    svg_color(color)
+                ),
+                Shape::Circle {
+                    center,
+                    radius,
+                    color,
+                } => format!(
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"{radius}\" fill=\"{}\"/>",
+                    center.x,
+                    center.y,
+                    svg_color(color)
+                ),
+                Shape::Text {
+                    pos,
+                    text,
+                    size,
+                    color,
+                    anchor,
+                } => format!(
+                    "  <text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{size}\" fill=\"{}\" dominant-baseline=\"central\" text-anchor=\"{}\" xml:space=\"preserve\">{}</text>",
+                    pos.x,
+                    pos.y,
+                    svg_color(color),
+                    match anchor {
+                        Anchor::Start => "start",
+                        Anchor::End => "end",
+                    },
+                    escape(&text)
+                ),
+            });
+        }
+        svg.push("</svg>".to_string());
+        svg.join("\n") + "\n"
+    }
+
+    fn comp_layout(&self, idx: usize) -> CompLayout {
+        let comp = &self.components[idx];
+        let in_names: Vec<String> = comp
+            .info
+            .inputs
+            .iter()
+            .map(|io| format!(" {}", io.name))
+            .collect();
+        let out_names: Vec<String> = comp
+            .info
+            .outputs
+            .iter()
+            .map(|io| format!("{} ", io.name))
+            .collect();
+        let column_width = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| text_width(name, FONT_SIZE))
+                .fold(0.0, f32::max)
+        };
+
+        let in_width = column_width(&in_names);
+        let out_width = column_width(&out_names);
+        let name_width = text_width(&self.comp_label(idx), FONT_SIZE);
+        let body_height = column_height(comp.input_count().max(comp.output_count()).max(1));
+        let padding = FONT_SIZE / 4.0;
+
+        let min = comp.pos;
+        let size = Vec2::new(
+            in_width + name_width + out_width + 2.0 * SPACING.x,
+            body_height + 2.0 * padding,
+        );
+        let rect = Rect::from_min_size(min, size);
+
+        let pins = |count: usize, x: f32| {
+            let offset = (body_height - column_height(count)) / 2.0;
+            (0..count)
+                .map(|i| {
+                    let y = min.y
+                        + padding
+                        + offset
+                        + i as f32 * (FONT_SIZE + SPACING.y)
+                        + FONT_SIZE / 2.0;
+                    Pos2::new(x, y)
+                })
+                .collect()
+        };
+
+        CompLayout {
+            rect,
+            inputs: pins(comp.input_count(), rect.left()),
+            outputs: pins(comp.output_count(), rect.right()),
+            name_pos: Pos2::new(min.x + in_width + SPACING.x, rect.center().y),
+        }
+    }
+
+    /// Name of a component, followed by the name of the board IO for inputs
+    /// and outputs.
+    fn comp_label(&self, idx: usize) -> String {
+        let comp = &self.components[idx];
+        let io = if comp.is_input() {
+            self.inputs.iter().find(|io| io.idx == idx)
+        } else if comp.is_output() {
+            self.outputs.iter().find(|io| io.idx == idx)
+        } else {
+            None
+        };
+        match io {
+            Some(io) => format!("{} {}", comp.info.name, io.name),
+            None => comp.info.name.clone(),
+        }
+    }
+
+    /// Shapes to draw the board with, and the area they cover.
+    pub(crate) fn shapes(&self, values: bool) -> (Rect, Vec<Shape>) {
+        let layouts: Vec<CompLayout> = (0..self.components.len())
+            .map(|idx| self.comp_layout(idx))
+            .collect();
+
+        let mut bounds = Rect::NOTHING;
+        let mut wires = vec![];
+        for board_conn in &self.conns {
+            let (from, to) = (board_conn.conn.from, board_conn.conn.to);
+            let (Some(start), Some(end)) = (
+                layouts[from.0].outputs.get(from.1),
+                layouts[to.0].inputs.get(to.1),
+            ) else {
+                continue;
+            };
+
+            // Wires are attached to the pins as the canvas does
+            let mut points = board_conn.points.clone();
+            let len = points.len();
+            if len < 2 {
+                continue;
+            }
+            points[0] = *start;
+            points[1].y = start.y;
+            points[len - 1] = *end;
+            points[len - 2].y = end.y;
+            bounds = points
+                .iter()
+                .fold(bounds, |rect, p| rect.union(Rect::from_pos(*p)));
+
+            let data = self.components[from.0].outputs_data[from.1];
+            let color = if values {
+                data_color(data)
+            } else {
+                Color32::WHITE
+            };
+            let width = if data.size == 1 { 2.0 } else { 4.0 };
+            for point in &points[1..len - 1] {
+                wires.push(Shape::Circle {
+                    center: *point,
+                    radius: 3.0,
+                    color,
+                });
+            }
+            if values && data.size > 1 {
+                wires.push(Shape::Text {
+                    pos: points[0] + Vec2::new(PIN_SIZE, -VALUE_FONT_SIZE / 2.0 - 2.0),
+                    text: format!("{:0width$b}", data.value, width = data.size),
+                    size: VALUE_FONT_SIZE,
+                    color,
+                    anchor: Anchor::Start,
+                });
+            }
+            wires.push(Shape::Line {
+                points,
+                width,
+                color,
+            });
+        }
+
+        let mut shapes = vec![];
+        for (idx, layout) in layouts.iter().enumerate() {
+            let comp = &self.components[idx];
+            bounds = bounds.union(layout.rect.expand(PIN_SIZE / 2.0));
+            shapes.push(Shape::Rect {
+                rect: layout.rect,
+                rounding: 4.0,
+                fill: COMP_FILL,
+            });
+            shapes.push(Shape::Text {
+                pos: layout.name_pos,
+                text: self.comp_label(idx),
+                size: FONT_SIZE,
+                color: Color32::WHITE,
+                anchor: Anchor::Start,
+            });
+
+            let ports = comp
+                .info
+                .inputs
+                .iter()
+                .zip(&layout.inputs)
+                .zip(&comp.inputs_data)
+                .map(|((io, pos), data)| (format!(" {}", io.name), *pos, *data, Anchor::Start));
+            let ports = ports.chain(
+                comp.info
+                    .outputs
+                    .iter()
+                    .zip(&layout.outputs)
+                    .zip(&comp.outputs_data)
+                    .map(|((io, pos), data)| (format!("{} ", io.name), *pos, *data, Anchor::End)),
+            );
+            for (name, pos, data, anchor) in ports {
+                if !name.trim().is_empty() {
+                    shapes.push(Shape::Text {
+                        pos,
+                        text: name,
+                        size: FONT_SIZE,
+                        color: PORT_TEXT,
+                        anchor,
+                    });
+                }
+                shapes.push(Shape::Circle {
+                    center: pos,
+                    radius: PIN_SIZE / 2.0,
+                    color: if values {
+                        data_color(data)
+                    } else {
+                        Color32::LIGHT_GRAY
+                    },
+                });
+            }
+        }
+
+        // Wires are drawn below their junctions and values, and those below
+        // the components
+        wires.sort_by_key(|shape| !matches!(shape, Shape::Line { .. }));
+        wires.extend(shapes);
+
+        let bounds = if bounds.is_positive() {
+            bounds.expand(MARGIN)
+        } else {
+            Rect::from_min_size(Pos2::ZERO, Vec2::splat(2.0 * MARGIN))
+        };
+        let mut shapes = vec![Shape::Rect {
+            rect: bounds,
+            rounding: 0.0,
+            fill: BACKGROUND,
+        }];
+        shapes.extend(wires);
+        (bounds, shapes)
+    }
+}
+
+fn svg_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(feature = "png")]
+mod png {
+    use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+    use egui::Color32;
+    use tiny_skia::{
+        FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Stroke, Transform,
+    };
+
+    use super::{Anchor, Shape};
+    use crate::{errors::RenderPngError, Board};
+
+    fn paint(color: Color32) -> Paint<'static> {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(color.r(), color.g(), color.b(), color.a());
+        paint.anti_alias = true;
+        paint
+    }
+
+    fn rounded_rect(rect: egui::Rect, r: f32) -> Option<tiny_skia::Path> {
+        let r = r.min(rect.width() / 2.0).min(rect.height() / 2.0);
+        let (x0, y0, x1, y1) = (rect.min.x, rect.min.y, rect.max.x, rect.max.y);
+        let mut pb = PathBuilder::new();
+        pb.move_to(x0 + r, y0);
+        pb.line_to(x1 - r, y0);
+        pb.quad_to(x1, y0, x1, y0 + r);
+        pb.line_to(x1, y1 - r);
+        pb.quad_to(x1, y1, x1 - r, y1);
+        pb.line_to(x0 + r, y1);
+        pb.quad_to(x0, y1, x0, y1 - r);
+        pb.line_to(x0, y0 + r);
+        pb.quad_to(x0, y0, x0 + r, y0);
+        pb.close();
+        pb.finish()
+    }
+
+    /// Draws text with the monospace font of the Logix App.
+    fn draw_text(
+        pixmap: &mut Pixmap,
+        font: &FontRef,
+        (x, y): (f32, f32),
+        text: &str,
+        size: f32,
+        color: Color32,
+        anchor: Anchor,
+    ) {
+        let font = font.as_scaled(PxScale::from(size));
+        let width: f32 = text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum();
+        let mut x = match anchor {
+            Anchor::Start => x,
+            Anchor::End => x - width,
+        };
+        let baseline = y + (font.ascent() + font.descent()) / 2.0;
+        let (pw, ph) = (pixmap.width() as i32, pixmap.height() as i32);
+        let stride = pixmap.width() as usize;
+        let pixels = pixmap.pixels_mut();
+        for c in text.chars() {
+            let glyph_id = font.glyph_id(c);
+            let glyph = glyph_id.with_scale_and_position(size, point(x, baseline));
+            x += font.h_advance(glyph_id);
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px >= pw || py >= ph {
+                    return;
+                }
+                let pixel = &mut pixels[py as usize * stride + px as usize];
+                let blend = |src: u8, dst: u8| {
+                    (f32::from(src) * coverage + f32::from(dst) * (1.0 - coverage)).round() as u8
+                };
+                if let Some(blended) = PremultipliedColorU8::from_rgba(
+                    blend(color.r(), pixel.red()),
+                    blend(color.g(), pixel.green()),
+                    blend(color.b(), pixel.blue()),
+                    255,
+                ) {
+                    *pixel = blended;
+                }
+            });
+        }
+    }
+
+    impl Board {
+        /// Renders the board as a PNG image, `scale` times the size of the
+        /// SVG one (see [`Board::to_svg`]).
+        pub fn to_png(&self, values: bool, scale: f32) -> Result<Vec<u8>, RenderPngError> {
+            let (bounds, shapes) = self.shapes(values);
+            let (width, height) = (
+                (bounds.width() * scale).ceil() as u32,
+                (bounds.height() * scale).ceil() as u32,
+            );
+            let mut pixmap =
+                Pixmap::new(width, height).ok_or(RenderPngError::InvalidSize(width, height))?;
+            let transform = Transform::from_row(
+                scale,
+                0.0,
+                0.0,
+                scale,
+                -bounds.min.x * scale,
+                -bounds.min.y * scale,
+            );
+
+            let fonts = egui::FontDefinitions::default();
+            let font = FontRef::try_from_slice(&fonts.font_data["Hack"].font)
+                .expect("The default monospace font is valid");
+
+            for shape in shapes {
+                match shape {
+                    Shape::Rect {
+                        rect,
+                        rounding,
+                        fill,
+                    } => {
+                        if let Some(path) = rounded_rect(rect, rounding) {
+                            pixmap.fill_path(
+                                &path,
+                                &paint(fill),
+                                FillRule::Winding,
+                                transform,
+                                None,
+                            );
+                        }
+                    }
+                    Shape::Line {
+                        points,
+                        width,
+                        color,
+                    } => {
+                        let mut pb = PathBuilder::new();
+                        pb.move_to(points[0].x, points[0].y);
+                        for p in &points[1..] {
+                            pb.line_to(p.x, p.y);
+                        }
+                        if let Some(path) = pb.finish() {
+                            let stroke = Stroke {
+                                width,
+                                ..Stroke::default()
+                            };
+                            pixmap.stroke_path(&path, &paint(color), &stroke, transform, None);
+                        }
+                    }
+                    Shape::Circle {
+                        center,
+                        radius,
+                        color,
+                    } => {
+                        if let Some(path) = PathBuilder::from_circle(center.x, center.y, radius) {
+                            pixmap.fill_path(
+                                &path,
+                                &paint(color),
+                                FillRule::Winding,
+                                transform,
+                                None,
+                            );
+                        }
+                    }
+                    Shape::Text {
+                        pos,
+                        text,
+                        size,
+                        color,
+                        anchor,
+                    } => draw_text(
+                        &mut pixmap,
+                        &font,
+                        (
+                            (pos.x - bounds.min.x) * scale,
+                            (pos.y - bounds.min.y) * scale,
+                        ),
+                        &text,
+                        size * scale,
+                        color,
+                        anchor,
+                    ),
+                }
+            }
+
+            let image = image::RgbaImage::from_raw(width, height, pixmap.take())
+                .expect("The pixmap has the size of the image");
+            let mut png = std::io::Cursor::new(vec![]);
+            image.write_to(&mut png, image::ImageFormat::Png)?;
+            Ok(png.into_inner())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use asmhdl::Data;
+    use egui::Pos2;
+
+    use crate::{Board, BoardComponent, ComponentInfo};
+
+    /// `in -> not -> out` with the not gate driven high.
+    fn board() -> Board {
+        let mut board = Board {
+            name: "Inv".into(),
+            ..Default::default()
+        };
+        let comps = [
+            (ComponentInfo::input(1), Pos2::new(0.0, 0.0)),
+            (ComponentInfo::not_gate(), Pos2::new(150.0, 0.0)),
+            (ComponentInfo::output(1), Pos2::new(300.0, 0.0)),
+        ];
+        for (i, (info, pos)) in comps.into_iter().enumerate() {
+            board.add_comp(
+                BoardComponent::from_comp_info(info)
+                    .with_pos(pos)
+                    .with_id(i),
+            );
+        }
+        // Board IOs are added with the components
+        board.inputs[0].name = "a".into();
+        board.outputs[0].name = "q".into();
+        for (from, to) in [(0, 1), (1, 2)] {
+            board.add_conn(
+                from,
+                to,
+                0,
+                0,
+                vec![Pos2::ZERO, Pos2::new(10.0, 0.0), Pos2::ZERO, Pos2::ZERO],
+            );
+        }
+        board.components[1].outputs_data[0] = Data::high();
+        board
+    }
+
+    #[test]
+    fn test_svg() {
+        let board = board();
+        let svg = board.to_svg(false);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains(">IN a</text>"));
+        assert!(svg.contains(">OUT q</text>"));
+        assert!(!svg.contains("#90ee90"));
+
+        // The high output of the gate is drawn in green
+        let svg = board.to_svg(true);
+        assert_eq!(svg.matches("stroke=\"#90ee90\"").count(), 1);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_png() {
+        let png = board().to_png(true, 2.0).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use crate::{errors::ExportVerilogError, Board, CompSource};
+
+impl Board {
+    /// Writes the board and the boards it uses as structural Verilog, see
+    /// [`logix_sim::verilog::to_verilog`].
+    pub fn to_verilog(&mut self) -> Result<String, ExportVerilogError> {
+        let (_, mut comp) = self.build_component(CompSource::default(), &mut 0)?;
+        if self.name.is_empty() {
+            comp.name = None;
+        }
+        Ok(logix_sim::verilog::to_verilog(&comp)?)
+    }
+
+    /// Writes the board as a Verilog file, see [`Board::to_verilog`].
+    pub fn export_verilog(&mut self, path: &Path) -> Result<(), ExportVerilogError> {
+        std::fs::write(path, self.to_verilog()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_board_to_verilog() {
+        let dir = std::env::temp_dir().join("logix_board_to_verilog");
+        std::fs::create_dir_all(&dir).unwrap();
+        let adders =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../logix_lang/examples/adders.lgx");
+        let code = format!(
+            "{}\nMain ( subc ( add = Adder<2> ) ins (a:2, b:2) outs (s:2, c) design (
+                a -> add.a, b -> add.b, add.res -> s, add.carr -> c
+            ))",
+            std::fs::read_to_string(adders).unwrap()
+        );
+        let lgx_path = dir.join("main.lgx");
+        std::fs::write(&lgx_path, code).unwrap();
+        let board_path = Board::import_lgx(&lgx_path, &dir).unwrap();
+
+        let verilog = Board::load(&board_path).unwrap().to_verilog().unwrap();
+        let modules: Vec<&str> = verilog
+            .lines()
+            .filter_map(|line| line.strip_prefix("module "))
+            .collect();
+        assert_eq!(modules, vec!["FullAdder (", "Adder_2_ (", "Main ("]);
+        assert!(verilog.contains("    input [1:0] a,\n    input [1:0] b,\n"));
+        assert!(verilog.contains("    output [1:0] s,\n    output c\n"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use logix_board::{Board, CompSource};
+use logix_core::component::Component;
+use logix_sim::{
+    equivalence::Circuit,
+    flatten::FlattenComponent,
+    headless::{top_level_ports, Port},
+    primitives::primitive::ExtraInfo,
+};
+
+use crate::errors::LoadDesignError;
+
+/// Flattened design together with its named top-level ports.
+pub struct Design {
+    pub comp: FlattenComponent,
+    pub inputs: Vec<Port>,
+    pub outputs: Vec<Port>,
+}
+
+impl Design {
+    /// Loads a design from a file, see [`load_component`].
+    pub fn load(path: &Path) -> Result<Self, LoadDesignError> {
+        Self::from_comp(load_component(path)?)
+    }
+
+    /// Design of a board opened from `path`. The components of the board are
+    /// given the ids they have in the design.
+    pub fn from_board(board: &mut Board, path: &Path) -> Result<Self, LoadDesignError> {
+        Self::from_comp(build_board(board, path)?)
+    }
+
+    /// Design of a component, with its ports named after its port
+    /// descriptors.
+    fn from_comp(comp: Component<ExtraInfo>) -> Result<Self, LoadDesignError> {
+        let comp = FlattenComponent::new(comp)?;
+        let (inputs, outputs) = top_level_ports(&comp);
+        Ok(Design {
+            comp,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Circuit of the design, to compare it with another one.
+    pub fn circuit(&self) -> Circuit<'_> {
+        Circuit {
+            comp: &self.comp,
+            inputs: &self.inputs,
+            outputs: &self.outputs,
+        }
+    }
+}
+
+/// Loads a `.lgx` circuit, a `.lgxb` board or a structural Verilog (`.v`) or
+/// BLIF (`.blif`) netlist as a component.
+pub fn load_component(path: &Path) -> Result<Component<ExtraInfo>, LoadDesignError> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("lgx") => Ok(logix_lang::build_from_file(&path.to_string_lossy())?),
+        Some("lgxb") => build_board(&mut Board::open(&path.to_path_buf())?, path),
+        Some("v") => Ok(logix_lang::build_from_verilog(&std::fs::read_to_string(
+            path,
+        )?)?),
+        Some("blif") => Ok(logix_lang::build_from_blif(&std::fs::read_to_string(
+            path,
+        )?)?),
+        _ => Err(LoadDesignError::UnsupportedFile(path.to_path_buf())),
+    }
+}
+
+/// Builds a board opened from `path`, giving its components the ids they
+/// have in the design.
+fn build_board(board: &mut Board, path: &Path) -> Result<Component<ExtraInfo>, LoadDesignError> {
+    let (_, comp) = board.build_component(CompSource::Local(path.to_path_buf()), &mut 0)?;
+    Ok(comp)
+}
+
+#[cfg(test)]
+mod tests {
+    use asmhdl::Data;
+    use logix_sim::HeadlessSimulator;
+
+    use super::*;
+
+    fn run(design: Design, a: usize, b: usize) -> Vec<usize> {
+        let mut sim = HeadlessSimulator::new(design.comp);
+        sim.set_input(design.inputs[0].id, Data::new(a, 2)).unwrap();
+        sim.set_input(design.inputs[1].id, Data::new(b, 2)).unwrap();
+        sim.settle().unwrap();
+        design
+            .outputs
+            .iter()
+            .map(|port| sim.output(port.id).unwrap().value)
+            .collect()
+    }
+
+    #[test]
+    fn test_lgx_and_board_ports() {
+        let dir = std::env::temp_dir().join("logix_cli_design");
+        std::fs::create_dir_all(&dir).unwrap();
+        let adders =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../logix_lang/examples/adders.lgx");
+        let code = format!(
+            "{}\nMain ( subc ( add = Adder<2> ) ins (a:2, b:2) outs (s:2, c) design (
+                a -> add.a, b -> add.b, add.res -> s, add.carr -> c
+            ))",
+            std::fs::read_to_string(adders).unwrap()
+        );
+        let lgx_path = dir.join("main.lgx");
+        std::fs::write(&lgx_path, code).unwrap();
+        let board_path = Board::import_lgx(&lgx_path, &dir).unwrap();
+
+        for path in [lgx_path, board_path] {
+            let design = Design::load(&path).unwrap();
+            let names = |ports: &[Port]| ports.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+            assert_eq!(names(&design.inputs), vec!["a", "b"]);
+            assert_eq!(names(&design.outputs), vec!["s", "c"]);
+            assert_eq!(design.outputs[0].bits, 2);
+            assert_eq!(run(design, 3, 2), vec![1, 1]);
+        }
+
+        let netlist_path = dir.join("main.v");
+        std::fs::write(
+            &netlist_path,
+            "module main (input [1:0] a, b, output [1:0] s, output c);
+                 assign s = a ^ b;
+                 assign c = (a[0] & b[0]) | (a[1] & b[1]);
+             endmodule",
+        )
+        .unwrap();
+        let design = Design::load(&netlist_path).unwrap();
+        assert_eq!(design.inputs[0].name, "a");
+        assert_eq!(design.outputs[0].bits, 2);
+        assert_eq!(run(design, 3, 2), vec![1, 1]);
+
+        assert!(matches!(
+            Design::load(Path::new("circuit.txt")),
+            Err(LoadDesignError::UnsupportedFile(_))
+        ));
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use logix_sim::{
+    equivalence::{check_circuits, Equivalence},
+    headless::Port,
+};
+
+use crate::{design::Design, errors::EquivError};
+
+/// Checks whether two combinational circuits compute the same outputs for
+/// every value of their inputs. Inputs and outputs are matched in order.
+#[derive(Debug, clap::Args)]
+pub struct EquivArgs {
+    /// First circuit (`.lgx`, `.lgxb`, `.v` or `.blif`)
+    left: PathBuf,
+    /// Second circuit
+    right: PathBuf,
+}
+
+fn format_values(ports: &[Port], values: &[usize]) -> String {
+    ports
+        .iter()
+        .zip(values)
+        .map(|(port, value)| format!("{}={:0width$b}", port.name, value, width = port.bits))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns whether the circuits are equivalent.
+pub fn equiv(args: EquivArgs) -> Result<bool, EquivError> {
+    let left = Design::load(&args.left)?;
+    let right = Design::load(&args.right)?;
+    match check_circuits(left.circuit(), right.circuit())? {
+        Equivalence::Equivalent => {
+            println!("The circuits are equivalent");
+            Ok(true)
+        }
+        Equivalence::Different(counterexample) => {
+            println!(
+                "The circuits differ for {}",
+                format_values(&left.inputs, &counterexample.inputs)
+            );
+            println!(
+                "  {}: {}",
+                args.left.display(),
+                format_values(&left.outputs, &counterexample.left)
+            );
+            println!(
+                "  {}: {}",
+                args.right.display(),
+                format_values(&right.outputs, &counterexample.right)
+            );
+            Ok(false)
+        }
+    }
+}
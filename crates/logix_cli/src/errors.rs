@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LoadDesignError {
+    #[error("Unsupported file type: {0:?}. Expected a .lgx, .lgxb, .v or .blif file")]
+    UnsupportedFile(PathBuf),
+    #[error("Failed to build lgx file.\n{0}")]
+    Lgx(#[from] logix_lang::BuildError),
+    #[error("Failed to read netlist.\n{0}")]
+    ReadNetlist(#[from] std::io::Error),
+    #[error("Failed to build netlist.\n{0}")]
+    Netlist(#[from] logix_lang::NetlistError),
+    #[error("Failed to open board.\n{0}")]
+    OpenBoard(#[from] logix_board::errors::OpenBoardError),
+    #[error("Failed to build board.\n{0}")]
+    BuildBoard(#[from] logix_board::errors::BoardBuildError),
+    #[error("Failed to flatten component")]
+    Flatten(#[from] logix_sim::errors::FlattenComponentError),
+}
+
+#[derive(Debug, Error)]
+pub enum StimulusError {
+    #[error("Failed to read stimulus file.\n{0}")]
+    Read(#[from] std::io::Error),
+    #[error("Line {0}: invalid time {1:?}")]
+    InvalidTime(usize, String),
+    #[error("Line {0}: expected `name=value`, found {1:?}")]
+    InvalidAssignment(usize, String),
+    #[error("Line {0}: invalid value {1:?}")]
+    InvalidValue(usize, String),
+    #[error("Line {0}: time goes backwards")]
+    UnorderedTime(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum RunError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("Invalid stimulus.\n{0}")]
+    Stimulus(#[from] StimulusError),
+    #[error("Unknown input: {0}")]
+    UnknownInput(String),
+    #[error("Cannot run for a number of cycles: the circuit has no clock")]
+    NoClock,
+    #[error("Simulation failed.\n{0}")]
+    Simulation(#[from] logix_sim::errors::HeadlessSimError),
+    #[error("Failed to write output.\n{0}")]
+    WriteOutput(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum TableError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("{0}")]
+    TruthTable(#[from] logix_sim::errors::TruthTableError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum TestError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("Testbench {0:?} does not reference a design")]
+    MissingDesign(PathBuf),
+    #[error("{0}")]
+    Testbench(#[from] logix_sim::errors::TestbenchError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("{0}")]
+    Verilog(#[from] logix_sim::errors::VerilogError),
+    #[error("Failed to export board.\n{0}")]
+    BoardVerilog(#[from] logix_board::errors::ExportVerilogError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("Invalid stimulus.\n{0}")]
+    Stimulus(#[from] StimulusError),
+    #[error("Unknown input: {0}")]
+    UnknownInput(String),
+    #[error("Simulation failed.\n{0}")]
+    Simulation(#[from] logix_sim::errors::HeadlessSimError),
+    #[error("Failed to read the simulated values.\n{0}")]
+    Values(#[from] logix_sim::errors::DataRequestError),
+    #[error("Cannot draw {0:?}. Only boards (.lgxb files) can be drawn")]
+    NotABoard(PathBuf),
+    #[error("Unsupported image {0:?}. Expected a .svg or .png file")]
+    UnsupportedImage(std::path::PathBuf),
+    #[error("{0}")]
+    Png(#[from] logix_board::errors::RenderPngError),
+    #[error("Failed to write image.\n{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum EquivError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("{0}")]
+    Equivalence(#[from] logix_sim::errors::EquivalenceError),
+}
+
+#[derive(Debug, Error)]
+pub enum SynthError {
+    #[error("{0}")]
+    LoadDesign(#[from] LoadDesignError),
+    #[error("{0}")]
+    Synth(#[from] logix_lang::SynthError),
+    #[error("{0}")]
+    TruthTable(#[from] logix_sim::errors::TruthTableError),
+    #[error("Failed to write board.\n{0}")]
+    Board(#[from] logix_board::errors::ImportNetlistError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum FsmError {
+    #[error("Failed to read state machine.\n{0}")]
+    Read(#[from] std::io::Error),
+    #[error("{0}")]
+    Fsm(#[from] logix_lang::FsmError),
+    #[error("Failed to write board.\n{0}")]
+    Board(#[from] logix_board::errors::ImportNetlistError),
+}
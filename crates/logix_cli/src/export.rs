@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use logix_board::Board;
+
+use crate::{
+    design::{load_component, Design},
+    errors::{ExportError, LoadDesignError},
+};
+
+/// Converts a circuit to other formats.
+#[derive(Debug, clap::Args)]
+pub struct ExportArgs {
+    /// Circuit to convert (`.lgx`, `.lgxb`, `.v` or `.blif`)
+    file: PathBuf,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Verilog)]
+    format: ExportFormat,
+    /// File to write the result to instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Structural Verilog, a module per composite component
+    Verilog,
+    /// Graphviz graph of the flattened circuit
+    Dot,
+    /// JSON netlist of the flattened circuit
+    Json,
+}
+
+pub fn export(args: ExportArgs) -> Result<(), ExportError> {
+    let text = match args.format {
+        ExportFormat::Verilog => to_verilog(&args)?,
+        ExportFormat::Dot => Design::load(&args.file)?.comp.to_dot(),
+        ExportFormat::Json => Design::load(&args.file)?.comp.to_netlist().to_json() + "\n",
+    };
+    match &args.output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn to_verilog(args: &ExportArgs) -> Result<String, ExportError> {
+    Ok(match args.file.extension().and_then(|ext| ext.to_str()) {
+        Some("lgxb") => Board::open(&args.file)
+            .map_err(LoadDesignError::from)?
+            .to_verilog()?,
+        _ => logix_sim::verilog::to_verilog(&load_component(&args.file)?)?,
+    })
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use logix_lang::Logic;
+
+use crate::{design::Design, errors::SynthError};
+
+/// Prints a minimized expression of each output bit of a combinational
+/// circuit.
+#[derive(Debug, clap::Args)]
+pub struct ExprArgs {
+    /// Circuit to read (`.lgx`, `.lgxb`, `.v` or `.blif`)
+    file: PathBuf,
+}
+
+pub fn expr(args: ExprArgs) -> Result<(), SynthError> {
+    let design = Design::load(&args.file)?;
+    let logic = Logic::from_circuit(design.comp, &design.inputs, &design.outputs)?;
+    for (name, expr) in logic.minimize() {
+        println!("{} = {}", name, expr);
+    }
+    Ok(())
+}
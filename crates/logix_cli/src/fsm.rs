@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use logix_board::Board;
+use logix_lang::{Fsm, FsmTarget, GateStyle};
+
+use crate::errors::FsmError;
+
+/// Builds a state machine description into boards: next state and output
+/// logic feeding a state register, or a single AsmHDL component.
+#[derive(Debug, clap::Args)]
+pub struct FsmArgs {
+    /// State machine description (`.fsm`)
+    file: PathBuf,
+    /// Build an AsmHDL component, also written as a `.asmhdl` file
+    #[arg(long)]
+    asm: bool,
+    /// Build the logic with nand gates only
+    #[arg(long, conflicts_with = "asm")]
+    nand: bool,
+    /// Directory to write the files to
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+}
+
+pub fn fsm(args: FsmArgs) -> Result<(), FsmError> {
+    let fsm = Fsm::parse(&std::fs::read_to_string(&args.file)?)?;
+    let target = match (args.asm, args.nand) {
+        (true, _) => FsmTarget::Asm,
+        (false, true) => FsmTarget::Gates(GateStyle::Nand),
+        (false, false) => FsmTarget::Gates(GateStyle::AndOrNot),
+    };
+    if args.asm {
+        let path = args.output.join(format!("{}.asmhdl", fsm.name));
+        std::fs::write(&path, fsm.to_asm().to_code())?;
+        println!("Wrote {}", path.display());
+    }
+    let path = Board::import_fsm(&fsm, target, &args.output)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
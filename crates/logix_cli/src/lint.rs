@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use crate::{design::Design, errors::LoadDesignError};
+
+/// Checks a circuit for combinational loops, unused outputs, unconnected
+/// inputs and undriven outputs.
+#[derive(Debug, clap::Args)]
+pub struct LintArgs {
+    /// Circuit to check (`.lgx`, `.lgxb`, `.v` or `.blif`)
+    file: PathBuf,
+}
+
+/// Returns whether the circuit has no warnings.
+pub fn lint(args: LintArgs) -> Result<bool, LoadDesignError> {
+    let design = Design::load(&args.file)?;
+    let lints = design.comp.lint();
+    for lint in &lints {
+        println!("warning: {}", lint);
+    }
+    println!("{} warnings", lints.len());
+    Ok(lints.is_empty())
+}
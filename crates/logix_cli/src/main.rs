@@ -0,0 +1,71 @@
+mod design;
+mod equiv;
+mod errors;
+mod export;
+mod expr;
+mod fsm;
+mod lint;
+mod render;
+mod run;
+mod stimulus;
+mod synth;
+mod table;
+mod test;
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+/// Runs circuits without the graphical interface.
+#[derive(Debug, Parser)]
+#[command(name = "logix", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    Run(run::RunArgs),
+    Table(table::TableArgs),
+    Test(test::TestArgs),
+    Export(export::ExportArgs),
+    Render(render::RenderArgs),
+    Lint(lint::LintArgs),
+    Equiv(equiv::EquivArgs),
+    Synth(synth::SynthArgs),
+    Expr(expr::ExprArgs),
+    Fsm(fsm::FsmArgs),
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let res = match Cli::parse().command {
+        Command::Run(args) => run::run(args).map(|()| true).map_err(|err| err.to_string()),
+        Command::Table(args) => table::table(args).map_err(|err| err.to_string()),
+        Command::Test(args) => test::test(args).map_err(|err| err.to_string()),
+        Command::Export(args) => export::export(args)
+            .map(|()| true)
+            .map_err(|err| err.to_string()),
+        Command::Render(args) => render::render(args)
+            .map(|()| true)
+            .map_err(|err| err.to_string()),
+        Command::Lint(args) => lint::lint(args).map_err(|err| err.to_string()),
+        Command::Equiv(args) => equiv::equiv(args).map_err(|err| err.to_string()),
+        Command::Synth(args) => synth::synth(args)
+            .map(|()| true)
+            .map_err(|err| err.to_string()),
+        Command::Expr(args) => expr::expr(args)
+            .map(|()| true)
+            .map_err(|err| err.to_string()),
+        Command::Fsm(args) => fsm::fsm(args).map(|()| true).map_err(|err| err.to_string()),
+    };
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use asmhdl::Data;
+use logix_board::Board;
+use logix_sim::HeadlessSimulator;
+
+use crate::{
+    design::Design,
+    errors::{LoadDesignError, RenderError},
+    run::parse_time,
+    stimulus,
+};
+
+/// Draws a board as an SVG or PNG image.
+#[derive(Debug, clap::Args)]
+pub struct RenderArgs {
+    /// Board to draw (`.lgxb`)
+    file: PathBuf,
+    /// Image to write (`.svg` or `.png`)
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Simulate the board for this time (e.g. `500ns`) and draw the values
+    /// of the signals at the end
+    #[arg(short, long, value_parser = parse_time)]
+    time: Option<u128>,
+    /// File with the values to drive the inputs with while simulating
+    #[arg(short, long)]
+    stimulus: Option<PathBuf>,
+    /// Scale of PNG images
+    #[arg(long, default_value_t = 1.0)]
+    scale: f32,
+}
+
+pub fn render(args: RenderArgs) -> Result<(), RenderError> {
+    if args.file.extension().and_then(|ext| ext.to_str()) != Some("lgxb") {
+        return Err(RenderError::NotABoard(args.file));
+    }
+    let mut board = Board::open(&args.file).map_err(LoadDesignError::from)?;
+
+    let values = args.time.is_some() || args.stimulus.is_some();
+    if values {
+        simulate(&mut board, &args)?;
+    }
+
+    match args.output.extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => std::fs::write(&args.output, board.to_svg(values))?,
+        Some("png") => std::fs::write(&args.output, board.to_png(values, args.scale)?)?,
+        _ => return Err(RenderError::UnsupportedImage(args.output)),
+    }
+    Ok(())
+}
+
+/// Sets the values of the board components to the ones they have after
+/// simulating the board.
+fn simulate(board: &mut Board, args: &RenderArgs) -> Result<(), RenderError> {
+    let design = Design::from_board(board, &args.file)?;
+    let stimulus = args
+        .stimulus
+        .as_deref()
+        .map(stimulus::load)
+        .transpose()?
+        .unwrap_or_default();
+    let end = args
+        .time
+        .unwrap_or_else(|| stimulus.last().map_or(0, |event| event.time));
+
+    let mut sim = HeadlessSimulator::new(design.comp);
+    for event in stimulus.into_iter().take_while(|event| event.time <= end) {
+        sim.run_until(event.time)?;
+        for (name, value) in event.assigns {
+            let port = design
+                .inputs
+                .iter()
+                .find(|port| port.name == name)
+                .ok_or(RenderError::UnknownInput(name))?;
+            sim.set_input(port.id, Data::new(value, port.bits))?;
+        }
+    }
+    sim.run_until(end)?;
+    sim.settle()?;
+
+    board.load_values(sim.comp())?;
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use asmhdl::Data;
+use clap::ValueEnum;
+use logix_sim::{headless::Port, testbench, HeadlessSimulator};
+
+use crate::{design::Design, errors::RunError, stimulus};
+
+/// Simulates a circuit over time.
+#[derive(Debug, clap::Args)]
+pub struct RunArgs {
+    /// Circuit to simulate (`.lgx`, `.lgxb`, `.v` or `.blif`)
+    file: PathBuf,
+    /// File with the values to drive the inputs with over time
+    #[arg(short, long)]
+    stimulus: Option<PathBuf>,
+    /// Simulated time to run for (e.g. `500ns`, `2us`). Defaults to the time
+    /// of the last stimulus
+    #[arg(short, long, value_parser = parse_time, conflicts_with = "cycles")]
+    time: Option<u128>,
+    /// Number of cycles of the fastest clock to run for
+    #[arg(short, long)]
+    cycles: Option<u128>,
+    /// Print the outputs every time they change instead of only at the end
+    #[arg(long)]
+    trace: bool,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// File to write the results to instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Simplify the logic of the circuit before simulating it
+    #[arg(short = 'O', long)]
+    optimize: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    Text,
+    Csv,
+}
+
+pub(crate) fn parse_time(time: &str) -> Result<u128, String> {
+    testbench::parse_time(time).ok_or_else(|| format!("invalid time {time:?}"))
+}
+
+fn format_data(data: Data) -> String {
+    format!("{:0width$b}", data.value, width = data.size)
+}
+
+/// Writes the output values, either as a table row or as a line per output.
+struct Dump {
+    out: Box<dyn Write>,
+    format: Format,
+    trace: bool,
+}
+
+impl Dump {
+    fn header(&mut self, outputs: &[Port]) -> io::Result<()> {
+        if !self.trace {
+            return Ok(());
+        }
+        match self.format {
+            Format::Text => Ok(()),
+            Format::Csv => {
+                let names = outputs.iter().map(|port| port.name.as_str());
+                writeln!(
+                    self.out,
+                    "{}",
+                    std::iter::once("time")
+                        .chain(names)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )
+            }
+        }
+    }
+
+    fn row(&mut self, time: u128, values: &[(&str, Data)]) -> io::Result<()> {
+        match (self.format, self.trace) {
+            (Format::Text, true) => {
+                let values = values
+                    .iter()
+                    .map(|(name, data)| format!("{}={}", name, format_data(*data)))
+                    .collect::<Vec<_>>();
+                writeln!(self.out, "{:>10}ns  {}", time, values.join(" "))
+            }
+            (Format::Text, false) => values.iter().try_for_each(|(name, data)| {
+                writeln!(self.out, "{} = {}", name, format_data(*data))
+            }),
+            (Format::Csv, true) => {
+                let values = values.iter().map(|(_, data)| format_data(*data));
+                writeln!(
+                    self.out,
+                    "{}",
+                    std::iter::once(time.to_string())
+                        .chain(values)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )
+            }
+            (Format::Csv, false) => {
+                writeln!(self.out, "name,value")?;
+                values.iter().try_for_each(|(name, data)| {
+                    writeln!(self.out, "{},{}", name, format_data(*data))
+                })
+            }
+        }
+    }
+}
+
+pub fn run(args: RunArgs) -> Result<(), RunError> {
+    let mut design = Design::load(&args.file)?;
+    if args.optimize {
+        design.comp.optimize();
+    }
+    let stimulus = args
+        .stimulus
+        .as_deref()
+        .map(stimulus::load)
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut sim = HeadlessSimulator::new(design.comp);
+    let end = match (args.time, args.cycles) {
+        (Some(time), _) => time,
+        (None, Some(cycles)) => sim.clock().ok_or(RunError::NoClock)?.cycle_end(cycles),
+        (None, None) => stimulus.last().map_or(0, |event| event.time),
+    };
+
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut dump = Dump {
+        out,
+        format: args.format,
+        trace: args.trace,
+    };
+    dump.header(&design.outputs)?;
+
+    let mut events = stimulus.into_iter().peekable();
+    let mut last_values = None;
+    loop {
+        while let Some(event) = events.next_if(|event| event.time <= sim.time()) {
+            for (name, value) in event.assigns {
+                let port = design
+                    .inputs
+                    .iter()
+                    .find(|port| port.name == name)
+                    .ok_or(RunError::UnknownInput(name))?;
+                sim.set_input(port.id, Data::new(value, port.bits))?;
+            }
+        }
+        sim.settle()?;
+
+        let values = design
+            .outputs
+            .iter()
+            .map(|port| Ok((port.name.as_str(), sim.output(port.id)?)))
+            .collect::<Result<Vec<_>, RunError>>()?;
+        if args.trace && last_values.as_ref() != Some(&values) {
+            dump.row(sim.time(), &values)?;
+        }
+        last_values = Some(values);
+
+        if sim.time() >= end {
+            break;
+        }
+        let limit = events.peek().map_or(end, |event| event.time.min(end));
+        sim.step_until(limit)?;
+    }
+
+    if !args.trace {
+        dump.row(sim.time(), &last_values.unwrap_or_default())?;
+    }
+    dump.out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Stimulus files drive the inputs of a design over time.
+//!
+//! Each line holds a time followed by the assignments to apply at that time.
+//! Times are in nanoseconds unless a unit (`ns`, `us`, `ms`, `s`) is given and
+//! must not decrease. Values can be written in decimal, `0x` hex or `0b`
+//! binary. Everything after a `#` is a comment.
+//!
+//! ```text
+//! # time  inputs
+//! 0       a=0 b=0
+//! 20ns    a=1
+//! 1.5us   b=0b1010
+//! ```
+
+use std::path::Path;
+
+use logix_sim::testbench::{parse_time, parse_value};
+
+use crate::errors::StimulusError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StimulusEvent {
+    /// Time in nanoseconds.
+    pub time: u128,
+    pub assigns: Vec<(String, usize)>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<StimulusEvent>, StimulusError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+pub fn parse(src: &str) -> Result<Vec<StimulusEvent>, StimulusError> {
+    let mut events: Vec<StimulusEvent> = vec![];
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let Some(time) = words.next() else {
+            continue;
+        };
+        let time =
+            parse_time(time).ok_or_else(|| StimulusError::InvalidTime(line_no, time.into()))?;
+        if events.last().is_some_and(|last| last.time > time) {
+            return Err(StimulusError::UnorderedTime(line_no));
+        }
+
+        let assigns = words
+            .map(|word| {
+                let (name, value) = word
+                    .split_once('=')
+                    .filter(|(name, _)| !name.is_empty())
+                    .ok_or_else(|| StimulusError::InvalidAssignment(line_no, word.into()))?;
+                let value = parse_value(value)
+                    .ok_or_else(|| StimulusError::InvalidValue(line_no, value.into()))?;
+                Ok::<_, StimulusError>((name.to_string(), value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        events.push(StimulusEvent { time, assigns });
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stimulus() {
+        let events = parse(
+            "# header\n\
+             0 a=0 b=0x3\n\
+             \n\
+             20ns a=1 # comment\n\
+             1.5us b=0b10\n",
+        )
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                StimulusEvent {
+                    time: 0,
+                    assigns: vec![("a".into(), 0), ("b".into(), 3)],
+                },
+                StimulusEvent {
+                    time: 20,
+                    assigns: vec![("a".into(), 1)],
+                },
+                StimulusEvent {
+                    time: 1500,
+                    assigns: vec![("b".into(), 2)],
+                },
+            ]
+        );
+
+        assert!(matches!(
+            parse("10 a=1\n5 a=0"),
+            Err(StimulusError::UnorderedTime(2))
+        ));
+        assert!(matches!(
+            parse("10ps a=1"),
+            Err(StimulusError::InvalidTime(1, _))
+        ));
+        assert!(matches!(
+            parse("0 a"),
+            Err(StimulusError::InvalidAssignment(1, _))
+        ));
+        assert!(matches!(
+            parse("0 a=0x"),
+            Err(StimulusError::InvalidValue(1, _))
+        ));
+    }
+}
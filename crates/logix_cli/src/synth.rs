@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use logix_board::Board;
+use logix_lang::{GateStyle, Logic};
+use logix_sim::TruthTable;
+
+use crate::errors::SynthError;
+
+/// Builds boolean equations or a truth table into a board of minimized
+/// logic.
+#[derive(Debug, clap::Args)]
+pub struct SynthArgs {
+    /// Equations like `f = (a & b) | !c`, separated by `;` or new lines
+    #[arg(
+        short,
+        long,
+        required_unless_present = "table",
+        conflicts_with = "table"
+    )]
+    equations: Option<String>,
+    /// Truth table (`.json` or CSV). Missing rows are don't cares
+    #[arg(short, long)]
+    table: Option<PathBuf>,
+    /// Number of input columns of a CSV table. By default every column but
+    /// the last one is an input
+    #[arg(long)]
+    inputs: Option<usize>,
+    /// Build the board with nand gates only
+    #[arg(long)]
+    nand: bool,
+    /// Name of the board
+    #[arg(short, long, default_value = "Main")]
+    name: String,
+    /// Directory to write the board to
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+}
+
+pub fn synth(args: SynthArgs) -> Result<(), SynthError> {
+    let logic = match (&args.equations, &args.table) {
+        (Some(equations), _) => Logic::from_equations(equations)?,
+        (None, Some(table)) => {
+            let src = std::fs::read_to_string(table)?;
+            let table = match table.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => TruthTable::from_json(&src)?,
+                _ => {
+                    let columns = src.lines().next().unwrap_or_default().split(',').count();
+                    let inputs = args.inputs.unwrap_or(columns.saturating_sub(1));
+                    TruthTable::from_csv(&src, inputs)?
+                }
+            };
+            Logic::from_truth_table(&table)?
+        }
+        (None, None) => unreachable!("Clap requires equations or a table"),
+    };
+
+    for (name, expr) in logic.minimize() {
+        println!("{} = {}", name, expr);
+    }
+    let style = if args.nand {
+        GateStyle::Nand
+    } else {
+        GateStyle::AndOrNot
+    };
+    let path = Board::import_logic(&logic, &args.name, style, &args.output)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use logix_sim::TruthTable;
+
+use crate::{design::Design, errors::TableError};
+
+/// Prints the truth table of a combinational circuit or checks it against an
+/// expected one.
+#[derive(Debug, clap::Args)]
+pub struct TableArgs {
+    /// Circuit to enumerate (`.lgx`, `.lgxb`, `.v` or `.blif`)
+    file: PathBuf,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = TableFormat::Text)]
+    format: TableFormat,
+    /// File to write the table to instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Expected table (`.json` or CSV). Only the mismatching rows are
+    /// reported
+    #[arg(short, long)]
+    expect: Option<PathBuf>,
+    /// Simplify the logic of the circuit before enumerating it
+    #[arg(short = 'O', long)]
+    optimize: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TableFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+/// Returns whether the circuit matches the expected table, if any.
+pub fn table(args: TableArgs) -> Result<bool, TableError> {
+    let mut design = Design::load(&args.file)?;
+    if args.optimize {
+        design.comp.optimize();
+    }
+    let table = TruthTable::generate(design.comp, &design.inputs, &design.outputs)?;
+
+    let text = match args.format {
+        TableFormat::Text => table.to_string(),
+        TableFormat::Csv => table.to_csv(),
+        TableFormat::Json => table.to_json(),
+    };
+    match &args.output {
+        Some(path) => std::fs::write(path, text)?,
+        None if args.expect.is_none() => print!("{}", text),
+        None => (),
+    }
+
+    let Some(expect) = &args.expect else {
+        return Ok(true);
+    };
+    let src = std::fs::read_to_string(expect)?;
+    let expected = match expect.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => TruthTable::from_json(&src)?,
+        _ => TruthTable::from_csv(&src, design.inputs.len())?,
+    };
+    let mismatches = table.compare(&expected)?;
+    for mismatch in &mismatches {
+        println!("{}", TruthTable::describe(&expected, mismatch));
+    }
+    println!(
+        "{} of {} rows match",
+        expected.rows.len() - mismatches.len(),
+        expected.rows.len()
+    );
+    Ok(mismatches.is_empty())
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use logix_sim::Testbench;
+
+use crate::{design::Design, errors::TestError};
+
+/// Runs `.lgxt` testbenches and reports the failed expectations.
+#[derive(Debug, clap::Args)]
+pub struct TestArgs {
+    /// Testbenches to run
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Circuit to test instead of the one referenced by the testbenches
+    #[arg(short, long)]
+    design: Option<PathBuf>,
+}
+
+/// Returns whether every testbench passed.
+pub fn test(args: TestArgs) -> Result<bool, TestError> {
+    let mut passed = true;
+    for file in &args.files {
+        let tb = Testbench::parse(&std::fs::read_to_string(file)?)?;
+        let path = match (&args.design, &tb.design) {
+            (Some(path), _) => path.clone(),
+            (None, Some(path)) => file.parent().unwrap_or(file).join(path),
+            (None, None) => return Err(TestError::MissingDesign(file.clone())),
+        };
+        let design = Design::load(&path)?;
+        let report = tb.run(design.comp, &design.inputs, &design.outputs)?;
+
+        for failure in &report.failures {
+            println!("{}: {}", file.display(), failure);
+        }
+        println!(
+            "{}: {} ({} of {} checks passed)",
+            file.display(),
+            if report.passed() { "ok" } else { "FAILED" },
+            report.checks - report.failures.len(),
+            report.checks
+        );
+        passed &= report.passed();
+    }
+    Ok(passed)
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::default::Default;
 
+use crate::errors::BuildComponentError;
+
 /// Represents a location of a port inside a Component.
 ///
 /// The first item is the index of the component in the sub-components vector.
@@ -39,13 +41,13 @@ impl Conn {
     /// # Arguments
     ///
     /// * `from_idx` - An integer representing the index of the sub-component
-    ///     where the connection starts.
+    ///   where the connection starts.
     /// * `from_port` - An integer representing the index of the output port
-    ///     where the connection starts.
+    ///   where the connection starts.
     /// * `to_idx` - An integer representing the index of the sub-component
-    ///     where the connection ends.
+    ///   where the connection ends.
     /// * `to_port` - An integer representing the index of the input port
-    ///     where the connection starts.
+    ///   where the connection starts.
     pub fn new(from_idx: usize, from_port: usize, to_idx: usize, to_port: usize) -> Conn {
         Conn {
             from: (from_idx, from_port),
@@ -54,6 +56,57 @@ impl Conn {
     }
 }
 
+/// Direction of a port.
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum PortDirection {
+    #[default]
+    Input,
+    Output,
+}
+
+/// Describes a port of a component.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortDesc {
+    /// Name of the port, unique among the ports of the same direction.
+    pub name: String,
+
+    /// Amount of bits of the port.
+    pub width: usize,
+
+    pub direction: PortDirection,
+
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl PortDesc {
+    /// Creates the descriptor of an input port.
+    pub fn input(name: impl Into<String>, width: usize) -> Self {
+        PortDesc {
+            name: name.into(),
+            width,
+            direction: PortDirection::Input,
+            description: None,
+        }
+    }
+
+    /// Creates the descriptor of an output port.
+    pub fn output(name: impl Into<String>, width: usize) -> Self {
+        PortDesc {
+            name: name.into(),
+            width,
+            direction: PortDirection::Output,
+            description: None,
+        }
+    }
+
+    /// Sets the description of the port.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
 /// Holds all the information of the sub-components of a component.
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct SubComponent<E: Default + Clone + Serialize> {
@@ -70,6 +123,25 @@ pub struct SubComponent<E: Default + Clone + Serialize> {
     pub out_addrs: Vec<PortAddr>,
 }
 
+impl<E: Default + Clone + Serialize> SubComponent<E> {
+    /// Returns the index of the sub-component with the given name, see
+    /// [`Component::instance_name`].
+    pub fn comp_idx(&self, name: &str) -> Option<usize> {
+        self.components
+            .iter()
+            .position(|c| c.instance_name() == Some(name))
+    }
+
+    /// Returns the address of a port given as `component.port`, using the
+    /// names of the sub-components and of their ports.
+    pub fn port_addr(&self, path: &str, direction: PortDirection) -> Option<PortAddr> {
+        let (comp_name, port_name) = path.rsplit_once('.')?;
+        let idx = self.comp_idx(comp_name)?;
+        let port = self.components[idx].port_idx(port_name, direction)?;
+        Some((idx, port))
+    }
+}
+
 /// Represents a component.
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct Component<E: Default + Clone + Serialize> {
@@ -79,12 +151,25 @@ pub struct Component<E: Default + Clone + Serialize> {
     /// Name of the component.
     pub name: Option<String>,
 
-    /// Input ports of the component.
+    /// Name of this use of the component inside the component that holds
+    /// it, like the instance names of a design.
+    #[serde(default)]
+    pub instance: Option<String>,
+
+    /// Amount of input ports of the component.
     pub inputs: usize,
 
-    /// Output ports of the component.
+    /// Amount of output ports of the component.
     pub outputs: usize,
 
+    /// Descriptors of the input ports, one per input.
+    #[serde(default)]
+    pub input_ports: Vec<PortDesc>,
+
+    /// Descriptors of the output ports, one per output.
+    #[serde(default)]
+    pub output_ports: Vec<PortDesc>,
+
     /// Option that holds the sub-component information.
     ///
     /// If None, then the component is consider a base component.
@@ -94,12 +179,51 @@ pub struct Component<E: Default + Clone + Serialize> {
     pub extra: E,
 }
 
+impl<E: Default + Clone + Serialize> Component<E> {
+    /// Returns the instance name of the component, or its name if it has no
+    /// instance name.
+    pub fn instance_name(&self) -> Option<&str> {
+        self.instance.as_deref().or(self.name.as_deref())
+    }
+
+    /// Returns the descriptors of the ports of the given direction.
+    pub fn ports(&self, direction: PortDirection) -> &[PortDesc] {
+        match direction {
+            PortDirection::Input => &self.input_ports,
+            PortDirection::Output => &self.output_ports,
+        }
+    }
+
+    /// Returns the index of the port with the given name and direction.
+    pub fn port_idx(&self, name: &str, direction: PortDirection) -> Option<usize> {
+        self.ports(direction).iter().position(|p| p.name == name)
+    }
+
+    /// Returns the descriptor of the port with the given name and direction.
+    pub fn port(&self, name: &str, direction: PortDirection) -> Option<&PortDesc> {
+        self.ports(direction).iter().find(|p| p.name == name)
+    }
+}
+
+/// Descriptors named `{prefix}{i}` of 1 bit ports.
+fn default_ports(count: usize, direction: PortDirection) -> Vec<PortDesc> {
+    (0..count)
+        .map(|i| match direction {
+            PortDirection::Input => PortDesc::input(format!("in{i}"), 1),
+            PortDirection::Output => PortDesc::output(format!("out{i}"), 1),
+        })
+        .collect()
+}
+
 #[derive(Default)]
 pub struct ComponentBuilder<E: Default + Clone + Serialize> {
     id: usize,
     name: Option<String>,
+    instance: Option<String>,
     inputs: usize,
     outputs: usize,
+    input_ports: Option<Vec<PortDesc>>,
+    output_ports: Option<Vec<PortDesc>>,
 
     sub_comps: Option<Vec<Component<E>>>,
     connections: Option<Vec<Conn>>,
@@ -114,8 +238,11 @@ impl<E: Default + Clone + Serialize> ComponentBuilder<E> {
         ComponentBuilder {
             id,
             name: None,
+            instance: None,
             inputs: 0,
             outputs: 0,
+            input_ports: None,
+            output_ports: None,
             sub_comps: None,
             connections: None,
             in_addrs: None,
@@ -134,6 +261,17 @@ impl<E: Default + Clone + Serialize> ComponentBuilder<E> {
         self
     }
 
+    /// Sets the instance name of the component.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance`: String that represents the name of this use of the
+    ///   component.
+    pub fn instance(mut self, instance: String) -> Self {
+        self.instance = Some(instance);
+        self
+    }
+
     /// Sets the amount of input ports.
     ///
     /// # Arguments
@@ -166,6 +304,44 @@ impl<E: Default + Clone + Serialize> ComponentBuilder<E> {
         self
     }
 
+    /// Sets the input ports, and their amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `ports`: Vector of [`PortDesc`] that describes each input port.
+    pub fn input_ports(mut self, ports: Vec<PortDesc>) -> Self {
+        self.inputs = ports.len();
+        self.input_ports = Some(
+            ports
+                .into_iter()
+                .map(|p| PortDesc {
+                    direction: PortDirection::Input,
+                    ..p
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Sets the output ports, and their amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `ports`: Vector of [`PortDesc`] that describes each output port.
+    pub fn output_ports(mut self, ports: Vec<PortDesc>) -> Self {
+        self.outputs = ports.len();
+        self.output_ports = Some(
+            ports
+                .into_iter()
+                .map(|p| PortDesc {
+                    direction: PortDirection::Output,
+                    ..p
+                })
+                .collect(),
+        );
+        self
+    }
+
     /// Sets the subcomponents.
     ///
     /// # Arguments
@@ -181,7 +357,7 @@ impl<E: Default + Clone + Serialize> ComponentBuilder<E> {
     /// # Arguments
     ///
     /// * `connections`: Vector of [`Conn`] that holds all the connections between
-    ///     the sub-components.
+    ///   the sub-components.
     pub fn connections(mut self, connections: Vec<Conn>) -> Self {
         self.connections = Some(connections);
         self
@@ -218,65 +394,241 @@ impl<E: Default + Clone + Serialize> ComponentBuilder<E> {
     }
 
     /// Builds the [`Component`].
-    pub fn build(self) -> Component<E> {
-        // Build dependency map
-        // let mut dep_map: Option<Vec<Vec<usize>>> = None;
-        // if let Some(sub_comps) = &self.sub_comps {
-        //     let mut map = vec![vec![]; sub_comps.len()];
-        //     if let Some(connections) = &self.connections {
-        //         for conn in connections {
-        //             map[idx_of(conn.to)].push(idx_of(conn.from));
-        //         }
-        //     }
-        //     dep_map = Some(map);
-        // }
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BuildComponentError`] if the connections or the port
+    /// addresses refer to non existing sub-components or ports, if an input
+    /// of a sub-component is driven more than once or if the addresses or
+    /// the port descriptors do not match the amount of input and output
+    /// ports.
+    ///
+    /// Ports without descriptors are named `in{i}` and `out{i}` and are 1 bit
+    /// wide.
+    pub fn build(self) -> Result<Component<E>, BuildComponentError> {
+        let id = self.id;
+        let input_ports = self
+            .input_ports
+            .unwrap_or_else(|| default_ports(self.inputs, PortDirection::Input));
+        if input_ports.len() != self.inputs {
+            return Err(BuildComponentError::PortDescriptorMismatch(
+                id,
+                PortDirection::Input,
+                input_ports.len(),
+                self.inputs,
+            ));
+        }
+        let output_ports = self
+            .output_ports
+            .unwrap_or_else(|| default_ports(self.outputs, PortDirection::Output));
+        if output_ports.len() != self.outputs {
+            return Err(BuildComponentError::PortDescriptorMismatch(
+                id,
+                PortDirection::Output,
+                output_ports.len(),
+                self.outputs,
+            ));
+        }
 
         let mut sub = None;
         if let Some(sub_comps) = self.sub_comps {
+            let input_count = |idx: usize| {
+                sub_comps
+                    .get(idx)
+                    .map(|c| c.inputs)
+                    .ok_or(BuildComponentError::InvalidComponentIndex(id, idx))
+            };
             let mut used_inputs: Vec<Vec<bool>> =
                 sub_comps.iter().map(|c| vec![false; c.inputs]).collect();
+            let mut drive = |to: PortAddr| {
+                if addr_of(to) >= input_count(idx_of(to))? {
+                    return Err(BuildComponentError::InvalidInputPort(id, to));
+                }
+                let used = &mut used_inputs[idx_of(to)][addr_of(to)];
+                if *used {
+                    return Err(BuildComponentError::MultipleDrivers(id, to));
+                }
+                *used = true;
+                Ok(())
+            };
 
             let connections = self.connections.unwrap_or_default();
             for conn in connections.iter() {
-                if used_inputs[idx_of(conn.to)][addr_of(conn.to)] {
-                    panic!(
-                        "[{0}] Input {1} of comp {2} has two entries (one from [{3}; {4}])",
-                        self.id,
-                        addr_of(conn.to),
-                        idx_of(conn.to),
-                        idx_of(conn.from),
-                        addr_of(conn.from)
-                    );
+                let from_comp = sub_comps.get(idx_of(conn.from)).ok_or(
+                    BuildComponentError::InvalidComponentIndex(id, idx_of(conn.from)),
+                )?;
+                if addr_of(conn.from) >= from_comp.outputs {
+                    return Err(BuildComponentError::InvalidOutputPort(id, conn.from));
                 }
-                used_inputs[idx_of(conn.to)][addr_of(conn.to)] = true;
+                drive(conn.to)?;
             }
 
             let in_addrs = self.in_addrs.unwrap_or_default();
-            for (in_idx, (comp_idx, addr)) in in_addrs.iter() {
-                if used_inputs[*comp_idx][*addr] {
-                    panic!(
-                        "[{0}] Input {1} of comp {2} has two entries (one from input {3})",
-                        self.id, addr, comp_idx, in_idx,
-                    );
+            for (in_idx, addr) in in_addrs.iter() {
+                if *in_idx >= self.inputs {
+                    return Err(BuildComponentError::InputCountMismatch(
+                        id,
+                        *in_idx,
+                        self.inputs,
+                    ));
+                }
+                drive(*addr)?;
+            }
+
+            let out_addrs = self.out_addrs.unwrap_or_default();
+            for (out_idx, addr) in out_addrs.iter().enumerate() {
+                if sub_comps
+                    .get(idx_of(*addr))
+                    .is_none_or(|c| addr_of(*addr) >= c.outputs)
+                {
+                    return Err(BuildComponentError::DanglingOutput(id, out_idx, *addr));
                 }
-                used_inputs[*comp_idx][*addr] = true;
+            }
+            if out_addrs.len() != self.outputs {
+                return Err(BuildComponentError::OutputCountMismatch(
+                    id,
+                    out_addrs.len(),
+                    self.outputs,
+                ));
             }
 
             sub = Some(SubComponent {
                 components: sub_comps,
                 connections,
                 in_addrs,
-                out_addrs: self.out_addrs.unwrap_or_default(),
+                out_addrs,
             });
         }
 
-        Component {
+        Ok(Component {
             id: self.id,
             name: self.name,
+            instance: self.instance,
             inputs: self.inputs,
             outputs: self.outputs,
+            input_ports,
+            output_ports,
             extra: self.extra,
             sub,
-        }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(id: usize) -> Component<()> {
+        ComponentBuilder::new(id).port_count(2, 1).build().unwrap()
+    }
+
+    /// Component with two gates, the second one fed by the first one.
+    fn builder() -> ComponentBuilder<()> {
+        ComponentBuilder::new(0)
+            .port_count(3, 1)
+            .sub_comps(vec![gate(1), gate(2)])
+            .connections(vec![Conn::new(0, 0, 1, 0)])
+            .in_addrs(vec![(0, (0, 0)), (1, (0, 1)), (2, (1, 1))])
+            .out_addrs(vec![(1, 0)])
+    }
+
+    #[test]
+    fn test_build() {
+        let comp = builder().build().unwrap();
+        assert_eq!(comp.sub.unwrap().components.len(), 2);
+    }
+
+    #[test]
+    fn test_named_ports() {
+        let comp = ComponentBuilder::<()>::new(0)
+            .name("Main".into())
+            .input_ports(vec![PortDesc::input("a", 2), PortDesc::input("b", 2)])
+            .output_ports(vec![PortDesc::output("q", 1).with_description("a == b")])
+            .sub_comps(vec![gate(1)])
+            .in_addrs(vec![(0, (0, 0)), (1, (0, 1))])
+            .out_addrs(vec![(0, 0)])
+            .build()
+            .unwrap();
+        assert_eq!(comp.inputs, 2);
+        assert_eq!(comp.port_idx("b", PortDirection::Input), Some(1));
+        assert_eq!(comp.port_idx("q", PortDirection::Input), None);
+        assert_eq!(
+            comp.port("q", PortDirection::Output).unwrap().description,
+            Some("a == b".into())
+        );
+
+        // Ports without descriptors get default ones
+        let gate = gate(1);
+        assert_eq!(gate.input_ports[1], PortDesc::input("in1", 1));
+        let sub = ComponentBuilder::new(0)
+            .sub_comps(vec![
+                ComponentBuilder::<()>::new(1)
+                    .name("and".into())
+                    .port_count(2, 1)
+                    .build()
+                    .unwrap(),
+                ComponentBuilder::<()>::new(2)
+                    .name("Or".into())
+                    .instance("or1".into())
+                    .port_count(2, 1)
+                    .build()
+                    .unwrap(),
+            ])
+            .build()
+            .unwrap()
+            .sub
+            .unwrap();
+        assert_eq!(sub.port_addr("and.in1", PortDirection::Input), Some((0, 1)));
+        assert_eq!(
+            sub.port_addr("and.out0", PortDirection::Output),
+            Some((0, 0))
+        );
+        assert_eq!(sub.port_addr("or.in0", PortDirection::Input), None);
+        // Instance names take the place of names
+        assert_eq!(sub.comp_idx("or1"), Some(1));
+        assert_eq!(sub.comp_idx("Or"), None);
+
+        let err = ComponentBuilder::<()>::new(0)
+            .input_ports(vec![PortDesc::input("a", 1)])
+            .in_count(2)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            BuildComponentError::PortDescriptorMismatch(0, PortDirection::Input, 1, 2)
+        );
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let err = |builder: ComponentBuilder<()>| builder.build().unwrap_err();
+        assert_eq!(
+            err(builder().connections(vec![Conn::new(2, 0, 1, 0)])),
+            BuildComponentError::InvalidComponentIndex(0, 2)
+        );
+        assert_eq!(
+            err(builder().connections(vec![Conn::new(0, 1, 1, 0)])),
+            BuildComponentError::InvalidOutputPort(0, (0, 1))
+        );
+        assert_eq!(
+            err(builder().connections(vec![Conn::new(0, 0, 1, 2)])),
+            BuildComponentError::InvalidInputPort(0, (1, 2))
+        );
+        assert_eq!(
+            err(builder().connections(vec![Conn::new(0, 0, 1, 1)])),
+            BuildComponentError::MultipleDrivers(0, (1, 1))
+        );
+        assert_eq!(
+            err(builder().in_addrs(vec![(3, (1, 1))])),
+            BuildComponentError::InputCountMismatch(0, 3, 3)
+        );
+        assert_eq!(
+            err(builder().out_addrs(vec![(1, 1)])),
+            BuildComponentError::DanglingOutput(0, 0, (1, 1))
+        );
+        assert_eq!(
+            err(builder().out_addrs(vec![(1, 0), (0, 0)])),
+            BuildComponentError::OutputCountMismatch(0, 2, 1)
+        );
     }
 }
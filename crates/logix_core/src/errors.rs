@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+use crate::component::{PortAddr, PortDirection};
+
+/// Structural errors found when building a [`Component`].
+///
+/// The first field of every variant is the id of the component being built.
+///
+/// [`Component`]: crate::component::Component
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BuildComponentError {
+    #[error("[{0}] Sub-component {1} does not exist")]
+    InvalidComponentIndex(usize, usize),
+
+    #[error("[{0}] Sub-component {} has no input {}", .1.0, .1.1)]
+    InvalidInputPort(usize, PortAddr),
+
+    #[error("[{0}] Sub-component {} has no output {}", .1.0, .1.1)]
+    InvalidOutputPort(usize, PortAddr),
+
+    #[error("[{0}] Input {} of sub-component {} has more than one driver", .1.1, .1.0)]
+    MultipleDrivers(usize, PortAddr),
+
+    #[error("[{0}] Output {1} is mapped to a non existing port {2:?}")]
+    DanglingOutput(usize, usize, PortAddr),
+
+    #[error("[{0}] Input {1} is mapped but the component has {2} inputs")]
+    InputCountMismatch(usize, usize, usize),
+
+    #[error("[{0}] {1} outputs are mapped but the component has {2} outputs")]
+    OutputCountMismatch(usize, usize, usize),
+
+    #[error("[{0}] {2} {1:?} ports are described but the component has {3}")]
+    PortDescriptorMismatch(usize, PortDirection, usize, usize),
+}
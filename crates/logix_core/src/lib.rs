@@ -1,5 +1,9 @@
 pub mod component;
+pub mod errors;
+pub mod traversal;
 
 pub mod prelude {
     pub use crate::component::*;
+    pub use crate::errors::*;
+    pub use crate::traversal::*;
 }
@@ -0,0 +1,393 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use crate::component::{Component, PortAddr, SubComponent};
+
+/// Location of a component inside a hierarchy.
+///
+/// Holds the indices of the sub-components to go through, starting at the
+/// root component. The root component has an empty path.
+pub type CompPath = Vec<usize>;
+
+/// Iterator over a component and all its nested sub-components, visited in
+/// depth-first pre-order.
+///
+/// Created with [`Component::iter_dfs`].
+pub struct DepthFirst<'a, E: Default + Clone + Serialize> {
+    stack: Vec<(CompPath, &'a Component<E>)>,
+}
+
+impl<'a, E: Default + Clone + Serialize> Iterator for DepthFirst<'a, E> {
+    type Item = (CompPath, &'a Component<E>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, comp) = self.stack.pop()?;
+        if let Some(sub) = &comp.sub {
+            for (idx, child) in sub.components.iter().enumerate().rev() {
+                self.stack.push((child_path(&path, idx), child));
+            }
+        }
+        Some((path, comp))
+    }
+}
+
+/// Iterator over a component and all its nested sub-components, visited
+/// level by level.
+///
+/// Created with [`Component::iter_bfs`].
+pub struct BreadthFirst<'a, E: Default + Clone + Serialize> {
+    queue: VecDeque<(CompPath, &'a Component<E>)>,
+}
+
+impl<'a, E: Default + Clone + Serialize> Iterator for BreadthFirst<'a, E> {
+    type Item = (CompPath, &'a Component<E>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, comp) = self.queue.pop_front()?;
+        if let Some(sub) = &comp.sub {
+            for (idx, child) in sub.components.iter().enumerate() {
+                self.queue.push_back((child_path(&path, idx), child));
+            }
+        }
+        Some((path, comp))
+    }
+}
+
+fn child_path(path: &[usize], idx: usize) -> CompPath {
+    let mut path = path.to_vec();
+    path.push(idx);
+    path
+}
+
+/// Visits a component hierarchy in depth-first order with mutable access.
+///
+/// Used with [`Component::visit_mut`].
+pub trait VisitorMut<E: Default + Clone + Serialize> {
+    /// Called before the sub-components of `comp` are visited. Returning
+    /// `false` skips them.
+    fn enter(&mut self, _path: &[usize], _comp: &mut Component<E>) -> bool {
+        true
+    }
+
+    /// Called after the sub-components of `comp` are visited.
+    fn leave(&mut self, _path: &[usize], _comp: &mut Component<E>) {}
+}
+
+/// One end of a connection inside a component.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// Port of the component that holds the sub-components.
+    Parent(usize),
+
+    /// Port of a sub-component.
+    Sub(PortAddr),
+}
+
+impl<E: Default + Clone + Serialize> Component<E> {
+    /// Iterates over the component and its nested sub-components in
+    /// depth-first pre-order, together with their paths.
+    pub fn iter_dfs(&self) -> DepthFirst<'_, E> {
+        DepthFirst {
+            stack: vec![(vec![], self)],
+        }
+    }
+
+    /// Iterates over the component and its nested sub-components level by
+    /// level, together with their paths.
+    pub fn iter_bfs(&self) -> BreadthFirst<'_, E> {
+        BreadthFirst {
+            queue: VecDeque::from([(vec![], self)]),
+        }
+    }
+
+    /// Returns the nested sub-component at the given path.
+    pub fn get(&self, path: &[usize]) -> Option<&Component<E>> {
+        path.iter()
+            .try_fold(self, |comp, idx| comp.sub.as_ref()?.components.get(*idx))
+    }
+
+    /// Returns the nested sub-component at the given path.
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Component<E>> {
+        path.iter().try_fold(self, |comp, idx| {
+            comp.sub.as_mut()?.components.get_mut(*idx)
+        })
+    }
+
+    /// Returns the path of the first component with the given id, and the
+    /// component itself.
+    pub fn find_by_id(&self, id: usize) -> Option<(CompPath, &Component<E>)> {
+        self.iter_dfs().find(|(_, comp)| comp.id == id)
+    }
+
+    /// Returns the path of the nested sub-component with the given name path.
+    ///
+    /// A name path holds the names of the sub-components to go through (see
+    /// [`Component::instance_name`]) separated by `/`, e.g. `adder/fa0/xor`.
+    /// Components without a name are written as `#` followed by their index.
+    pub fn resolve(&self, name_path: &str) -> Option<CompPath> {
+        let mut comp = self;
+        let mut path = vec![];
+        for segment in name_path.split('/').filter(|s| !s.is_empty()) {
+            let sub = comp.sub.as_ref()?;
+            let idx = match segment.strip_prefix('#') {
+                Some(idx) => idx.parse().ok().filter(|i| *i < sub.components.len())?,
+                None => sub.comp_idx(segment)?,
+            };
+            comp = &sub.components[idx];
+            path.push(idx);
+        }
+        Some(path)
+    }
+
+    /// Returns the nested sub-component with the given name path.
+    ///
+    /// See [`Component::resolve`] for the format of name paths.
+    pub fn get_by_name(&self, name_path: &str) -> Option<&Component<E>> {
+        self.get(&self.resolve(name_path)?)
+    }
+
+    /// Returns the name path of the nested sub-component at the given path.
+    ///
+    /// See [`Component::resolve`] for the format of name paths.
+    pub fn name_path(&self, path: &[usize]) -> Option<String> {
+        let mut comp = self;
+        let mut names = vec![];
+        for idx in path {
+            comp = comp.sub.as_ref()?.components.get(*idx)?;
+            names.push(
+                comp.instance_name()
+                    .map_or_else(|| format!("#{idx}"), ToString::to_string),
+            );
+        }
+        Some(names.join("/"))
+    }
+
+    /// Visits the component and its nested sub-components in depth-first
+    /// order.
+    pub fn visit_mut<V: VisitorMut<E>>(&mut self, visitor: &mut V) {
+        self.visit_mut_at(&mut vec![], visitor);
+    }
+
+    fn visit_mut_at<V: VisitorMut<E>>(&mut self, path: &mut CompPath, visitor: &mut V) {
+        if visitor.enter(path, self) {
+            if let Some(sub) = &mut self.sub {
+                for (idx, child) in sub.components.iter_mut().enumerate() {
+                    path.push(idx);
+                    child.visit_mut_at(path, visitor);
+                    path.pop();
+                }
+            }
+        }
+        visitor.leave(path, self);
+    }
+
+    /// Calls `f` on the component and its nested sub-components in
+    /// depth-first pre-order.
+    pub fn for_each_mut<F: FnMut(&[usize], &mut Component<E>)>(&mut self, f: F) {
+        struct ForEach<F>(F);
+
+        impl<E: Default + Clone + Serialize, F: FnMut(&[usize], &mut Component<E>)> VisitorMut<E>
+            for ForEach<F>
+        {
+            fn enter(&mut self, path: &[usize], comp: &mut Component<E>) -> bool {
+                (self.0)(path, comp);
+                true
+            }
+        }
+
+        self.visit_mut(&mut ForEach(f));
+    }
+}
+
+impl<E: Default + Clone + Serialize> SubComponent<E> {
+    /// Returns the ports that drive the given port.
+    ///
+    /// `to` is either an input port of a sub-component or an output port of
+    /// the parent component. The returned ports are either output ports of
+    /// sub-components or input ports of the parent component.
+    pub fn fan_in(&self, to: Endpoint) -> Vec<Endpoint> {
+        match to {
+            Endpoint::Parent(out_idx) => self
+                .out_addrs
+                .get(out_idx)
+                .map(|addr| Endpoint::Sub(*addr))
+                .into_iter()
+                .collect(),
+            Endpoint::Sub(addr) => self
+                .in_addrs
+                .iter()
+                .filter(|(_, to)| *to == addr)
+                .map(|(in_idx, _)| Endpoint::Parent(*in_idx))
+                .chain(
+                    self.connections
+                        .iter()
+                        .filter(|conn| conn.to == addr)
+                        .map(|conn| Endpoint::Sub(conn.from)),
+                )
+                .collect(),
+        }
+    }
+
+    /// Returns the ports driven by the given port.
+    ///
+    /// `from` is either an output port of a sub-component or an input port
+    /// of the parent component. The returned ports are either input ports of
+    /// sub-components or output ports of the parent component.
+    pub fn fan_out(&self, from: Endpoint) -> Vec<Endpoint> {
+        match from {
+            Endpoint::Parent(in_idx) => self
+                .in_addrs
+                .iter()
+                .filter(|(idx, _)| *idx == in_idx)
+                .map(|(_, to)| Endpoint::Sub(*to))
+                .collect(),
+            Endpoint::Sub(addr) => self
+                .connections
+                .iter()
+                .filter(|conn| conn.from == addr)
+                .map(|conn| Endpoint::Sub(conn.to))
+                .chain(
+                    self.out_addrs
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, from)| **from == addr)
+                        .map(|(out_idx, _)| Endpoint::Parent(out_idx)),
+                )
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{ComponentBuilder, Conn};
+
+    fn gate(id: usize, name: &str) -> Component<()> {
+        ComponentBuilder::new(id)
+            .name(name.into())
+            .port_count(2, 1)
+            .build()
+            .unwrap()
+    }
+
+    /// Main (0)
+    /// ├── half (1)
+    /// │   ├── xor (2)
+    /// │   └── and (3)
+    /// └── or (4)
+    fn hierarchy() -> Component<()> {
+        let half = ComponentBuilder::new(1)
+            .name("half".into())
+            .port_count(2, 2)
+            .sub_comps(vec![gate(2, "xor"), gate(3, "and")])
+            .in_addrs(vec![(0, (0, 0)), (0, (1, 0)), (1, (0, 1)), (1, (1, 1))])
+            .out_addrs(vec![(0, 0), (1, 0)])
+            .build()
+            .unwrap();
+        ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(3, 2)
+            .sub_comps(vec![half, gate(4, "or")])
+            .connections(vec![Conn::new(0, 1, 1, 0)])
+            .in_addrs(vec![(0, (0, 0)), (1, (0, 1)), (2, (1, 1))])
+            .out_addrs(vec![(0, 0), (1, 0)])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_iterators() {
+        let comp = hierarchy();
+        let dfs: Vec<_> = comp.iter_dfs().map(|(path, c)| (path, c.id)).collect();
+        assert_eq!(
+            dfs,
+            vec![
+                (vec![], 0),
+                (vec![0], 1),
+                (vec![0, 0], 2),
+                (vec![0, 1], 3),
+                (vec![1], 4)
+            ]
+        );
+        let bfs: Vec<_> = comp.iter_bfs().map(|(_, c)| c.id).collect();
+        assert_eq!(bfs, vec![0, 1, 4, 2, 3]);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut comp = hierarchy();
+        assert_eq!(comp.get(&[0, 1]).unwrap().id, 3);
+        assert!(comp.get(&[1, 0]).is_none());
+        assert_eq!(comp.find_by_id(3).unwrap().0, vec![0, 1]);
+        assert!(comp.find_by_id(5).is_none());
+
+        assert_eq!(comp.resolve("half/and"), Some(vec![0, 1]));
+        assert_eq!(comp.resolve("#1"), Some(vec![1]));
+        assert_eq!(comp.resolve("half/or"), None);
+        assert_eq!(comp.get_by_name("or").unwrap().id, 4);
+        assert_eq!(comp.name_path(&[0, 0]).unwrap(), "half/xor");
+
+        comp.get_mut(&[0, 0]).unwrap().name = None;
+        assert_eq!(comp.name_path(&[0, 0]).unwrap(), "half/#0");
+        assert_eq!(comp.resolve("half/#0"), Some(vec![0, 0]));
+    }
+
+    #[test]
+    fn test_fan() {
+        let comp = hierarchy();
+        let sub = comp.sub.as_ref().unwrap();
+        assert_eq!(
+            sub.fan_in(Endpoint::Sub((1, 0))),
+            vec![Endpoint::Sub((0, 1))]
+        );
+        assert_eq!(sub.fan_in(Endpoint::Sub((1, 1))), vec![Endpoint::Parent(2)]);
+        assert_eq!(
+            sub.fan_out(Endpoint::Sub((0, 1))),
+            vec![Endpoint::Sub((1, 0))]
+        );
+        assert_eq!(
+            sub.fan_out(Endpoint::Sub((0, 0))),
+            vec![Endpoint::Parent(0)]
+        );
+
+        let half = comp.get(&[0]).unwrap().sub.as_ref().unwrap();
+        assert_eq!(
+            half.fan_out(Endpoint::Parent(1)),
+            vec![Endpoint::Sub((0, 1)), Endpoint::Sub((1, 1))]
+        );
+        assert_eq!(
+            half.fan_in(Endpoint::Parent(1)),
+            vec![Endpoint::Sub((1, 0))]
+        );
+    }
+
+    #[test]
+    fn test_visitors() {
+        struct Depth {
+            current: usize,
+            max: usize,
+        }
+
+        impl VisitorMut<()> for Depth {
+            fn enter(&mut self, _path: &[usize], comp: &mut Component<()>) -> bool {
+                self.current += 1;
+                self.max = self.max.max(self.current);
+                comp.name.as_deref() != Some("half")
+            }
+
+            fn leave(&mut self, _path: &[usize], _comp: &mut Component<()>) {
+                self.current -= 1;
+            }
+        }
+
+        let mut comp = hierarchy();
+        let mut depth = Depth { current: 0, max: 0 };
+        comp.visit_mut(&mut depth);
+        assert_eq!((depth.current, depth.max), (0, 2));
+
+        comp.for_each_mut(|path, c| c.id += 10 * path.len());
+        let ids: Vec<_> = comp.iter_dfs().map(|(_, c)| c.id).collect();
+        assert_eq!(ids, vec![0, 11, 22, 23, 14]);
+    }
+}
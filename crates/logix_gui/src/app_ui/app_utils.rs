@@ -46,6 +46,10 @@ impl LogixApp {
         self.toasts.error(err).set_closable(true);
     }
 
+    pub fn notify_warn(&mut self, msg: impl Into<String>) {
+        self.toasts.warning(msg).set_closable(true);
+    }
+
     pub fn notify_if_err<T, E>(&mut self, res: Result<T, E>) -> Option<T>
     where
         E: Display,
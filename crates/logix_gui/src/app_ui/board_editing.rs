@@ -1,13 +1,10 @@
-use super::{
-    board::{Board, CompSource, UserInteraction},
-    errors::SimulationError,
-    id_map::IdMap,
-};
+use super::errors::SimulationError;
 use egui::{emath::TSTransform, Pos2};
 use egui_notify::Toasts;
 use log::error;
+use logix_board::{Board, CompSource, IdMap, UserInteraction};
 use logix_core::component::PortAddr;
-use logix_sim::{flatten::FlattenComponent, Simulator};
+use logix_sim::{flatten::FlattenComponent, simulator::SpeedMode, stats::DesignStats, Simulator};
 use std::{path::PathBuf, time::Duration};
 
 #[derive(Default)]
@@ -23,15 +20,19 @@ pub struct BoardEditing {
     pub over_connection: Option<usize>,
 
     pub sim: Option<Simulator>,
+    pub sim_speed: SpeedMode,
     pub sim_ids: IdMap,
     pub sim_at: Option<(Vec<usize>, Board)>,
 
+    pub stats: Option<DesignStats>,
+
     pub toasts: Toasts,
 }
 
 impl BoardEditing {
     pub fn show(&mut self, ctx: &egui::Context) {
         self.draw_canvas(ctx);
+        self.stats_window(ctx);
         self.toasts.show(ctx);
     }
 
@@ -65,18 +66,42 @@ impl BoardEditing {
         }
 
         let flatten = FlattenComponent::new(comp)?;
-        self.sim = Some(Simulator::new(flatten));
-        self.sim.as_mut().unwrap().start(true);
+        let mut sim = Simulator::new(flatten);
+        sim.set_speed(self.sim_speed);
+        sim.start(true);
+        self.sim = Some(sim);
         self.sim_ids = sim_ids;
         Ok(())
     }
 
+    pub fn compute_stats(&mut self) -> Result<(), SimulationError> {
+        let mut initial_id = 0;
+        let (_, comp) = self
+            .board
+            .build_component(CompSource::Local(self.file.clone()), &mut initial_id)?;
+        self.stats = Some(FlattenComponent::new(comp)?.stats());
+        Ok(())
+    }
+
     pub fn pause_resume_sim(&mut self) {
         if let Some(sim) = self.sim.as_mut() {
             sim.pause_resume();
         }
     }
 
+    pub fn set_sim_speed(&mut self, speed: SpeedMode) {
+        self.sim_speed = speed;
+        if let Some(sim) = self.sim.as_mut() {
+            sim.set_speed(speed);
+        }
+    }
+
+    pub fn step_sim(&mut self) {
+        if let Some(sim) = self.sim.as_mut() {
+            sim.step();
+        }
+    }
+
     pub fn stop_sim(&mut self) {
         if let Some(sim) = self.sim.as_mut() {
             sim.stop();
@@ -159,64 +184,43 @@ impl BoardEditing {
     }
 
     pub fn update_comp_vals(&mut self) {
-        let Some(sim) = self.sim.as_mut() else {
+        let Some(sim) = self.sim.as_ref() else {
             return;
         };
-        let res: Result<(), SimulationError> = sim.state(|state| {
-            let (ids, board) = match self.sim_at.as_mut() {
-                Some((path, board)) => (Some(path), board),
-                None => (None, &mut self.board),
-            };
+        let snapshot = sim.snapshot();
+        let (ids, board) = match self.sim_at.as_mut() {
+            Some((path, board)) => (path.as_slice(), board),
+            None => (&[][..], &mut self.board),
+        };
+        let res: Result<(), SimulationError> =
             board.components.iter_mut().try_for_each(|board_comp| {
-                if let Some(interactions) = board_comp.user_interaction.as_mut() {
-                    let prim_comp = state
-                        .comp
-                        .get_prim_comp(
-                            ids.as_ref().map_or(&[], |ids| ids.as_slice()),
-                            Some(board_comp.id),
-                        )
-                        .map_err(|e| SimulationError::RequestComponentData {
-                            comp_name: board_comp.info.name.clone(),
-                            comp_id: board_comp.id,
-                            err: logix_sim::errors::DataRequestError::InvalidComponentId(e),
-                        })?;
-                    for interaction in interactions.iter_mut() {
+                let request_err = |err| SimulationError::RequestComponentData {
+                    comp_name: board_comp.info.name.clone(),
+                    comp_id: board_comp.id,
+                    err,
+                };
+                if let Some(interactions) = board_comp.user_interaction.take() {
+                    for interaction in interactions {
                         match interaction {
                             UserInteraction::ChangeInput(port, data) => {
-                                prim_comp.inputs[*port] = *data;
+                                sim.set_input(ids, Some(board_comp.id), port, data)
                             }
                             UserInteraction::ChangeOutput(port, data) => {
-                                prim_comp.outputs[*port] = *data;
+                                sim.set_output(ids, Some(board_comp.id), port, data)
                             }
                         }
+                        .map_err(request_err)?;
                     }
-                    let id = prim_comp.id;
-                    {
-                        // Dropping the reference to prim_comp
-                        let _ = prim_comp;
-                    }
-                    let comp_idx = state.comp.id_to_idx[&id];
-                    state.to_upd.push(comp_idx);
-                    board_comp.user_interaction = None;
                     return Ok(());
                 }
 
-                let (input_datas, output_datas) = state
-                    .comp
-                    .get_status(
-                        ids.as_ref().map_or(&[], |ids| ids.as_slice()),
-                        Some(board_comp.id),
-                    )
-                    .map_err(|e| SimulationError::RequestComponentData {
-                        comp_name: board_comp.info.name.clone(),
-                        comp_id: board_comp.id,
-                        err: e,
-                    })?;
+                let (input_datas, output_datas) = snapshot
+                    .status(ids, Some(board_comp.id))
+                    .map_err(request_err)?;
                 board_comp.inputs_data = input_datas;
                 board_comp.outputs_data = output_datas;
                 Ok(())
-            })
-        });
+            });
 
         if let Err(err) = res {
             error!(
@@ -1,7 +1,7 @@
 use asmhdl::AsmComponent;
 use indexmap::IndexMap;
 
-use crate::app_ui::board::ComponentInfo;
+use logix_board::ComponentInfo;
 
 use super::{flip_flops::flip_flops_lib, gates::gates_lib, plexers::plexers_lib};
 
@@ -1,4 +1,4 @@
-use crate::app_ui::board::ComponentInfo;
+use logix_board::ComponentInfo;
 
 use super::Library;
 
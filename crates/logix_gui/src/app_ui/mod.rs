@@ -2,11 +2,9 @@ mod app_config;
 mod app_data;
 mod app_state;
 mod app_utils;
-mod board;
 mod board_editing;
 mod errors;
 mod folder_tree;
-mod id_map;
 mod pages;
 mod shortcuts;
 
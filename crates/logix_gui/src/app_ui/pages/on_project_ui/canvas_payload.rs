@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crate::app_ui::board::ComponentInfo;
+use logix_board::ComponentInfo;
 
 #[derive(Debug)]
 pub enum CanvasPayload {
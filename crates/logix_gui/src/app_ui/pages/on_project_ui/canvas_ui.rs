@@ -1,9 +1,9 @@
 use std::path::Path;
 
 use egui::{emath::TSTransform, epaint::PathShape, Color32, Id, Rect, Response, Shape, Stroke, Ui};
+use logix_board::BoardComponent;
 
 use crate::app_ui::{
-    board::BoardComponent,
     board_editing::BoardEditing,
     pages::on_project_ui::{canvas_payload::CanvasPayload, wire_dir::WireDir},
 };
@@ -1,8 +1,8 @@
 use egui::{emath::TSTransform, Color32, Pos2, Rect, Response, Sense, Ui, Vec2};
+use logix_board::{BoardAction, CompSource, UserInteraction};
 use logix_sim::primitives::primitive::Primitive;
 
 use crate::app_ui::{
-    board::{BoardAction, CompSource, UserInteraction},
     board_editing::BoardEditing,
     pages::on_project_ui::{
         constants::{COMP_FONT_SIZE, PIN_SIZE},
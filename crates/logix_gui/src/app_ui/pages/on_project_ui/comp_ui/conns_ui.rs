@@ -1,8 +1,8 @@
 use egui::{epaint::PathShape, Color32, Pos2, Rect, Sense, Shape, Stroke};
+use logix_board::BoardAction;
 use logix_core::component::Conn;
 
 use crate::app_ui::{
-    board::BoardAction,
     board_editing::BoardEditing,
     pages::on_project_ui::{
         constants::{HIGH_COLOR, LOW_COLOR},
@@ -1,12 +1,10 @@
 use std::ops::RangeInclusive;
 
 use egui::Ui;
+use logix_board::{BoardComponent, ComponentInfo};
 use logix_sim::primitives::primitive::Primitive;
 
-use crate::app_ui::{
-    board::{BoardComponent, ComponentInfo},
-    board_editing::BoardEditing,
-};
+use crate::app_ui::board_editing::BoardEditing;
 
 impl BoardEditing {
     fn comp_slider(ui: &mut Ui, text: &str, curr_val: usize, mut on_value: impl FnMut(usize)) {
@@ -50,6 +48,26 @@ impl BoardEditing {
         );
     }
 
+    fn clock_time_drag(ui: &mut Ui, text: &str, value: &mut u128) {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::DragValue::from_get_set(|val| {
+                    #[allow(clippy::cast_possible_truncation)]
+                    #[allow(clippy::cast_sign_loss)]
+                    if let Some(v) = val {
+                        *value = v as u128;
+                        return v;
+                    }
+                    #[allow(clippy::cast_precision_loss)]
+                    return *value as f64;
+                })
+                .range(0.0..=1e12)
+                .suffix("ns"),
+            );
+            ui.label(text);
+        });
+    }
+
     #[allow(clippy::too_many_lines)]
     pub fn specific_comp_context_menu(&mut self, ui: &mut Ui, idx: usize) {
         let comp = self.board.components.get_mut(idx).unwrap();
@@ -124,22 +142,30 @@ impl BoardEditing {
                         comp.update_comp_info(ComponentInfo::joiner(v));
                     });
                 }
-                Primitive::Clock { period: current_p } => {
+                Primitive::Clock(clock) => {
+                    let mut config = *clock;
                     ui.add(
                         egui::Slider::from_get_set(1e-6..=1e9, |val| {
                             #[allow(clippy::cast_possible_truncation)]
                             #[allow(clippy::cast_sign_loss)]
                             if let Some(v) = val {
                                 let val_to_ns = 1_000_000_000.0 / v;
-                                *current_p = val_to_ns as u128;
+                                config.period = (val_to_ns as u128).max(1);
                                 return v;
                             }
                             #[allow(clippy::cast_precision_loss)]
-                            return 1_000_000_000.0 / *current_p as f64;
+                            return 1_000_000_000.0 / config.period as f64;
                         })
                         .logarithmic(true)
                         .text("Frec (Hz)"),
                     );
+                    ui.add(egui::Slider::new(&mut config.duty, 1..=99).text("Duty (%)"));
+                    Self::clock_time_drag(ui, "Phase", &mut config.phase);
+                    Self::clock_time_drag(ui, "Delay", &mut config.delay);
+                    ui.checkbox(&mut config.enable, "Enable input");
+                    if config != *clock {
+                        comp.update_comp_info(ComponentInfo::clock(config));
+                    }
                 }
 
                 Primitive::Input { bits: _ } => {
@@ -1,11 +1,11 @@
 use std::path::PathBuf;
 
 use egui::{CollapsingHeader, Color32, Sense, Ui, Vec2};
+use logix_board::IdMap;
 
 use crate::app_ui::{
     app_state::{AppState, LeftPannelState},
     folder_tree::Folder,
-    id_map::IdMap,
     logix_app::LogixApp,
 };
 
@@ -53,7 +53,8 @@ impl LogixApp {
                 .as_ref()
                 .map_or(&[], |(path, _)| path.as_slice()),
         );
-        let path = self.board_editing_mut().sim_ids.board_tree(
+        let path = board_tree(
+            &mut self.board_editing_mut().sim_ids,
             ui,
             &mut selected_path,
             current_path.as_slice(),
@@ -132,55 +133,52 @@ impl LogixApp {
     }
 }
 
-impl IdMap {
-    pub fn board_tree(
-        &mut self,
-        ui: &mut Ui,
-        slected_path: &mut Vec<usize>,
-        current_path: &[usize],
-    ) -> Option<Vec<usize>> {
-        let id = ui.id().with(("board_tree", self.id));
-
-        let mut to_return = None;
-        slected_path.push(self.id);
-        let in_path = !current_path.is_empty() && self.id == current_path[0];
-        let text =
-            egui::RichText::new(self.name.clone()).color(if in_path && current_path.len() == 1 {
-                Color32::LIGHT_GREEN
-            } else {
-                Color32::WHITE
-            });
-        let header = CollapsingHeader::new(text)
-            .id_source(id)
-            .default_open(in_path)
-            .open(if in_path && current_path.len() > 1 {
-                Some(true)
-            } else {
-                None
-            })
-            .show(ui, |ui| {
-                for sub in self
-                    .sub_ids
-                    .iter_mut()
-                    .filter(|sub| sub.source.local().is_some())
+fn board_tree(
+    map: &mut IdMap,
+    ui: &mut Ui,
+    slected_path: &mut Vec<usize>,
+    current_path: &[usize],
+) -> Option<Vec<usize>> {
+    let id = ui.id().with(("board_tree", map.id));
+
+    let mut to_return = None;
+    slected_path.push(map.id);
+    let in_path = !current_path.is_empty() && map.id == current_path[0];
+    let text = egui::RichText::new(map.name.clone()).color(if in_path && current_path.len() == 1 {
+        Color32::LIGHT_GREEN
+    } else {
+        Color32::WHITE
+    });
+    let header = CollapsingHeader::new(text)
+        .id_source(id)
+        .default_open(in_path)
+        .open(if in_path && current_path.len() > 1 {
+            Some(true)
+        } else {
+            None
+        })
+        .show(ui, |ui| {
+            for sub in map
+                .sub_ids
+                .iter_mut()
+                .filter(|sub| sub.source.local().is_some())
+            {
+                let next_current_path = current_path.get(1..).unwrap_or(&[]);
+                if let Some(new_selected_path) =
+                    board_tree(sub, ui, slected_path, next_current_path)
                 {
-                    let next_current_path = current_path.get(1..).unwrap_or(&[]);
-                    if let Some(new_selected_path) =
-                        sub.board_tree(ui, slected_path, next_current_path)
-                    {
-                        to_return = Some(new_selected_path);
-                    }
+                    to_return = Some(new_selected_path);
                 }
-            })
-            .header_response;
-
-        if header.clicked() {
-            to_return = Some(slected_path.clone());
-        }
+            }
+        })
+        .header_response;
 
-        slected_path.pop();
-        to_return
+    if header.clicked() {
+        to_return = Some(slected_path.clone());
     }
+
+    slected_path.pop();
+    to_return
 }
 
 impl Folder {
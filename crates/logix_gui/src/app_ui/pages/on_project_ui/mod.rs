@@ -6,6 +6,7 @@ mod empty_ui;
 mod left_panel_ui;
 mod library_ui;
 mod on_project_utils;
+mod stats_ui;
 mod status_bar_ui;
 mod tabs_ui;
 mod top_panel_ui;
@@ -2,13 +2,13 @@ use std::path::PathBuf;
 
 use egui::KeyboardShortcut;
 use log::error;
+use logix_board::{errors::OpenBoardError, Board};
+use logix_lang::{Fsm, FsmTarget, GateStyle};
 use rfd::FileDialog;
 
 use crate::app_ui::{
     app_state::{AppState, LeftPannelState},
-    board::Board,
     board_editing::BoardEditing,
-    errors::OpenBoardError,
     logix_app::LogixApp,
     shortcuts::shortcut_string,
 };
@@ -137,6 +137,93 @@ impl LogixApp {
         self.notify_if_err(res);
     }
 
+    pub fn import_lgx(&mut self) {
+        let file = FileDialog::new()
+            .set_directory(self.folder.current_path.clone())
+            .add_filter("Logix Circuit", &["lgx"]);
+        if let Some(lgx_file) = file.pick_file() {
+            let res = Board::import_lgx(&lgx_file, &self.folder.current_path);
+            if let Some(main_board) = self.notify_if_err(res) {
+                if self.load_board(&main_board).is_ok() {
+                    self.selected_file = Some(main_board);
+                }
+            }
+        }
+    }
+
+    pub fn import_netlist(&mut self) {
+        let file = FileDialog::new()
+            .set_directory(self.folder.current_path.clone())
+            .add_filter("Verilog/BLIF netlist", &["v", "blif"]);
+        if let Some(netlist_file) = file.pick_file() {
+            let res = Board::import_netlist(&netlist_file, &self.folder.current_path);
+            if let Some(main_board) = self.notify_if_err(res) {
+                if self.load_board(&main_board).is_ok() {
+                    self.selected_file = Some(main_board);
+                }
+            }
+        }
+    }
+
+    pub fn import_fsm(&mut self) {
+        let file = FileDialog::new()
+            .set_directory(self.folder.current_path.clone())
+            .add_filter("State machine", &["fsm"]);
+        if let Some(fsm_file) = file.pick_file() {
+            let res = std::fs::read_to_string(&fsm_file)
+                .map_err(|err| err.to_string())
+                .and_then(|code| Fsm::parse(&code).map_err(|err| err.to_string()))
+                .and_then(|fsm| {
+                    let target = FsmTarget::Gates(GateStyle::AndOrNot);
+                    Board::import_fsm(&fsm, target, &self.folder.current_path)
+                        .map_err(|err| err.to_string())
+                });
+            if let Some(main_board) = self.notify_if_err(res) {
+                if self.load_board(&main_board).is_ok() {
+                    self.selected_file = Some(main_board);
+                }
+            }
+        }
+    }
+
+    pub fn import_logisim(&mut self) {
+        let file = FileDialog::new()
+            .set_directory(self.folder.current_path.clone())
+            .add_filter("Logisim Circuit", &["circ"]);
+        if let Some(circ_file) = file.pick_file() {
+            let res = Board::import_logisim(&circ_file, &self.folder.current_path);
+            if let Some((main_board, unsupported)) = self.notify_if_err(res) {
+                if !unsupported.is_empty() {
+                    self.notify_warn(format!(
+                        "Some elements were not imported:\n{}",
+                        unsupported.join("\n")
+                    ));
+                }
+                if self.load_board(&main_board).is_ok() {
+                    self.selected_file = Some(main_board);
+                }
+            }
+        }
+    }
+
+    pub fn export_current_board_lgx(&mut self) {
+        let file = FileDialog::new()
+            .set_directory(self.folder.current_path.clone())
+            .set_file_name(
+                self.board_editing()
+                    .file
+                    .with_extension("lgx")
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy(),
+            )
+            .add_filter("Logix Circuit", &["lgx"]);
+        if let Some(lgx_file) = file.save_file() {
+            let res = self.board_editing().board.export_lgx(&lgx_file);
+            self.notify_if_err(res);
+        }
+    }
+
     pub fn save_current_board_as(&mut self) {
         let file = FileDialog::new()
             .set_directory(self.folder.current_path.clone())
@@ -169,6 +256,11 @@ impl LogixApp {
         }
     }
 
+    pub fn show_current_stats(&mut self) {
+        let res = self.board_editing_mut().compute_stats();
+        self.notify_if_err(res);
+    }
+
     pub fn pause_resume_current_sim(&mut self) {
         self.board_editing_mut().pause_resume_sim();
     }
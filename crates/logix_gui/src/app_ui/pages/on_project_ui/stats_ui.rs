@@ -0,0 +1,59 @@
+use crate::app_ui::board_editing::BoardEditing;
+
+impl BoardEditing {
+    pub fn stats_window(&mut self, ctx: &egui::Context) {
+        let Some(stats) = &self.stats else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Statistics")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("stats_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Primitives");
+                        ui.label(stats.primitives.values().sum::<usize>().to_string());
+                        ui.end_row();
+                        for (kind, count) in &stats.primitives {
+                            ui.label(format!("    {kind}"));
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                        ui.label("Transistors (est.)");
+                        ui.label(stats.transistors.to_string());
+                        ui.end_row();
+                        ui.label("Max fan-out");
+                        ui.label(stats.max_fan_out.to_string());
+                        ui.end_row();
+                        ui.label("Logic depth");
+                        ui.label(stats.logic_depth.to_string());
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.label("Primitives by hierarchy level");
+                egui::Grid::new("stats_levels_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (level, counts) in stats.levels.iter().enumerate() {
+                            let counts: Vec<String> = counts
+                                .iter()
+                                .map(|(kind, count)| format!("{kind}: {count}"))
+                                .collect();
+                            ui.label(level.to_string());
+                            ui.label(counts.join(", "));
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if !open {
+            self.stats = None;
+        }
+    }
+}
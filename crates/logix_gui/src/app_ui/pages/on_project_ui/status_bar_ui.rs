@@ -1,4 +1,5 @@
 use egui::Color32;
+use logix_sim::simulator::SpeedMode;
 
 use crate::app_ui::logix_app::LogixApp;
 
@@ -7,13 +8,14 @@ impl LogixApp {
         self.exist_active_board() && self.board_tabs[self.current_tab].sim.is_some()
     }
 
-    pub fn status_bar(&self, ctx: &egui::Context) {
+    pub fn status_bar(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::bottom("status_bar")
             .exact_height(20.0)
             .show(ctx, |ui| {
                 ui.horizontal_centered(|ui| {
                     if self.is_sim_running() {
-                        ui.label(egui::RichText::new("Running").color(Color32::LIGHT_GREEN));
+                        self.sim_status(ui);
+                        ui.separator();
                     }
 
                     if self.render_time > 0.0 {
@@ -22,4 +24,77 @@ impl LogixApp {
                 });
             });
     }
+
+    fn sim_status(&mut self, ui: &mut egui::Ui) {
+        let Some(stats) = self
+            .board_editing()
+            .sim
+            .as_ref()
+            .map(|s| s.snapshot().stats)
+        else {
+            return;
+        };
+        if stats.running {
+            ui.label(egui::RichText::new("Running").color(Color32::LIGHT_GREEN));
+        } else {
+            ui.label(egui::RichText::new("Paused").color(Color32::YELLOW));
+        }
+        ui.separator();
+
+        let mut speed = self.board_editing().sim_speed;
+        egui::ComboBox::from_id_source("sim_speed")
+            .selected_text(speed_name(speed))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut speed, SpeedMode::RealTime, "Real time");
+                if ui
+                    .selectable_label(matches!(speed, SpeedMode::Slower(_)), "Slower")
+                    .clicked()
+                    && !matches!(speed, SpeedMode::Slower(_))
+                {
+                    speed = SpeedMode::Slower(10);
+                }
+                ui.selectable_value(
+                    &mut speed,
+                    SpeedMode::AsFastAsPossible,
+                    speed_name(SpeedMode::AsFastAsPossible),
+                );
+                ui.selectable_value(
+                    &mut speed,
+                    SpeedMode::ClockStep,
+                    speed_name(SpeedMode::ClockStep),
+                );
+            });
+        if let SpeedMode::Slower(factor) = &mut speed {
+            ui.add(egui::DragValue::new(factor).range(2..=u32::MAX).prefix("x"));
+        }
+        if speed == SpeedMode::ClockStep && ui.button("Step").clicked() {
+            self.board_editing_mut().step_sim();
+        }
+        if speed != self.board_editing().sim_speed {
+            self.board_editing_mut().set_sim_speed(speed);
+        }
+        ui.separator();
+
+        ui.label(format!("Time: {}", format_time(stats.time)));
+        ui.label(format!("Events/s: {:.0}", stats.events_per_sec));
+    }
+}
+
+const fn speed_name(speed: SpeedMode) -> &'static str {
+    match speed {
+        SpeedMode::RealTime => "Real time",
+        SpeedMode::Slower(_) => "Slower",
+        SpeedMode::AsFastAsPossible => "As fast as possible",
+        SpeedMode::ClockStep => "Step per clock edge",
+    }
+}
+
+/// Formats a simulation time given in nanoseconds with the largest unit
+/// that keeps it above one.
+fn format_time(ns: u128) -> String {
+    let units = [(1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us")];
+    units.iter().find(|(scale, _)| ns >= *scale).map_or_else(
+        || format!("{ns}ns"),
+        |(scale, unit)| format!("{}.{:03}{unit}", ns / scale, ns % scale * 1000 / scale),
+    )
 }
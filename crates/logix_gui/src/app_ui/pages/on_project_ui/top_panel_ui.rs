@@ -59,6 +59,33 @@ impl LogixApp {
             ui.close_menu();
         }
         ui.separator();
+        if ui.button("Import .lgx").clicked() {
+            self.import_lgx();
+            ui.close_menu();
+        }
+        if ui.button("Import Verilog/BLIF").clicked() {
+            self.import_netlist();
+            ui.close_menu();
+        }
+        if ui.button("Import state machine").clicked() {
+            self.import_fsm();
+            ui.close_menu();
+        }
+        if ui.button("Import Logisim .circ").clicked() {
+            self.import_logisim();
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(
+                self.exist_active_board(),
+                egui::Button::new("Export board as .lgx"),
+            )
+            .clicked()
+        {
+            self.export_current_board_lgx();
+            ui.close_menu();
+        }
+        ui.separator();
         if ui.button("Exit").clicked() {
             std::process::exit(0);
         }
@@ -85,6 +112,15 @@ impl LogixApp {
                         ui.close_menu();
                     }
                 });
+                ui.menu_button("Analysis", |ui| {
+                    if ui
+                        .add_enabled(self.exist_active_board(), egui::Button::new("Statistics"))
+                        .clicked()
+                    {
+                        self.show_current_stats();
+                        ui.close_menu();
+                    }
+                });
             });
             ui.add_space(1.0);
         });
@@ -1,7 +1,6 @@
-use std::{
-    collections::HashMap,
-    fmt::{Display, Formatter},
-};
+use std::fmt::{Display, Formatter};
+
+use logix_sim::primitives::clock::ClockConfig;
 
 #[derive(Debug)]
 pub enum Primitive {
@@ -11,9 +10,12 @@ pub enum Primitive {
     Xor(usize),
     Nand(usize),
     Nor(usize),
-    Clock(f64),
+    Clock(ClockConfig),
     HighConst,
     LowConst,
+    /// Constant `value` of `width` bits.
+    Const(usize, usize),
+    Switch,
     Input(usize),
     Output(usize),
     Splitter(usize),
@@ -29,11 +31,12 @@ impl Display for Primitive {
 #[derive(Debug)]
 pub enum Comp {
     Primitive(Primitive),
-    Composite(String),
+    Composite(String, Vec<usize>),
 }
 
 impl Comp {
-    pub fn from_name(name: &str, ins_count: usize, clock_frec: f64) -> Self {
+    pub fn from_name(name: &str, args: &[usize], clock_period: u128) -> Self {
+        let ins_count = args.first().copied().unwrap_or(0);
         match name {
             "And" => Comp::Primitive(Primitive::And(ins_count)),
             "Or" => Comp::Primitive(Primitive::Or(ins_count)),
@@ -41,14 +44,19 @@ impl Comp {
             "Xor" => Comp::Primitive(Primitive::Xor(ins_count)),
             "Nand" => Comp::Primitive(Primitive::Nand(ins_count)),
             "Nor" => Comp::Primitive(Primitive::Nor(ins_count)),
-            "Clock" => Comp::Primitive(Primitive::Clock(clock_frec)),
+            "Clock" => Comp::Primitive(Primitive::Clock(ClockConfig::new(clock_period))),
             "High" => Comp::Primitive(Primitive::HighConst),
             "Low" => Comp::Primitive(Primitive::LowConst),
+            "Const" => Comp::Primitive(Primitive::Const(
+                ins_count,
+                args.get(1).copied().unwrap_or(1),
+            )),
+            "Switch" => Comp::Primitive(Primitive::Switch),
             "In" => Comp::Primitive(Primitive::Input(ins_count)),
             "Out" => Comp::Primitive(Primitive::Output(ins_count)),
             "Splitter" => Comp::Primitive(Primitive::Splitter(ins_count)),
             "Joiner" => Comp::Primitive(Primitive::Joiner(ins_count)),
-            _ => Comp::Composite(name.to_string()),
+            _ => Comp::Composite(name.to_string(), vec![]),
         }
     }
 
@@ -76,78 +84,346 @@ impl PinAddr {
     }
 }
 
+impl Display for PinAddr {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            // The first pin is the one used when no pin is given
+            PinAddr::ByIdx(comp, 0) => write!(f, "{comp}"),
+            PinAddr::ByIdx(comp, idx) => write!(f, "{comp}.{idx}"),
+            PinAddr::ByName(comp, pin) => write!(f, "{comp}.{pin}"),
+        }
+    }
+}
+
+/// Range of bits (or pins), both ends inclusive.
+///
+/// The range can be descending (`7..0`), in which case the items are listed
+/// from `from` down to `to`.
+#[derive(Debug, Clone, Copy)]
+pub struct BitRange {
+    pub from: usize,
+    pub to: usize,
+}
+
+impl BitRange {
+    pub fn new(from: usize, to: usize) -> Self {
+        BitRange { from, to }
+    }
+
+    pub fn single(idx: usize) -> Self {
+        BitRange { from: idx, to: idx }
+    }
+
+    pub fn len(&self) -> usize {
+        self.from.abs_diff(self.to) + 1
+    }
+
+    /// Indexes covered by the range in the order they were written.
+    pub fn indexes(&self) -> Vec<usize> {
+        if self.from <= self.to {
+            (self.from..=self.to).collect()
+        } else {
+            (self.to..=self.from).rev().collect()
+        }
+    }
+}
+
+impl Display for BitRange {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        if self.from == self.to {
+            write!(f, "{}", self.from)
+        } else {
+            write!(f, "{}..{}", self.from, self.to)
+        }
+    }
+}
+
+/// One side of a connection.
+#[derive(Debug, Clone)]
+pub enum Signal {
+    /// A single pin, optionally restricted to some of its bits (`a[0]`,
+    /// `a.res[0..3]`).
+    Pin(PinAddr, Option<BitRange>),
+
+    /// Several pins of the same component (`a.0..7`).
+    PinRange(String, BitRange),
+
+    /// Concatenation of signals (`{a, b.0..3}`).
+    ///
+    /// The first signal holds the least significant bits.
+    Concat(Vec<Signal>),
+}
+
 #[derive(Debug)]
 pub struct ConnDecl {
-    pub src: PinAddr,
-    pub dest: PinAddr,
+    pub src: Signal,
+    pub dest: Signal,
 }
 
-#[derive(Debug)]
-pub struct CompDecl {
+impl Display for Signal {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Signal::Pin(addr, None) => write!(f, "{addr}"),
+            Signal::Pin(addr, Some(bits)) => write!(f, "{addr}[{bits}]"),
+            Signal::PinRange(comp, pins) => write!(f, "{comp}.{pins}"),
+            Signal::Concat(items) => {
+                let items: Vec<String> = items.iter().map(ToString::to_string).collect();
+                write!(f, "{{{}}}", items.join(", "))
+            }
+        }
+    }
+}
+
+/// Binary operators of compile-time expressions.
+#[derive(Debug, Clone, Copy)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// Compile-time integer expression.
+///
+/// Expressions are evaluated when the component is built, once the values of
+/// its parameters and of the enclosing `for` variables are known.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Num(i128),
+    Var(String),
+    Op(Box<Expr>, BinOp, Box<Expr>),
+}
+
+impl BinOp {
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Sub => 0,
+            BinOp::Mul | BinOp::Div | BinOp::Mod => 1,
+        }
+    }
+}
+
+impl Display for BinOp {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let symbol = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+impl Expr {
+    pub fn op(lhs: Expr, op: BinOp, rhs: Expr) -> Self {
+        Expr::Op(Box::new(lhs), op, Box::new(rhs))
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Expr::Num(value) => write!(f, "{value}"),
+            Expr::Var(name) => write!(f, "{name}"),
+            Expr::Op(lhs, op, rhs) => {
+                // Operators are left associative, so the right operand needs
+                // parentheses also with the same precedence
+                let operand = |expr: &Expr, strict: bool| match expr {
+                    Expr::Op(_, inner, _)
+                        if inner.precedence() < op.precedence()
+                            || (strict && inner.precedence() == op.precedence()) =>
+                    {
+                        format!("({expr})")
+                    }
+                    _ => expr.to_string(),
+                };
+                write!(f, "{} {op} {}", operand(lhs, false), operand(rhs, true))
+            }
+        }
+    }
+}
+
+/// Range of expressions, both ends inclusive (`i`, `0..N-1`).
+#[derive(Debug, Clone)]
+pub struct RangeExpr {
+    pub from: Expr,
+    pub to: Option<Expr>,
+}
+
+impl RangeExpr {
+    pub fn single(expr: Expr) -> Self {
+        RangeExpr {
+            from: expr,
+            to: None,
+        }
+    }
+
+    pub fn new(from: Expr, to: Expr) -> Self {
+        RangeExpr { from, to: Some(to) }
+    }
+}
+
+/// Pin selected after the `.` of a signal.
+#[derive(Debug, Clone)]
+pub enum PinSel {
+    Name(String),
+    Idx(RangeExpr),
+}
+
+/// One side of a connection as written in the source.
+///
+/// It is turned into a [`Signal`] once the expressions are evaluated.
+#[derive(Debug, Clone)]
+pub enum SignalDecl {
+    /// `comp[i]...` optionally followed by `.pin[bits]`.
+    ///
+    /// When `comp` is an instance array the first index selects the
+    /// instance. Any other index selects bits.
+    Pin {
+        comp: String,
+        indexes: Vec<RangeExpr>,
+        pin: Option<PinSel>,
+        bits: Option<RangeExpr>,
+    },
+    Concat(Vec<SignalDecl>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnItemDecl {
+    pub src: SignalDecl,
+    pub dest: SignalDecl,
+}
+
+/// `for <var> in <from>..<to> ( ... )` header.
+///
+/// The range is inclusive and ascending: it is empty when `from > to`.
+#[derive(Debug, Clone)]
+pub struct ForDecl {
+    pub var: String,
+    pub from: Expr,
+    pub to: Expr,
+}
+
+/// Time or frequency literal (`10ns`, `1MHz`).
+///
+/// A number without unit is a frequency in hertz.
+#[derive(Debug, Clone)]
+pub struct TimeLit {
+    pub value: f64,
+    pub unit: Option<String>,
+}
+
+/// Component instantiation as written in the source (`And(N)`, `Adder<4>`).
+#[derive(Debug, Clone)]
+pub struct CompInst {
     pub name: String,
-    pub subc: HashMap<(String, usize), Comp>,
-    pub design: Vec<ConnDecl>,
+    pub params: Vec<Expr>,
+    pub args: Vec<Expr>,
+    pub time_arg: Option<TimeLit>,
+    /// Options following the time argument (`duty = 25`, `enable`).
+    pub options: Vec<(String, Option<TimeLit>)>,
+}
 
-    pub ins: Vec<usize>,
-    pub outs: Vec<usize>,
+#[derive(Debug, Clone)]
+pub enum SubcItem {
+    /// `name = Comp` or `name[i] = Comp` (instance array).
+    Inst(String, Option<Expr>, CompInst),
+    For(ForDecl, Vec<SubcItem>),
+}
 
-    pub in_idx_by_name: HashMap<String, usize>,
-    pub out_idx_by_name: HashMap<String, usize>,
+#[derive(Debug, Clone)]
+pub enum DesignItem {
+    Conn(Box<ConnItemDecl>),
+    For(ForDecl, Vec<DesignItem>),
+}
+
+#[derive(Debug)]
+pub struct CompDecl {
+    pub name: String,
+    pub params: Vec<String>,
+    pub subc: Vec<SubcItem>,
+    pub design: Vec<DesignItem>,
 }
 
 impl CompDecl {
-    pub fn new(name: String, subc: HashMap<(String, usize), Comp>, design: Vec<ConnDecl>) -> Self {
-        let mut ins = subc
-            .iter()
-            .filter(|((_, _), c)| c.is_input())
-            .collect::<Vec<_>>();
-
-        let mut outs = subc
-            .iter()
-            .filter(|((_, _), c)| c.is_output())
-            .collect::<Vec<_>>();
-
-        ins.sort_by(|((_, a), _), ((_, b), _)| a.cmp(b));
-        outs.sort_by(|((_, a), _), ((_, b), _)| a.cmp(b));
-
-        let in_idx_by_name: HashMap<String, usize> = ins
-            .iter()
-            .enumerate()
-            .map(|(i, ((n, _), _))| (n.clone(), i))
-            .collect();
-
-        let out_idx_by_name: HashMap<String, usize> = outs
-            .iter()
-            .enumerate()
-            .map(|(i, ((n, _), _))| (n.clone(), i))
-            .collect();
-
-        let ins = ins.iter().map(|((_, i), _)| *i).collect();
-        let outs = outs.iter().map(|((_, i), _)| *i).collect();
+    pub fn new(
+        name: String,
+        params: Vec<String>,
+        mut subc: Vec<SubcItem>,
+        ins_decl: Vec<(String, Expr)>,
+        outs_decl: Vec<(String, Expr)>,
+        design: Vec<DesignItem>,
+    ) -> Self {
+        // Pins declared in `ins`/`outs` are placed after the ones declared
+        // as sub-components
+        let io_inst = |name: &str, bits: Expr| CompInst {
+            name: name.to_string(),
+            params: vec![],
+            args: vec![bits],
+            time_arg: None,
+            options: vec![],
+        };
+        for (pin_name, bits) in ins_decl {
+            subc.push(SubcItem::Inst(pin_name, None, io_inst("In", bits)));
+        }
+        for (pin_name, bits) in outs_decl {
+            subc.push(SubcItem::Inst(pin_name, None, io_inst("Out", bits)));
+        }
 
         CompDecl {
             name,
+            params,
             subc,
             design,
-            ins,
-            outs,
-            in_idx_by_name,
-            out_idx_by_name,
         }
     }
 }
 
+#[derive(Debug)]
+pub enum Import {
+    /// `use path.to.file;` imports the components of a `.lgx` file.
+    Lgx(String),
+
+    /// `use asm path.to.file as Name;` imports an `.asmhdl` component under
+    /// the given name.
+    Asm(String, String),
+}
+
 #[derive(Debug)]
 pub struct Circuit {
-    pub imports: Option<Vec<String>>,
+    pub imports: Option<Vec<Import>>,
     pub comps: Vec<CompDecl>,
 }
 
+/// Parse error of a number literal that does not fit in a `usize`.
+pub fn too_large<T>(literal: &str) -> lalrpop_util::ParseError<usize, T, String> {
+    lalrpop_util::ParseError::User {
+        error: format!("Number {literal} is too large"),
+    }
+}
+
 pub mod prelude {
+    pub use super::BinOp;
+    pub use super::BitRange;
     pub use super::Circuit;
     pub use super::Comp;
     pub use super::CompDecl;
+    pub use super::CompInst;
     pub use super::ConnDecl;
+    pub use super::ConnItemDecl;
+    pub use super::DesignItem;
+    pub use super::Expr;
+    pub use super::ForDecl;
+    pub use super::Import;
     pub use super::PinAddr;
+    pub use super::PinSel;
     pub use super::Primitive;
+    pub use super::RangeExpr;
+    pub use super::Signal;
+    pub use super::SignalDecl;
+    pub use super::SubcItem;
+    pub use super::TimeLit;
 }
@@ -0,0 +1,311 @@
+//! Importer of combinational BLIF netlists.
+//!
+//! Supported commands are `.model`, `.inputs`, `.outputs`, `.names` (with
+//! their single output cover, turned into a sum of products) and `.subckt`.
+//! The first model is the top one.
+
+use std::collections::HashMap;
+
+use logix_core::component::Component;
+use logix_sim::primitives::primitive::ExtraInfo;
+
+use crate::netlist::{Cell, GateKind, InstancePorts, NetModule, Netlist, NetlistError, Signal};
+
+/// Builds the first model of a BLIF file.
+///
+/// Ports and subcircuits keep their names as instance names, as in
+/// [`crate::build_from_file`].
+pub fn build_from_blif(code: &str) -> Result<Component<ExtraInfo>, NetlistError> {
+    parse(code)?.build()
+}
+
+/// Lines without comments, joining the ones ending with `\`.
+fn logical_lines(code: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut lines: Vec<(usize, Vec<&str>)> = vec![];
+    let mut continues = false;
+    for (i, line) in code.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim_end();
+        let (line, next_continues) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let words = line.split_whitespace();
+        match lines.last_mut() {
+            Some((_, last)) if continues => last.extend(words),
+            _ => lines.push((i + 1, words.collect())),
+        }
+        continues = next_continues;
+    }
+    lines.retain(|(_, words)| !words.is_empty());
+    lines
+}
+
+fn parse(code: &str) -> Result<Netlist, NetlistError> {
+    let mut netlist = Netlist::default();
+    let mut module: Option<NetModule> = None;
+    let mut cover: Option<Cover> = None;
+    let mut temps = 0;
+
+    for (line, words) in logical_lines(code) {
+        if !words[0].starts_with('.') {
+            let cover = cover
+                .as_mut()
+                .ok_or_else(|| NetlistError::Syntax(line, "cover row outside .names".into()))?;
+            cover.add_row(line, &words)?;
+            continue;
+        }
+        if let (Some(done), Some(module)) = (cover.take(), module.as_mut()) {
+            done.lower(module, &mut temps);
+        }
+
+        match words[0] {
+            ".model" => {
+                if let Some(done) = module.take() {
+                    netlist.modules.push(done);
+                }
+                let name = words.get(1).copied().unwrap_or("top").to_string();
+                netlist.top.get_or_insert_with(|| name.clone());
+                module = Some(NetModule {
+                    name,
+                    ..Default::default()
+                });
+                temps = 0;
+            }
+            ".inputs" => current(&mut module, line, words[0])?
+                .inputs
+                .extend(words[1..].iter().map(|name| (name.to_string(), 1))),
+            ".outputs" => current(&mut module, line, words[0])?
+                .outputs
+                .extend(words[1..].iter().map(|name| (name.to_string(), 1))),
+            ".names" => {
+                current(&mut module, line, words[0])?;
+                let (output, inputs) = words[1..]
+                    .split_last()
+                    .ok_or_else(|| NetlistError::Syntax(line, ".names without signals".into()))?;
+                cover = Some(Cover {
+                    inputs: inputs.iter().map(|name| name.to_string()).collect(),
+                    output: output.to_string(),
+                    rows: vec![],
+                    on_set: true,
+                });
+            }
+            ".subckt" => {
+                let model = words
+                    .get(1)
+                    .ok_or_else(|| NetlistError::Syntax(line, ".subckt without model".into()))?;
+                let ports = words[2..]
+                    .iter()
+                    .map(|conn| {
+                        let (formal, actual) = conn.split_once('=').ok_or_else(|| {
+                            NetlistError::Syntax(
+                                line,
+                                format!("expected formal=actual, found {conn}"),
+                            )
+                        })?;
+                        Ok((formal.to_string(), vec![Signal::Net(actual.to_string(), 0)]))
+                    })
+                    .collect::<Result<Vec<_>, NetlistError>>()?;
+                let module = current(&mut module, line, words[0])?;
+                let name = format!("{}{}", model.to_lowercase(), module.cells.len());
+                module.cells.push(Cell::Instance {
+                    module: model.to_string(),
+                    name,
+                    ports: InstancePorts::Named(ports),
+                });
+            }
+            ".end" => {
+                if let Some(done) = module.take() {
+                    netlist.modules.push(done);
+                }
+            }
+            other => return Err(NetlistError::Unsupported(line, other.into())),
+        }
+    }
+    if let (Some(done), Some(module)) = (cover.take(), module.as_mut()) {
+        done.lower(module, &mut temps);
+    }
+    netlist.modules.extend(module);
+    Ok(netlist)
+}
+
+fn current<'a>(
+    module: &'a mut Option<NetModule>,
+    line: usize,
+    command: &str,
+) -> Result<&'a mut NetModule, NetlistError> {
+    module
+        .as_mut()
+        .ok_or_else(|| NetlistError::Syntax(line, format!("{command} outside .model")))
+}
+
+/// Single output cover of a `.names` command.
+struct Cover {
+    inputs: Vec<String>,
+    output: String,
+    /// Input literals of each row: `Some(true)` for `1`, `Some(false)` for
+    /// `0` and `None` for `-`.
+    rows: Vec<Vec<Option<bool>>>,
+    /// Whether the rows give the ones of the function (or the zeros).
+    on_set: bool,
+}
+
+impl Cover {
+    fn add_row(&mut self, line: usize, words: &[&str]) -> Result<(), NetlistError> {
+        let (literals, value) = match words {
+            [value] if self.inputs.is_empty() => ("", *value),
+            [literals, value] => (*literals, *value),
+            _ => return Err(NetlistError::Syntax(line, "invalid cover row".into())),
+        };
+        if literals.len() != self.inputs.len() {
+            return Err(NetlistError::WidthMismatch(
+                format!("cover row at line {line}"),
+                literals.len(),
+                self.inputs.len(),
+            ));
+        }
+        let row = literals
+            .chars()
+            .map(|c| match c {
+                '1' => Ok(Some(true)),
+                '0' => Ok(Some(false)),
+                '-' => Ok(None),
+                _ => Err(NetlistError::Syntax(line, format!("invalid literal {c:?}"))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.on_set = match value {
+            "1" => true,
+            "0" => false,
+            _ => {
+                return Err(NetlistError::Syntax(
+                    line,
+                    format!("invalid output {value:?}"),
+                ))
+            }
+        };
+        self.rows.push(row);
+        Ok(())
+    }
+
+    /// Writes the cover as a sum of products.
+    fn lower(self, module: &mut NetModule, temps: &mut usize) {
+        let mut gate = |kind: GateKind, inputs: Vec<Signal>| {
+            *temps += 1;
+            let output = Signal::Net(format!("${temps}"), 0);
+            module.cells.push(Cell::Gate {
+                kind,
+                name: None,
+                inputs,
+                output: output.clone(),
+            });
+            output
+        };
+
+        let mut negated: HashMap<usize, Signal> = HashMap::new();
+        let mut terms = vec![];
+        for row in &self.rows {
+            let mut literals = vec![];
+            for (i, literal) in row.iter().enumerate() {
+                let input = Signal::Net(self.inputs[i].clone(), 0);
+                match literal {
+                    Some(true) => literals.push(input),
+                    Some(false) => literals.push(
+                        negated
+                            .entry(i)
+                            .or_insert_with(|| gate(GateKind::Not, vec![input]))
+                            .clone(),
+                    ),
+                    None => (),
+                }
+            }
+            terms.push(match literals.len() {
+                0 => Signal::Const(true),
+                1 => literals.remove(0),
+                _ => gate(GateKind::And, literals),
+            });
+        }
+
+        let sum = match terms.len() {
+            0 => Signal::Const(false),
+            1 => terms.remove(0),
+            _ => gate(GateKind::Or, terms),
+        };
+        let value = match (self.on_set, sum) {
+            (true, sum) => sum,
+            (false, Signal::Const(value)) => Signal::Const(!value),
+            (false, sum) => gate(GateKind::Not, vec![sum]),
+        };
+        module
+            .cells
+            .push(Cell::Alias(Signal::Net(self.output, 0), value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asmhdl::Data;
+    use logix_sim::{flatten::FlattenComponent, headless::top_level_ports, HeadlessSimulator};
+
+    const ADDER: &str = "
+# Two bit adder
+.model adder2
+.inputs a0 a1 b0 b1
+.outputs s0 s1 \\
+    cout
+.names zero
+.subckt fa a=a0 b=b0 cin=zero s=s0 cout=c
+.subckt fa a=a1 b=b1 cin=c s=s1 cout=cout
+.end
+
+.model fa
+.inputs a b cin
+.outputs s cout
+.names a b cin s
+100 1
+010 1
+001 1
+111 1
+.names a b cin cout
+00- 0
+0-0 0
+-00 0
+.end
+";
+
+    #[test]
+    fn test_blif_adder() {
+        for (a, b) in [(0, 0), (1, 2), (3, 3), (2, 3)] {
+            let comp = build_from_blif(ADDER).unwrap();
+            assert_eq!(comp.name.as_deref(), Some("adder2"));
+            let sub = &comp.sub.as_ref().unwrap().components;
+            assert_eq!(sub.last().unwrap().instance.as_deref(), Some("fa2"));
+
+            let comp = FlattenComponent::new(comp).unwrap();
+            let (ins, outs) = top_level_ports(&comp);
+            let mut sim = HeadlessSimulator::new(comp);
+            for (i, port) in ins.iter().enumerate() {
+                let value = if i < 2 { a >> i } else { b >> (i - 2) };
+                sim.set_input(port.id, Data::new(value & 1, 1)).unwrap();
+            }
+            sim.settle().unwrap();
+            let sum = outs
+                .iter()
+                .enumerate()
+                .map(|(i, port)| sim.output(port.id).unwrap().value << i)
+                .sum::<usize>();
+            assert_eq!(sum, a + b);
+        }
+    }
+
+    #[test]
+    fn test_blif_errors() {
+        assert!(matches!(
+            build_from_blif(".model m\n.inputs a\n.outputs q\n.latch a q re clk 0\n.end"),
+            Err(NetlistError::Unsupported(4, _))
+        ));
+        assert!(matches!(
+            build_from_blif(".model m\n.inputs a b\n.outputs y\n.names a b y\n1 1\n.end"),
+            Err(NetlistError::WidthMismatch(..))
+        ));
+    }
+}
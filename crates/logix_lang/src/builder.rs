@@ -1,19 +1,31 @@
+use asmhdl::AsmComponent;
 use lalrpop_util::lalrpop_mod;
 use log::debug;
 use std::collections::HashMap;
 use std::path::Path;
 use thiserror::Error;
 
+use crate::elaborate::{elaborate, ElabComp};
 use crate::primitive_builders::{
-    and_gate, clock, high_const, input, joiner, low_const, nand_gate, nor_gate, not_gate, or_gate,
-    output, splitter, xor_gate,
+    and_gate, clock, const_value, custom, high_const, input, joiner, low_const, nand_gate,
+    nor_gate, not_gate, or_gate, output, splitter, switch, xor_gate,
 };
-use logix_core::component::{Component, ComponentBuilder, Conn, PortAddr};
+use logix_core::component::{Component, ComponentBuilder, Conn, PortAddr, PortDesc, PortDirection};
+use logix_core::errors::BuildComponentError;
 use logix_sim::primitives::primitive::ExtraInfo;
 
 use crate::ast::prelude::*;
 
-lalrpop_mod!(pub grammar);
+lalrpop_mod!(#[allow(clippy::type_complexity)] pub grammar);
+
+/// Components declared in `.lgx` files, by name.
+type CompMap = HashMap<String, Box<CompDecl>>;
+
+/// Components imported from `.asmhdl` files, by the name given in the import.
+type AsmMap = HashMap<String, AsmComponent>;
+
+/// Maximum depth of composite components nested inside one another.
+const MAX_NESTING: usize = 64;
 
 #[derive(Debug, Clone, Error)]
 pub enum BuildError {
@@ -32,6 +44,60 @@ pub enum BuildError {
     #[error("Output pin not found: {0}")]
     OutputPinNotFound(String),
 
+    #[error("Pin index {1} out of range in: {0}")]
+    PinIndexOutOfRange(String, usize),
+
+    #[error("Bit index {1} out of range in: {0}")]
+    BitIndexOutOfRange(String, usize),
+
+    #[error("Width mismatch in connection {0} ({1} bits to {2} bits)")]
+    WidthMismatch(String, usize, usize),
+
+    #[error("Component {0} expects {1} parameters, {2} given")]
+    ParamCountMismatch(String, usize, usize),
+
+    #[error("Undefined variable: {0}")]
+    UndefinedVariable(String),
+
+    #[error("Expression {0} evaluates to a negative value ({1})")]
+    NegativeValue(String, i128),
+
+    #[error("Division by zero")]
+    DivisionByZero,
+
+    #[error("Expression {0} overflows")]
+    Overflow(String),
+
+    #[error("Component {0} instantiates itself")]
+    RecursiveComponent(String),
+
+    #[error("Component {0} is nested more than {MAX_NESTING} levels deep")]
+    NestingTooDeep(String),
+
+    #[error("Invalid indexing of: {0}")]
+    InvalidIndexing(String),
+
+    #[error("Duplicated sub-component name: {0}")]
+    DuplicateName(String),
+
+    #[error("Inputs and outputs can not be generated: {0}")]
+    GeneratedPin(String),
+
+    #[error("Unknown time unit: {0}")]
+    UnknownUnit(String),
+
+    #[error("Invalid clock period: {0}ns")]
+    InvalidClockPeriod(f64),
+
+    #[error("Invalid option of {0}: {1}")]
+    InvalidOption(String, String),
+
+    #[error("Constant {0} does not fit in {1} bits")]
+    ConstOutOfRange(usize, usize),
+
+    #[error("Invalid component.\n{0}")]
+    InvalidComponent(#[from] BuildComponentError),
+
     #[error("Subcircuit module not found: {0}")]
     ImportError(String),
 
@@ -39,18 +105,22 @@ pub enum BuildError {
     ModuleSintaxError(String, String),
 }
 
-pub fn build_from_file(
-    main_path: &str,
-) -> Result<(Component<ExtraInfo>, HashMap<usize, String>), BuildError> {
+pub fn build_from_file(main_path: &str) -> Result<Component<ExtraInfo>, BuildError> {
     debug!("Building from file: {}", main_path);
-    let comp_map = get_comp_map(main_path.to_string())?;
+    let (comp_map, asm_map) = get_comp_map(main_path.to_string())?;
     let main = comp_map
         .get("Main")
         .ok_or(BuildError::NoMainComponentFound)?;
     let mut last_id: usize = 0;
-    let mut id_map: HashMap<usize, String> = HashMap::new();
-    let comp = comp_decl_to_comp(main, "main", &comp_map, &mut last_id, &mut id_map)?;
-    Ok((comp, id_map))
+    comp_decl_to_comp(
+        main,
+        "main",
+        &[],
+        &comp_map,
+        &asm_map,
+        &mut last_id,
+        &mut vec![],
+    )
 }
 
 fn get_loc(loc: usize, text: &str) -> (usize, usize) {
@@ -70,31 +140,37 @@ fn get_loc(loc: usize, text: &str) -> (usize, usize) {
     (line, col)
 }
 
-fn get_comp_map(lgx_path: String) -> Result<HashMap<String, Box<CompDecl>>, BuildError> {
-    debug!("Getting component map from: {}", lgx_path);
-
-    let text = std::fs::read_to_string(lgx_path.clone())
-        .map_err(|_| BuildError::ImportError(lgx_path.to_string()))?;
-
-    debug!("Parsing file: {}", lgx_path);
-    let circuit = grammar::CircuitParser::new().parse(&text).map_err(|e| {
+fn parse_circuit(text: &str, lgx_path: &str) -> Result<Circuit, BuildError> {
+    grammar::CircuitParser::new().parse(text).map_err(|e| {
         let (line, col) = match &e {
-            lalrpop_util::ParseError::InvalidToken { location } => get_loc(*location, &text),
+            lalrpop_util::ParseError::InvalidToken { location } => get_loc(*location, text),
             lalrpop_util::ParseError::UnrecognizedEof {
                 location,
                 expected: _,
-            } => get_loc(*location, &text),
+            } => get_loc(*location, text),
             lalrpop_util::ParseError::UnrecognizedToken { token, expected: _ } => {
-                get_loc(token.0, &text)
+                get_loc(token.0, text)
+            }
+            lalrpop_util::ParseError::ExtraToken { token } => get_loc(token.0, text),
+            lalrpop_util::ParseError::User { error } => {
+                return BuildError::ModuleSintaxError(lgx_path.to_string(), error.clone());
             }
-            lalrpop_util::ParseError::ExtraToken { token: _ } => todo!(),
-            lalrpop_util::ParseError::User { error: _ } => todo!(),
         };
         BuildError::ModuleSintaxError(lgx_path.to_string(), format!("[{}:{}] {}", line, col, e))
-    })?;
+    })
+}
+
+fn get_comp_map(lgx_path: String) -> Result<(CompMap, AsmMap), BuildError> {
+    debug!("Getting component map from: {}", lgx_path);
+
+    let text = std::fs::read_to_string(lgx_path.clone())
+        .map_err(|_| BuildError::ImportError(lgx_path.to_string()))?;
+
+    debug!("Parsing file: {}", lgx_path);
+    let circuit = parse_circuit(&text, &lgx_path)?;
 
     debug!("Building component map");
-    let mut comp_map: HashMap<String, Box<CompDecl>> = circuit
+    let mut comp_map: CompMap = circuit
         .comps
         .into_iter()
         .map(|comp| (comp.name.clone(), Box::new(comp)))
@@ -105,195 +181,715 @@ fn get_comp_map(lgx_path: String) -> Result<HashMap<String, Box<CompDecl>>, Buil
         comp_map.keys().collect::<Vec<&String>>()
     );
 
+    let mut asm_map = AsmMap::new();
+
     if let Some(imports) = &circuit.imports {
         debug!("Processing imports: {:?}", imports);
         for import in imports {
+            let (file, ext) = match import {
+                Import::Lgx(file) => (file, "lgx"),
+                Import::Asm(file, _) => (file, "asmhdl"),
+            };
             let path = Path::new(&lgx_path)
                 .parent()
                 .unwrap()
-                .join(format!("{}.lgx", import))
+                .join(format!("{}.{}", file, ext))
                 .to_str()
                 .unwrap()
                 .to_string();
             debug!("Importing: {}", path);
-            let imported_map = get_comp_map(path)?;
-            comp_map.extend(imported_map);
+            match import {
+                Import::Lgx(_) => {
+                    let (imported_map, imported_asm) = get_comp_map(path)?;
+                    comp_map.extend(imported_map);
+                    asm_map.extend(imported_asm);
+                }
+                Import::Asm(_, name) => {
+                    let code = std::fs::read_to_string(&path)
+                        .map_err(|_| BuildError::ImportError(path.clone()))?;
+                    let asm = AsmComponent::try_from_code(&code).map_err(|err| {
+                        BuildError::ModuleSintaxError(path.clone(), err.to_string())
+                    })?;
+                    asm_map.insert(name.clone(), asm);
+                }
+            }
         }
     }
 
-    Ok(comp_map)
+    Ok((comp_map, asm_map))
 }
 
 fn comp_decl_to_comp(
     comp: &CompDecl,
     name: &str,
-    comp_map: &HashMap<String, Box<CompDecl>>,
+    params: &[usize],
+    comp_map: &CompMap,
+    asm_map: &AsmMap,
     last_id: &mut usize,
-    id_map: &mut HashMap<usize, String>,
+    chain: &mut Vec<String>,
 ) -> Result<Component<ExtraInfo>, BuildError> {
     debug!("Processing component: {}", name);
 
-    let mut subc = comp.subc.iter().collect::<Vec<_>>();
+    // Composite components are named after their declaration, the instance
+    // name is set by the component holding them
+    let decl_name = match params {
+        [] => comp.name.clone(),
+        _ => format!("{}<{}>", comp.name, join_params(params)),
+    };
+    // The chain holds the declarations currently being built, a repeated one
+    // would recurse forever
+    if chain.contains(&decl_name) {
+        return Err(BuildError::RecursiveComponent(decl_name));
+    }
+    if chain.len() >= MAX_NESTING {
+        return Err(BuildError::NestingTooDeep(decl_name));
+    }
+    chain.push(decl_name.clone());
 
-    subc.sort_by(|a, b| a.0 .1.cmp(&b.0 .1));
+    let elab = elaborate(comp, params)?;
 
-    let subc_map = subc
+    let subc_map = elab
+        .subc
         .iter()
         .enumerate()
-        .map(|(i, ((n, _), _))| (n.clone(), i))
+        .map(|(i, (n, _))| (n.clone(), i))
         .collect();
 
-    let subc: Vec<Component<ExtraInfo>> = subc
+    let subc: Vec<Component<ExtraInfo>> = elab
+        .subc
         .iter()
-        .map(|((subc_name, _), sub_comp)| match sub_comp {
-            Comp::Primitive(prim) => {
-                *last_id += 1;
-                let prim = match prim {
-                    Primitive::And(ins_count) => and_gate(*last_id, *ins_count),
-                    Primitive::Or(ins_count) => or_gate(*last_id, *ins_count),
-                    Primitive::Not => not_gate(*last_id),
-                    Primitive::Nand(ins_count) => nand_gate(*last_id, *ins_count),
-                    Primitive::Nor(ins_count) => nor_gate(*last_id, *ins_count),
-                    Primitive::HighConst => high_const(*last_id),
-                    Primitive::LowConst => low_const(*last_id),
-                    Primitive::Clock(frec) => clock(*last_id, *frec),
-                    Primitive::Xor(ins_count) => xor_gate(*last_id, *ins_count),
-                    Primitive::Input(bits) => input(*last_id, *bits),
-                    Primitive::Output(bits) => output(*last_id, *bits),
-                    Primitive::Splitter(bits) => splitter(*last_id, *bits),
-                    Primitive::Joiner(bits) => joiner(*last_id, *bits),
-                };
-                id_map.insert(*last_id, subc_name.to_string());
-                debug!("Creating primitive: {} with id {}", subc_name, *last_id);
-                Ok(prim)
-            }
-            Comp::Composite(name) => {
-                let decl = comp_map
-                    .get(name)
-                    .ok_or(BuildError::ComponentDeclNotFound(name.to_string()))?;
-                let compose = comp_decl_to_comp(decl, subc_name, comp_map, last_id, id_map)?;
-                Ok(compose)
-            }
+        .map(|(subc_name, sub_comp)| {
+            let mut comp = match sub_comp {
+                Comp::Primitive(prim) => {
+                    *last_id += 1;
+                    let prim = match prim {
+                        Primitive::And(ins_count) => and_gate(*last_id, *ins_count),
+                        Primitive::Or(ins_count) => or_gate(*last_id, *ins_count),
+                        Primitive::Not => not_gate(*last_id),
+                        Primitive::Nand(ins_count) => nand_gate(*last_id, *ins_count),
+                        Primitive::Nor(ins_count) => nor_gate(*last_id, *ins_count),
+                        Primitive::HighConst => high_const(*last_id),
+                        Primitive::LowConst => low_const(*last_id),
+                        Primitive::Clock(config) => clock(*last_id, *config),
+                        Primitive::Const(value, width) => const_value(*last_id, *value, *width),
+                        Primitive::Switch => switch(*last_id),
+                        Primitive::Xor(ins_count) => xor_gate(*last_id, *ins_count),
+                        Primitive::Input(bits) => input(*last_id, *bits),
+                        Primitive::Output(bits) => output(*last_id, *bits),
+                        Primitive::Splitter(bits) => splitter(*last_id, *bits),
+                        Primitive::Joiner(bits) => joiner(*last_id, *bits),
+                    };
+                    debug!("Creating primitive: {} with id {}", subc_name, *last_id);
+                    prim
+                }
+                Comp::Composite(name, _) if asm_map.contains_key(name) => {
+                    *last_id += 1;
+                    debug!("Creating custom: {} with id {}", subc_name, *last_id);
+                    custom(*last_id, asm_map[name].clone())
+                }
+                Comp::Composite(name, params) => {
+                    let decl = comp_map
+                        .get(name)
+                        .ok_or(BuildError::ComponentDeclNotFound(name.to_string()))?;
+                    comp_decl_to_comp(decl, subc_name, params, comp_map, asm_map, last_id, chain)?
+                }
+            };
+            comp.instance = Some(subc_name.clone());
+            Ok(comp)
         })
         .collect::<Result<Vec<Component<ExtraInfo>>, BuildError>>()?;
 
-    let conns: Vec<Conn> = get_connections(comp, &subc_map, comp_map)?;
+    let (subc, conns) = get_connections(&elab, &subc_map, subc, last_id)?;
 
     let mut in_addrs: Vec<(usize, PortAddr)> = vec![];
     let mut out_addrs: Vec<PortAddr> = vec![];
 
-    for (i, idx) in comp.ins.iter().enumerate() {
-        in_addrs.push((i, (*idx, 0)));
+    let ins = elab
+        .subc
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, c))| c.is_input());
+    for (i, (idx, _)) in ins.enumerate() {
+        in_addrs.push((i, (idx, 0)));
     }
 
-    for idx in comp.outs.iter() {
-        out_addrs.push((*idx, 0));
+    let outs = elab
+        .subc
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, c))| c.is_output());
+    for (idx, _) in outs {
+        out_addrs.push((idx, 0));
     }
 
+    // Ports are named after their pins
+    let ports = |addrs: &mut dyn Iterator<Item = usize>, direction| {
+        addrs
+            .map(|idx| PortDesc {
+                name: elab.subc[idx].0.clone(),
+                width: subc[idx].input_ports[0].width,
+                direction,
+                description: None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let in_ports = ports(
+        &mut in_addrs.iter().map(|(_, (idx, _))| *idx),
+        PortDirection::Input,
+    );
+    let out_ports = ports(
+        &mut out_addrs.iter().map(|(idx, _)| *idx),
+        PortDirection::Output,
+    );
     let in_count: usize = in_addrs.len();
     let out_count: usize = out_addrs.len();
 
     *last_id += 1;
-    id_map.insert(*last_id, name.to_string());
     debug!("Creating component: {} with id {}", name, *last_id);
     debug!("{} In addrs: {:?}", in_count, in_addrs);
     debug!("{} Out addrs: {:?}", out_count, out_addrs);
 
+    chain.pop();
+
     Ok(ComponentBuilder::new(*last_id)
-        .name(name.to_string())
-        .port_count(in_count, out_count)
+        .name(decl_name)
+        .input_ports(in_ports)
+        .output_ports(out_ports)
         .sub_comps(subc)
         .connections(conns)
         .in_addrs(in_addrs)
         .out_addrs(out_addrs)
         .extra(ExtraInfo::new(*last_id))
-        .build())
+        .build()?)
 }
 
-fn get_connections(
-    comp: &CompDecl,
-    subc_map: &HashMap<String, usize>,
-    comp_map: &HashMap<String, Box<CompDecl>>,
-) -> Result<Vec<Conn>, BuildError> {
-    debug!("Processing connections for: {}", comp.name);
+fn join_params(params: &[usize]) -> String {
+    params
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let mut conns = vec![];
+/// Resolved pin of a sub-component together with the selected bits.
+#[derive(Debug, Clone, Copy)]
+struct PinRef {
+    addr: PortAddr,
+    width: usize,
+    bits: Option<BitRange>,
+}
+
+impl PinRef {
+    fn width(&self) -> usize {
+        self.bits.map_or(self.width, |bits| bits.len())
+    }
+
+    fn is_whole(&self) -> bool {
+        match self.bits {
+            Some(bits) => bits.from == 0 && bits.to + 1 == self.width,
+            None => true,
+        }
+    }
+
+    fn bit_list(&self) -> Vec<(PortAddr, usize, usize)> {
+        let bits = self
+            .bits
+            .map_or((0..self.width).collect(), |bits| bits.indexes());
+        bits.into_iter()
+            .map(|bit| (self.addr, self.width, bit))
+            .collect()
+    }
+}
+
+/// Returns the bit width of the input and output ports of a component.
+fn port_widths(comp: &Component<ExtraInfo>) -> (Vec<usize>, Vec<usize>) {
+    let widths = |ports: &[PortDesc]| ports.iter().map(|port| port.width).collect();
+    (widths(&comp.input_ports), widths(&comp.output_ports))
+}
+
+/// Creates the connections of a component design.
+///
+/// Connections between whole pins of the same width are made directly. When
+/// bits are selected, pin ranges are used or signals are concatenated, the
+/// connection is made bit by bit, adding a splitter to every multi-bit source
+/// pin and a joiner to every multi-bit destination pin involved.
+struct DesignWiring<'a> {
+    subc_map: &'a HashMap<String, usize>,
+    subc: Vec<Component<ExtraInfo>>,
+    widths: Vec<(Vec<usize>, Vec<usize>)>,
+    conns: Vec<Conn>,
+    splitters: HashMap<PortAddr, usize>,
+    joiners: HashMap<PortAddr, usize>,
+}
+
+impl<'a> DesignWiring<'a> {
+    fn new(subc_map: &'a HashMap<String, usize>, subc: Vec<Component<ExtraInfo>>) -> Self {
+        let widths = subc.iter().map(port_widths).collect();
+        DesignWiring {
+            subc_map,
+            subc,
+            widths,
+            conns: vec![],
+            splitters: HashMap::new(),
+            joiners: HashMap::new(),
+        }
+    }
 
-    let get_subc_idx = |name: &str| -> Result<usize, BuildError> {
-        subc_map
+    fn subc_idx(&self, name: &str) -> Result<usize, BuildError> {
+        self.subc_map
             .get(name)
             .ok_or(BuildError::ComponentRefNotFound(name.to_string()))
             .copied()
-    };
+    }
+
+    fn pin_idx_by_name(
+        &self,
+        comp_name: &str,
+        pin_name: &str,
+        is_src: bool,
+    ) -> Result<usize, BuildError> {
+        let comp = &self.subc[self.subc_idx(comp_name)?];
+        if is_src {
+            comp.port_idx(pin_name, PortDirection::Output)
+                .ok_or(BuildError::OutputPinNotFound(pin_name.to_string()))
+        } else {
+            comp.port_idx(pin_name, PortDirection::Input)
+                .ok_or(BuildError::InputPinNotFound(pin_name.to_string()))
+        }
+    }
 
-    let get_pin_idx =
-        |comp_name: &str, pin_name: &str, is_input: bool| -> Result<usize, BuildError> {
-            let comp = get_comp_decl(comp, comp_name, comp_map)
-                .map_err(|_| BuildError::ComponentRefNotFound(comp_name.to_string()))?;
-            if is_input {
-                comp.out_idx_by_name
-                    .get(pin_name)
-                    .copied()
-                    .ok_or(BuildError::InputPinNotFound(pin_name.to_string()))
-            } else {
-                comp.in_idx_by_name
-                    .get(pin_name)
-                    .copied()
-                    .ok_or(BuildError::OutputPinNotFound(pin_name.to_string()))
+    fn pin_ref(
+        &self,
+        comp_name: &str,
+        pin_idx: usize,
+        bits: Option<BitRange>,
+        is_src: bool,
+    ) -> Result<PinRef, BuildError> {
+        let comp_idx = self.subc_idx(comp_name)?;
+        let (ins, outs) = &self.widths[comp_idx];
+        let width = *(if is_src { outs } else { ins }).get(pin_idx).ok_or(
+            BuildError::PinIndexOutOfRange(comp_name.to_string(), pin_idx),
+        )?;
+        if let Some(bits) = bits {
+            if let Some(bit) = bits.indexes().into_iter().find(|bit| *bit >= width) {
+                return Err(BuildError::BitIndexOutOfRange(
+                    format!("{}.{}", comp_name, pin_idx),
+                    bit,
+                ));
             }
+        }
+        Ok(PinRef {
+            addr: (comp_idx, pin_idx),
+            width,
+            bits,
+        })
+    }
+
+    fn resolve(&self, signal: &Signal, is_src: bool) -> Result<Vec<PinRef>, BuildError> {
+        match signal {
+            Signal::Pin(pin, bits) => {
+                let pin_idx = match pin {
+                    PinAddr::ByName(comp_name, pin_name) => {
+                        self.pin_idx_by_name(comp_name, pin_name, is_src)?
+                    }
+                    PinAddr::ByIdx(_, idx) => *idx,
+                };
+                Ok(vec![self.pin_ref(pin.name(), pin_idx, *bits, is_src)?])
+            }
+            Signal::PinRange(comp_name, pins) => pins
+                .indexes()
+                .into_iter()
+                .map(|pin_idx| self.pin_ref(comp_name, pin_idx, None, is_src))
+                .collect(),
+            Signal::Concat(items) => items
+                .iter()
+                .map(|item| self.resolve(item, is_src))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|refs| refs.into_iter().flatten().collect()),
+        }
+    }
+
+    fn add_comp(&mut self, comp: Component<ExtraInfo>) -> usize {
+        self.widths.push(port_widths(&comp));
+        self.subc.push(comp);
+        self.subc.len() - 1
+    }
+
+    fn splitter_of(&mut self, addr: PortAddr, width: usize, last_id: &mut usize) -> usize {
+        if let Some(idx) = self.splitters.get(&addr) {
+            return *idx;
+        }
+        *last_id += 1;
+        let mut comp = splitter(*last_id, width);
+        comp.instance = Some(format!("{}.{}$split", self.subc_name(addr.0), addr.1));
+        let idx = self.add_comp(comp);
+        self.conns.push(Conn::new(addr.0, addr.1, idx, 0));
+        self.splitters.insert(addr, idx);
+        idx
+    }
+
+    fn joiner_of(&mut self, addr: PortAddr, width: usize, last_id: &mut usize) -> usize {
+        if let Some(idx) = self.joiners.get(&addr) {
+            return *idx;
+        }
+        *last_id += 1;
+        let mut comp = joiner(*last_id, width);
+        comp.instance = Some(format!("{}.{}$join", self.subc_name(addr.0), addr.1));
+        let idx = self.add_comp(comp);
+        self.conns.push(Conn::new(idx, 0, addr.0, addr.1));
+        self.joiners.insert(addr, idx);
+        idx
+    }
+
+    fn subc_name(&self, idx: usize) -> &str {
+        self.subc_map
+            .iter()
+            .find(|(_, i)| **i == idx)
+            .map_or("", |(name, _)| name.as_str())
+    }
+
+    fn connect_bit(
+        &mut self,
+        src: (PortAddr, usize, usize),
+        dest: (PortAddr, usize, usize),
+        last_id: &mut usize,
+    ) {
+        let ((src_addr, src_width, src_bit), (dest_addr, dest_width, dest_bit)) = (src, dest);
+        let from = match src_width {
+            1 => src_addr,
+            _ => (self.splitter_of(src_addr, src_width, last_id), src_bit),
         };
+        let to = match dest_width {
+            1 => dest_addr,
+            _ => (self.joiner_of(dest_addr, dest_width, last_id), dest_bit),
+        };
+        self.conns.push(Conn::new(from.0, from.1, to.0, to.1));
+    }
+
+    fn connect(&mut self, conn: &ConnDecl, last_id: &mut usize) -> Result<(), BuildError> {
+        let srcs = self.resolve(&conn.src, true)?;
+        let dests = self.resolve(&conn.dest, false)?;
+
+        let pin_to_pin = srcs.len() == dests.len()
+            && srcs
+                .iter()
+                .zip(dests.iter())
+                .all(|(s, d)| s.width() == d.width());
+
+        if pin_to_pin {
+            for (src, dest) in srcs.iter().zip(dests.iter()) {
+                if src.is_whole() && dest.is_whole() {
+                    debug!("|  Pin to pin: ({:?}, {:?})", src.addr, dest.addr);
+                    self.conns
+                        .push(Conn::new(src.addr.0, src.addr.1, dest.addr.0, dest.addr.1));
+                    continue;
+                }
+                for (src_bit, dest_bit) in src.bit_list().into_iter().zip(dest.bit_list()) {
+                    self.connect_bit(src_bit, dest_bit, last_id);
+                }
+            }
+            return Ok(());
+        }
+
+        let src_bits: Vec<_> = srcs.iter().flat_map(PinRef::bit_list).collect();
+        let dest_bits: Vec<_> = dests.iter().flat_map(PinRef::bit_list).collect();
+        if src_bits.len() != dest_bits.len() {
+            return Err(BuildError::WidthMismatch(
+                format!("{} -> {}", conn.src, conn.dest),
+                src_bits.len(),
+                dest_bits.len(),
+            ));
+        }
+        debug!("|  Bit to bit: {} bits", src_bits.len());
+        for (src_bit, dest_bit) in src_bits.into_iter().zip(dest_bits) {
+            self.connect_bit(src_bit, dest_bit, last_id);
+        }
+        Ok(())
+    }
+}
 
-    for conn in &comp.design {
+fn get_connections(
+    elab: &ElabComp,
+    subc_map: &HashMap<String, usize>,
+    subc: Vec<Component<ExtraInfo>>,
+    last_id: &mut usize,
+) -> Result<(Vec<Component<ExtraInfo>>, Vec<Conn>), BuildError> {
+    let mut wiring = DesignWiring::new(subc_map, subc);
+    for conn in &elab.design {
         debug!("|  Processing connection: {:?}", conn);
+        wiring.connect(conn, last_id)?;
+    }
 
-        let src_comp_idx = get_subc_idx(conn.src.name())?;
-        let dest_comp_idx = get_subc_idx(conn.dest.name())?;
+    Ok((wiring.subc, wiring.conns))
+}
 
-        let src_pin_idx = match &conn.src {
-            PinAddr::ByName(_, n) => get_pin_idx(conn.src.name(), n, true)?,
-            PinAddr::ByIdx(_, idx) => *idx,
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asmhdl::Data;
+    use logix_sim::flatten::FlattenComponent;
+    use logix_sim::primitives::clock::ClockConfig;
+    use logix_sim::primitives::primitive::Primitive as SimPrimitive;
+
+    fn build_main(code: &str) -> Result<Component<ExtraInfo>, BuildError> {
+        let circuit = parse_circuit(code, "main.lgx")?;
+        let comp_map: CompMap = circuit
+            .comps
+            .into_iter()
+            .map(|comp| (comp.name.clone(), Box::new(comp)))
+            .collect();
+        let main = comp_map.get("Main").unwrap();
+        let asm_map = AsmMap::new();
+        comp_decl_to_comp(main, "main", &[], &comp_map, &asm_map, &mut 0, &mut vec![])
+    }
 
-        let dest_pin_idx = match &conn.dest {
-            PinAddr::ByName(_, n) => get_pin_idx(conn.dest.name(), n, false)?,
-            PinAddr::ByIdx(_, idx) => *idx,
-        };
+    #[test]
+    fn test_bus_concat() {
+        let code = format!(
+            "{}\n{}",
+            include_str!("../examples/adders.lgx"),
+            "Main ( subc ( add = Adder4 ) ins (x:4, y:4) outs (s:4, c) design (
+                x -> add.a, y -> add.b, add.res -> s, add.carr -> c
+            ))"
+        );
+        let main = build_main(&code).unwrap();
+        let sub = main.sub.as_ref().unwrap();
+        assert_eq!(main.inputs, 2);
+        assert_eq!(main.outputs, 2);
+
+        // 4 adders, 4 IO pins, splitters for `a` and `b` and a joiner for `res`
+        let adder = &sub.components[0];
+        assert_eq!(adder.sub.as_ref().unwrap().components.len(), 11);
+        assert_eq!(port_widths(adder), (vec![4, 4], vec![4, 1]));
+    }
 
-        conns.push(Conn::new(
-            src_comp_idx,
-            src_pin_idx,
-            dest_comp_idx,
-            dest_pin_idx,
+    #[test]
+    fn test_pin_range_and_slices() {
+        let code = "Main (
+            subc ( s = Splitter(8), j = Joiner(8) )
+            ins (x:8)
+            outs (y:8, low:4, msb)
+            design (
+                x -> s,
+                s.0..7 -> j.7..0,
+                j -> y,
+                x[0..3] -> low,
+                y[7] -> msb
+            )
+        )";
+        let main = build_main(code).unwrap();
+        let sub = main.sub.as_ref().unwrap();
+        // Splitter of `x`, joiner of `low` and splitter of the `y` output pin
+        assert_eq!(sub.components.len(), 9);
+        assert_eq!(sub.connections.len(), 1 + 8 + 1 + 1 + 4 + 1 + 1 + 1);
+    }
+
+    #[test]
+    fn test_width_mismatch() {
+        let code = "Main ( ins (x:4) outs (y:3) design ( x -> y ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::WidthMismatch(_, 4, 3))
         ));
 
-        debug!(
-            "|  Internal to internal: (({}, {}), ({}, {}))",
-            src_comp_idx, src_pin_idx, dest_comp_idx, dest_pin_idx
+        // Messages show the connections as written
+        let code = "Main ( ins (x:4) outs (y:3) design ( {x[0..1], x} -> y ) )";
+        assert_eq!(
+            build_main(code).unwrap_err().to_string(),
+            "Width mismatch in connection {x[0..1], x} -> y (6 bits to 3 bits)"
+        );
+        let code = "Main ( subc ( g = And(2 - 3 * (1 + 1)) ) outs (o) design ( g -> o ) )";
+        assert_eq!(
+            build_main(code).unwrap_err().to_string(),
+            "Expression 2 - 3 * (1 + 1) evaluates to a negative value (-4)"
         );
     }
 
-    Ok(conns)
-}
+    #[test]
+    fn test_overflow() {
+        for expr in [
+            "18446744073709551615 + 1",
+            "18446744073709551615 * 18446744073709551615",
+            "(0 - 9223372036854775808 * 9223372036854775808 - 9223372036854775808 * 9223372036854775808) / (0 - 1)",
+            "(0 - 9223372036854775808 * 9223372036854775808 - 9223372036854775808 * 9223372036854775808) % (0 - 1)",
+        ] {
+            let code = format!("Main ( subc ( g = And({expr}) ) outs (o) design ( g -> o ) )");
+            assert!(matches!(
+                build_main(&code),
+                Err(BuildError::Overflow(text)) if text == expr
+            ));
+        }
+    }
 
-fn get_comp_decl<'a>(
-    comp: &'a CompDecl,
-    name: &'a str,
-    comp_map: &'a HashMap<String, Box<CompDecl>>,
-) -> Result<&'a CompDecl, BuildError> {
-    let subc = comp
-        .subc
-        .iter()
-        .filter_map(|((n, _), c)| if n == name { Some(c) } else { None })
-        .next()
-        .ok_or(BuildError::ComponentRefNotFound(name.to_string()))?;
+    #[test]
+    fn test_recursive_component() {
+        let code = "A<N> ( subc ( a = A<N> ) ins (x) outs (y) design ( x -> y ) )
+            Main ( subc ( a = A<1> ) ins (x) outs (y) design ( x -> y ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::RecursiveComponent(name)) if name == "A<1>"
+        ));
 
-    let subc_type = match subc {
-        Comp::Primitive(prim) => prim.to_string(),
-        Comp::Composite(name) => name.clone(),
-    };
-    let comp_decl = comp_map
-        .get(&subc_type)
-        .ok_or(BuildError::ComponentDeclNotFound(subc_type.clone()))?;
+        let code = "A<N> ( subc ( a = A<N+1> ) ins (x) outs (y) design ( x -> y ) )
+            Main ( subc ( a = A<1> ) ins (x) outs (y) design ( x -> y ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::NestingTooDeep(name)) if name == format!("A<{}>", MAX_NESTING)
+        ));
+    }
+
+    #[test]
+    fn test_generic_adder() {
+        let code = format!(
+            "{}\n{}",
+            include_str!("../examples/adders.lgx"),
+            "Main ( subc ( add = Adder<8> ) ins (x:8, y:8) outs (s:8, c) design (
+                x -> add.a, y -> add.b, add.res -> s, add.carr -> c
+            ))"
+        );
+        let main = build_main(&code).unwrap();
+        let adder = &main.sub.as_ref().unwrap().components[0];
+        assert_eq!(adder.name.as_deref(), Some("Adder<8>"));
+
+        // 8 full adders, the zero constant, 4 IO pins, 2 splitters and a joiner
+        assert_eq!(adder.sub.as_ref().unwrap().components.len(), 16);
+        assert_eq!(port_widths(adder), (vec![8, 8], vec![8, 1]));
+    }
+
+    #[test]
+    fn test_param_errors() {
+        let code = "Wide<N> ( ins (a:N) outs (b:N) design ( a -> b ) )
+            Main ( subc ( w = Wide ) ins (x) design ( x -> w.a ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::ParamCountMismatch(_, 1, 0))
+        ));
+
+        let code = "Main ( subc ( for i in 0..1 ( x[i] = In ) ) design ( x[0] -> x[1] ) )";
+        assert!(matches!(build_main(code), Err(BuildError::GeneratedPin(_))));
+    }
 
-    Ok(comp_decl)
+    #[test]
+    fn test_lint_paths() {
+        let code = "
+            Latch ( subc ( n1 = Nor(2), n2 = Nor(2) ) ins (s, r) outs (q) design (
+                s -> n1.0, r -> n2.1, n1.0 -> n2.0, n2.0 -> n1.1, n2.0 -> q
+            ))
+            Main ( subc ( latch = Latch ) ins (s, r) outs (q) design (
+                s -> latch.s, r -> latch.r, latch.q -> q
+            ))";
+        let main = FlattenComponent::new(build_main(code).unwrap()).unwrap();
+        let messages: Vec<String> = main.lint().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec!["Combinational loop between Main.latch.n1, Main.latch.n2"]
+        );
+    }
+
+    #[test]
+    fn test_asm_import() {
+        let main = build_from_file("examples/asm_counter.lgx").unwrap();
+        let sub = main.sub.as_ref().unwrap();
+
+        let ff0 = &sub.components[2];
+        assert_eq!(ff0.instance.as_deref(), Some("ff0"));
+        assert!(matches!(
+            ff0.extra.primitive,
+            Some(SimPrimitive::Custom { .. })
+        ));
+        assert_eq!((ff0.inputs, ff0.outputs), (3, 2));
+
+        // `ff0.Q` feeds `ff1.CLK` and the joiner of `q`
+        let q_conns = sub
+            .connections
+            .iter()
+            .filter(|conn| conn.from == (2, 0))
+            .count();
+        assert_eq!(q_conns, 2);
+    }
+
+    #[test]
+    fn test_malformed_asm_import() {
+        let dir = std::env::temp_dir().join("logix_lang_malformed_asm");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ff.asmhdl"), "_info:\nupdate_type sometimes\n").unwrap();
+        let main_path = dir.join("main.lgx");
+        std::fs::write(
+            &main_path,
+            "use asm ff as FF;\nMain ( subc ( ff = FF ) outs (o) design ( ff.Q -> o ) )",
+        )
+        .unwrap();
+
+        let result = build_from_file(main_path.to_str().unwrap());
+        assert!(matches!(
+            result,
+            Err(BuildError::ModuleSintaxError(path, msg))
+                if path.ends_with("ff.asmhdl") && msg == "Invalid update type: sometimes"
+        ));
+    }
+
+    #[test]
+    fn test_primitive_args() {
+        let code = "Main (
+            subc ( sw = Switch, k = Const(0xA, 4), c1 = Clock(10ns), c2 = Clock(1MHz) )
+            outs (a, b:4, c, d)
+            design ( sw.0 -> a, k.0 -> b, c1.0 -> c, c2.0 -> d )
+        )";
+        let main = build_main(code).unwrap();
+        let prims: Vec<_> = main.sub.as_ref().unwrap().components[..4]
+            .iter()
+            .map(|comp| comp.extra.primitive.clone().unwrap())
+            .collect();
+        assert!(matches!(prims[0], SimPrimitive::Switch));
+        assert!(matches!(prims[1], SimPrimitive::Const { value } if value == Data::new(10, 4)));
+        assert!(matches!(prims[2], SimPrimitive::Clock(clock) if clock == ClockConfig::new(10)));
+        assert!(matches!(prims[3], SimPrimitive::Clock(clock) if clock.period == 1000));
+
+        let code = "Main ( subc ( c = Clock(10ps) ) outs (o) design ( c.0 -> o ) )";
+        assert!(matches!(build_main(code), Err(BuildError::UnknownUnit(_))));
+
+        let code = "Main (
+            subc ( c = Clock(10ns, duty = 25, phase = 2ns, delay = 1us, enable) )
+            ins (en) outs (o) design ( en -> c.0, c.0 -> o )
+        )";
+        let main = build_main(code).unwrap();
+        let clock = &main.sub.as_ref().unwrap().components[0];
+        let expected = ClockConfig {
+            duty: 25,
+            phase: 2,
+            delay: 1000,
+            enable: true,
+            ..ClockConfig::new(10)
+        };
+        assert!(matches!(clock.extra.primitive, Some(SimPrimitive::Clock(c)) if c == expected));
+        assert_eq!(clock.inputs, 1);
+
+        let code = "Main ( subc ( c = Clock(10ns, duty = 100) ) outs (o) design ( c.0 -> o ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::InvalidOption(..))
+        ));
+        let code = "Main ( subc ( c = Clock(10ns, phase = 3) ) outs (o) design ( c.0 -> o ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::InvalidOption(..))
+        ));
+
+        let code =
+            "Main ( subc ( k = Const(0xFFFFFFFFFFFFFFFFFFFF, 4) ) outs (o:4) design ( k.0 -> o ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::ModuleSintaxError(_, msg)) if msg.contains("0xFFFFFFFFFFFFFFFFFFFF")
+        ));
+
+        let code =
+            "Main ( subc ( k = Const(0xFFFFFFFFFFFFFFFF, 64) ) outs (o:64) design ( k.0 -> o ) )";
+        assert!(build_main(code).is_ok());
+
+        let code = "Main ( subc ( k = Const(4, 2) ) outs (o:2) design ( k.0 -> o ) )";
+        assert!(matches!(
+            build_main(code),
+            Err(BuildError::ConstOutOfRange(4, 2))
+        ));
+    }
 }
@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+
+use log::debug;
+
+use logix_sim::primitives::clock::ClockConfig;
+
+use crate::ast::prelude::*;
+use crate::builder::BuildError;
+
+/// Values of the parameters and `for` variables in scope.
+type Env = HashMap<String, i128>;
+
+/// Component declaration with all the expressions evaluated and the `for`
+/// blocks unrolled.
+#[derive(Debug)]
+pub struct ElabComp {
+    /// Sub-components in declaration order.
+    pub subc: Vec<(String, Comp)>,
+    pub design: Vec<ConnDecl>,
+}
+
+pub fn eval(expr: &Expr, env: &Env) -> Result<i128, BuildError> {
+    match expr {
+        Expr::Num(n) => Ok(*n),
+        Expr::Var(name) => env
+            .get(name)
+            .copied()
+            .ok_or(BuildError::UndefinedVariable(name.clone())),
+        Expr::Op(lhs_expr, op, rhs_expr) => {
+            let (lhs, rhs) = (eval(lhs_expr, env)?, eval(rhs_expr, env)?);
+            let value = match op {
+                BinOp::Add => lhs.checked_add(rhs),
+                BinOp::Sub => lhs.checked_sub(rhs),
+                BinOp::Mul => lhs.checked_mul(rhs),
+                BinOp::Div | BinOp::Mod if rhs == 0 => return Err(BuildError::DivisionByZero),
+                BinOp::Div => lhs.checked_div(rhs),
+                BinOp::Mod => lhs.checked_rem(rhs),
+            };
+            value.ok_or_else(|| BuildError::Overflow(expr.to_string()))
+        }
+    }
+}
+
+fn eval_usize(expr: &Expr, env: &Env) -> Result<usize, BuildError> {
+    let value = eval(expr, env)?;
+    usize::try_from(value).map_err(|_| match value {
+        ..0 => BuildError::NegativeValue(expr.to_string(), value),
+        _ => BuildError::Overflow(expr.to_string()),
+    })
+}
+
+fn eval_range(range: &RangeExpr, env: &Env) -> Result<BitRange, BuildError> {
+    let from = eval_usize(&range.from, env)?;
+    match &range.to {
+        Some(to) => Ok(BitRange::new(from, eval_usize(to, env)?)),
+        None => Ok(BitRange::single(from)),
+    }
+}
+
+fn for_values(decl: &ForDecl, env: &Env) -> Result<std::ops::RangeInclusive<i128>, BuildError> {
+    Ok(eval(&decl.from, env)?..=eval(&decl.to, env)?)
+}
+
+/// Converts a time literal to nanoseconds, if it has a time unit.
+fn time_ns(lit: &TimeLit) -> Option<f64> {
+    match lit.unit.as_deref()? {
+        "ns" => Some(lit.value),
+        "us" => Some(lit.value * 1e3),
+        "ms" => Some(lit.value * 1e6),
+        "s" => Some(lit.value * 1e9),
+        _ => None,
+    }
+}
+
+/// Converts a time or frequency literal to a period in nanoseconds.
+fn period_ns(lit: &TimeLit) -> Result<u128, BuildError> {
+    let period = match lit.unit.as_deref() {
+        _ if time_ns(lit).is_some() => time_ns(lit).unwrap_or_default(),
+        None | Some("Hz") => 1e9 / lit.value,
+        Some("kHz") => 1e6 / lit.value,
+        Some("MHz") => 1e3 / lit.value,
+        Some("GHz") => 1.0 / lit.value,
+        Some(unit) => return Err(BuildError::UnknownUnit(unit.to_string())),
+    };
+    if !period.is_finite() || period < 1.0 {
+        return Err(BuildError::InvalidClockPeriod(period));
+    }
+    Ok(period as u128)
+}
+
+/// Sets an option of a clock: `duty = <percent>`, `phase = <time>`,
+/// `delay = <time>` or `enable`.
+fn clock_option(
+    clock: &mut ClockConfig,
+    name: &str,
+    value: Option<&TimeLit>,
+) -> Result<(), BuildError> {
+    match (name, value) {
+        ("duty", Some(lit)) if lit.unit.is_none() && (1.0..100.0).contains(&lit.value) => {
+            clock.duty = lit.value as u8;
+        }
+        ("phase", Some(lit)) if time_ns(lit).is_some() => {
+            clock.phase = time_ns(lit).unwrap_or_default() as u128;
+        }
+        ("delay", Some(lit)) if time_ns(lit).is_some() => {
+            clock.delay = time_ns(lit).unwrap_or_default() as u128;
+        }
+        ("enable", None) => clock.enable = true,
+        _ => return Err(BuildError::InvalidOption("Clock".into(), name.into())),
+    }
+    Ok(())
+}
+
+fn inst_to_comp(inst: &CompInst, env: &Env) -> Result<Comp, BuildError> {
+    let args = inst
+        .args
+        .iter()
+        .map(|arg| eval_usize(arg, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    let period = inst.time_arg.as_ref().map(period_ns).transpose()?;
+    let mut comp = Comp::from_name(&inst.name, &args, period.unwrap_or(0));
+    match &mut comp {
+        Comp::Primitive(Primitive::Clock(clock)) => {
+            if clock.period == 0 {
+                return Err(BuildError::InvalidClockPeriod(0.0));
+            }
+            for (name, value) in &inst.options {
+                clock_option(clock, name, value.as_ref())?;
+            }
+        }
+        _ if !inst.options.is_empty() => {
+            let option = inst.options[0].0.clone();
+            return Err(BuildError::InvalidOption(inst.name.clone(), option));
+        }
+        Comp::Primitive(Primitive::Const(value, width)) => {
+            let fits = *width > 0 && (*width >= usize::BITS as usize || *value >> *width == 0);
+            if !fits {
+                return Err(BuildError::ConstOutOfRange(*value, *width));
+            }
+        }
+        _ => (),
+    }
+    if let Comp::Composite(_, params) = &mut comp {
+        *params = inst
+            .params
+            .iter()
+            .map(|param| eval_usize(param, env))
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(comp)
+}
+
+fn elaborate_subc(
+    items: &[SubcItem],
+    env: &mut Env,
+    in_generate: bool,
+    subc: &mut Vec<(String, Comp)>,
+    arrays: &mut HashSet<String>,
+) -> Result<(), BuildError> {
+    for item in items {
+        match item {
+            SubcItem::Inst(name, idx, inst) => {
+                let comp = inst_to_comp(inst, env)?;
+                if (in_generate || idx.is_some()) && (comp.is_input() || comp.is_output()) {
+                    return Err(BuildError::GeneratedPin(name.clone()));
+                }
+                let inst_name = match idx {
+                    Some(idx) => {
+                        arrays.insert(name.clone());
+                        format!("{}[{}]", name, eval_usize(idx, env)?)
+                    }
+                    None => name.clone(),
+                };
+                if subc.iter().any(|(n, _)| *n == inst_name) {
+                    return Err(BuildError::DuplicateName(inst_name));
+                }
+                subc.push((inst_name, comp));
+            }
+            SubcItem::For(decl, items) => {
+                let shadowed = env.get(&decl.var).copied();
+                for value in for_values(decl, env)? {
+                    env.insert(decl.var.clone(), value);
+                    elaborate_subc(items, env, true, subc, arrays)?;
+                }
+                match shadowed {
+                    Some(value) => env.insert(decl.var.clone(), value),
+                    None => env.remove(&decl.var),
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+fn elaborate_signal(
+    signal: &SignalDecl,
+    env: &Env,
+    arrays: &HashSet<String>,
+) -> Result<Signal, BuildError> {
+    let (comp, indexes, pin, bits) = match signal {
+        SignalDecl::Pin {
+            comp,
+            indexes,
+            pin,
+            bits,
+        } => (comp, indexes, pin, bits),
+        SignalDecl::Concat(items) => {
+            return items
+                .iter()
+                .map(|item| elaborate_signal(item, env, arrays))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Signal::Concat);
+        }
+    };
+
+    // The first index of an instance array selects the instance
+    let (comp_name, indexes) = if arrays.contains(comp) {
+        match indexes.split_first() {
+            Some((RangeExpr { from, to: None }, rest)) => {
+                (format!("{}[{}]", comp, eval_usize(from, env)?), rest)
+            }
+            _ => return Err(BuildError::InvalidIndexing(comp.clone())),
+        }
+    } else {
+        (comp.clone(), indexes.as_slice())
+    };
+
+    let Some(pin) = pin else {
+        if indexes.len() > 1 {
+            return Err(BuildError::InvalidIndexing(comp_name));
+        }
+        let bits = indexes
+            .first()
+            .map(|bits| eval_range(bits, env))
+            .transpose()?;
+        return Ok(Signal::Pin(PinAddr::ByIdx(comp_name, 0), bits));
+    };
+
+    if !indexes.is_empty() {
+        return Err(BuildError::InvalidIndexing(comp_name));
+    }
+    let bits = bits
+        .as_ref()
+        .map(|bits| eval_range(bits, env))
+        .transpose()?;
+    match pin {
+        PinSel::Name(pin_name) => Ok(Signal::Pin(
+            PinAddr::ByName(comp_name, pin_name.clone()),
+            bits,
+        )),
+        PinSel::Idx(RangeExpr { from, to: None }) => Ok(Signal::Pin(
+            PinAddr::ByIdx(comp_name, eval_usize(from, env)?),
+            bits,
+        )),
+        PinSel::Idx(pins) => match bits {
+            Some(_) => Err(BuildError::InvalidIndexing(comp_name)),
+            None => Ok(Signal::PinRange(comp_name, eval_range(pins, env)?)),
+        },
+    }
+}
+
+fn elaborate_design(
+    items: &[DesignItem],
+    env: &mut Env,
+    arrays: &HashSet<String>,
+    design: &mut Vec<ConnDecl>,
+) -> Result<(), BuildError> {
+    for item in items {
+        match item {
+            DesignItem::Conn(conn) => design.push(ConnDecl {
+                src: elaborate_signal(&conn.src, env, arrays)?,
+                dest: elaborate_signal(&conn.dest, env, arrays)?,
+            }),
+            DesignItem::For(decl, items) => {
+                let shadowed = env.get(&decl.var).copied();
+                for value in for_values(decl, env)? {
+                    env.insert(decl.var.clone(), value);
+                    elaborate_design(items, env, arrays, design)?;
+                }
+                match shadowed {
+                    Some(value) => env.insert(decl.var.clone(), value),
+                    None => env.remove(&decl.var),
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Evaluates a component declaration for the given parameter values.
+pub fn elaborate(decl: &CompDecl, params: &[usize]) -> Result<ElabComp, BuildError> {
+    if decl.params.len() != params.len() {
+        return Err(BuildError::ParamCountMismatch(
+            decl.name.clone(),
+            decl.params.len(),
+            params.len(),
+        ));
+    }
+    debug!("Elaborating {} with params {:?}", decl.name, params);
+
+    let mut env: Env = decl
+        .params
+        .iter()
+        .cloned()
+        .zip(params.iter().map(|p| *p as i128))
+        .collect();
+
+    let mut subc = vec![];
+    let mut arrays = HashSet::new();
+    elaborate_subc(&decl.subc, &mut env, false, &mut subc, &mut arrays)?;
+
+    let mut design = vec![];
+    elaborate_design(&decl.design, &mut env, &arrays, &mut design)?;
+
+    Ok(ElabComp { subc, design })
+}
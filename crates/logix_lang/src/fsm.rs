@@ -0,0 +1,913 @@
+//! Finite state machines described in text, built into an AsmHDL component
+//! or into gates with a state register.
+//!
+//! ```text
+//! # Detects two ones in a row
+//! name Detector
+//! type mealy
+//! encoding one_hot
+//! inputs x
+//! outputs z, count:2
+//!
+//! state idle / count=0
+//! state one / count=1
+//! idle -> one when x
+//! one -> one when x / z=1
+//! one -> idle
+//! ```
+//!
+//! The first state is the initial one. Every machine has a `clk` input and
+//! a `rst` input that moves it back to the initial state on a rising edge
+//! of the clock. On the other rising edges the first transition of the
+//! current state whose condition holds is taken, and the machine stays in
+//! its state if none does. Conditions are expressions of the inputs, as in
+//! [`Expr::parse`], and transitions without one are always taken.
+//!
+//! Outputs are set by the states (`/ out=value`, Moore outputs) and, in
+//! Mealy machines, by the transitions: while a transition would be taken
+//! its outputs override those of the state. Outputs not given are 0.
+
+use std::collections::HashMap;
+
+use asmhdl::{AsmCommand, AsmComponent, AsmExpr, AsmProgramUpdateType, Data};
+use logix_core::{
+    component::{Component, ComponentBuilder, Conn, PortDesc},
+    errors::BuildComponentError,
+};
+use logix_sim::{primitives::primitive::ExtraInfo, truth_table::MAX_INPUT_BITS};
+use thiserror::Error;
+
+use crate::{
+    primitive_builders::{custom, input, output},
+    synth::{Expr, Function, GateStyle, Logic, SynthError},
+};
+
+/// Names used by the ports of the generated components.
+const RESERVED_NAMES: [&str; 4] = ["clk", "rst", "state", "next"];
+
+#[derive(Debug, Error)]
+pub enum FsmError {
+    #[error("Line {0}: {1}")]
+    Syntax(usize, String),
+
+    #[error("Line {0}: {1} is defined more than once")]
+    Duplicate(usize, String),
+
+    #[error("Line {0}: {1} is a reserved name")]
+    ReservedName(usize, String),
+
+    #[error("Line {0}: unknown state {1}")]
+    UnknownState(usize, String),
+
+    #[error("Line {0}: unknown input {1}")]
+    UnknownInput(usize, String),
+
+    #[error("Line {0}: unknown output {1}")]
+    UnknownOutput(usize, String),
+
+    #[error("Line {0}: value {2} does not fit in output {1}")]
+    ValueOutOfRange(usize, String, usize),
+
+    #[error("Line {0}: only Mealy machines can set outputs in transitions")]
+    TransitionOutputs(usize),
+
+    #[error("No states defined")]
+    NoStates,
+
+    #[error("{0}")]
+    Synth(#[from] SynthError),
+
+    #[error("{0}")]
+    Build(#[from] BuildComponentError),
+}
+
+/// Whether the outputs depend on the state only or also on the inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FsmType {
+    #[default]
+    Moore,
+    Mealy,
+}
+
+/// Codes of the states in the state register.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Index of the state.
+    #[default]
+    Binary,
+    /// One bit per state.
+    OneHot,
+    /// Gray code of the index, so consecutive states differ in one bit.
+    Gray,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub name: String,
+    /// Value of each output in the state.
+    pub outputs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub from: usize,
+    pub to: usize,
+    pub condition: Expr,
+    /// Value of each output while the transition would be taken. The same
+    /// as the outputs of `from` in Moore machines.
+    pub outputs: Vec<usize>,
+}
+
+/// What a machine is built into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsmTarget {
+    /// A single AsmHDL component.
+    Asm,
+    /// Next state and output logic made of gates, feeding a state register.
+    Gates(GateStyle),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fsm {
+    pub name: String,
+    pub fsm_type: FsmType,
+    pub encoding: Encoding,
+    pub inputs: Vec<String>,
+    /// Names and widths of the outputs.
+    pub outputs: Vec<(String, usize)>,
+    pub states: Vec<State>,
+    /// Transitions in order of priority.
+    pub transitions: Vec<Transition>,
+}
+
+/// Transition as written, resolved once all the states are known.
+struct RawTransition {
+    line: usize,
+    from: String,
+    to: String,
+    condition: Expr,
+    outputs: Option<Vec<(usize, usize)>>,
+}
+
+impl Fsm {
+    /// Parses the description of a machine, see the [module docs](self).
+    pub fn parse(code: &str) -> Result<Self, FsmError> {
+        let mut fsm = Fsm {
+            name: "Fsm".to_string(),
+            fsm_type: FsmType::default(),
+            encoding: Encoding::default(),
+            inputs: vec![],
+            outputs: vec![],
+            states: vec![],
+            transitions: vec![],
+        };
+        let mut raw_transitions = vec![];
+        for (i, line) in code.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.contains("->") {
+                raw_transitions.push(fsm.parse_transition(line_no, line)?);
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match keyword {
+                "name" => fsm.name = identifier(line_no, rest)?.to_string(),
+                "type" => {
+                    fsm.fsm_type = match rest {
+                        "moore" => FsmType::Moore,
+                        "mealy" => FsmType::Mealy,
+                        _ => return Err(syntax(line_no, "expected `moore` or `mealy`")),
+                    }
+                }
+                "encoding" => {
+                    fsm.encoding = match rest {
+                        "binary" => Encoding::Binary,
+                        "one_hot" => Encoding::OneHot,
+                        "gray" => Encoding::Gray,
+                        _ => return Err(syntax(line_no, "expected `binary`, `one_hot` or `gray`")),
+                    }
+                }
+                "inputs" => {
+                    for name in list(rest) {
+                        let name = fsm.new_name(line_no, name)?;
+                        fsm.inputs.push(name);
+                    }
+                }
+                "outputs" => {
+                    if !fsm.states.is_empty() {
+                        return Err(syntax(
+                            line_no,
+                            "outputs must be declared before the states",
+                        ));
+                    }
+                    for item in list(rest) {
+                        let (name, width) = match item.split_once(':') {
+                            Some((name, width)) => match width.parse::<usize>() {
+                                Ok(width) if (1..usize::BITS as usize).contains(&width) => {
+                                    (name, width)
+                                }
+                                _ => return Err(syntax(line_no, "invalid output width")),
+                            },
+                            None => (item, 1),
+                        };
+                        let name = fsm.new_name(line_no, name)?;
+                        fsm.outputs.push((name, width));
+                    }
+                }
+                "state" => {
+                    let (name, outputs) = rest.split_once('/').unwrap_or((rest, ""));
+                    let name = identifier(line_no, name.trim())?;
+                    if fsm.state_idx(name).is_some() {
+                        return Err(FsmError::Duplicate(line_no, name.to_string()));
+                    }
+                    let mut values = vec![0; fsm.outputs.len()];
+                    for (output, value) in fsm.parse_outputs(line_no, outputs)? {
+                        values[output] = value;
+                    }
+                    fsm.states.push(State {
+                        name: name.to_string(),
+                        outputs: values,
+                    });
+                }
+                _ => return Err(syntax(line_no, &format!("unknown statement {keyword:?}"))),
+            }
+        }
+        if fsm.states.is_empty() {
+            return Err(FsmError::NoStates);
+        }
+
+        for raw in raw_transitions {
+            let from = fsm
+                .state_idx(&raw.from)
+                .ok_or(FsmError::UnknownState(raw.line, raw.from))?;
+            let to = fsm
+                .state_idx(&raw.to)
+                .ok_or(FsmError::UnknownState(raw.line, raw.to))?;
+            let mut outputs = fsm.states[from].outputs.clone();
+            for (output, value) in raw.outputs.unwrap_or_default() {
+                outputs[output] = value;
+            }
+            fsm.transitions.push(Transition {
+                from,
+                to,
+                condition: raw.condition,
+                outputs,
+            });
+        }
+        Ok(fsm)
+    }
+
+    /// Parses `from -> to [when condition] [/ out=value, ...]`.
+    fn parse_transition(&self, line_no: usize, line: &str) -> Result<RawTransition, FsmError> {
+        let (transition, outputs) = match line.split_once('/') {
+            Some((transition, outputs)) => {
+                if self.fsm_type == FsmType::Moore {
+                    return Err(FsmError::TransitionOutputs(line_no));
+                }
+                (transition, Some(self.parse_outputs(line_no, outputs)?))
+            }
+            None => (line, None),
+        };
+        let (from, rest) = transition.split_once("->").unwrap();
+        let (to, condition) = match rest.trim().split_once(char::is_whitespace) {
+            Some((to, condition)) => {
+                let Some(condition) = condition.trim().strip_prefix("when") else {
+                    return Err(syntax(line_no, "expected `when` after the target state"));
+                };
+                (to, self.parse_condition(line_no, condition)?)
+            }
+            None => (rest.trim(), Expr::Const(true)),
+        };
+        Ok(RawTransition {
+            line: line_no,
+            from: identifier(line_no, from.trim())?.to_string(),
+            to: identifier(line_no, to)?.to_string(),
+            condition,
+            outputs,
+        })
+    }
+
+    fn parse_condition(&self, line_no: usize, src: &str) -> Result<Expr, FsmError> {
+        let condition = Expr::parse(src).map_err(|err| match err {
+            SynthError::Syntax(_, msg) => FsmError::Syntax(line_no, msg),
+            err => err.into(),
+        })?;
+        let mut vars = vec![];
+        condition.vars(&mut vars);
+        if let Some(var) = vars.into_iter().find(|var| !self.inputs.contains(var)) {
+            return Err(FsmError::UnknownInput(line_no, var));
+        }
+        Ok(condition)
+    }
+
+    /// Parses `out=value, ...` into output indices and values.
+    fn parse_outputs(&self, line_no: usize, src: &str) -> Result<Vec<(usize, usize)>, FsmError> {
+        list(src)
+            .map(|item| {
+                let Some((name, value)) = item.split_once('=') else {
+                    return Err(syntax(line_no, "expected `output=value`"));
+                };
+                let name = name.trim();
+                let output = self
+                    .outputs
+                    .iter()
+                    .position(|(output, _)| output == name)
+                    .ok_or_else(|| FsmError::UnknownOutput(line_no, name.to_string()))?;
+                let value = parse_value(value.trim())
+                    .ok_or_else(|| syntax(line_no, &format!("invalid value {value:?}")))?;
+                if value >> self.outputs[output].1 != 0 {
+                    return Err(FsmError::ValueOutOfRange(line_no, name.to_string(), value));
+                }
+                Ok((output, value))
+            })
+            .collect()
+    }
+
+    /// Checks the name of a new input or output.
+    fn new_name(&self, line_no: usize, name: &str) -> Result<String, FsmError> {
+        let name = identifier(line_no, name)?;
+        if RESERVED_NAMES.contains(&name) || name.starts_with('_') {
+            return Err(FsmError::ReservedName(line_no, name.to_string()));
+        }
+        if self.inputs.iter().any(|input| input == name)
+            || self.outputs.iter().any(|(output, _)| output == name)
+        {
+            return Err(FsmError::Duplicate(line_no, name.to_string()));
+        }
+        Ok(name.to_string())
+    }
+
+    fn state_idx(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|state| state.name == name)
+    }
+
+    /// Width of the state register.
+    pub fn state_bits(&self) -> usize {
+        match self.encoding {
+            Encoding::OneHot => self.states.len(),
+            Encoding::Binary | Encoding::Gray => {
+                (usize::BITS - (self.states.len() - 1).leading_zeros()).max(1) as usize
+            }
+        }
+    }
+
+    /// Code of a state in the state register.
+    pub fn code(&self, state: usize) -> usize {
+        match self.encoding {
+            Encoding::Binary => state,
+            Encoding::OneHot => 1 << state,
+            Encoding::Gray => state ^ (state >> 1),
+        }
+    }
+
+    /// First transition of a state whose condition holds.
+    fn taken(&self, state: usize, input: &impl Fn(&str) -> bool) -> Option<&Transition> {
+        self.transitions
+            .iter()
+            .find(|t| t.from == state && t.condition.eval(input))
+    }
+
+    /// State after a rising edge of the clock, without reset.
+    pub fn next_state(&self, state: usize, input: &impl Fn(&str) -> bool) -> usize {
+        self.taken(state, input).map_or(state, |t| t.to)
+    }
+
+    /// Values of the outputs in a state.
+    pub fn output_values(&self, state: usize, input: &impl Fn(&str) -> bool) -> &[usize] {
+        match (self.fsm_type, self.taken(state, input)) {
+            (FsmType::Mealy, Some(transition)) => &transition.outputs,
+            _ => &self.states[state].outputs,
+        }
+    }
+
+    /// AsmHDL component with the inputs `clk`, `rst` and those of the
+    /// machine.
+    pub fn to_asm(&self) -> AsmComponent {
+        let bits = self.state_bits();
+        let state_code = |state: usize| AsmExpr::Const(Data::new(self.code(state), bits));
+        let state = || AsmExpr::Var("_state".into());
+        let mut cmds = edge_cmds("_out");
+        cmds.extend([
+            cmp_high(AsmExpr::Var("rst".into())),
+            AsmCommand::Jne {
+                label: "_next".into(),
+            },
+            mov("_state", state_code(0)),
+            goto("_out"),
+            label("_next"),
+        ]);
+
+        // Next state, on rising edges
+        for i in 0..self.states.len() {
+            cmds.push(AsmCommand::Cmp {
+                v1: state(),
+                v2: state_code(i),
+            });
+            cmds.push(AsmCommand::Je {
+                label: format!("_n{i}"),
+            });
+        }
+        cmds.push(goto("_out"));
+        for i in 0..self.states.len() {
+            cmds.push(label(&format!("_n{i}")));
+            for (t, transition) in self.transitions.iter().enumerate() {
+                if transition.from == i {
+                    cmds.push(cmp_high(asm_expr(&transition.condition)));
+                    cmds.push(AsmCommand::Je {
+                        label: format!("_t{t}"),
+                    });
+                }
+            }
+            cmds.push(goto("_out"));
+        }
+        for (t, transition) in self.transitions.iter().enumerate() {
+            cmds.push(label(&format!("_t{t}")));
+            cmds.push(mov("_state", state_code(transition.to)));
+            cmds.push(goto("_out"));
+        }
+
+        // Outputs of the current state
+        cmds.push(label("_out"));
+        for i in 0..self.states.len() {
+            cmds.push(AsmCommand::Cmp {
+                v1: state(),
+                v2: state_code(i),
+            });
+            cmds.push(AsmCommand::Je {
+                label: format!("_o{i}"),
+            });
+        }
+        cmds.push(goto("_end"));
+        for (i, state) in self.states.iter().enumerate() {
+            cmds.push(label(&format!("_o{i}")));
+            let transitions: Vec<(usize, &Transition)> = match self.fsm_type {
+                FsmType::Moore => vec![],
+                FsmType::Mealy => self
+                    .transitions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| t.from == i)
+                    .collect(),
+            };
+            for (t, transition) in &transitions {
+                cmds.push(cmp_high(asm_expr(&transition.condition)));
+                cmds.push(AsmCommand::Je {
+                    label: format!("_o{i}_{t}"),
+                });
+            }
+            cmds.extend(self.output_cmds(&state.outputs));
+            for (t, transition) in transitions {
+                cmds.push(label(&format!("_o{i}_{t}")));
+                cmds.extend(self.output_cmds(&transition.outputs));
+            }
+        }
+        cmds.push(label("_end"));
+
+        let mut comp = AsmComponent::new(&self.name)
+            .with_description(match self.fsm_type {
+                FsmType::Moore => "Moore state machine",
+                FsmType::Mealy => "Mealy state machine",
+            })
+            .with_update(AsmProgramUpdateType::InputChanges)
+            .with_input("clk", 1)
+            .with_input("rst", 1);
+        for name in &self.inputs {
+            comp = comp.with_input(name, 1);
+        }
+        for (name, width) in &self.outputs {
+            comp = comp.with_output(name, *width);
+        }
+        comp.with_default("_last_clk", Data::low())
+            .with_default("_state", Data::new(self.code(0), bits))
+            .with_cmds(cmds)
+    }
+
+    /// Sets the outputs and jumps to the end of the program.
+    fn output_cmds(&self, values: &[usize]) -> Vec<AsmCommand> {
+        let mut cmds: Vec<AsmCommand> = self
+            .outputs
+            .iter()
+            .zip(values)
+            .map(|((name, width), value)| mov(name, AsmExpr::Const(Data::new(*value, *width))))
+            .collect();
+        cmds.push(goto("_end"));
+        cmds
+    }
+
+    /// Register holding the state, which starts in the initial state.
+    fn state_register(&self) -> AsmComponent {
+        let bits = self.state_bits();
+        let mut cmds = edge_cmds("_end");
+        cmds.extend([
+            mov("_q", AsmExpr::Var("D".into())),
+            label("_end"),
+            mov("Q", AsmExpr::Var("_q".into())),
+        ]);
+        AsmComponent::new(&format!("{}_state", self.name))
+            .with_description("State register, loaded on rising edges of the clock")
+            .with_update(AsmProgramUpdateType::InputChanges)
+            .with_input("D", bits)
+            .with_input("clk", 1)
+            .with_output("Q", bits)
+            .with_default("_last_clk", Data::low())
+            .with_default("_q", Data::new(self.code(0), bits))
+            .with_cmds(cmds)
+    }
+
+    /// Next state and outputs as functions of the state register, `rst` and
+    /// the inputs. Codes of no state are don't cares.
+    fn logic(&self) -> Result<Logic, FsmError> {
+        let bits = self.state_bits();
+        let var_count = bits + 1 + self.inputs.len();
+        if var_count > MAX_INPUT_BITS {
+            return Err(SynthError::TooManyInputs(var_count).into());
+        }
+
+        let mut inputs = vec![("state".to_string(), bits), ("rst".to_string(), 1)];
+        inputs.extend(self.inputs.iter().map(|name| (name.clone(), 1)));
+        let mut outputs = vec![("next".to_string(), bits)];
+        outputs.extend(self.outputs.iter().cloned());
+        let out_bits: usize = outputs.iter().map(|(_, width)| width).sum();
+        let mut functions = vec![Function::default(); out_bits];
+
+        let states: HashMap<usize, usize> =
+            (0..self.states.len()).map(|i| (self.code(i), i)).collect();
+        let in_count = self.inputs.len();
+        for combination in 0..1usize << var_count {
+            let code = combination >> (in_count + 1);
+            let rst = (combination >> in_count) & 1 == 1;
+            let input = |name: &str| {
+                let idx = self.inputs.iter().position(|i| i == name).unwrap();
+                (combination >> (in_count - 1 - idx)) & 1 == 1
+            };
+
+            // Values of the outputs, none if they don't matter
+            let (next, values) = match states.get(&code) {
+                Some(&state) => {
+                    let next = if rst {
+                        0
+                    } else {
+                        self.next_state(state, &input)
+                    };
+                    (Some(next), Some(self.output_values(state, &input)))
+                }
+                None => (rst.then_some(0), None),
+            };
+            let mut bit = 0;
+            let mut set = |value: Option<usize>, width: usize| {
+                for b in (0..width).rev() {
+                    match value {
+                        Some(value) if (value >> b) & 1 == 1 => functions[bit].on.push(combination),
+                        Some(_) => (),
+                        None => functions[bit].dont_care.push(combination),
+                    }
+                    bit += 1;
+                }
+            };
+            set(next.map(|state| self.code(state)), bits);
+            for (i, (_, width)) in self.outputs.iter().enumerate() {
+                set(values.map(|values| values[i]), *width);
+            }
+        }
+        Ok(Logic {
+            inputs,
+            outputs,
+            functions,
+        })
+    }
+
+    /// Builds the machine into a component with the inputs `clk`, `rst`
+    /// and those of the machine.
+    pub fn build(&self, target: FsmTarget) -> Result<Component<ExtraInfo>, FsmError> {
+        let (mut sub_comps, mut last_id) = match target {
+            FsmTarget::Asm => (vec![], 0),
+            FsmTarget::Gates(style) => {
+                let logic = self
+                    .logic()?
+                    .build(&format!("{}_logic", self.name), style)?;
+                let last_id = logic.id;
+                (vec![logic], last_id)
+            }
+        };
+        let mut next_id = || {
+            last_id += 1;
+            last_id
+        };
+
+        let mut in_ports = vec![("clk", 1), ("rst", 1)];
+        in_ports.extend(self.inputs.iter().map(|name| (name.as_str(), 1)));
+        let mut comps = vec![];
+        for (name, width) in &in_ports {
+            let mut comp = input(next_id(), *width);
+            comp.instance = Some(name.to_string());
+            comps.push(comp);
+        }
+        for (name, width) in &self.outputs {
+            let mut comp = output(next_id(), *width);
+            comp.instance = Some(name.clone());
+            comps.push(comp);
+        }
+
+        // Index of the first component after the inputs and outputs
+        let core = comps.len();
+        let out_start = in_ports.len();
+        let mut connections = vec![];
+        match target {
+            FsmTarget::Asm => {
+                comps.push(custom(next_id(), self.to_asm()));
+                for i in 0..in_ports.len() {
+                    connections.push(Conn::new(i, 0, core, i));
+                }
+                for j in 0..self.outputs.len() {
+                    connections.push(Conn::new(core, j, out_start + j, 0));
+                }
+            }
+            FsmTarget::Gates(_) => {
+                let register = core + 1;
+                comps.append(&mut sub_comps);
+                comps.push(custom(next_id(), self.state_register()));
+                connections.push(Conn::new(0, 0, register, 1));
+                connections.push(Conn::new(register, 0, core, 0));
+                connections.push(Conn::new(core, 0, register, 0));
+                for i in 1..in_ports.len() {
+                    connections.push(Conn::new(i, 0, core, i));
+                }
+                for j in 0..self.outputs.len() {
+                    connections.push(Conn::new(core, j + 1, out_start + j, 0));
+                }
+            }
+        }
+
+        let id = next_id();
+        Ok(ComponentBuilder::new(id)
+            .name(self.name.clone())
+            .input_ports(
+                in_ports
+                    .iter()
+                    .map(|(name, width)| PortDesc::input(*name, *width))
+                    .collect(),
+            )
+            .output_ports(
+                self.outputs
+                    .iter()
+                    .map(|(name, width)| PortDesc::output(name, *width))
+                    .collect(),
+            )
+            .sub_comps(comps)
+            .connections(connections)
+            .in_addrs((0..in_ports.len()).map(|i| (i, (i, 0))).collect())
+            .out_addrs(
+                (0..self.outputs.len())
+                    .map(|j| (out_start + j, 0))
+                    .collect(),
+            )
+            .extra(ExtraInfo::new(id))
+            .build()?)
+    }
+}
+
+fn syntax(line_no: usize, msg: &str) -> FsmError {
+    FsmError::Syntax(line_no, msg.to_string())
+}
+
+/// Items separated by commas or spaces.
+fn list(src: &str) -> impl Iterator<Item = &str> {
+    src.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+}
+
+/// Checks that a name is valid in AsmHDL code.
+fn identifier(line_no: usize, name: &str) -> Result<&str, FsmError> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(name)
+    } else {
+        Err(syntax(line_no, &format!("invalid name {name:?}")))
+    }
+}
+
+/// Parses a decimal, `0b` binary or `0x` hexadecimal value.
+fn parse_value(src: &str) -> Option<usize> {
+    if let Some(bin) = src.strip_prefix("0b") {
+        usize::from_str_radix(bin, 2).ok()
+    } else if let Some(hex) = src.strip_prefix("0x") {
+        usize::from_str_radix(hex, 16).ok()
+    } else {
+        src.parse().ok()
+    }
+}
+
+fn asm_expr(expr: &Expr) -> AsmExpr {
+    let all = |exprs: &[Expr]| exprs.iter().map(asm_expr).collect();
+    match expr {
+        Expr::Const(c) => AsmExpr::Const(Data::new(usize::from(*c), 1)),
+        Expr::Var(name) => AsmExpr::Var(name.clone()),
+        Expr::Not(expr) => AsmExpr::Not(Box::new(asm_expr(expr))),
+        Expr::And(exprs) => AsmExpr::And(all(exprs)),
+        Expr::Or(exprs) => AsmExpr::Or(all(exprs)),
+        Expr::Xor(exprs) => AsmExpr::Xor(all(exprs)),
+    }
+}
+
+/// Detects a rising edge of `clk`, jumping to `label` if there is none.
+fn edge_cmds(label: &str) -> Vec<AsmCommand> {
+    let clk = || AsmExpr::Var("clk".into());
+    let last_clk = || AsmExpr::Var("_last_clk".into());
+    vec![
+        mov(
+            "_edge",
+            AsmExpr::And(vec![clk(), AsmExpr::Not(Box::new(last_clk()))]),
+        ),
+        mov("_last_clk", clk()),
+        cmp_high(AsmExpr::Var("_edge".into())),
+        AsmCommand::Jne {
+            label: label.into(),
+        },
+    ]
+}
+
+fn mov(name: &str, value: AsmExpr) -> AsmCommand {
+    AsmCommand::Mov {
+        name: name.into(),
+        value,
+    }
+}
+
+fn label(name: &str) -> AsmCommand {
+    AsmCommand::Label { name: name.into() }
+}
+
+fn goto(label: &str) -> AsmCommand {
+    AsmCommand::Goto {
+        label: label.into(),
+    }
+}
+
+fn cmp_high(value: AsmExpr) -> AsmCommand {
+    AsmCommand::Cmp {
+        v1: value,
+        v2: AsmExpr::Const(Data::high()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logix_sim::{
+        flatten::FlattenComponent,
+        headless::{top_level_ports, HeadlessSimulator},
+    };
+
+    use super::*;
+
+    const DETECTOR: &str = "
+        # Detects two ones in a row
+        name Detector
+        type mealy
+        inputs x
+        outputs z, count:2
+
+        state idle / count=0
+        state one / count=1
+        state many / count=0b10
+        idle -> one when x
+        one -> many when x / z=1
+        many -> many when x / z=1
+        one -> idle
+        many -> idle
+    ";
+
+    const COUNTER: &str = "
+        name Counter
+        inputs up, en
+        outputs q:2
+        state s0
+        state s1 / q=1
+        state s2 / q=2
+        state s3 / q=3
+        s0 -> s1 when en & up
+        s1 -> s2 when en & up
+        s2 -> s3 when en & up
+        s3 -> s0 when en & up
+        s0 -> s3 when en & !up
+        s1 -> s0 when en & !up
+        s2 -> s1 when en & !up
+        s3 -> s2 when en & !up
+    ";
+
+    /// Simulates the built machine along the model for some clock cycles.
+    fn check_sim(fsm: &Fsm, target: FsmTarget) {
+        let comp = fsm.build(target).unwrap();
+        let comp = FlattenComponent::new(comp).unwrap();
+        let (ins, outs) = top_level_ports(&comp);
+        let mut sim = HeadlessSimulator::new(comp);
+        sim.settle().unwrap();
+
+        let mut state = 0;
+        for cycle in 0..40usize {
+            // Pseudo-random inputs, with a reset in the middle
+            let bits = (cycle * 7 + cycle / 3) % 5;
+            let rst = cycle == 20;
+            let input = |name: &str| {
+                let idx = fsm.inputs.iter().position(|i| i == name).unwrap();
+                (bits >> idx) & 1 == 1
+            };
+            sim.set_input(ins[1].id, Data::new(rst as usize, 1))
+                .unwrap();
+            for (i, port) in ins[2..].iter().enumerate() {
+                let value = input(&fsm.inputs[i]) as usize;
+                sim.set_input(port.id, Data::new(value, 1)).unwrap();
+            }
+            sim.settle().unwrap();
+            sim.set_input(ins[0].id, Data::high()).unwrap();
+            sim.settle().unwrap();
+            state = if rst {
+                0
+            } else {
+                fsm.next_state(state, &input)
+            };
+
+            let values: Vec<usize> = outs
+                .iter()
+                .map(|p| sim.output(p.id).unwrap().value)
+                .collect();
+            assert_eq!(values, fsm.output_values(state, &input), "cycle {cycle}");
+            sim.set_input(ins[0].id, Data::low()).unwrap();
+            sim.settle().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let fsm = Fsm::parse(DETECTOR).unwrap();
+        assert_eq!(fsm.name, "Detector");
+        assert_eq!(fsm.fsm_type, FsmType::Mealy);
+        assert_eq!(fsm.outputs, vec![("z".into(), 1), ("count".into(), 2)]);
+        assert_eq!(fsm.states[2].outputs, vec![0, 2]);
+        // Transition outputs override those of the state
+        assert_eq!(fsm.transitions[1].outputs, vec![1, 1]);
+        assert_eq!(fsm.transitions[3].condition, Expr::Const(true));
+
+        let errors = [
+            ("state a\na -> b", "Line 2: unknown state b"),
+            (
+                "inputs x\nstate a\na -> a when y",
+                "Line 3: unknown input y",
+            ),
+            (
+                "outputs z\nstate a / z=2",
+                "Line 2: value 2 does not fit in output z",
+            ),
+            ("inputs clk", "Line 1: clk is a reserved name"),
+            (
+                "outputs z\nstate a\na -> a / z=1",
+                "Line 3: only Mealy machines can set outputs in transitions",
+            ),
+            ("inputs x", "No states defined"),
+        ];
+        for (code, msg) in errors {
+            assert_eq!(Fsm::parse(code).unwrap_err().to_string(), msg);
+        }
+    }
+
+    #[test]
+    fn test_encodings() {
+        let mut fsm = Fsm::parse(COUNTER).unwrap();
+        assert_eq!(fsm.state_bits(), 2);
+        assert_eq!(
+            (0..4).map(|i| fsm.code(i)).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        fsm.encoding = Encoding::Gray;
+        assert_eq!(
+            (0..4).map(|i| fsm.code(i)).collect::<Vec<_>>(),
+            [0, 1, 3, 2]
+        );
+        fsm.encoding = Encoding::OneHot;
+        assert_eq!(fsm.state_bits(), 4);
+        assert_eq!(fsm.code(3), 0b1000);
+
+        // The generated code can be read back
+        let asm = fsm.to_asm();
+        let parsed = AsmComponent::from_code(&asm.to_code());
+        assert_eq!(parsed.to_code(), asm.to_code());
+    }
+
+    #[test]
+    fn test_simulate() {
+        for code in [DETECTOR, COUNTER] {
+            let mut fsm = Fsm::parse(code).unwrap();
+            for encoding in [Encoding::Binary, Encoding::OneHot, Encoding::Gray] {
+                fsm.encoding = encoding;
+                check_sim(&fsm, FsmTarget::Asm);
+                check_sim(&fsm, FsmTarget::Gates(GateStyle::AndOrNot));
+                check_sim(&fsm, FsmTarget::Gates(GateStyle::Nand));
+            }
+        }
+    }
+}
@@ -1,5 +1,16 @@
 mod ast;
+mod blif;
 mod builder;
+mod elaborate;
+mod fsm;
+mod netlist;
 mod primitive_builders;
+mod synth;
+mod verilog;
 
-pub use builder::build_from_file;
+pub use blif::build_from_blif;
+pub use builder::{build_from_file, BuildError};
+pub use fsm::{Encoding, Fsm, FsmError, FsmTarget, FsmType, State, Transition};
+pub use netlist::NetlistError;
+pub use synth::{Expr, Function, GateStyle, Logic, SynthError};
+pub use verilog::build_from_verilog;
@@ -0,0 +1,455 @@
+//! Gate-level netlists shared by the Verilog and BLIF importers.
+//!
+//! Parsers lower their input into single bit signals connected by gates,
+//! aliases and module instances. Multi-bit ports are split and joined when
+//! the netlist is built into components.
+
+use std::collections::HashMap;
+
+use log::warn;
+use logix_core::{
+    component::{Component, ComponentBuilder, Conn, PortAddr, PortDesc},
+    errors::BuildComponentError,
+};
+use logix_sim::primitives::primitive::ExtraInfo;
+use thiserror::Error;
+
+use crate::primitive_builders::{
+    and_gate, high_const, input, joiner, low_const, nand_gate, nor_gate, not_gate, or_gate, output,
+    splitter, xor_gate,
+};
+
+#[derive(Debug, Clone, Error)]
+pub enum NetlistError {
+    #[error("Line {0}: {1}")]
+    Syntax(usize, String),
+
+    #[error("Line {0}: unsupported construct: {1}")]
+    Unsupported(usize, String),
+
+    #[error("Module not found: {0}")]
+    ModuleNotFound(String),
+
+    #[error("No top module found")]
+    NoTopModule,
+
+    #[error("Module {0} instantiates itself")]
+    RecursiveModule(String),
+
+    #[error("Module {0} has no port {1}")]
+    PortNotFound(String, String),
+
+    #[error("Instance {0} connects {2} ports, module has {1}")]
+    PortCountMismatch(String, usize, usize),
+
+    #[error("Width mismatch in {0} ({1} bits to {2} bits)")]
+    WidthMismatch(String, usize, usize),
+
+    #[error("Bit index {1} out of range in: {0}")]
+    BitIndexOutOfRange(String, usize),
+
+    #[error("Net {1} in module {0} has more than one driver")]
+    MultipleDrivers(String, String),
+
+    #[error("Invalid component.\n{0}")]
+    InvalidComponent(#[from] BuildComponentError),
+}
+
+/// Single bit signal: a bit of a net (starting at the least significant one)
+/// or a constant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Signal {
+    Net(String, usize),
+    Const(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateKind {
+    And,
+    Or,
+    Nand,
+    Nor,
+    Xor,
+    Xnor,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+pub enum InstancePorts {
+    /// Signals by port name, least significant bit first.
+    Named(Vec<(String, Vec<Signal>)>),
+    /// Signals in the order of the module ports.
+    Positional(Vec<Vec<Signal>>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Cell {
+    Gate {
+        kind: GateKind,
+        name: Option<String>,
+        inputs: Vec<Signal>,
+        output: Signal,
+    },
+    /// Both signals are the same wire.
+    Alias(Signal, Signal),
+    Instance {
+        module: String,
+        name: String,
+        ports: InstancePorts,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetModule {
+    pub name: String,
+    /// Input ports with their width.
+    pub inputs: Vec<(String, usize)>,
+    /// Output ports with their width.
+    pub outputs: Vec<(String, usize)>,
+    pub cells: Vec<Cell>,
+}
+
+impl NetModule {
+    fn port(&self, name: &str) -> Option<(usize, usize)> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .enumerate()
+            .find(|(_, (port, _))| port == name)
+            .map(|(i, (_, width))| (i, *width))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Netlist {
+    pub modules: Vec<NetModule>,
+    /// Top module. If not given, the last module that is not instantiated by
+    /// any other is used.
+    pub top: Option<String>,
+}
+
+impl Netlist {
+    fn module(&self, name: &str) -> Result<&NetModule, NetlistError> {
+        self.modules
+            .iter()
+            .find(|module| module.name == name)
+            .ok_or_else(|| NetlistError::ModuleNotFound(name.to_string()))
+    }
+
+    fn top(&self) -> Result<&NetModule, NetlistError> {
+        if let Some(top) = &self.top {
+            return self.module(top);
+        }
+        let instantiated: Vec<&str> = self
+            .modules
+            .iter()
+            .flat_map(|module| &module.cells)
+            .filter_map(|cell| match cell {
+                Cell::Instance { module, .. } => Some(module.as_str()),
+                _ => None,
+            })
+            .collect();
+        self.modules
+            .iter()
+            .rev()
+            .find(|module| !instantiated.contains(&module.name.as_str()))
+            .ok_or(NetlistError::NoTopModule)
+    }
+
+    /// Builds the top module. Port pins, named gates and module instances
+    /// keep their names as instance names.
+    pub fn build(&self) -> Result<Component<ExtraInfo>, NetlistError> {
+        self.build_module(self.top()?, &mut vec![], &mut 0)
+    }
+
+    fn build_module(
+        &self,
+        module: &NetModule,
+        stack: &mut Vec<String>,
+        last_id: &mut usize,
+    ) -> Result<Component<ExtraInfo>, NetlistError> {
+        if stack.contains(&module.name) {
+            return Err(NetlistError::RecursiveModule(module.name.clone()));
+        }
+        stack.push(module.name.clone());
+
+        let mut builder = ModuleBuilder::default();
+        let mut in_addrs = vec![];
+        let mut out_addrs = vec![];
+        for (i, (name, width)) in module.inputs.iter().enumerate() {
+            let idx = builder.add(input, *width, last_id);
+            builder.comps[idx].instance = Some(name.clone());
+            in_addrs.push((i, (idx, 0)));
+            builder.drive_bits(name, *width, (idx, 0), last_id);
+        }
+        for (name, width) in &module.outputs {
+            let idx = builder.add(output, *width, last_id);
+            builder.comps[idx].instance = Some(name.clone());
+            out_addrs.push((idx, 0));
+            let bits: Vec<Signal> = (0..*width)
+                .map(|bit| Signal::Net(name.clone(), bit))
+                .collect();
+            builder.sink_bits(&bits, (idx, 0), last_id);
+        }
+
+        for cell in &module.cells {
+            match cell {
+                Cell::Gate {
+                    kind,
+                    name,
+                    inputs,
+                    output,
+                } => {
+                    let count = inputs.len();
+                    let gate = |id| match kind {
+                        GateKind::And => and_gate(id, count),
+                        GateKind::Or => or_gate(id, count),
+                        GateKind::Nand => nand_gate(id, count),
+                        GateKind::Nor => nor_gate(id, count),
+                        GateKind::Xor | GateKind::Xnor => xor_gate(id, count),
+                        GateKind::Not => not_gate(id),
+                    };
+                    let idx = builder.push(gate(next_id(last_id)));
+                    if let Some(name) = name {
+                        builder.comps[idx].instance = Some(name.clone());
+                    }
+                    for (i, signal) in inputs.iter().enumerate() {
+                        builder.sink(signal, (idx, i), last_id);
+                    }
+                    let out = if *kind == GateKind::Xnor {
+                        let not = builder.push(not_gate(next_id(last_id)));
+                        builder.conns.push(Conn::new(idx, 0, not, 0));
+                        not
+                    } else {
+                        idx
+                    };
+                    builder.drive(output, (out, 0), last_id);
+                }
+                Cell::Alias(a, b) => {
+                    let (a, b) = (builder.signal(a, last_id), builder.signal(b, last_id));
+                    builder.union(a, b);
+                }
+                Cell::Instance {
+                    module: module_name,
+                    name,
+                    ports,
+                } => {
+                    let sub_module = self.module(module_name)?;
+                    let mut comp = self.build_module(sub_module, stack, last_id)?;
+                    comp.instance = Some(name.clone());
+                    let idx = builder.push(comp);
+
+                    let port_count = sub_module.inputs.len() + sub_module.outputs.len();
+                    let signals: Vec<(usize, &Vec<Signal>)> = match ports {
+                        InstancePorts::Named(ports) => ports
+                            .iter()
+                            .map(|(port, signals)| {
+                                let (i, _) = sub_module.port(port).ok_or_else(|| {
+                                    NetlistError::PortNotFound(module_name.clone(), port.clone())
+                                })?;
+                                Ok((i, signals))
+                            })
+                            .collect::<Result<_, NetlistError>>()?,
+                        InstancePorts::Positional(ports) => {
+                            if ports.len() != port_count {
+                                return Err(NetlistError::PortCountMismatch(
+                                    name.clone(),
+                                    port_count,
+                                    ports.len(),
+                                ));
+                            }
+                            ports.iter().enumerate().collect()
+                        }
+                    };
+
+                    for (i, signals) in signals {
+                        let (port, width) = sub_module
+                            .inputs
+                            .iter()
+                            .chain(&sub_module.outputs)
+                            .nth(i)
+                            .expect("Port index checked above");
+                        if signals.len() != *width {
+                            return Err(NetlistError::WidthMismatch(
+                                format!("{name}.{port}"),
+                                signals.len(),
+                                *width,
+                            ));
+                        }
+                        match i.checked_sub(sub_module.inputs.len()) {
+                            None => builder.sink_bits(signals, (idx, i), last_id),
+                            Some(out) => {
+                                builder.drive_signals(signals, (idx, out), last_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let conns = builder.connect(&module.name)?;
+        stack.pop();
+
+        *last_id += 1;
+        Ok(ComponentBuilder::new(*last_id)
+            .name(module.name.clone())
+            .input_ports(
+                module
+                    .inputs
+                    .iter()
+                    .map(|(name, width)| PortDesc::input(name, *width))
+                    .collect(),
+            )
+            .output_ports(
+                module
+                    .outputs
+                    .iter()
+                    .map(|(name, width)| PortDesc::output(name, *width))
+                    .collect(),
+            )
+            .sub_comps(builder.comps)
+            .connections(conns)
+            .in_addrs(in_addrs)
+            .out_addrs(out_addrs)
+            .extra(ExtraInfo::new(*last_id))
+            .build()?)
+    }
+}
+
+fn next_id(last_id: &mut usize) -> usize {
+    *last_id += 1;
+    *last_id
+}
+
+/// Components of a module being built and the signals they drive and read.
+///
+/// Signals are merged with a union-find as aliases are found, so drivers
+/// and sinks are only matched once the whole module is known.
+#[derive(Default)]
+struct ModuleBuilder {
+    comps: Vec<Component<ExtraInfo>>,
+    conns: Vec<Conn>,
+    signals: HashMap<Signal, usize>,
+    parents: Vec<usize>,
+    drivers: Vec<(usize, PortAddr)>,
+    sinks: Vec<(usize, PortAddr)>,
+}
+
+impl ModuleBuilder {
+    fn push(&mut self, comp: Component<ExtraInfo>) -> usize {
+        self.comps.push(comp);
+        self.comps.len() - 1
+    }
+
+    fn add(
+        &mut self,
+        build: fn(usize, usize) -> Component<ExtraInfo>,
+        width: usize,
+        last_id: &mut usize,
+    ) -> usize {
+        let id = next_id(last_id);
+        self.push(build(id, width))
+    }
+
+    fn signal(&mut self, signal: &Signal, last_id: &mut usize) -> usize {
+        if let Some(id) = self.signals.get(signal) {
+            return *id;
+        }
+        let id = self.parents.len();
+        self.parents.push(id);
+        self.signals.insert(signal.clone(), id);
+        if let Signal::Const(value) = signal {
+            let build = if *value { high_const } else { low_const };
+            let idx = self.push(build(next_id(last_id)));
+            self.drivers.push((id, (idx, 0)));
+        }
+        id
+    }
+
+    fn find(&mut self, id: usize) -> usize {
+        let mut root = id;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        self.parents[id] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+
+    fn drive(&mut self, signal: &Signal, from: PortAddr, last_id: &mut usize) {
+        let id = self.signal(signal, last_id);
+        self.drivers.push((id, from));
+    }
+
+    fn sink(&mut self, signal: &Signal, to: PortAddr, last_id: &mut usize) {
+        let id = self.signal(signal, last_id);
+        self.sinks.push((id, to));
+    }
+
+    /// Drives the bits of a net from a (possibly multi-bit) output port.
+    fn drive_bits(&mut self, net: &str, width: usize, from: PortAddr, last_id: &mut usize) {
+        let bits: Vec<Signal> = (0..width)
+            .map(|bit| Signal::Net(net.to_string(), bit))
+            .collect();
+        self.drive_signals(&bits, from, last_id);
+    }
+
+    fn drive_signals(&mut self, bits: &[Signal], from: PortAddr, last_id: &mut usize) {
+        if let [bit] = bits {
+            self.drive(bit, from, last_id);
+            return;
+        }
+        let idx = self.add(splitter, bits.len(), last_id);
+        self.conns.push(Conn::new(from.0, from.1, idx, 0));
+        for (i, bit) in bits.iter().enumerate() {
+            self.drive(bit, (idx, i), last_id);
+        }
+    }
+
+    /// Reads signals into a (possibly multi-bit) input port.
+    fn sink_bits(&mut self, bits: &[Signal], to: PortAddr, last_id: &mut usize) {
+        if let [bit] = bits {
+            self.sink(bit, to, last_id);
+            return;
+        }
+        let idx = self.add(joiner, bits.len(), last_id);
+        self.conns.push(Conn::new(idx, 0, to.0, to.1));
+        for (i, bit) in bits.iter().enumerate() {
+            self.sink(bit, (idx, i), last_id);
+        }
+    }
+
+    fn connect(&mut self, module: &str) -> Result<Vec<Conn>, NetlistError> {
+        let names: HashMap<usize, String> = self
+            .signals
+            .iter()
+            .filter_map(|(signal, id)| match signal {
+                Signal::Net(name, bit) => Some((*id, format!("{name}[{bit}]"))),
+                Signal::Const(_) => None,
+            })
+            .collect();
+        let name = |id: usize| names.get(&id).cloned().unwrap_or_default();
+
+        let mut drivers: HashMap<usize, PortAddr> = HashMap::new();
+        for (id, from) in self.drivers.clone() {
+            let root = self.find(id);
+            if drivers.insert(root, from).is_some() {
+                return Err(NetlistError::MultipleDrivers(module.to_string(), name(id)));
+            }
+        }
+
+        let mut conns = std::mem::take(&mut self.conns);
+        for (id, to) in self.sinks.clone() {
+            let root = self.find(id);
+            match drivers.get(&root) {
+                Some(from) => conns.push(Conn::new(from.0, from.1, to.0, to.1)),
+                None => warn!("Net {} in module {} is not driven", name(id), module),
+            }
+        }
+        Ok(conns)
+    }
+}
@@ -1,6 +1,6 @@
-use asmhdl::Data;
+use asmhdl::{AsmComponent, Data};
 use logix_core::prelude::*;
-use logix_sim::primitives::prelude::{ExtraInfo, Primitive};
+use logix_sim::primitives::prelude::{ClockConfig, ExtraInfo, Primitive};
 
 fn base_component_extra(
     id: usize,
@@ -9,11 +9,25 @@ fn base_component_extra(
     info: ExtraInfo,
 ) -> Component<ExtraInfo> {
     let name = info.primitive.as_ref().unwrap().to_string();
+    named_component(id, name, in_count, out_count, info)
+}
+
+fn named_component(
+    id: usize,
+    name: String,
+    in_count: usize,
+    out_count: usize,
+    info: ExtraInfo,
+) -> Component<ExtraInfo> {
+    let prim = info.primitive.as_ref().unwrap();
+    let (in_ports, out_ports) = prim.port_descs(in_count, out_count);
     ComponentBuilder::new(id)
-        .port_count(in_count, out_count)
+        .input_ports(in_ports)
+        .output_ports(out_ports)
         .name(name)
         .extra(info)
         .build()
+        .expect("Components without sub-components are always valid")
 }
 
 pub fn not_gate(id: usize) -> Component<ExtraInfo> {
@@ -85,12 +99,18 @@ pub fn joiner(id: usize, bits: usize) -> Component<ExtraInfo> {
     base_component_extra(id, bits, 1, ExtraInfo::from_primitive(id, prim))
 }
 
-pub fn clock(id: usize, frec: f64) -> Component<ExtraInfo> {
-    let frec_in_nano = (1e9 / frec) as u128;
-    let prim = Primitive::Clock {
-        period: frec_in_nano,
-    };
-    base_component_extra(id, 0, 1, ExtraInfo::from_primitive(id, prim))
+pub fn clock(id: usize, clock: ClockConfig) -> Component<ExtraInfo> {
+    let prim = Primitive::Clock(clock);
+    base_component_extra(
+        id,
+        usize::from(clock.enable),
+        1,
+        ExtraInfo::from_primitive(id, prim),
+    )
+}
+
+pub fn switch(id: usize) -> Component<ExtraInfo> {
+    base_component_extra(id, 0, 1, ExtraInfo::from_primitive(id, Primitive::Switch))
 }
 
 pub fn high_const(id: usize) -> Component<ExtraInfo> {
@@ -104,3 +124,24 @@ pub fn low_const(id: usize) -> Component<ExtraInfo> {
     let prim = Primitive::Const { value: Data::low() };
     base_component_extra(id, 0, 1, ExtraInfo::from_primitive(id, prim))
 }
+
+pub fn const_value(id: usize, value: usize, width: usize) -> Component<ExtraInfo> {
+    let prim = Primitive::Const {
+        value: Data::new(value, width),
+    };
+    base_component_extra(id, 0, 1, ExtraInfo::from_primitive(id, prim))
+}
+
+pub fn custom(id: usize, comp: AsmComponent) -> Component<ExtraInfo> {
+    let (in_count, out_count) = (comp.inputs.len(), comp.outputs.len());
+    let name = comp.name.clone();
+    let state = comp.new_program_state();
+    let prim = Primitive::Custom { comp, state };
+    named_component(
+        id,
+        name,
+        in_count,
+        out_count,
+        ExtraInfo::from_primitive(id, prim),
+    )
+}
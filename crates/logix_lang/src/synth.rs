@@ -0,0 +1,780 @@
+//! Synthesis of boolean functions into gates, and extraction of minimized
+//! expressions from combinational circuits.
+//!
+//! Functions are given as equations (`f = (a & b) | !c`), truth tables or
+//! combinational circuits. Each output bit is minimized into a sum of
+//! products with the Quine–McCluskey method: the rows missing from a truth
+//! table are don't cares, which are used to merge more terms. The products
+//! are then built with and, or and not gates, or with nand gates only.
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use logix_core::component::Component;
+use logix_sim::{
+    errors::TruthTableError,
+    flatten::FlattenComponent,
+    headless::Port,
+    primitives::primitive::ExtraInfo,
+    truth_table::{TruthTable, MAX_INPUT_BITS},
+};
+use thiserror::Error;
+
+use crate::netlist::{Cell, GateKind, NetModule, Netlist, NetlistError, Signal};
+
+#[derive(Debug, Error)]
+pub enum SynthError {
+    #[error("Line {0}: {1}")]
+    Syntax(usize, String),
+
+    #[error("Output {0} is defined more than once")]
+    DuplicateOutput(String),
+
+    #[error("No outputs defined")]
+    NoOutputs,
+
+    #[error("Too many input bits ({0}), at most {MAX_INPUT_BITS} are supported")]
+    TooManyInputs(usize),
+
+    #[error("{0}")]
+    TruthTable(#[from] TruthTableError),
+
+    #[error("{0}")]
+    Netlist(#[from] NetlistError),
+}
+
+/// Boolean expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Const(bool),
+    Var(String),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Xor(Vec<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression with the operators `!` (or `~`), `&` (or `*`),
+    /// `^` and `|` (or `+`), from highest to lowest precedence, the
+    /// constants `0` and `1` and parentheses.
+    pub fn parse(src: &str) -> Result<Self, SynthError> {
+        ExprParser::new(src, 1, &HashMap::new()).parse()
+    }
+
+    pub fn eval(&self, value: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Expr::Const(c) => *c,
+            Expr::Var(name) => value(name),
+            Expr::Not(expr) => !expr.eval(value),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.eval(value)),
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.eval(value)),
+            Expr::Xor(exprs) => exprs.iter().fold(false, |acc, expr| acc ^ expr.eval(value)),
+        }
+    }
+
+    /// Adds the variables of the expression to `vars` in order of
+    /// appearance.
+    pub(crate) fn vars(&self, vars: &mut Vec<String>) {
+        match self {
+            Expr::Const(_) => (),
+            Expr::Var(name) => {
+                if !vars.contains(name) {
+                    vars.push(name.clone());
+                }
+            }
+            Expr::Not(expr) => expr.vars(vars),
+            Expr::And(exprs) | Expr::Or(exprs) | Expr::Xor(exprs) => {
+                exprs.iter().for_each(|expr| expr.vars(vars));
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(_) => 0,
+            Expr::Xor(_) => 1,
+            Expr::And(_) => 2,
+            Expr::Const(_) | Expr::Var(_) | Expr::Not(_) => 3,
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let operand = |expr: &Expr, f: &mut Formatter<'_>| {
+            if expr.precedence() <= self.precedence()
+                && matches!(expr, Expr::And(_) | Expr::Or(_) | Expr::Xor(_))
+            {
+                write!(f, "({expr})")
+            } else {
+                write!(f, "{expr}")
+            }
+        };
+        let join = |exprs: &[Expr], op: &str, f: &mut Formatter<'_>| {
+            for (i, expr) in exprs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {op} ")?;
+                }
+                operand(expr, f)?;
+            }
+            Ok(())
+        };
+        match self {
+            Expr::Const(c) => write!(f, "{}", u8::from(*c)),
+            Expr::Var(name) => write!(f, "{name}"),
+            Expr::Not(expr) => {
+                write!(f, "!")?;
+                operand(expr, f)
+            }
+            Expr::And(exprs) => join(exprs, "&", f),
+            Expr::Or(exprs) => join(exprs, "|", f),
+            Expr::Xor(exprs) => join(exprs, "^", f),
+        }
+    }
+}
+
+/// Recursive descent parser of an expression in a line of equations.
+struct ExprParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    /// Outputs defined by the previous equations, which are replaced by
+    /// their expressions.
+    outputs: &'a HashMap<String, Expr>,
+}
+
+impl<'a> ExprParser<'a> {
+    fn new(src: &str, line: usize, outputs: &'a HashMap<String, Expr>) -> Self {
+        ExprParser {
+            chars: src.chars().collect(),
+            pos: 0,
+            line,
+            outputs,
+        }
+    }
+
+    fn error(&self, msg: String) -> SynthError {
+        SynthError::Syntax(self.line, msg)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse(mut self) -> Result<Expr, SynthError> {
+        let expr = self.parse_binary(0)?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(c) => Err(self.error(format!("unexpected {c:?}"))),
+        }
+    }
+
+    /// Operands joined by the operators of `level` (`|`, `^` or `&`) or
+    /// higher.
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, SynthError> {
+        const OPERATORS: [&str; 3] = ["|+", "^", "&*"];
+        if level == OPERATORS.len() {
+            return self.parse_unary();
+        }
+        let mut operands = vec![self.parse_binary(level + 1)?];
+        while self.peek().is_some_and(|c| OPERATORS[level].contains(c)) {
+            self.pos += 1;
+            operands.push(self.parse_binary(level + 1)?);
+        }
+        if operands.len() == 1 {
+            return Ok(operands.pop().unwrap());
+        }
+        Ok(match level {
+            0 => Expr::Or(operands),
+            1 => Expr::Xor(operands),
+            _ => Expr::And(operands),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, SynthError> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("unexpected end of expression".into()));
+        };
+        self.pos += 1;
+        match c {
+            '!' | '~' => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            '(' => {
+                let expr = self.parse_binary(0)?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("expected ')'".into()));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            '0' | '1' => Ok(Expr::Const(c == '1')),
+            c if is_name_char(c) => {
+                let start = self.pos - 1;
+                while self.chars.get(self.pos).is_some_and(|c| is_name_char(*c)) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                Ok(self.outputs.get(&name).cloned().unwrap_or(Expr::Var(name)))
+            }
+            c => Err(self.error(format!("unexpected {c:?}"))),
+        }
+    }
+}
+
+/// Characters of variable names. Brackets allow the bits of multi-bit ports,
+/// as `a[0]`.
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_[]".contains(c)
+}
+
+/// Name of the variable of a bit of a port.
+fn bit_name(port: &str, width: usize, bit: usize) -> String {
+    if width == 1 {
+        port.to_string()
+    } else {
+        format!("{port}[{bit}]")
+    }
+}
+
+/// Bits of ports, most significant first.
+fn port_bits(ports: &[(String, usize)]) -> impl Iterator<Item = (&str, usize, usize)> {
+    ports.iter().flat_map(|(name, width)| {
+        (0..*width)
+            .rev()
+            .map(move |bit| (name.as_str(), *width, bit))
+    })
+}
+
+/// Boolean function given by the input combinations where it is high and
+/// those where its value doesn't matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Function {
+    pub on: Vec<usize>,
+    pub dont_care: Vec<usize>,
+}
+
+/// Boolean functions of the bits of some inputs.
+///
+/// Bits are numbered in a combination of the inputs as in a truth table:
+/// the most significant bit of the first input is the most significant bit
+/// of the combination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Logic {
+    /// Names and widths of the inputs.
+    pub inputs: Vec<(String, usize)>,
+    /// Names and widths of the outputs.
+    pub outputs: Vec<(String, usize)>,
+    /// Function of each bit of the outputs, most significant first.
+    pub functions: Vec<Function>,
+}
+
+impl Logic {
+    /// Parses equations like `f = (a & b) | !c`, one per line or separated
+    /// by `;`. Text after `#` is ignored. Outputs may be used in the
+    /// equations that follow them; any other name is an input.
+    pub fn from_equations(code: &str) -> Result<Self, SynthError> {
+        let mut outputs: Vec<(String, Expr)> = vec![];
+        let mut defined = HashMap::new();
+        for (i, line) in code.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            for equation in line.split(';').filter(|eq| !eq.trim().is_empty()) {
+                let Some((name, expr)) = equation.split_once('=') else {
+                    return Err(SynthError::Syntax(
+                        i + 1,
+                        format!("expected `name = expression`, found {:?}", equation.trim()),
+                    ));
+                };
+                let name = name.trim();
+                if name.is_empty() || !name.chars().all(is_name_char) {
+                    return Err(SynthError::Syntax(
+                        i + 1,
+                        format!("invalid output name {name:?}"),
+                    ));
+                }
+                if defined.contains_key(name) {
+                    return Err(SynthError::DuplicateOutput(name.to_string()));
+                }
+                let expr = ExprParser::new(expr, i + 1, &defined).parse()?;
+                defined.insert(name.to_string(), expr.clone());
+                outputs.push((name.to_string(), expr));
+            }
+        }
+        Self::from_exprs(&outputs)
+    }
+
+    /// Functions of named expressions. The inputs are the variables of the
+    /// expressions, in order of appearance.
+    pub fn from_exprs(outputs: &[(String, Expr)]) -> Result<Self, SynthError> {
+        if outputs.is_empty() {
+            return Err(SynthError::NoOutputs);
+        }
+        let mut vars = vec![];
+        for (_, expr) in outputs {
+            expr.vars(&mut vars);
+        }
+        if vars.len() > MAX_INPUT_BITS {
+            return Err(SynthError::TooManyInputs(vars.len()));
+        }
+
+        let mut functions = vec![Function::default(); outputs.len()];
+        for combination in 0..1usize << vars.len() {
+            let value = |name: &str| {
+                let var = vars.iter().position(|v| v == name).unwrap();
+                (combination >> (vars.len() - 1 - var)) & 1 == 1
+            };
+            for ((_, expr), function) in outputs.iter().zip(&mut functions) {
+                if expr.eval(&value) {
+                    function.on.push(combination);
+                }
+            }
+        }
+
+        Ok(Logic {
+            inputs: vars.into_iter().map(|var| (var, 1)).collect(),
+            outputs: outputs.iter().map(|(name, _)| (name.clone(), 1)).collect(),
+            functions,
+        })
+    }
+
+    /// Functions of the outputs of a truth table. Combinations of the inputs
+    /// missing from the table are don't cares.
+    pub fn from_truth_table(table: &TruthTable) -> Result<Self, SynthError> {
+        let inputs: Vec<(String, usize)> = table
+            .inputs
+            .iter()
+            .map(|col| (col.name.clone(), col.bits))
+            .collect();
+        let outputs: Vec<(String, usize)> = table
+            .outputs
+            .iter()
+            .map(|col| (col.name.clone(), col.bits))
+            .collect();
+        let total_bits: usize = inputs.iter().map(|(_, bits)| bits).sum();
+        if total_bits > MAX_INPUT_BITS {
+            return Err(SynthError::TooManyInputs(total_bits));
+        }
+
+        let out_bits = port_bits(&outputs).count();
+        let mut values: Vec<Option<Vec<bool>>> = vec![None; 1 << total_bits];
+        for row in &table.rows {
+            let combination = row
+                .inputs
+                .iter()
+                .zip(&inputs)
+                .fold(0, |acc, (value, (_, bits))| (acc << bits) | value);
+            let bits: Vec<bool> = outputs
+                .iter()
+                .zip(&row.outputs)
+                .flat_map(|((_, width), value)| {
+                    (0..*width).rev().map(move |bit| (value >> bit) & 1 == 1)
+                })
+                .collect();
+            values[combination] = Some(bits);
+        }
+
+        let mut functions = vec![Function::default(); out_bits];
+        for (combination, bits) in values.iter().enumerate() {
+            for (i, function) in functions.iter_mut().enumerate() {
+                match bits {
+                    Some(bits) if bits[i] => function.on.push(combination),
+                    Some(_) => (),
+                    None => function.dont_care.push(combination),
+                }
+            }
+        }
+        Ok(Logic {
+            inputs,
+            outputs,
+            functions,
+        })
+    }
+
+    /// Functions of the outputs of a combinational circuit, found by
+    /// enumerating its inputs.
+    pub fn from_circuit(
+        comp: FlattenComponent,
+        inputs: &[Port],
+        outputs: &[Port],
+    ) -> Result<Self, SynthError> {
+        Self::from_truth_table(&TruthTable::generate(comp, inputs, outputs)?)
+    }
+
+    fn var_names(&self) -> Vec<String> {
+        port_bits(&self.inputs)
+            .map(|(port, width, bit)| bit_name(port, width, bit))
+            .collect()
+    }
+
+    /// Minimized sums of products of the functions.
+    fn covers(&self) -> Vec<Vec<Cube>> {
+        let vars = port_bits(&self.inputs).count();
+        self.functions
+            .iter()
+            .map(|function| {
+                let terms = function.on.iter().chain(&function.dont_care).copied();
+                cover(&prime_implicants(vars, terms), &function.on)
+            })
+            .collect()
+    }
+
+    /// Minimized expression of each bit of the outputs, named `out` or
+    /// `out[bit]` for multi-bit outputs.
+    pub fn minimize(&self) -> Vec<(String, Expr)> {
+        let vars = self.var_names();
+        port_bits(&self.outputs)
+            .zip(self.covers())
+            .map(|((port, width, bit), cubes)| {
+                (bit_name(port, width, bit), sum_of_products(&cubes, &vars))
+            })
+            .collect()
+    }
+
+    /// Builds the minimized functions into a component named `name`.
+    pub fn build(&self, name: &str, style: GateStyle) -> Result<Component<ExtraInfo>, SynthError> {
+        let mut gates = GateBuilder {
+            style,
+            vars: port_bits(&self.inputs)
+                .map(|(port, _, bit)| Signal::Net(port.to_string(), bit))
+                .collect(),
+            ..Default::default()
+        };
+        let mut cells = vec![];
+        for ((port, _, bit), cubes) in port_bits(&self.outputs).zip(self.covers()) {
+            let signal = gates.sum(&cubes);
+            cells.push(Cell::Alias(Signal::Net(port.to_string(), bit), signal));
+        }
+        cells.extend(gates.cells);
+
+        let module = NetModule {
+            name: name.to_string(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            cells,
+        };
+        Ok(Netlist {
+            modules: vec![module],
+            top: None,
+        }
+        .build()?)
+    }
+}
+
+/// Product of literals: the variables set in `mask` must have the values in
+/// `value`. Variables are numbered from the most significant bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Cube {
+    mask: usize,
+    value: usize,
+}
+
+impl Cube {
+    fn covers(&self, combination: usize) -> bool {
+        combination & self.mask == self.value
+    }
+
+    /// Literals of the product, as variable indices and whether they are
+    /// negated.
+    fn literals(&self, vars: usize) -> Vec<(usize, bool)> {
+        (0..vars)
+            .filter(|var| (self.mask >> (vars - 1 - var)) & 1 == 1)
+            .map(|var| (var, (self.value >> (vars - 1 - var)) & 1 == 0))
+            .collect()
+    }
+}
+
+/// Products that can't be merged into larger ones, found by merging the
+/// pairs of products that differ in a single variable until none is left.
+fn prime_implicants(vars: usize, terms: impl Iterator<Item = usize>) -> Vec<Cube> {
+    let full = (1 << vars) - 1;
+    let mut cubes: HashSet<Cube> = terms.map(|value| Cube { mask: full, value }).collect();
+    let mut primes = vec![];
+    while !cubes.is_empty() {
+        let mut merged = HashSet::new();
+        let mut used = HashSet::new();
+        for cube in &cubes {
+            for var in (0..vars).map(|v| 1 << v).filter(|bit| cube.mask & bit != 0) {
+                let other = Cube {
+                    mask: cube.mask,
+                    value: cube.value ^ var,
+                };
+                if cubes.contains(&other) {
+                    used.insert(*cube);
+                    merged.insert(Cube {
+                        mask: cube.mask & !var,
+                        value: cube.value & !var,
+                    });
+                }
+            }
+        }
+        primes.extend(cubes.iter().filter(|cube| !used.contains(cube)));
+        cubes = merged;
+    }
+    primes.sort();
+    primes
+}
+
+/// Prime implicants covering every combination of `on`: those that are the
+/// only ones covering some combination, and then those covering the most
+/// combinations left.
+fn cover(primes: &[Cube], on: &[usize]) -> Vec<Cube> {
+    let mut chosen: Vec<Cube> = vec![];
+    for combination in on {
+        let mut covering = primes.iter().filter(|p| p.covers(*combination));
+        if let (Some(prime), None) = (covering.next(), covering.next()) {
+            if !chosen.contains(prime) {
+                chosen.push(*prime);
+            }
+        }
+    }
+
+    let mut left: Vec<usize> = on
+        .iter()
+        .copied()
+        .filter(|c| !chosen.iter().any(|p| p.covers(*c)))
+        .collect();
+    while !left.is_empty() {
+        let best = *primes
+            .iter()
+            .max_by_key(|p| {
+                let covered = left.iter().filter(|c| p.covers(**c)).count();
+                (covered, Reverse(p.mask.count_ones()))
+            })
+            .expect("Every combination is covered by a prime implicant");
+        chosen.push(best);
+        left.retain(|c| !best.covers(*c));
+    }
+
+    chosen.sort_by_key(|cube| Reverse((cube.mask, cube.value)));
+    chosen
+}
+
+fn sum_of_products(cubes: &[Cube], vars: &[String]) -> Expr {
+    let mut products: Vec<Expr> = cubes
+        .iter()
+        .map(|cube| {
+            let mut literals: Vec<Expr> = cube
+                .literals(vars.len())
+                .into_iter()
+                .map(|(var, negated)| {
+                    let var = Expr::Var(vars[var].clone());
+                    if negated {
+                        Expr::Not(Box::new(var))
+                    } else {
+                        var
+                    }
+                })
+                .collect();
+            match literals.len() {
+                0 => Expr::Const(true),
+                1 => literals.pop().unwrap(),
+                _ => Expr::And(literals),
+            }
+        })
+        .collect();
+    match products.len() {
+        0 => Expr::Const(false),
+        1 => products.pop().unwrap(),
+        _ => Expr::Or(products),
+    }
+}
+
+/// Gates used to build the synthesized functions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GateStyle {
+    /// And gates for the products, or gates for the sums and not gates for
+    /// the negated inputs.
+    #[default]
+    AndOrNot,
+    /// Nand gates only: a sum of products is the nand of the nands of the
+    /// products, and a single input nand negates it.
+    Nand,
+}
+
+/// Gates of a module being synthesized, shared between the outputs.
+#[derive(Default)]
+struct GateBuilder {
+    style: GateStyle,
+    /// Input bits, by variable index.
+    vars: Vec<Signal>,
+    cells: Vec<Cell>,
+    negated: HashMap<usize, Signal>,
+    /// Signal of each product: the product itself, or its negation with
+    /// nand gates.
+    products: HashMap<Cube, Signal>,
+}
+
+impl GateBuilder {
+    fn gate(&mut self, kind: GateKind, inputs: Vec<Signal>) -> Signal {
+        // Net names can't clash with the ports, which have no `#`
+        let output = Signal::Net(format!("#{}", self.cells.len()), 0);
+        self.cells.push(Cell::Gate {
+            kind,
+            name: None,
+            inputs,
+            output: output.clone(),
+        });
+        output
+    }
+
+    fn literal(&mut self, var: usize, negated: bool) -> Signal {
+        let signal = self.vars[var].clone();
+        if !negated {
+            return signal;
+        }
+        if let Some(negated) = self.negated.get(&var) {
+            return negated.clone();
+        }
+        let kind = match self.style {
+            GateStyle::AndOrNot => GateKind::Not,
+            GateStyle::Nand => GateKind::Nand,
+        };
+        let negated = self.gate(kind, vec![signal]);
+        self.negated.insert(var, negated.clone());
+        negated
+    }
+
+    fn product(&mut self, cube: Cube) -> Signal {
+        if let Some(signal) = self.products.get(&cube) {
+            return signal.clone();
+        }
+        let literals = cube.literals(self.vars.len());
+        let signal = match (self.style, literals.as_slice()) {
+            (GateStyle::AndOrNot, [(var, negated)]) => self.literal(*var, *negated),
+            (GateStyle::Nand, [(var, negated)]) => self.literal(*var, !negated),
+            (style, _) => {
+                let inputs = literals
+                    .iter()
+                    .map(|(var, negated)| self.literal(*var, *negated))
+                    .collect();
+                let kind = match style {
+                    GateStyle::AndOrNot => GateKind::And,
+                    GateStyle::Nand => GateKind::Nand,
+                };
+                self.gate(kind, inputs)
+            }
+        };
+        self.products.insert(cube, signal.clone());
+        signal
+    }
+
+    fn sum(&mut self, cubes: &[Cube]) -> Signal {
+        if cubes.is_empty() {
+            return Signal::Const(false);
+        }
+        if cubes.iter().any(|cube| cube.mask == 0) {
+            return Signal::Const(true);
+        }
+        if let [cube] = cubes {
+            if let (GateStyle::Nand, [(var, negated)]) =
+                (self.style, cube.literals(self.vars.len()).as_slice())
+            {
+                return self.literal(*var, *negated);
+            }
+        }
+        let products: Vec<Signal> = cubes.iter().map(|cube| self.product(*cube)).collect();
+        match (self.style, products.as_slice()) {
+            (GateStyle::AndOrNot, [product]) => product.clone(),
+            (GateStyle::AndOrNot, _) => self.gate(GateKind::Or, products),
+            (GateStyle::Nand, _) => self.gate(GateKind::Nand, products),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logix_sim::{
+        equivalence::{check_equivalence, Equivalence},
+        headless::top_level_ports,
+        primitives::primitive::Primitive,
+    };
+
+    use super::*;
+
+    fn minimized(code: &str) -> Vec<String> {
+        Logic::from_equations(code)
+            .unwrap()
+            .minimize()
+            .into_iter()
+            .map(|(name, expr)| format!("{name} = {expr}"))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let expr = Expr::parse("!(a | b) & c ^ ~d + 1").unwrap();
+        assert_eq!(expr.to_string(), "!(a | b) & c ^ !d | 1");
+        assert!(matches!(
+            Expr::parse("a & (b | c"),
+            Err(SynthError::Syntax(1, _))
+        ));
+        assert!(matches!(
+            Logic::from_equations("f = a\nf = b"),
+            Err(SynthError::DuplicateOutput(_))
+        ));
+    }
+
+    #[test]
+    fn test_minimize() {
+        assert_eq!(
+            minimized("f = (a & b) | !c # comment\ng = a & b | a & !b; h = !(a ^ a)"),
+            vec!["f = a & b | !c", "g = a", "h = 1"]
+        );
+        // Outputs can be used by the equations that follow them
+        assert_eq!(
+            minimized("x = a ^ b\ny = x & c"),
+            vec!["x = a & !b | !a & b", "y = a & !b & c | !a & b & c"]
+        );
+
+        // Missing rows are don't cares
+        let table = TruthTable::from_csv("a,b,c,f\n0,0,0,0\n0,0,1,1\n0,1,1,1\n1,1,1,1", 3).unwrap();
+        let logic = Logic::from_truth_table(&table).unwrap();
+        assert_eq!(logic.minimize(), vec![("f".into(), Expr::Var("c".into()))]);
+    }
+
+    #[test]
+    fn test_build() {
+        let logic =
+            Logic::from_equations("s = a ^ b ^ c\nco = a & b | c & (a ^ b)\nz = 0").unwrap();
+        let and_or = logic.build("FullAdder", GateStyle::AndOrNot).unwrap();
+        let nand = logic.build("FullAdder", GateStyle::Nand).unwrap();
+
+        let prims: Vec<Primitive> = nand
+            .sub
+            .as_ref()
+            .unwrap()
+            .components
+            .iter()
+            .filter_map(|comp| comp.extra.primitive.clone())
+            .collect();
+        assert!(prims.iter().all(|prim| matches!(
+            prim,
+            Primitive::NandGate
+                | Primitive::Input { .. }
+                | Primitive::Output { .. }
+                | Primitive::Const { .. }
+        )));
+        let mut ports: Vec<&str> = (nand.input_ports.iter())
+            .chain(&nand.output_ports)
+            .map(|port| port.name.as_str())
+            .collect();
+        ports.sort();
+        assert_eq!(ports, vec!["a", "b", "c", "co", "s", "z"]);
+
+        assert_eq!(
+            check_equivalence(and_or.clone(), nand).unwrap(),
+            Equivalence::Equivalent
+        );
+
+        // Extracted back from the circuit
+        let comp = FlattenComponent::new(and_or).unwrap();
+        let (inputs, outputs) = top_level_ports(&comp);
+        let extracted = Logic::from_circuit(comp, &inputs, &outputs).unwrap();
+        assert_eq!(extracted.functions, logic.functions);
+    }
+}
@@ -0,0 +1,888 @@
+//! Importer of structural Verilog.
+//!
+//! Supported constructs are module declarations (with ANSI or non-ANSI port
+//! lists), `input`/`output`/`wire` declarations, gate primitives (`and`, `or`,
+//! `nand`, `nor`, `xor`, `xnor`, `not`, `buf`), continuous assignments with
+//! bitwise operators (`~`, `&`, `|`, `^`, `~^`), bit and part selects,
+//! concatenations, sized constants and module instances with named or
+//! positional ports. `timescale` and `default_nettype` directives are
+//! skipped.
+
+use std::collections::HashMap;
+
+use logix_core::component::Component;
+use logix_sim::primitives::primitive::ExtraInfo;
+
+use crate::netlist::{Cell, GateKind, InstancePorts, NetModule, Netlist, NetlistError, Signal};
+
+/// Builds the top module of a structural Verilog file.
+///
+/// The top module is the last one that is not instantiated by any other.
+/// Ports, named gates and module instances keep their names as instance
+/// names, as in [`crate::build_from_file`].
+pub fn build_from_verilog(code: &str) -> Result<Component<ExtraInfo>, NetlistError> {
+    Parser::new(code)?.parse()?.build()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Sym(char),
+}
+
+/// Compiler directives that are skipped, like the `timescale` written by
+/// [`logix_sim::verilog::to_verilog`].
+const IGNORED_DIRECTIVES: [&str; 2] = ["timescale", "default_nettype"];
+
+fn tokenize(code: &str) -> Result<Vec<(Token, usize)>, NetlistError> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            '/' if next == Some('/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    line += usize::from(chars[i] == '\n');
+                    i += 1;
+                }
+                i += 2;
+            }
+            // Attributes
+            '(' if next == Some('*') && chars.get(i + 2) != Some(&')') => {
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&')')) {
+                    line += usize::from(chars[i] == '\n');
+                    i += 1;
+                }
+                i += 2;
+            }
+            // Directives that do not change the structure are skipped
+            '`' => {
+                let directive: String = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                    .collect();
+                if !IGNORED_DIRECTIVES.contains(&directive.as_str()) {
+                    return Err(NetlistError::Unsupported(
+                        line,
+                        format!("compiler directive `{directive}"),
+                    ));
+                }
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == '\\' => {
+                let start = i;
+                if c == '\\' {
+                    // Escaped identifiers end at the first whitespace
+                    while i < chars.len() && !chars[i].is_whitespace() {
+                        i += 1;
+                    }
+                    tokens.push((Token::Ident(chars[start + 1..i].iter().collect()), line));
+                    continue;
+                }
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+            }
+            c if c.is_ascii_digit() || c == '\'' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || "_'".contains(chars[i]))
+                {
+                    i += 1;
+                }
+                tokens.push((Token::Number(chars[start..i].iter().collect()), line));
+            }
+            c if "()[]{},;:.=~&|^#".contains(c) => {
+                tokens.push((Token::Sym(c), line));
+                i += 1;
+            }
+            c => {
+                return Err(NetlistError::Syntax(
+                    line,
+                    format!("unexpected character {c:?}"),
+                ))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a number like `12`, `4'b1010`, `8'hff` or `'d3`.
+///
+/// Returns its width (if sized) and its value.
+fn parse_number(number: &str) -> Option<(Option<usize>, usize)> {
+    let number = number.replace('_', "");
+    let Some((size, value)) = number.split_once('\'') else {
+        return Some((None, number.parse().ok()?));
+    };
+    let value = value.strip_prefix(['s', 'S']).unwrap_or(value);
+    let radix = match value.chars().next()?.to_ascii_lowercase() {
+        'b' => 2,
+        'o' => 8,
+        'd' => 10,
+        'h' => 16,
+        _ => return None,
+    };
+    let value = usize::from_str_radix(&value[1..], radix).ok()?;
+    let size = match size {
+        "" => None,
+        size => Some(size.parse().ok()?),
+    };
+    Some((size, value))
+}
+
+/// Bits of an expression, least significant first.
+struct Value {
+    bits: Vec<Signal>,
+    /// Unsized constants take the width of the place where they are used.
+    sized: bool,
+}
+
+impl Value {
+    fn resize(self, width: usize) -> Vec<Signal> {
+        if self.sized {
+            return self.bits;
+        }
+        let mut bits = self.bits;
+        bits.resize(width, Signal::Const(false));
+        bits
+    }
+}
+
+const GATES: [(&str, Option<GateKind>); 8] = [
+    ("and", Some(GateKind::And)),
+    ("or", Some(GateKind::Or)),
+    ("nand", Some(GateKind::Nand)),
+    ("nor", Some(GateKind::Nor)),
+    ("xor", Some(GateKind::Xor)),
+    ("xnor", Some(GateKind::Xnor)),
+    ("not", Some(GateKind::Not)),
+    ("buf", None),
+];
+
+/// Keywords of behavioral constructs, which are not supported.
+const UNSUPPORTED: &str = "inout reg always initial parameter localparam generate \
+    function task integer supply0 supply1";
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Declared nets of the current module with their `[msb:lsb]` range.
+    nets: HashMap<String, (usize, usize)>,
+    cells: Vec<Cell>,
+    temps: usize,
+}
+
+impl Parser {
+    fn new(code: &str) -> Result<Self, NetlistError> {
+        Ok(Self {
+            tokens: tokenize(code)?,
+            pos: 0,
+            nets: HashMap::new(),
+            cells: vec![],
+            temps: 0,
+        })
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| token)
+    }
+
+    fn is_sym(&self, sym: char) -> bool {
+        self.peek() == Some(&Token::Sym(sym))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(id)) if id == keyword)
+    }
+
+    fn error(&self, expected: &str) -> NetlistError {
+        let found = match self.peek() {
+            Some(Token::Ident(id) | Token::Number(id)) => id.clone(),
+            Some(Token::Sym(sym)) => sym.to_string(),
+            None => "end of file".into(),
+        };
+        NetlistError::Syntax(self.line(), format!("expected {expected}, found {found:?}"))
+    }
+
+    fn eat(&mut self, sym: char) -> bool {
+        let found = self.is_sym(sym);
+        self.pos += usize::from(found);
+        found
+    }
+
+    fn expect(&mut self, sym: char) -> Result<(), NetlistError> {
+        if self.eat(sym) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("{sym:?}")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, NetlistError> {
+        match self.peek() {
+            Some(Token::Ident(id)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Ok(id)
+            }
+            _ => Err(self.error("an identifier")),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, NetlistError> {
+        match self.peek() {
+            Some(Token::Number(number)) => {
+                let value = number.parse().map_err(|_| self.error("a number"))?;
+                self.pos += 1;
+                Ok(value)
+            }
+            _ => Err(self.error("a number")),
+        }
+    }
+
+    fn parse(mut self) -> Result<Netlist, NetlistError> {
+        let mut netlist = Netlist::default();
+        while self.peek().is_some() {
+            match self.ident()?.as_str() {
+                "module" => netlist.modules.push(self.module()?),
+                other => {
+                    self.pos -= 1;
+                    return Err(NetlistError::Unsupported(self.line(), other.into()));
+                }
+            }
+        }
+        Ok(netlist)
+    }
+
+    fn module(&mut self) -> Result<NetModule, NetlistError> {
+        self.nets.clear();
+        self.temps = 0;
+        let mut module = NetModule {
+            name: self.ident()?,
+            ..Default::default()
+        };
+        if self.is_sym('#') {
+            return Err(NetlistError::Unsupported(self.line(), "parameters".into()));
+        }
+
+        // Port order as in the header, directions may come later
+        let mut ports: Vec<String> = vec![];
+        let mut directions: HashMap<String, bool> = HashMap::new();
+        if self.eat('(') && !self.eat(')') {
+            let mut direction = None;
+            loop {
+                if self.is_keyword("input") || self.is_keyword("output") {
+                    direction = Some(self.ident()? == "input");
+                    if self.is_keyword("wire") {
+                        self.pos += 1;
+                    }
+                    self.declare_range()?;
+                }
+                if self.is_keyword("inout") || self.is_keyword("reg") {
+                    return Err(NetlistError::Unsupported(self.line(), self.ident()?));
+                }
+                let name = self.ident()?;
+                if ports.contains(&name) {
+                    return Err(NetlistError::Syntax(
+                        self.line(),
+                        format!("port {name} is declared twice"),
+                    ));
+                }
+                if let Some(is_input) = direction {
+                    directions.insert(name.clone(), is_input);
+                    self.declare(&name);
+                }
+                ports.push(name);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect(')')?;
+        }
+        self.expect(';')?;
+
+        while !self.is_keyword("endmodule") {
+            let line = self.line();
+            let keyword = self.ident()?;
+            match keyword.as_str() {
+                "input" | "output" | "wire" => {
+                    if keyword != "wire" && self.is_keyword("wire") {
+                        self.pos += 1;
+                    }
+                    let range = self.declare_range()?;
+                    loop {
+                        let name = self.ident()?;
+                        self.nets.insert(name.clone(), range);
+                        if keyword != "wire" {
+                            directions.insert(name.clone(), keyword == "input");
+                        }
+                        if self.eat('=') {
+                            let lhs = self.net_bits(&name, None)?;
+                            self.assign(lhs)?;
+                        }
+                        if !self.eat(',') {
+                            break;
+                        }
+                    }
+                    self.expect(';')?;
+                }
+                "assign" => {
+                    loop {
+                        let lhs = self.lvalue()?;
+                        self.expect('=')?;
+                        self.assign(lhs)?;
+                        if !self.eat(',') {
+                            break;
+                        }
+                    }
+                    self.expect(';')?;
+                }
+                kw if UNSUPPORTED
+                    .split_whitespace()
+                    .any(|unsupported| unsupported == kw) =>
+                {
+                    return Err(NetlistError::Unsupported(line, kw.into()));
+                }
+                kw => match GATES.iter().find(|(name, _)| *name == kw) {
+                    Some((_, kind)) => self.gates(*kind)?,
+                    None => self.instances(kw.to_string())?,
+                },
+            }
+        }
+        self.pos += 1;
+
+        for name in ports {
+            let width = self.width(&name);
+            match directions.get(&name) {
+                Some(true) => module.inputs.push((name, width)),
+                Some(false) => module.outputs.push((name, width)),
+                None => {
+                    return Err(NetlistError::Syntax(
+                        self.line(),
+                        format!("port {name} has no direction"),
+                    ))
+                }
+            }
+        }
+        module.cells = std::mem::take(&mut self.cells);
+        Ok(module)
+    }
+
+    /// Parses an optional `[msb:lsb]` range.
+    fn declare_range(&mut self) -> Result<(usize, usize), NetlistError> {
+        let range = if self.eat('[') {
+            let msb = self.number()?;
+            self.expect(':')?;
+            let lsb = self.number()?;
+            self.expect(']')?;
+            (msb, lsb)
+        } else {
+            (0, 0)
+        };
+        // Remembered for the names declared right after it in port lists
+        self.nets.insert(String::new(), range);
+        Ok(range)
+    }
+
+    fn declare(&mut self, name: &str) {
+        let range = self.nets.get("").copied().unwrap_or((0, 0));
+        self.nets.insert(name.to_string(), range);
+    }
+
+    fn width(&self, name: &str) -> usize {
+        self.nets
+            .get(name)
+            .map_or(1, |(msb, lsb)| msb.abs_diff(*lsb) + 1)
+    }
+
+    /// Bits of a net (from `msb` to `lsb` if given), least significant first.
+    fn net_bits(
+        &self,
+        name: &str,
+        select: Option<(usize, usize)>,
+    ) -> Result<Vec<Signal>, NetlistError> {
+        let (msb, lsb) = self.nets.get(name).copied().unwrap_or((0, 0));
+        let (hi, lo) = select.unwrap_or((msb, lsb));
+        let offset = |bit: usize| {
+            let in_range = if msb >= lsb {
+                (lsb..=msb).contains(&bit)
+            } else {
+                (msb..=lsb).contains(&bit)
+            };
+            if !in_range {
+                return Err(NetlistError::BitIndexOutOfRange(name.to_string(), bit));
+            }
+            Ok(bit.abs_diff(lsb))
+        };
+        let (hi, lo) = (offset(hi)?, offset(lo)?);
+        let bits: Vec<usize> = if hi >= lo {
+            (lo..=hi).collect()
+        } else {
+            (hi..=lo).rev().collect()
+        };
+        Ok(bits
+            .into_iter()
+            .map(|bit| Signal::Net(name.to_string(), bit))
+            .collect())
+    }
+
+    fn select(&mut self) -> Result<Option<(usize, usize)>, NetlistError> {
+        if !self.eat('[') {
+            return Ok(None);
+        }
+        let msb = self.number()?;
+        let lsb = if self.eat(':') { self.number()? } else { msb };
+        self.expect(']')?;
+        Ok(Some((msb, lsb)))
+    }
+
+    fn lvalue(&mut self) -> Result<Vec<Signal>, NetlistError> {
+        if self.eat('{') {
+            let mut parts = vec![];
+            loop {
+                parts.push(self.lvalue()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('}')?;
+            return Ok(parts.into_iter().rev().flatten().collect());
+        }
+        let name = self.ident()?;
+        let select = self.select()?;
+        self.net_bits(&name, select)
+    }
+
+    fn assign(&mut self, lhs: Vec<Signal>) -> Result<(), NetlistError> {
+        let line = self.line();
+        let rhs = self.expr()?.resize(lhs.len());
+        if rhs.len() != lhs.len() {
+            return Err(NetlistError::WidthMismatch(
+                format!("assignment at line {line}"),
+                rhs.len(),
+                lhs.len(),
+            ));
+        }
+        for (lhs, rhs) in lhs.into_iter().zip(rhs) {
+            self.cells.push(Cell::Alias(lhs, rhs));
+        }
+        Ok(())
+    }
+
+    fn temp(&mut self) -> Signal {
+        self.temps += 1;
+        Signal::Net(format!("${}", self.temps), 0)
+    }
+
+    fn gate(&mut self, kind: GateKind, inputs: Vec<Signal>) -> Signal {
+        let output = self.temp();
+        self.cells.push(Cell::Gate {
+            kind,
+            name: None,
+            inputs,
+            output: output.clone(),
+        });
+        output
+    }
+
+    /// Applies a gate bit by bit, unsized constants take the width of the
+    /// other operand.
+    fn bitwise(&mut self, kind: GateKind, a: Value, b: Value) -> Result<Value, NetlistError> {
+        let width = match (a.sized, b.sized) {
+            (true, _) => a.bits.len(),
+            (false, true) => b.bits.len(),
+            (false, false) => a.bits.len().max(b.bits.len()),
+        };
+        let (a, b) = (a.resize(width), b.resize(width));
+        if a.len() != b.len() {
+            return Err(NetlistError::WidthMismatch(
+                format!("expression at line {}", self.line()),
+                b.len(),
+                a.len(),
+            ));
+        }
+        let bits = a
+            .into_iter()
+            .zip(b)
+            .map(|(a, b)| self.gate(kind, vec![a, b]))
+            .collect();
+        Ok(Value { bits, sized: true })
+    }
+
+    fn expr(&mut self) -> Result<Value, NetlistError> {
+        let mut value = self.xor_expr()?;
+        while self.eat('|') {
+            let rhs = self.xor_expr()?;
+            value = self.bitwise(GateKind::Or, value, rhs)?;
+        }
+        Ok(value)
+    }
+
+    fn xor_expr(&mut self) -> Result<Value, NetlistError> {
+        let mut value = self.and_expr()?;
+        loop {
+            let xnor = (self.is_sym('~') && self.peek_at(1) == Some(&Token::Sym('^')))
+                || (self.is_sym('^') && self.peek_at(1) == Some(&Token::Sym('~')));
+            let kind = if xnor {
+                self.pos += 2;
+                GateKind::Xnor
+            } else if self.eat('^') {
+                GateKind::Xor
+            } else {
+                return Ok(value);
+            };
+            let rhs = self.and_expr()?;
+            value = self.bitwise(kind, value, rhs)?;
+        }
+    }
+
+    fn and_expr(&mut self) -> Result<Value, NetlistError> {
+        let mut value = self.unary()?;
+        while self.eat('&') {
+            let rhs = self.unary()?;
+            value = self.bitwise(GateKind::And, value, rhs)?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<Value, NetlistError> {
+        if self.eat('~') {
+            let value = self.unary()?;
+            let bits = value
+                .bits
+                .into_iter()
+                .map(|bit| self.gate(GateKind::Not, vec![bit]))
+                .collect();
+            return Ok(Value {
+                bits,
+                sized: value.sized,
+            });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Value, NetlistError> {
+        if self.eat('(') {
+            let value = self.expr()?;
+            self.expect(')')?;
+            return Ok(value);
+        }
+        if self.eat('{') {
+            // Replication: {n{expr}}
+            if let (Some(Token::Number(_)), Some(Token::Sym('{'))) = (self.peek(), self.peek_at(1))
+            {
+                let count = self.number()?;
+                self.expect('{')?;
+                let value = self.expr()?;
+                self.expect('}')?;
+                self.expect('}')?;
+                let bits = (0..count).flat_map(|_| value.bits.clone()).collect();
+                return Ok(Value { bits, sized: true });
+            }
+            let mut parts = vec![];
+            loop {
+                parts.push(self.expr()?.bits);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('}')?;
+            let bits = parts.into_iter().rev().flatten().collect();
+            return Ok(Value { bits, sized: true });
+        }
+        if let Some(Token::Number(number)) = self.peek() {
+            let (size, value) = parse_number(number)
+                .ok_or_else(|| self.error("a binary, octal, decimal or hex number"))?;
+            self.pos += 1;
+            let width =
+                size.unwrap_or_else(|| (usize::BITS - value.leading_zeros()).max(1) as usize);
+            let bits = (0..width)
+                .map(|bit| Signal::Const(bit < usize::BITS as usize && (value >> bit) & 1 == 1))
+                .collect();
+            return Ok(Value {
+                bits,
+                sized: size.is_some(),
+            });
+        }
+        let name = self.ident()?;
+        let select = self.select()?;
+        Ok(Value {
+            bits: self.net_bits(&name, select)?,
+            sized: true,
+        })
+    }
+
+    /// Single bit terminal of a gate.
+    fn terminal(&mut self) -> Result<Signal, NetlistError> {
+        let line = self.line();
+        let bits = self.expr()?.resize(1);
+        match <[Signal; 1]>::try_from(bits) {
+            Ok([bit]) => Ok(bit),
+            Err(bits) => Err(NetlistError::WidthMismatch(
+                format!("gate terminal at line {line}"),
+                bits.len(),
+                1,
+            )),
+        }
+    }
+
+    /// Gate instances: `and g1 (y, a, b), g2 (z, c, d);`
+    fn gates(&mut self, kind: Option<GateKind>) -> Result<(), NetlistError> {
+        if self.is_sym('#') {
+            return Err(NetlistError::Unsupported(self.line(), "delays".into()));
+        }
+        loop {
+            let name = match self.peek() {
+                Some(Token::Ident(_)) => Some(self.ident()?),
+                _ => None,
+            };
+            self.expect('(')?;
+            let mut terminals = vec![];
+            loop {
+                terminals.push(self.terminal()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect(')')?;
+            if terminals.len() < 2 {
+                return Err(self.error("an output and at least one input"));
+            }
+            match kind {
+                // Several outputs and a single input
+                Some(GateKind::Not) | None => {
+                    let input = terminals.pop().expect("At least two terminals");
+                    for output in terminals {
+                        self.cells.push(match kind {
+                            Some(kind) => Cell::Gate {
+                                kind,
+                                name: name.clone(),
+                                inputs: vec![input.clone()],
+                                output,
+                            },
+                            None => Cell::Alias(output, input.clone()),
+                        });
+                    }
+                }
+                Some(kind) => {
+                    let output = terminals.remove(0);
+                    self.cells.push(Cell::Gate {
+                        kind,
+                        name,
+                        inputs: terminals,
+                        output,
+                    });
+                }
+            }
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect(';')
+    }
+
+    /// Module instances: `Adder add0 (.a(x), .b(y)), add1 (x, y);`
+    fn instances(&mut self, module: String) -> Result<(), NetlistError> {
+        if self.is_sym('#') {
+            return Err(NetlistError::Unsupported(self.line(), "parameters".into()));
+        }
+        loop {
+            let name = self.ident()?;
+            self.expect('(')?;
+            let ports = if self.is_sym('.') {
+                let mut ports = vec![];
+                loop {
+                    self.expect('.')?;
+                    let port = self.ident()?;
+                    self.expect('(')?;
+                    if !self.eat(')') {
+                        ports.push((port, self.expr()?.bits));
+                        self.expect(')')?;
+                    }
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+                InstancePorts::Named(ports)
+            } else {
+                let mut ports = vec![];
+                while !self.is_sym(')') {
+                    ports.push(self.expr()?.bits);
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+                InstancePorts::Positional(ports)
+            };
+            self.expect(')')?;
+            self.cells.push(Cell::Instance {
+                module: module.clone(),
+                name,
+                ports,
+            });
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect(';')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asmhdl::Data;
+    use logix_sim::{flatten::FlattenComponent, headless::top_level_ports, HeadlessSimulator};
+
+    /// Outputs of the circuit for the given input values.
+    fn eval(comp: Component<ExtraInfo>, inputs: &[usize]) -> Vec<usize> {
+        let comp = FlattenComponent::new(comp).unwrap();
+        let (ins, outs) = top_level_ports(&comp);
+        let mut sim = HeadlessSimulator::new(comp);
+        for (port, value) in ins.iter().zip(inputs) {
+            sim.set_input(port.id, Data::new(*value, port.bits))
+                .unwrap();
+        }
+        sim.settle().unwrap();
+        outs.iter()
+            .map(|port| sim.output(port.id).unwrap().value)
+            .collect()
+    }
+
+    const ADDER: &str = "
+        // Full adder made of gates
+        module full_adder(a, b, cin, s, cout);
+            input a, b, cin;
+            output s, cout;
+            wire t1, t2, t3;
+            xor x1 (t1, a, b), x2 (s, t1, cin);
+            and (t2, a, b);
+            and (t3, t1, cin);
+            or (cout, t2, t3);
+        endmodule
+
+        /* Two bit adder */
+        module adder2 (input [1:0] a, input [1:0] b, output [2:0] sum);
+            wire c;
+            full_adder fa0 (.a(a[0]), .b(b[0]), .cin(1'b0), .s(sum[0]), .cout(c));
+            full_adder fa1 (a[1], b[1], c, sum[1], sum[2]);
+        endmodule
+    ";
+
+    #[test]
+    fn test_structural_modules() {
+        let comp = build_from_verilog(ADDER).unwrap();
+        assert_eq!(comp.name.as_deref(), Some("adder2"));
+        assert_eq!((comp.inputs, comp.outputs), (2, 1));
+        let sub_names: Vec<&str> = comp
+            .sub
+            .as_ref()
+            .unwrap()
+            .components
+            .iter()
+            .filter_map(|c| c.instance.as_deref())
+            .collect();
+        assert_eq!(sub_names, vec!["a", "b", "sum", "fa0", "fa1"]);
+
+        for a in 0..4 {
+            for b in 0..4 {
+                let comp = build_from_verilog(ADDER).unwrap();
+                assert_eq!(eval(comp, &[a, b]), vec![a + b]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_assign_expressions() {
+        let code = "
+            module m (input [3:0] a, input s, output [3:0] y, output p, q, output [1:0] k);
+                assign y = {a[1:0], a[3:2]} ^ {4{s}};
+                assign p = a[0] ^ a[1] ^ a[2] ^ a[3];
+                assign q = ~(a[0] | s) ~^ a[3];
+                assign k = 2;
+            endmodule
+        ";
+        for (a, s) in [(0b0110_usize, 0), (0b1101, 1), (0b1000, 0)] {
+            let comp = build_from_verilog(code).unwrap();
+            let swapped = (a & 0b11) << 2 | a >> 2;
+            let y = if s == 1 { !swapped & 0b1111 } else { swapped };
+            let p = a.count_ones() as usize % 2;
+            let q = usize::from((a & 1 == 0 && s == 0) == (a >> 3 == 1));
+            assert_eq!(eval(comp, &[a, s]), vec![y, p, q, 2]);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |code: &str| build_from_verilog(code).unwrap_err();
+        assert!(matches!(
+            err("module m (input a, output y);\n  always y = a;\nendmodule"),
+            NetlistError::Unsupported(2, _)
+        ));
+        assert!(matches!(
+            err("module m (input a, output y); foo f (a, y); endmodule"),
+            NetlistError::ModuleNotFound(_)
+        ));
+        assert!(matches!(
+            err("module m (input [1:0] a, output y); assign y = a; endmodule"),
+            NetlistError::WidthMismatch(..)
+        ));
+        assert!(matches!(
+            err("module m (input a, b, output y); assign y = a; assign y = b; endmodule"),
+            NetlistError::MultipleDrivers(..)
+        ));
+        assert!(matches!(
+            err("module m (input a, input a, output y); assign y = a; endmodule"),
+            NetlistError::Syntax(1, msg) if msg.contains("port a")
+        ));
+        assert!(matches!(
+            err("`define W 4\nmodule m (input a, output y); assign y = a; endmodule"),
+            NetlistError::Unsupported(1, _)
+        ));
+    }
+
+    #[test]
+    fn test_export_roundtrip() {
+        let comp = build_from_verilog(ADDER).unwrap();
+        let code = logix_sim::verilog::to_verilog(&comp).unwrap();
+        assert!(code.starts_with("`timescale"));
+        for a in 0..4 {
+            for b in 0..4 {
+                let comp = build_from_verilog(&code).unwrap();
+                assert_eq!(eval(comp, &[a, b]), vec![a + b]);
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use asmhdl::{AsmProgramUpdateType, Data};
+use log::debug;
+use logix_core::component::PortAddr;
+
+use crate::{
+    flatten::FlattenComponent,
+    primitives::primitive::{Primitive, PrimitiveComponent},
+};
+
+/// Circuit being simulated along with the components waiting to be updated
+/// in the next delta cycle. Both simulators run on top of it.
+pub(crate) struct DeltaCycles {
+    pub comp: FlattenComponent,
+    /// Current simulation time in nanoseconds.
+    pub time: u128,
+    /// Components that depend on the time and are updated when it advances.
+    timed: Vec<usize>,
+    /// Components waiting to be updated.
+    pending: Vec<usize>,
+    queued: Vec<bool>,
+}
+
+impl DeltaCycles {
+    pub fn new(comp: FlattenComponent) -> Self {
+        let timed = comp
+            .components
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| match &c.prim_type {
+                Primitive::Clock(_) => true,
+                Primitive::Custom { comp, .. } => {
+                    matches!(comp.update_type, AsmProgramUpdateType::Always)
+                }
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        // Every component is updated once at the start
+        let len = comp.components.len();
+        DeltaCycles {
+            comp,
+            time: 0,
+            timed,
+            pending: (0..len).collect(),
+            queued: vec![true; len],
+        }
+    }
+
+    /// Whether no component is waiting to be updated.
+    pub fn is_stable(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn enqueue(&mut self, idx: usize) {
+        if !self.queued[idx] {
+            self.queued[idx] = true;
+            self.pending.push(idx);
+        }
+    }
+
+    /// Takes the components to update in the next delta cycle.
+    pub fn take_pending(&mut self) -> Vec<usize> {
+        let to_upd = std::mem::take(&mut self.pending);
+        for idx in &to_upd {
+            self.queued[*idx] = false;
+        }
+        to_upd
+    }
+
+    /// Time of the next change of the timed components accepted by
+    /// `filter`.
+    pub fn next_event(&self, filter: impl Fn(&PrimitiveComponent) -> bool) -> Option<u128> {
+        self.timed
+            .iter()
+            .map(|idx| &self.comp.components[*idx])
+            .filter(|c| filter(c))
+            .filter_map(|c| c.next_event(self.time))
+            .min()
+    }
+
+    /// Moves the time forward, queuing the components that depend on it.
+    pub fn advance_to(&mut self, time: u128) {
+        self.time = time;
+        for idx in self.timed.clone() {
+            self.enqueue(idx);
+        }
+    }
+
+    /// Writes a value to an input, queuing its component if it changed.
+    pub fn set_input(&mut self, (idx, port): PortAddr, val: Data) {
+        let dest = &mut self.comp.components[idx].inputs[port];
+        if *dest != val {
+            *dest = val;
+            self.enqueue(idx);
+        }
+    }
+
+    /// Updates a component and sends its outputs to its loads.
+    pub fn update(&mut self, idx: usize) {
+        self.comp.components[idx].update(self.time);
+        for conn_idx in 0..self.comp.connections[idx].len() {
+            let conn = self.comp.connections[idx][conn_idx];
+            let val = self.comp.components[idx].outputs[conn.from.1];
+            let dest = &mut self.comp.components[conn.to.0].inputs[conn.to.1];
+            if *dest != val {
+                debug!("{}ns: {:?} -> {:?}", self.time, conn, val);
+                *dest = val;
+                self.enqueue(conn.to.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,582 @@
+//! Equivalence checking of combinational circuits.
+//!
+//! Two circuits are equivalent when, for every value of their inputs, their
+//! outputs have the same values. Inputs and outputs are matched by name or,
+//! if the names of the two circuits differ, by position. Matched ports must
+//! have the same widths.
+//!
+//! Circuits made of gates, splitters, joiners and constants, without loops,
+//! are encoded bit by bit as clauses sharing the same input variables. The
+//! checker then asks the [SAT solver](crate::sat) for an input where some
+//! output differs: if there is none, the circuits are equivalent. Identical
+//! gates of both circuits are encoded once, so circuits with the same
+//! structure are proved equivalent without searching.
+//!
+//! Other circuits (e.g. with custom components or latches) are compared by
+//! simulating every combination of their inputs, as long as they have at
+//! most [`MAX_INPUT_BITS`] input bits.
+
+use std::collections::HashMap;
+
+use logix_core::prelude::*;
+
+use crate::{
+    errors::EquivalenceError,
+    flatten::FlattenComponent,
+    headless::{top_level_ports, Port},
+    primitives::primitive::{ExtraInfo, Primitive},
+    sat::{lit, neg, Lit, Solver},
+    truth_table::{TruthTable, MAX_INPUT_BITS},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    Equivalent,
+    Different(Counterexample),
+}
+
+/// Input values for which the outputs of two circuits differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub inputs: Vec<usize>,
+    /// Outputs of the left circuit.
+    pub left: Vec<usize>,
+    /// Outputs of the right circuit.
+    pub right: Vec<usize>,
+}
+
+/// Flattened circuit and the top-level ports compared by the checker.
+#[derive(Clone, Copy)]
+pub struct Circuit<'a> {
+    pub comp: &'a FlattenComponent,
+    pub inputs: &'a [Port],
+    pub outputs: &'a [Port],
+}
+
+const TRUE: Lit = lit(0, false);
+const FALSE: Lit = lit(0, true);
+
+/// Clauses of the gates of both circuits.
+struct Encoder {
+    solver: Solver,
+    /// Output of each encoded and (`false`) and xor (`true`) gate, by its
+    /// ordered inputs.
+    gates: HashMap<(bool, Lit, Lit), Lit>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        let mut solver = Solver::new();
+        solver.new_var();
+        solver.add_clause(&[TRUE]);
+        Encoder {
+            solver,
+            gates: HashMap::new(),
+        }
+    }
+
+    fn input(&mut self, bits: usize) -> Vec<Lit> {
+        (0..bits)
+            .map(|_| lit(self.solver.new_var(), false))
+            .collect()
+    }
+
+    fn and(&mut self, a: Lit, b: Lit) -> Lit {
+        if a == FALSE || b == FALSE || a == neg(b) {
+            return FALSE;
+        }
+        if a == TRUE || a == b {
+            return b;
+        }
+        if b == TRUE {
+            return a;
+        }
+        let key = (false, a.min(b), a.max(b));
+        if let Some(out) = self.gates.get(&key) {
+            return *out;
+        }
+        let out = lit(self.solver.new_var(), false);
+        self.solver.add_clause(&[neg(out), a]);
+        self.solver.add_clause(&[neg(out), b]);
+        self.solver.add_clause(&[out, neg(a), neg(b)]);
+        self.gates.insert(key, out);
+        out
+    }
+
+    fn or(&mut self, a: Lit, b: Lit) -> Lit {
+        neg(self.and(neg(a), neg(b)))
+    }
+
+    fn xor(&mut self, a: Lit, b: Lit) -> Lit {
+        // Negations are moved to the output
+        let negated = (a ^ b) & 1;
+        let (a, b) = (a & !1, b & !1);
+        if a == b {
+            return FALSE ^ negated;
+        }
+        if a == TRUE {
+            return neg(b) ^ negated;
+        }
+        if b == TRUE {
+            return neg(a) ^ negated;
+        }
+        let key = (true, a.min(b), a.max(b));
+        if let Some(out) = self.gates.get(&key) {
+            return *out ^ negated;
+        }
+        let out = lit(self.solver.new_var(), false);
+        self.solver.add_clause(&[neg(out), a, b]);
+        self.solver.add_clause(&[neg(out), neg(a), neg(b)]);
+        self.solver.add_clause(&[out, neg(a), b]);
+        self.solver.add_clause(&[out, a, neg(b)]);
+        self.gates.insert(key, out);
+        out ^ negated
+    }
+
+    /// Whether any bit of a signal is high, as read by gates.
+    fn any(&mut self, bits: &[Lit]) -> Lit {
+        bits.iter().fold(FALSE, |acc, bit| self.or(acc, *bit))
+    }
+
+    /// Values of the top-level outputs of `circuit` given the values of its
+    /// top-level inputs. Returns `None` if the circuit has loops or
+    /// primitives that can't be encoded.
+    fn encode(&mut self, circuit: Circuit, inputs: &[Vec<Lit>]) -> Option<Vec<Vec<Lit>>> {
+        let comp = circuit.comp;
+        let top_inputs: HashMap<usize, &Vec<Lit>> = circuit
+            .inputs
+            .iter()
+            .zip(inputs)
+            .map(|(port, lits)| (port.id, lits))
+            .collect();
+
+        // Primitives sorted so that they come after their drivers
+        let mut pending = vec![0; comp.components.len()];
+        let mut drivers = HashMap::new();
+        for (from, conns) in comp.connections.iter().enumerate() {
+            for conn in conns {
+                pending[conn.to.0] += 1;
+                drivers.insert(conn.to, (from, conn.from.1));
+            }
+        }
+        let mut order: Vec<usize> = (0..pending.len()).filter(|i| pending[*i] == 0).collect();
+        let mut next = 0;
+        while next < order.len() {
+            for conn in &comp.connections[order[next]] {
+                pending[conn.to.0] -= 1;
+                if pending[conn.to.0] == 0 {
+                    order.push(conn.to.0);
+                }
+            }
+            next += 1;
+        }
+        if order.len() < comp.components.len() {
+            return None;
+        }
+
+        let mut signals: Vec<Vec<Vec<Lit>>> = vec![vec![]; comp.components.len()];
+        for idx in order {
+            let prim = &comp.components[idx];
+            let ins: Vec<Vec<Lit>> = (0..prim.inputs.len())
+                .map(|port| match drivers.get(&(idx, port)) {
+                    Some((from, from_port)) => signals[*from][*from_port].clone(),
+                    None => vec![FALSE; prim.inputs[port].size],
+                })
+                .collect();
+
+            let outs = match &prim.prim_type {
+                Primitive::AndGate | Primitive::NandGate => {
+                    let mut out = TRUE;
+                    for signal in &ins {
+                        let bit = self.any(signal);
+                        out = self.and(out, bit);
+                    }
+                    let negated = matches!(prim.prim_type, Primitive::NandGate);
+                    vec![vec![out ^ negated as Lit]]
+                }
+                Primitive::OrGate | Primitive::NorGate => {
+                    let mut out = FALSE;
+                    for signal in &ins {
+                        let bit = self.any(signal);
+                        out = self.or(out, bit);
+                    }
+                    let negated = matches!(prim.prim_type, Primitive::NorGate);
+                    vec![vec![out ^ negated as Lit]]
+                }
+                Primitive::XorGate => {
+                    let mut out = FALSE;
+                    for signal in &ins {
+                        let bit = self.any(signal);
+                        out = self.xor(out, bit);
+                    }
+                    vec![vec![out]]
+                }
+                Primitive::NotGate => vec![ins[0].iter().map(|bit| neg(*bit)).collect()],
+                Primitive::Input { bits } => match top_inputs.get(&prim.id) {
+                    Some(lits) => vec![lits.to_vec()],
+                    None => vec![resized(&ins[0], *bits)],
+                },
+                Primitive::Output { bits } => vec![resized(&ins[0], *bits)],
+                Primitive::Splitter { bits } => (0..*bits)
+                    .map(|bit| vec![ins[0].get(bit).copied().unwrap_or(FALSE)])
+                    .collect(),
+                Primitive::Joiner { .. } => vec![ins.iter().map(|s| self.any(s)).collect()],
+                Primitive::Const { value } => vec![(0..value.size)
+                    .map(|bit| if value.get_bit(bit) { TRUE } else { FALSE })
+                    .collect()],
+                Primitive::Switch | Primitive::Clock(_) | Primitive::Custom { .. } => return None,
+            };
+            signals[idx] = outs;
+        }
+
+        Some(
+            circuit
+                .outputs
+                .iter()
+                .map(|port| signals[comp.id_to_idx[&port.id]][0].clone())
+                .collect(),
+        )
+    }
+
+    /// Inputs for which some bit of `left` and `right` differ, if any.
+    fn counterexample(
+        &mut self,
+        inputs: &[Vec<Lit>],
+        left: &[Vec<Lit>],
+        right: &[Vec<Lit>],
+    ) -> Option<Counterexample> {
+        let mut differ = FALSE;
+        for (l, r) in left.iter().flatten().zip(right.iter().flatten()) {
+            let bit = self.xor(*l, *r);
+            differ = self.or(differ, bit);
+        }
+        if differ == FALSE {
+            return None;
+        }
+        self.solver.add_clause(&[differ]);
+        if !self.solver.solve() {
+            return None;
+        }
+
+        let value = |lits: &Vec<Lit>| {
+            lits.iter()
+                .enumerate()
+                .map(|(i, bit)| (self.solver.model(*bit) as usize) << i)
+                .sum()
+        };
+        Some(Counterexample {
+            inputs: inputs.iter().map(value).collect(),
+            left: left.iter().map(value).collect(),
+            right: right.iter().map(value).collect(),
+        })
+    }
+}
+
+/// Signal truncated or extended with low bits to `bits`.
+fn resized(signal: &[Lit], bits: usize) -> Vec<Lit> {
+    (0..bits)
+        .map(|bit| signal.get(bit).copied().unwrap_or(FALSE))
+        .collect()
+}
+
+fn widths(ports: &[Port]) -> Vec<usize> {
+    ports.iter().map(|port| port.bits).collect()
+}
+
+/// First row of the truth tables of the circuits whose outputs differ.
+fn enumerate(left: Circuit, right: Circuit) -> Result<Option<Counterexample>, EquivalenceError> {
+    let total_bits: usize = left.inputs.iter().map(|port| port.bits).sum();
+    if total_bits > MAX_INPUT_BITS {
+        return Err(EquivalenceError::TooManyInputs(total_bits));
+    }
+    let left_table = TruthTable::generate(left.comp.clone(), left.inputs, left.outputs)?;
+    let right_table = TruthTable::generate(right.comp.clone(), right.inputs, right.outputs)?;
+    Ok(left_table
+        .rows
+        .into_iter()
+        .zip(right_table.rows)
+        .find(|(l, r)| l.outputs != r.outputs)
+        .map(|(l, r)| Counterexample {
+            inputs: l.inputs,
+            left: l.outputs,
+            right: r.outputs,
+        }))
+}
+
+/// Ports of `right` in the order of the ports of `left` with the same names,
+/// or in their own order if the names do not match one to one.
+fn match_ports(left: &[Port], right: &[Port]) -> Vec<Port> {
+    let mut names: Vec<&str> = left.iter().map(|port| port.name.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    let by_name: Option<Vec<Port>> = left
+        .iter()
+        .map(|port| right.iter().find(|other| other.name == port.name).cloned())
+        .collect();
+    match by_name {
+        Some(ports) if names.len() == left.len() && left.len() == right.len() => ports,
+        _ => right.to_vec(),
+    }
+}
+
+/// Checks whether two circuits compute the same outputs for every value of
+/// their inputs.
+///
+/// The values of a counterexample are given in the order of the ports of
+/// `left`.
+pub fn check_circuits(left: Circuit, right: Circuit) -> Result<Equivalence, EquivalenceError> {
+    let right_inputs = match_ports(left.inputs, right.inputs);
+    let right_outputs = match_ports(left.outputs, right.outputs);
+    let right = Circuit {
+        comp: right.comp,
+        inputs: &right_inputs,
+        outputs: &right_outputs,
+    };
+
+    if widths(left.inputs) != widths(right.inputs) {
+        return Err(EquivalenceError::InputMismatch(
+            widths(left.inputs),
+            widths(right.inputs),
+        ));
+    }
+    if widths(left.outputs) != widths(right.outputs) {
+        return Err(EquivalenceError::OutputMismatch(
+            widths(left.outputs),
+            widths(right.outputs),
+        ));
+    }
+
+    let mut encoder = Encoder::new();
+    let inputs: Vec<Vec<Lit>> = left
+        .inputs
+        .iter()
+        .map(|port| encoder.input(port.bits))
+        .collect();
+    let encoded = encoder
+        .encode(left, &inputs)
+        .zip(encoder.encode(right, &inputs));
+    let counterexample = match encoded {
+        Some((left_outs, right_outs)) => encoder.counterexample(&inputs, &left_outs, &right_outs),
+        None => enumerate(left, right)?,
+    };
+
+    Ok(match counterexample {
+        Some(counterexample) => Equivalence::Different(counterexample),
+        None => Equivalence::Equivalent,
+    })
+}
+
+/// Checks whether two components compute the same outputs for every value
+/// of their inputs. Their top-level inputs and outputs are matched as in
+/// [`check_circuits`].
+pub fn check_equivalence(
+    left: Component<ExtraInfo>,
+    right: Component<ExtraInfo>,
+) -> Result<Equivalence, EquivalenceError> {
+    let left = FlattenComponent::new(left)?;
+    let right = FlattenComponent::new(right)?;
+    let (left_ins, left_outs) = top_level_ports(&left);
+    let (right_ins, right_outs) = top_level_ports(&right);
+    check_circuits(
+        Circuit {
+            comp: &left,
+            inputs: &left_ins,
+            outputs: &left_outs,
+        },
+        Circuit {
+            comp: &right,
+            inputs: &right_ins,
+            outputs: &right_outs,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use asmhdl::AsmComponent;
+
+    use super::*;
+    use crate::test_utils::prim;
+
+    /// Circuit with two 2-bit inputs and a 2-bit output computed by `gate`
+    /// on each pair of bits.
+    fn bitwise(gate: Primitive) -> Component<ExtraInfo> {
+        ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(2, 1)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 2 }),
+                prim(2, 1, 1, Primitive::Input { bits: 2 }),
+                prim(3, 1, 2, Primitive::Splitter { bits: 2 }),
+                prim(4, 1, 2, Primitive::Splitter { bits: 2 }),
+                prim(5, 2, 1, gate.clone()),
+                prim(6, 2, 1, gate),
+                prim(7, 2, 1, Primitive::Joiner { bits: 2 }),
+                prim(8, 1, 1, Primitive::Output { bits: 2 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 3, 0),
+                Conn::new(2, 0, 4, 0),
+                Conn::new(3, 0, 4, 1),
+                Conn::new(2, 1, 5, 0),
+                Conn::new(3, 1, 5, 1),
+                Conn::new(4, 0, 6, 0),
+                Conn::new(5, 0, 6, 1),
+                Conn::new(6, 0, 7, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(7, 0)])
+            .build()
+            .unwrap()
+    }
+
+    /// Circuit with two 1-bit inputs driving `gate`.
+    fn single(gate: Primitive) -> Component<ExtraInfo> {
+        ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(2, 1)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                prim(3, 2, 1, gate),
+                prim(4, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 2, 1),
+                Conn::new(2, 0, 3, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(3, 0)])
+            .build()
+            .unwrap()
+    }
+
+    /// `!(!a | !b)` on two 1-bit inputs.
+    fn de_morgan() -> Component<ExtraInfo> {
+        ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(2, 1)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                prim(3, 1, 1, Primitive::NotGate),
+                prim(4, 1, 1, Primitive::NotGate),
+                prim(5, 2, 1, Primitive::NorGate),
+                prim(6, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 3, 0),
+                Conn::new(2, 0, 4, 0),
+                Conn::new(3, 0, 4, 1),
+                Conn::new(4, 0, 5, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(5, 0)])
+            .build()
+            .unwrap()
+    }
+
+    /// `in0 & !in1`, or `in1 & !in0` if `swap`, with named inputs.
+    fn and_not(names: [&str; 2], swap: bool) -> Component<ExtraInfo> {
+        let (kept, negated) = if swap { (1, 0) } else { (0, 1) };
+        ComponentBuilder::new(0)
+            .name("Main".into())
+            .input_ports(names.map(|name| PortDesc::input(name, 1)).to_vec())
+            .output_ports(vec![PortDesc::output("y", 1)])
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                prim(3, 1, 1, Primitive::NotGate),
+                prim(4, 2, 1, Primitive::AndGate),
+                prim(5, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(kept, 0, 3, 0),
+                Conn::new(negated, 0, 2, 0),
+                Conn::new(2, 0, 3, 1),
+                Conn::new(3, 0, 4, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(4, 0)])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_ports_matched_by_name() {
+        // Inputs declared in a different order
+        assert_eq!(
+            check_equivalence(and_not(["a", "b"], false), and_not(["b", "a"], true)).unwrap(),
+            Equivalence::Equivalent
+        );
+        assert!(matches!(
+            check_equivalence(and_not(["a", "b"], false), and_not(["b", "a"], false)).unwrap(),
+            Equivalence::Different(_)
+        ));
+        // Different names are matched by position
+        assert_eq!(
+            check_equivalence(and_not(["a", "b"], false), and_not(["x", "y"], false)).unwrap(),
+            Equivalence::Equivalent
+        );
+    }
+
+    /// Custom component computing `a & b`.
+    fn custom_and() -> Primitive {
+        let comp = AsmComponent::from_code(
+            "_info:\nname And\nupdate input_changes\n\n_inputs:\na 1\nb 1\n\n_outputs:\nout 1\n\n_start:\nmov out (a and b)\n",
+        );
+        let state = comp.new_program_state();
+        Primitive::Custom { comp, state }
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert_eq!(
+            check_equivalence(bitwise(Primitive::AndGate), bitwise(Primitive::AndGate)).unwrap(),
+            Equivalence::Equivalent
+        );
+        // Proved with the solver
+        assert_eq!(
+            check_equivalence(single(Primitive::AndGate), de_morgan()).unwrap(),
+            Equivalence::Equivalent
+        );
+        // Enumerated, as the custom component can't be encoded
+        assert_eq!(
+            check_equivalence(single(custom_and()), de_morgan()).unwrap(),
+            Equivalence::Equivalent
+        );
+
+        assert!(matches!(
+            check_equivalence(bitwise(Primitive::AndGate), single(Primitive::AndGate)),
+            Err(EquivalenceError::InputMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn test_different() {
+        let Equivalence::Different(counterexample) =
+            check_equivalence(bitwise(Primitive::AndGate), bitwise(Primitive::OrGate)).unwrap()
+        else {
+            panic!("And and or gates are not equivalent");
+        };
+        let [a, b] = counterexample.inputs[..] else {
+            panic!("Expected two inputs");
+        };
+        assert_eq!(counterexample.left, vec![a & b]);
+        assert_eq!(counterexample.right, vec![a | b]);
+        assert_ne!(a & b, a | b);
+
+        // First row of the truth table that differs
+        assert_eq!(
+            check_equivalence(single(custom_and()), single(Primitive::OrGate)).unwrap(),
+            Equivalence::Different(Counterexample {
+                inputs: vec![0, 1],
+                left: vec![0],
+                right: vec![1],
+            })
+        );
+    }
+}
@@ -23,3 +23,81 @@ pub enum DataRequestError {
     #[error("Invalid output port index: {0}")]
     InvalidOutputPortIndex(usize),
 }
+
+#[derive(Debug, Error)]
+pub enum HeadlessSimError {
+    #[error("Invalid component id: {0}")]
+    InvalidComponentId(usize),
+    #[error("Component {0} is not an input")]
+    NotAnInput(usize),
+    #[error("Component {0} is not an output")]
+    NotAnOutput(usize),
+    #[error("Value {value} does not fit in {bits} bits")]
+    ValueOutOfRange { value: usize, bits: usize },
+    #[error("Circuit did not settle at {0}ns")]
+    Unstable(u128),
+}
+
+#[derive(Debug, Error)]
+pub enum TruthTableError {
+    #[error("Too many input bits ({0}) to enumerate")]
+    TooManyInputs(usize),
+    #[error("The circuit has clocks or components that change over time")]
+    NotCombinational,
+    #[error("Simulation failed.\n{0}")]
+    Simulation(#[from] HeadlessSimError),
+    #[error("Expected inputs {0:?} do not match the circuit inputs")]
+    ColumnMismatch(Vec<String>),
+    #[error("Unknown output column: {0}")]
+    UnknownColumn(String),
+    #[error("Unknown row: {0}")]
+    UnknownRow(String),
+    #[error("Empty CSV table")]
+    EmptyCsv,
+    #[error("Line {0}: invalid CSV row {1:?}")]
+    InvalidCsv(usize, String),
+    #[error("Invalid JSON table.\n{0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum TestbenchError {
+    #[error("Line {0}: invalid command {1:?}")]
+    InvalidCommand(usize, String),
+    #[error("Line {0}: invalid time {1:?}")]
+    InvalidTime(usize, String),
+    #[error("Line {0}: invalid clock edge {1:?}, expected a number starting at 1")]
+    InvalidEdge(usize, String),
+    #[error("Line {0}: expected `name=value`, found {1:?}")]
+    InvalidAssignment(usize, String),
+    #[error("Line {0}: invalid value {1:?}")]
+    InvalidValue(usize, String),
+    #[error("Line {0}: clock edges used but there is no clock")]
+    NoClock(usize),
+    #[error("Unknown input: {0}")]
+    UnknownInput(String),
+    #[error("Unknown output: {0}")]
+    UnknownOutput(String),
+    #[error("Simulation failed.\n{0}")]
+    Simulation(#[from] HeadlessSimError),
+}
+
+#[derive(Debug, Error)]
+pub enum EquivalenceError {
+    #[error("{0}")]
+    Flatten(#[from] FlattenComponentError),
+    #[error("Input widths {0:?} and {1:?} do not match")]
+    InputMismatch(Vec<usize>, Vec<usize>),
+    #[error("Output widths {0:?} and {1:?} do not match")]
+    OutputMismatch(Vec<usize>, Vec<usize>),
+    #[error("Too many input bits ({0}) to enumerate")]
+    TooManyInputs(usize),
+    #[error("{0}")]
+    TruthTable(#[from] TruthTableError),
+}
+
+#[derive(Debug, Error)]
+pub enum VerilogError {
+    #[error("Component {0} is not composite")]
+    NotComposite(String),
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     errors::{ComponentRequestError, DataRequestError, FlattenComponentError},
@@ -11,7 +11,7 @@ use asmhdl::Data;
 use log::*;
 use logix_core::prelude::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum NestedConfig {
     Single(String, usize, Vec<PortAddr>, Vec<PortAddr>),
     Compose(
@@ -37,9 +37,55 @@ impl NestedConfig {
             NestedConfig::Compose(_, _, _, _, outs) => outs[idx],
         }
     }
+
+    /// Addresses of the ports of the component found following the ids of
+    /// `comp_path` and then `at`.
+    pub fn ports_at(
+        &self,
+        comp_path: &[usize],
+        at: Option<usize>,
+    ) -> Result<(&[PortAddr], &[PortAddr]), ComponentRequestError> {
+        let comp = self.find(comp_path, at)?;
+        Ok(match comp {
+            NestedConfig::Single(_, _, ins, outs) => (ins, outs),
+            NestedConfig::Compose(_, _, _, ins, outs) => (ins, outs),
+        })
+    }
+
+    /// Id of the primitive found following the ids of `comp_path` and then
+    /// `at`.
+    pub fn prim_id_at(
+        &self,
+        comp_path: &[usize],
+        at: Option<usize>,
+    ) -> Result<usize, ComponentRequestError> {
+        match self.find(comp_path, at)? {
+            NestedConfig::Single(_, id, _, _) => Ok(*id),
+            NestedConfig::Compose(..) => Err(ComponentRequestError::NonPrimitive),
+        }
+    }
+
+    fn find(
+        &self,
+        comp_path: &[usize],
+        at: Option<usize>,
+    ) -> Result<&NestedConfig, ComponentRequestError> {
+        let mut comp = self;
+        for id in comp_path.iter().chain(at.as_ref()) {
+            match comp {
+                NestedConfig::Compose(_, _, subs, _, _) => {
+                    comp = subs
+                        .get(id)
+                        .ok_or(ComponentRequestError::InvalidComponentId(*id))?;
+                }
+                _ => panic!("Component not found"),
+            }
+        }
+        Ok(comp)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FlattenComponent {
     pub components: Vec<PrimitiveComponent>,
     pub connections: Vec<Vec<Conn>>,
@@ -49,45 +95,45 @@ pub struct FlattenComponent {
     pub nested_config: NestedConfig,
 
     pub id_to_idx: HashMap<usize, usize>,
+
+    /// Descriptors of the inputs of the top component.
+    pub input_ports: Vec<PortDesc>,
+    /// Descriptors of the outputs of the top component.
+    pub output_ports: Vec<PortDesc>,
 }
 
 impl FlattenComponent {
     pub fn new(mut comp: Component<ExtraInfo>) -> Result<Self, FlattenComponentError> {
         let original = comp.clone();
-        let (_, mut nested_config) = reindex_connections(&mut comp, 0)?;
+        let (_, mut nested_config) = reindex_connections(&mut comp, 0, false)?;
         fix_inputs_data_addrs(&original, &mut nested_config);
 
         let (components, conns) = flat_comp(&comp);
+        let mut flatten = Self::from_parts(components, conns, nested_config);
+        flatten.input_ports = original.input_ports;
+        flatten.output_ports = original.output_ports;
+        Ok(flatten)
+    }
 
+    /// Builds a flattened component from its primitives, the connections
+    /// between them and the addresses of the nested components.
+    pub(crate) fn from_parts(
+        components: Vec<PrimitiveComponent>,
+        conns: Vec<Conn>,
+        nested_config: NestedConfig,
+    ) -> Self {
         // Build dependency map
-        let mut deps_mat: Vec<Vec<bool>> = vec![vec![false; components.len()]; components.len()];
-        let mut inv_deps_mat: Vec<Vec<bool>> =
-            vec![vec![false; components.len()]; components.len()];
-
+        let mut deps: Vec<Vec<usize>> = vec![vec![]; components.len()];
+        let mut inv_deps: Vec<Vec<usize>> = vec![vec![]; components.len()];
         for conn in &conns {
             let (from, to) = (idx_of(conn.from), idx_of(conn.to));
-            deps_mat[to][from] = true;
-            inv_deps_mat[from][to] = true;
+            deps[to].push(from);
+            inv_deps[from].push(to);
+        }
+        for list in deps.iter_mut().chain(inv_deps.iter_mut()) {
+            list.sort_unstable();
+            list.dedup();
         }
-
-        let deps: Vec<Vec<usize>> = deps_mat
-            .iter()
-            .map(|row| {
-                row.iter()
-                    .enumerate()
-                    .filter_map(|(i, v)| if *v { Some(i) } else { None })
-                    .collect()
-            })
-            .collect();
-        let inv_deps: Vec<Vec<usize>> = inv_deps_mat
-            .iter()
-            .map(|row| {
-                row.iter()
-                    .enumerate()
-                    .filter_map(|(i, v)| if *v { Some(i) } else { None })
-                    .collect()
-            })
-            .collect();
 
         let mut connections = components
             .iter()
@@ -104,14 +150,30 @@ impl FlattenComponent {
             .map(|(i, comp)| (comp.id, i))
             .collect();
 
-        Ok(FlattenComponent {
+        FlattenComponent {
             components,
             connections,
             deps,
             inv_deps,
             nested_config,
             id_to_idx,
-        })
+            input_ports: vec![],
+            output_ports: vec![],
+        }
+    }
+
+    /// Ids of the primitives placed directly in the top component.
+    pub(crate) fn top_level_ids(&self) -> HashSet<usize> {
+        match &self.nested_config {
+            NestedConfig::Single(_, id, _, _) => HashSet::from([*id]),
+            NestedConfig::Compose(_, _, subs, _, _) => subs
+                .values()
+                .filter_map(|sub| match sub {
+                    NestedConfig::Single(_, id, _, _) => Some(*id),
+                    NestedConfig::Compose(..) => None,
+                })
+                .collect(),
+        }
     }
 
     pub fn comp_by_id(&self, id: usize) -> &PrimitiveComponent {
@@ -133,34 +195,8 @@ impl FlattenComponent {
         comp_path: &[usize],
         at: Option<usize>,
     ) -> Result<&mut PrimitiveComponent, ComponentRequestError> {
-        let mut comp = &self.nested_config;
-        for id in comp_path {
-            match comp {
-                NestedConfig::Compose(_, _, subs, _, _) => {
-                    comp = subs
-                        .get(id)
-                        .ok_or(ComponentRequestError::InvalidComponentId(*id))?;
-                }
-                _ => panic!("Component not found"),
-            }
-        }
-
-        if let Some(at) = at {
-            match comp {
-                NestedConfig::Compose(_, _, subs, _, _) => {
-                    comp = subs
-                        .get(&at)
-                        .ok_or(ComponentRequestError::InvalidComponentId(at))?;
-                }
-                _ => panic!("Component not found"),
-            }
-        }
-
-        let NestedConfig::Single(_, id, _, _) = comp else {
-            return Err(ComponentRequestError::NonPrimitive);
-        };
-
-        Ok(self.comp_by_id_mut(*id))
+        let id = self.nested_config.prim_id_at(comp_path, at)?;
+        Ok(self.comp_by_id_mut(id))
     }
 
     pub fn get_input_status_at(&self, id: usize, idx: usize) -> Result<Data, DataRequestError> {
@@ -197,33 +233,7 @@ impl FlattenComponent {
         comp_path: &[usize],
         at: Option<usize>,
     ) -> Result<(Vec<Data>, Vec<Data>), DataRequestError> {
-        let mut comp = &self.nested_config;
-        for id in comp_path {
-            match comp {
-                NestedConfig::Compose(_, _, subs, _, _) => {
-                    comp = subs
-                        .get(id)
-                        .ok_or(ComponentRequestError::InvalidComponentId(*id))?;
-                }
-                _ => panic!("Component not found"),
-            }
-        }
-
-        if let Some(at) = at {
-            match comp {
-                NestedConfig::Compose(_, _, subs, _, _) => {
-                    comp = subs
-                        .get(&at)
-                        .ok_or(ComponentRequestError::InvalidComponentId(at))?;
-                }
-                _ => panic!("Component not found"),
-            }
-        }
-
-        let (ins, outs) = match comp {
-            NestedConfig::Single(_, _, ins, outs) => (ins, outs),
-            NestedConfig::Compose(_, _, _, ins, outs) => (ins, outs),
-        };
+        let (ins, outs) = self.nested_config.ports_at(comp_path, at)?;
         let in_bits = ins
             .iter()
             .map(|(idx, port)| self.components[*idx].inputs[*port])
@@ -239,20 +249,21 @@ impl FlattenComponent {
 fn flat_comp(comp: &Component<ExtraInfo>) -> (Vec<PrimitiveComponent>, Vec<Conn>) {
     let mut comps = vec![];
     let mut conns = vec![];
-    if let Some(sub) = &comp.sub {
-        conns.extend(sub.connections.clone());
-        for (flatten, conn) in sub.components.iter().map(flat_comp) {
-            comps.extend(flatten);
-            conns.extend(conn.clone());
+    for (_, comp) in comp.iter_dfs() {
+        match &comp.sub {
+            Some(sub) => conns.extend(sub.connections.iter().copied()),
+            None => comps.push(primitive_comp(comp)),
         }
-        return (comps, conns);
     }
+    (comps, conns)
+}
 
+fn primitive_comp(comp: &Component<ExtraInfo>) -> PrimitiveComponent {
     assert!(comp.extra.primitive.is_some());
 
     let in_count = comp.inputs;
     let id = comp.extra.id;
-    let new_comp = match comp.extra.primitive.as_ref().unwrap() {
+    match comp.extra.primitive.as_ref().unwrap() {
         Primitive::AndGate => PrimitiveComponent::and_gate(id, in_count),
         Primitive::OrGate => PrimitiveComponent::or_gate(id, in_count),
         Primitive::NotGate => PrimitiveComponent::not_gate(id),
@@ -264,19 +275,24 @@ fn flat_comp(comp: &Component<ExtraInfo>) -> (Vec<PrimitiveComponent>, Vec<Conn>
         Primitive::Output { bits } => PrimitiveComponent::output(id, *bits),
         Primitive::Splitter { bits } => PrimitiveComponent::splitter(id, *bits),
         Primitive::Joiner { bits } => PrimitiveComponent::joiner(id, *bits),
-        Primitive::Clock { period } => PrimitiveComponent::clock(id, *period),
+        Primitive::Clock(clock) => PrimitiveComponent::clock(id, *clock),
         Primitive::Const { value } => PrimitiveComponent::const_gate(id, *value),
         Primitive::Custom { comp, state } => {
             PrimitiveComponent::custom(id, comp.clone(), state.clone())
         }
-    };
+    }
+}
 
-    (vec![new_comp], vec![])
+/// Instance name of a component in the nested configuration, or the name of
+/// the component if it has none.
+fn name_of(comp: &Component<ExtraInfo>) -> String {
+    comp.instance_name().unwrap_or_default().to_string()
 }
 
 fn reindex_connections(
     comp: &mut Component<ExtraInfo>,
     start_idx: usize,
+    nested: bool,
 ) -> Result<(usize, NestedConfig), FlattenComponentError> {
     debug!(
         "Reindexing connections for component: {:?} statring from {}",
@@ -289,7 +305,7 @@ fn reindex_connections(
         let outs = (0..comp.outputs).map(|i| (start_idx, i)).collect();
         return Ok((
             start_idx + 1,
-            NestedConfig::Single(comp.name.clone().unwrap_or_default(), comp.id, ins, outs),
+            NestedConfig::Single(name_of(comp), comp.id, ins, outs),
         ));
     }
 
@@ -299,7 +315,7 @@ fn reindex_connections(
 
     // reindex subcomponents
     for comp in sub.components.as_mut_slice() {
-        let (new_start, config) = reindex_connections(comp, *idx_starts.last().unwrap())?;
+        let (new_start, config) = reindex_connections(comp, *idx_starts.last().unwrap(), true)?;
         idx_starts.push(new_start);
         sub_configs.insert(comp.id, config);
     }
@@ -310,6 +326,18 @@ fn reindex_connections(
     for i in 0..sub.connections.len() {
         debug!("Changing connection: {:?}", sub.connections[i]);
         let conn = &sub.connections[i];
+
+        // When the inputs of a nested component are wired to the destinations
+        // of its input primitives (as in boards), the connections from those
+        // primitives would drive the same ports with a constant value
+        let from_input = matches!(
+            sub.components[conn.from.0].extra.primitive,
+            Some(Primitive::Input { .. })
+        );
+        if nested && from_input && !sub.in_addrs.iter().any(|(_, (idx, _))| *idx == conn.from.0) {
+            continue;
+        }
+
         let from_idx: usize;
         let from_addr: usize;
         let mut to_ports = vec![];
@@ -378,13 +406,7 @@ fn reindex_connections(
     }
 
     let nested_out = sub.out_addrs.clone();
-    let config = NestedConfig::Compose(
-        comp.name.clone().unwrap_or_default(),
-        comp.id,
-        sub_configs,
-        nested_in,
-        nested_out,
-    );
+    let config = NestedConfig::Compose(name_of(comp), comp.id, sub_configs, nested_in, nested_out);
 
     return Ok((*idx_starts.last().unwrap(), config));
 }
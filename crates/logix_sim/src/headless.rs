@@ -0,0 +1,261 @@
+use asmhdl::Data;
+use logix_core::component::{PortAddr, PortDesc};
+
+use crate::{
+    delta::DeltaCycles,
+    errors::HeadlessSimError,
+    flatten::{FlattenComponent, NestedConfig},
+    primitives::{clock::ClockConfig, primitive::Primitive},
+};
+
+/// Maximum number of propagation rounds before a circuit is considered
+/// unstable (e.g. a ring oscillator).
+const MAX_SETTLE_ROUNDS: usize = 10_000;
+
+/// Named top-level input or output of a circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Port {
+    pub name: String,
+    /// Id of the `Input`/`Output` primitive.
+    pub id: usize,
+    pub bits: usize,
+}
+
+/// Top-level inputs and outputs of a flattened component, in port order and
+/// named after their descriptors (`in{i}` and `out{i}` if they have none).
+///
+/// Only the ports that lead to an `Input` or `Output` primitive are returned.
+pub fn top_level_ports(comp: &FlattenComponent) -> (Vec<Port>, Vec<Port>) {
+    let NestedConfig::Compose(_, _, _, ins, outs) = &comp.nested_config else {
+        return (vec![], vec![]);
+    };
+    let ports = |addrs: &[PortAddr], descs: &[PortDesc], prefix: &str| {
+        addrs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (idx, _))| {
+                let prim = &comp.components[*idx];
+                let (Primitive::Input { bits } | Primitive::Output { bits }) = prim.prim_type
+                else {
+                    return None;
+                };
+                let name = descs
+                    .get(i)
+                    .map(|desc| desc.name.clone())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| format!("{prefix}{i}"));
+                Some(Port {
+                    name,
+                    id: prim.id,
+                    bits,
+                })
+            })
+            .collect()
+    };
+    (
+        ports(ins, &comp.input_ports, "in"),
+        ports(outs, &comp.output_ports, "out"),
+    )
+}
+
+/// Simulator that runs on the calling thread using a simulated time instead
+/// of the wall clock, so the results of a run are reproducible.
+pub struct HeadlessSimulator {
+    core: DeltaCycles,
+}
+
+impl HeadlessSimulator {
+    pub fn new(comp: FlattenComponent) -> Self {
+        HeadlessSimulator {
+            core: DeltaCycles::new(comp),
+        }
+    }
+
+    pub fn comp(&self) -> &FlattenComponent {
+        &self.core.comp
+    }
+
+    /// Current simulation time in nanoseconds.
+    pub fn time(&self) -> u128 {
+        self.core.time
+    }
+
+    /// Fastest clock in the circuit. Clocks with an enable input are left
+    /// out, as their edges depend on the rest of the circuit.
+    pub fn clock(&self) -> Option<ClockConfig> {
+        self.core
+            .comp
+            .components
+            .iter()
+            .filter_map(|c| match c.prim_type {
+                Primitive::Clock(clock) if !clock.enable => Some(clock),
+                _ => None,
+            })
+            .min_by_key(|clock| clock.period)
+    }
+
+    /// Drives the value of an input component.
+    pub fn set_input(&mut self, id: usize, value: Data) -> Result<(), HeadlessSimError> {
+        let idx = self.idx_of(id)?;
+        let Primitive::Input { bits } = self.core.comp.components[idx].prim_type else {
+            return Err(HeadlessSimError::NotAnInput(id));
+        };
+        if bits < usize::BITS as usize && value.value >> bits != 0 {
+            return Err(HeadlessSimError::ValueOutOfRange {
+                value: value.value,
+                bits,
+            });
+        }
+        self.core.comp.components[idx].inputs[0] = Data::new(value.value, bits);
+        self.core.enqueue(idx);
+        Ok(())
+    }
+
+    /// Value received by an output component.
+    pub fn output(&self, id: usize) -> Result<Data, HeadlessSimError> {
+        let comp = &self.core.comp.components[self.idx_of(id)?];
+        if !comp.prim_type.is_output() {
+            return Err(HeadlessSimError::NotAnOutput(id));
+        }
+        Ok(comp.inputs[0])
+    }
+
+    /// Propagates the pending changes until the circuit is stable.
+    pub fn settle(&mut self) -> Result<(), HeadlessSimError> {
+        let mut rounds = 0;
+        while !self.core.is_stable() {
+            if rounds == MAX_SETTLE_ROUNDS {
+                return Err(HeadlessSimError::Unstable(self.core.time));
+            }
+            rounds += 1;
+
+            for idx in self.core.take_pending() {
+                self.core.update(idx);
+            }
+        }
+        Ok(())
+    }
+
+    /// Time of the next change that does not come from the inputs.
+    pub fn next_event(&self) -> Option<u128> {
+        self.core.next_event(|_| true)
+    }
+
+    /// Settles the circuit and advances to the next event if it happens
+    /// before `end`. Otherwise the time is moved to `end`.
+    ///
+    /// Returns whether an event was processed.
+    pub fn step_until(&mut self, end: u128) -> Result<bool, HeadlessSimError> {
+        self.settle()?;
+        match self.next_event() {
+            Some(time) if time <= end => {
+                self.core.advance_to(time);
+                self.settle()?;
+                Ok(true)
+            }
+            _ => {
+                self.core.time = self.core.time.max(end);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Runs the simulation until the given time.
+    pub fn run_until(&mut self, end: u128) -> Result<(), HeadlessSimError> {
+        while self.step_until(end)? {}
+        Ok(())
+    }
+
+    fn idx_of(&self, id: usize) -> Result<usize, HeadlessSimError> {
+        self.core
+            .comp
+            .id_to_idx
+            .get(&id)
+            .copied()
+            .ok_or(HeadlessSimError::InvalidComponentId(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prim;
+    use logix_core::prelude::*;
+
+    /// `in -> not -> out` plus `clock -> out`.
+    fn test_comp() -> FlattenComponent {
+        let comp = ComponentBuilder::new(0)
+            .input_ports(vec![PortDesc::input("a", 1)])
+            .output_ports(vec![PortDesc::output("y", 1), PortDesc::output("", 1)])
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::NotGate),
+                prim(3, 1, 1, Primitive::Output { bits: 1 }),
+                prim(4, 0, 1, Primitive::Clock(ClockConfig::new(10))),
+                prim(5, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 1, 0),
+                Conn::new(1, 0, 2, 0),
+                Conn::new(3, 0, 4, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(2, 0), (4, 0)])
+            .build()
+            .unwrap();
+        FlattenComponent::new(comp).unwrap()
+    }
+
+    #[test]
+    fn test_inputs_propagate() {
+        let mut sim = HeadlessSimulator::new(test_comp());
+        sim.settle().unwrap();
+        assert_eq!(sim.output(3).unwrap(), Data::high());
+
+        sim.set_input(1, Data::high()).unwrap();
+        sim.settle().unwrap();
+        assert_eq!(sim.output(3).unwrap(), Data::low());
+
+        assert!(matches!(
+            sim.set_input(1, Data::new(2, 2)),
+            Err(HeadlessSimError::ValueOutOfRange { .. })
+        ));
+        assert!(matches!(
+            sim.set_input(2, Data::high()),
+            Err(HeadlessSimError::NotAnInput(2))
+        ));
+    }
+
+    #[test]
+    fn test_top_level_ports() {
+        let (inputs, outputs) = top_level_ports(&test_comp());
+        let ids = |ports: &[Port]| {
+            ports
+                .iter()
+                .map(|p| (p.name.clone(), p.id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&inputs), vec![("a".into(), 1)]);
+        assert_eq!(ids(&outputs), vec![("y".into(), 3), ("out1".into(), 5)]);
+    }
+
+    #[test]
+    fn test_clock_steps() {
+        let mut sim = HeadlessSimulator::new(test_comp());
+        assert_eq!(sim.clock(), Some(ClockConfig::new(10)));
+
+        let mut edges = vec![];
+        while sim.step_until(30).unwrap() {
+            edges.push((sim.time(), sim.output(5).unwrap().value));
+        }
+        assert_eq!(
+            edges,
+            vec![(5, 1), (10, 0), (15, 1), (20, 0), (25, 1), (30, 0)]
+        );
+        assert_eq!(sim.time(), 30);
+
+        sim.run_until(34).unwrap();
+        assert_eq!(sim.time(), 34);
+        assert_eq!(sim.output(5).unwrap(), Data::low());
+    }
+}
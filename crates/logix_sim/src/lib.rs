@@ -1,6 +1,22 @@
+mod delta;
+pub mod equivalence;
 pub mod errors;
 pub mod flatten;
+pub mod headless;
+pub mod lint;
+pub mod netlist;
+pub mod optimize;
 pub mod primitives;
+mod sat;
 pub mod simulator;
+pub mod stats;
+#[cfg(test)]
+mod test_utils;
+pub mod testbench;
+pub mod truth_table;
+pub mod verilog;
 
+pub use headless::HeadlessSimulator;
 pub use simulator::Simulator;
+pub use testbench::Testbench;
+pub use truth_table::TruthTable;
@@ -0,0 +1,335 @@
+//! Static checks of flattened circuits.
+//!
+//! The lint pass looks for mistakes that the simulator accepts silently:
+//! combinational loops (feedback between gates without a custom component,
+//! which holds state, in between), primitives whose outputs drive nothing,
+//! unconnected inputs (which read as low) and outputs that nothing drives.
+//!
+//! The inputs of the top component are driven from outside and its outputs
+//! are read from outside, so they are only reported when left unused inside
+//! the circuit or undriven.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use logix_core::prelude::*;
+
+use crate::{
+    flatten::{FlattenComponent, NestedConfig},
+    primitives::prelude::Primitive,
+};
+
+/// Primitive referenced by a lint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintComp {
+    pub id: usize,
+
+    /// Instance names of the components that hold the primitive, from the
+    /// top one, and of the primitive, separated by `.` (e.g.
+    /// `Main.latch.n1`). Components without a name appear as `#{id}`.
+    pub path: String,
+}
+
+impl Display for LintComp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// Primitives that depend on each other without a custom component in
+    /// between.
+    CombinationalLoop(Vec<LintComp>),
+
+    /// Primitive whose outputs are not connected to anything.
+    UnusedOutputs(LintComp),
+
+    /// Input port of a primitive that nothing drives.
+    UnconnectedInput(LintComp, usize),
+
+    /// Output primitive that nothing drives.
+    UndrivenOutput(LintComp),
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lint::CombinationalLoop(comps) => {
+                let comps: Vec<String> = comps.iter().map(|c| c.to_string()).collect();
+                write!(f, "Combinational loop between {}", comps.join(", "))
+            }
+            Lint::UnusedOutputs(comp) => write!(f, "Outputs of {comp} drive nothing"),
+            Lint::UnconnectedInput(comp, port) => {
+                write!(
+                    f,
+                    "Input {port} of {comp} is not connected and reads as low"
+                )
+            }
+            Lint::UndrivenOutput(comp) => write!(f, "Output {comp} is never driven"),
+        }
+    }
+}
+
+/// Collects the hierarchical names of the primitives of `config`.
+fn primitive_paths(config: &NestedConfig, prefix: &str, paths: &mut HashMap<usize, String>) {
+    let (name, id) = match config {
+        NestedConfig::Single(name, id, ..) => (name, id),
+        NestedConfig::Compose(name, id, ..) => (name, id),
+    };
+    let name = if name.is_empty() {
+        format!("#{id}")
+    } else {
+        name.clone()
+    };
+    let path = if prefix.is_empty() {
+        name
+    } else {
+        format!("{prefix}.{name}")
+    };
+
+    match config {
+        NestedConfig::Single(..) => {
+            paths.insert(*id, path);
+        }
+        NestedConfig::Compose(_, _, subs, _, _) => {
+            for sub in subs.values() {
+                primitive_paths(sub, &path, paths);
+            }
+        }
+    }
+}
+
+/// Tarjan's algorithm over the dependencies of the primitives, skipping the
+/// custom components.
+struct LoopFinder<'a> {
+    flatten: &'a FlattenComponent,
+    index: Vec<Option<usize>>,
+    low_link: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    loops: Vec<Vec<usize>>,
+}
+
+impl LoopFinder<'_> {
+    fn is_sequential(&self, idx: usize) -> bool {
+        matches!(
+            self.flatten.components[idx].prim_type,
+            Primitive::Custom { .. }
+        )
+    }
+
+    fn visit(&mut self, idx: usize) {
+        self.index[idx] = Some(self.next_index);
+        self.low_link[idx] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(idx);
+        self.on_stack[idx] = true;
+
+        for &next in &self.flatten.inv_deps[idx] {
+            if self.is_sequential(next) {
+                continue;
+            }
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.low_link[idx] = self.low_link[idx].min(self.low_link[next]);
+                }
+                Some(next_index) if self.on_stack[next] => {
+                    self.low_link[idx] = self.low_link[idx].min(next_index);
+                }
+                Some(_) => (),
+            }
+        }
+
+        if Some(self.low_link[idx]) == self.index[idx] {
+            let mut comps = vec![];
+            loop {
+                let comp = self.stack.pop().unwrap();
+                self.on_stack[comp] = false;
+                comps.push(comp);
+                if comp == idx {
+                    break;
+                }
+            }
+            if comps.len() > 1 || self.flatten.inv_deps[idx].contains(&idx) {
+                comps.sort();
+                self.loops.push(comps);
+            }
+        }
+    }
+}
+
+impl FlattenComponent {
+    /// Indices of the primitives of each combinational loop.
+    pub(crate) fn combinational_loops(&self) -> Vec<Vec<usize>> {
+        let mut finder = LoopFinder {
+            flatten: self,
+            index: vec![None; self.components.len()],
+            low_link: vec![0; self.components.len()],
+            on_stack: vec![false; self.components.len()],
+            stack: vec![],
+            next_index: 0,
+            loops: vec![],
+        };
+        for idx in 0..self.components.len() {
+            if finder.index[idx].is_none() && !finder.is_sequential(idx) {
+                finder.visit(idx);
+            }
+        }
+        finder.loops
+    }
+
+    /// Checks the circuit for combinational loops, unused outputs,
+    /// unconnected inputs and undriven outputs.
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut paths = HashMap::new();
+        primitive_paths(&self.nested_config, "", &mut paths);
+        let lint_comp = |idx: usize| {
+            let id = self.components[idx].id;
+            LintComp {
+                id,
+                path: paths.get(&id).cloned().unwrap_or_default(),
+            }
+        };
+
+        let top = self.top_level_ids();
+
+        let mut lints = vec![];
+
+        let mut loops = self.combinational_loops();
+        loops.sort();
+        for comps in loops {
+            lints.push(Lint::CombinationalLoop(
+                comps.into_iter().map(lint_comp).collect(),
+            ));
+        }
+
+        let driven: HashSet<PortAddr> = self.connections.iter().flatten().map(|c| c.to).collect();
+        for (idx, comp) in self.components.iter().enumerate() {
+            let is_top = top.contains(&comp.id);
+            match comp.prim_type {
+                Primitive::Output { .. } => {
+                    if !driven.contains(&(idx, 0)) {
+                        lints.push(Lint::UndrivenOutput(lint_comp(idx)));
+                    }
+                    if !is_top && self.connections[idx].is_empty() {
+                        lints.push(Lint::UnusedOutputs(lint_comp(idx)));
+                    }
+                }
+                Primitive::Input { .. } if is_top => {
+                    if self.connections[idx].is_empty() {
+                        lints.push(Lint::UnusedOutputs(lint_comp(idx)));
+                    }
+                }
+                _ => {
+                    for port in 0..comp.inputs.len() {
+                        if !driven.contains(&(idx, port)) {
+                            lints.push(Lint::UnconnectedInput(lint_comp(idx), port));
+                        }
+                    }
+                    if !comp.outputs.is_empty() && self.connections[idx].is_empty() {
+                        lints.push(Lint::UnusedOutputs(lint_comp(idx)));
+                    }
+                }
+            }
+        }
+
+        lints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{primitives::primitive::ExtraInfo, test_utils::prim};
+
+    fn path(id: usize, path: &str) -> LintComp {
+        LintComp {
+            id,
+            path: path.into(),
+        }
+    }
+
+    fn named(comp: Component<ExtraInfo>, instance: &str) -> Component<ExtraInfo> {
+        Component {
+            instance: Some(instance.into()),
+            ..comp
+        }
+    }
+
+    /// Main with an SR latch made of two nor gates, an and gate with an
+    /// unconnected input and an inverter component whose output is unused.
+    fn main() -> FlattenComponent {
+        let inv = ComponentBuilder::new(10)
+            .name("Inv".into())
+            .instance("inv".into())
+            .port_count(1, 1)
+            .sub_comps(vec![
+                prim(11, 1, 1, Primitive::Input { bits: 1 }),
+                prim(12, 1, 1, Primitive::NotGate),
+                prim(13, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0), Conn::new(1, 0, 2, 0)])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(2, 0)])
+            .build()
+            .unwrap();
+        let main = ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(2, 3)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                named(prim(3, 2, 1, Primitive::NorGate), "n1"),
+                named(prim(4, 2, 1, Primitive::NorGate), "n2"),
+                prim(5, 1, 1, Primitive::Output { bits: 1 }),
+                prim(6, 2, 1, Primitive::AndGate),
+                prim(7, 1, 1, Primitive::Output { bits: 1 }),
+                inv,
+                prim(8, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 3, 1),
+                Conn::new(2, 0, 3, 0),
+                Conn::new(3, 0, 2, 1),
+                Conn::new(3, 0, 4, 0),
+                Conn::new(0, 0, 5, 0),
+                Conn::new(5, 0, 6, 0),
+                Conn::new(0, 0, 7, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(4, 0), (6, 0), (8, 0)])
+            .build()
+            .unwrap();
+        FlattenComponent::new(main).unwrap()
+    }
+
+    #[test]
+    fn test_lint() {
+        let lints = main().lint();
+        assert_eq!(
+            lints,
+            vec![
+                Lint::CombinationalLoop(vec![path(3, "Main.n1"), path(4, "Main.n2")]),
+                Lint::UnconnectedInput(path(6, "Main.and"), 1),
+                Lint::UnusedOutputs(path(13, "Main.inv.output")),
+                Lint::UndrivenOutput(path(8, "Main.output")),
+            ]
+        );
+        let messages: Vec<String> = lints.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Combinational loop between Main.n1, Main.n2",
+                "Input 1 of Main.and is not connected and reads as low",
+                "Outputs of Main.inv.output drive nothing",
+                "Output Main.output is never driven",
+            ]
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Exports of flattened components, to inspect the result of flattening.
+//!
+//! [`FlattenComponent::to_dot`] writes a Graphviz graph and
+//! [`FlattenComponent::to_netlist`] a [`Netlist`], which is serialized as
+//! JSON with the following format (version 2):
+//!
+//! ```json
+//! {
+//!   "format": "logix-netlist",
+//!   "version": 2,
+//!   "cells": [
+//!     { "index": 0, "id": 1, "name": "Input", "kind": "input",
+//!       "inputs": [2], "outputs": [2] }
+//!   ],
+//!   "connections": [
+//!     { "from": { "cell": 0, "port": 0 }, "to": { "cell": 2, "port": 1 },
+//!       "width": 2 }
+//!   ],
+//!   "hierarchy": {
+//!     "name": "Main", "id": 5, "cells": [0, 1], "inputs": [], "outputs": [],
+//!     "children": []
+//!   }
+//! }
+//! ```
+//!
+//! - `cells` are the primitives, by their index in the flattened component.
+//!   `kind` is one of `and`, `or`, `not`, `nand`, `nor`, `xor`, `switch`,
+//!   `input`, `output`, `splitter`, `joiner`, `clock`, `const` and `custom`.
+//!   Clocks have a `clock` with their `period`, `phase` and `delay` (in
+//!   nanoseconds), `duty` cycle (in percent) and whether they have an
+//!   `enable` input. Constants have a `value` and custom components a
+//!   `component` name. `inputs` and `outputs` are the widths of
+//!   the ports.
+//! - `connections` go from an output port to an input port of the cells.
+//! - `hierarchy` is the tree of composite components: each node has the
+//!   cells of the primitives it contains directly, the ports its inputs and
+//!   outputs are mapped to and its composite children, sorted by id.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    flatten::{FlattenComponent, NestedConfig},
+    primitives::{clock::ClockConfig, primitive::Primitive},
+};
+
+/// Name of the JSON netlist format.
+pub const NETLIST_FORMAT: &str = "logix-netlist";
+
+/// Current version of the JSON netlist format.
+pub const NETLIST_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Netlist {
+    pub format: String,
+    pub version: u32,
+    pub cells: Vec<NetlistCell>,
+    pub connections: Vec<NetlistConn>,
+    pub hierarchy: NetlistModule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetlistCell {
+    pub index: usize,
+    pub id: usize,
+    pub name: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock: Option<ClockConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetlistPort {
+    pub cell: usize,
+    pub port: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetlistConn {
+    pub from: NetlistPort,
+    pub to: NetlistPort,
+    pub width: usize,
+}
+
+/// Composite component of the hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetlistModule {
+    pub name: String,
+    pub id: usize,
+    pub cells: Vec<usize>,
+    pub inputs: Vec<NetlistPort>,
+    pub outputs: Vec<NetlistPort>,
+    pub children: Vec<NetlistModule>,
+}
+
+impl Netlist {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Netlists are always serializable")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Kind of a primitive in the netlist formats.
+pub(crate) const fn kind(prim: &Primitive) -> &'static str {
+    match prim {
+        Primitive::AndGate => "and",
+        Primitive::OrGate => "or",
+        Primitive::NotGate => "not",
+        Primitive::NandGate => "nand",
+        Primitive::NorGate => "nor",
+        Primitive::XorGate => "xor",
+        Primitive::Switch => "switch",
+        Primitive::Input { .. } => "input",
+        Primitive::Output { .. } => "output",
+        Primitive::Splitter { .. } => "splitter",
+        Primitive::Joiner { .. } => "joiner",
+        Primitive::Clock(_) => "clock",
+        Primitive::Const { .. } => "const",
+        Primitive::Custom { .. } => "custom",
+    }
+}
+
+fn port((cell, port): &(usize, usize)) -> NetlistPort {
+    NetlistPort {
+        cell: *cell,
+        port: *port,
+    }
+}
+
+/// Composite children of a nested config, sorted by id, and the indexes of
+/// its primitives.
+fn children<'a>(
+    comp: &FlattenComponent,
+    subs: &'a std::collections::HashMap<usize, NestedConfig>,
+) -> (Vec<&'a NestedConfig>, Vec<usize>) {
+    let mut subs: Vec<(&usize, &NestedConfig)> = subs.iter().collect();
+    subs.sort_by_key(|(id, _)| **id);
+    let mut composites = vec![];
+    let mut cells = vec![];
+    for (id, config) in subs {
+        match config {
+            NestedConfig::Single(..) => cells.extend(comp.id_to_idx.get(id)),
+            NestedConfig::Compose(..) => composites.push(config),
+        }
+    }
+    cells.sort_unstable();
+    (composites, cells)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl FlattenComponent {
+    /// Netlist of the primitives and their connections, with the hierarchy
+    /// of the composite components they come from.
+    pub fn to_netlist(&self) -> Netlist {
+        let cells = self
+            .components
+            .iter()
+            .enumerate()
+            .map(|(index, comp)| NetlistCell {
+                index,
+                id: comp.id,
+                name: comp.name.clone(),
+                kind: kind(&comp.prim_type).to_string(),
+                clock: match comp.prim_type {
+                    Primitive::Clock(clock) => Some(clock),
+                    _ => None,
+                },
+                value: match comp.prim_type {
+                    Primitive::Const { value } => Some(value.value),
+                    _ => None,
+                },
+                component: match &comp.prim_type {
+                    Primitive::Custom { comp, .. } => Some(comp.name.clone()),
+                    _ => None,
+                },
+                inputs: comp.inputs.iter().map(|data| data.size).collect(),
+                outputs: comp.outputs.iter().map(|data| data.size).collect(),
+            })
+            .collect();
+
+        let connections = self
+            .connections
+            .iter()
+            .flatten()
+            .map(|conn| NetlistConn {
+                from: port(&conn.from),
+                to: port(&conn.to),
+                width: self.components[conn.from.0].outputs[conn.from.1].size,
+            })
+            .collect();
+
+        Netlist {
+            format: NETLIST_FORMAT.to_string(),
+            version: NETLIST_VERSION,
+            cells,
+            connections,
+            hierarchy: self.netlist_module(&self.nested_config),
+        }
+    }
+
+    fn netlist_module(&self, config: &NestedConfig) -> NetlistModule {
+        match config {
+            NestedConfig::Single(name, id, ins, outs) => NetlistModule {
+                name: name.clone(),
+                id: *id,
+                cells: self.id_to_idx.get(id).copied().into_iter().collect(),
+                inputs: ins.iter().map(port).collect(),
+                outputs: outs.iter().map(port).collect(),
+                children: vec![],
+            },
+            NestedConfig::Compose(name, id, subs, ins, outs) => {
+                let (composites, cells) = children(self, subs);
+                NetlistModule {
+                    name: name.clone(),
+                    id: *id,
+                    cells,
+                    inputs: ins.iter().map(port).collect(),
+                    outputs: outs.iter().map(port).collect(),
+                    children: composites
+                        .into_iter()
+                        .map(|sub| self.netlist_module(sub))
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    /// Graphviz graph of the primitives, labelled with their name and id,
+    /// and their connections, labelled with the ports they join.
+    ///
+    /// The primitives of each composite component are grouped in a cluster.
+    pub fn to_dot(&self) -> String {
+        let (name, id) = match &self.nested_config {
+            NestedConfig::Single(name, id, ..) | NestedConfig::Compose(name, id, ..) => (name, id),
+        };
+        let mut lines = vec![
+            format!("digraph \"{}\" {{", escape(name)),
+            "    rankdir=LR;".to_string(),
+            "    node [shape=box];".to_string(),
+            format!("    label=\"{} #{id}\";", escape(name)),
+        ];
+        match &self.nested_config {
+            NestedConfig::Compose(_, _, subs, _, _) => self.write_dot_cluster(subs, 1, &mut lines),
+            NestedConfig::Single(..) => lines.push(self.dot_node(0, 1)),
+        }
+        for conn in self.connections.iter().flatten() {
+            let width = self.components[conn.from.0].outputs[conn.from.1].size;
+            let style = if width > 1 {
+                format!(", label=\"{width}\", penwidth=2")
+            } else {
+                String::new()
+            };
+            lines.push(format!(
+                "    n{} -> n{} [taillabel=\"{}\", headlabel=\"{}\"{style}];",
+                conn.from.0, conn.to.0, conn.from.1, conn.to.1
+            ));
+        }
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn dot_node(&self, idx: usize, depth: usize) -> String {
+        let comp = &self.components[idx];
+        format!(
+            "{}n{idx} [label=\"{}\\n#{}\"];",
+            "    ".repeat(depth),
+            escape(&comp.name),
+            comp.id
+        )
+    }
+
+    fn write_dot_cluster(
+        &self,
+        subs: &std::collections::HashMap<usize, NestedConfig>,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) {
+        let (composites, cells) = children(self, subs);
+        for idx in cells {
+            lines.push(self.dot_node(idx, depth));
+        }
+        let indent = "    ".repeat(depth);
+        for config in composites {
+            let NestedConfig::Compose(name, id, subs, _, _) = config else {
+                continue;
+            };
+            lines.push(format!("{indent}subgraph cluster_{id} {{"));
+            lines.push(format!("{indent}    label=\"{} #{id}\";", escape(name)));
+            self.write_dot_cluster(subs, depth + 1, lines);
+            lines.push(format!("{indent}}}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logix_core::prelude::*;
+
+    use super::*;
+    use crate::test_utils::prim;
+
+    /// Main with an inverter component inside.
+    fn nested() -> FlattenComponent {
+        let inv = ComponentBuilder::new(3)
+            .name("Inv".into())
+            .port_count(1, 1)
+            .sub_comps(vec![
+                prim(4, 1, 1, Primitive::Input { bits: 1 }),
+                prim(5, 1, 1, Primitive::NotGate),
+                prim(6, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0), Conn::new(1, 0, 2, 0)])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(2, 0)])
+            .build()
+            .unwrap();
+        let main = ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(1, 1)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                inv,
+                prim(2, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0), Conn::new(1, 0, 2, 0)])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(2, 0)])
+            .build()
+            .unwrap();
+        FlattenComponent::new(main).unwrap()
+    }
+
+    #[test]
+    fn test_netlist_json() {
+        let netlist = nested().to_netlist();
+        let kinds: Vec<&str> = netlist.cells.iter().map(|c| c.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["input", "input", "not", "output", "output"]);
+        let conns: Vec<(usize, usize)> = netlist
+            .connections
+            .iter()
+            .map(|c| (c.from.cell, c.to.cell))
+            .collect();
+        assert_eq!(conns, vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+        assert_eq!(netlist.hierarchy.name, "Main");
+        assert_eq!(netlist.hierarchy.cells, vec![0, 4]);
+        assert_eq!(netlist.hierarchy.children[0].name, "Inv");
+        assert_eq!(netlist.hierarchy.children[0].cells, vec![1, 2, 3]);
+
+        let json = netlist.to_json();
+        assert!(json.contains("\"format\": \"logix-netlist\""));
+        assert_eq!(Netlist::from_json(&json).unwrap(), netlist);
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = nested().to_dot();
+        assert!(dot.starts_with("digraph \"Main\" {\n"));
+        assert!(dot.contains("    subgraph cluster_3 {\n        label=\"Inv #3\";\n"));
+        assert!(dot.contains("        n2 [label=\"Not\\n#5\"];\n"));
+        assert!(dot.contains("    n0 -> n1 [taillabel=\"0\", headlabel=\"0\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
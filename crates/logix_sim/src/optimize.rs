@@ -0,0 +1,637 @@
+//! Logic optimizations of flattened circuits.
+//!
+//! The passes rewrite the primitives and connections of a
+//! [`FlattenComponent`] keeping the values of its top-level outputs for any
+//! value of its top-level inputs:
+//!
+//! - [`Pass::ConstantPropagation`] replaces gates whose output is fixed by
+//!   constant inputs with constants, and drops the inputs that don't change
+//!   the output (e.g. a high input of an and gate). Unconnected inputs read
+//!   as low, so they are constants too.
+//! - [`Pass::DoubleNegation`] connects the loads of two chained inverters to
+//!   the input of the first one.
+//! - [`Pass::CommonSubexpressions`] merges gates of the same kind driven by
+//!   the same signals.
+//! - [`Pass::DeadGates`] removes the primitives whose outputs drive nothing,
+//!   after connecting the loads of the inputs and outputs of nested
+//!   components to their sources.
+//!
+//! The inputs and outputs of nested components are seen through, as they
+//! only pass values along. Gates in combinational loops (e.g. latches) hold
+//! state, so they are left untouched.
+//!
+//! Only the primitives of the top component keep their nested configuration:
+//! the state of nested components of an optimized circuit can't be queried.
+
+use std::collections::HashMap;
+
+use asmhdl::Data;
+use logix_core::prelude::*;
+
+use crate::{
+    flatten::{FlattenComponent, NestedConfig},
+    netlist::kind,
+    primitives::{prelude::Primitive, primitive::PrimitiveComponent},
+};
+
+/// Optimization pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    ConstantPropagation,
+    DoubleNegation,
+    CommonSubexpressions,
+    DeadGates,
+}
+
+impl Pass {
+    /// Every pass, in the order they are run by [`FlattenComponent::optimize`].
+    pub const ALL: [Pass; 4] = [
+        Pass::ConstantPropagation,
+        Pass::DoubleNegation,
+        Pass::CommonSubexpressions,
+        Pass::DeadGates,
+    ];
+}
+
+/// Amount of primitives changed by each pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizeReport {
+    /// Gates replaced by constants or with constant inputs dropped.
+    pub constants: usize,
+
+    /// Inverter pairs bypassed.
+    pub negations: usize,
+
+    /// Gates merged with an equivalent one.
+    pub duplicates: usize,
+
+    /// Primitives removed.
+    pub removed: usize,
+}
+
+/// Where the value read by an input comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Source {
+    /// The input is not connected.
+    Low,
+    Port(PortAddr),
+}
+
+struct Optimizer {
+    comps: Vec<PrimitiveComponent>,
+    conns: Vec<Conn>,
+    removed: Vec<bool>,
+    /// Inputs and outputs of the top component, which are never changed.
+    top_io: Vec<bool>,
+    /// Primitives in combinational loops.
+    in_loop: Vec<bool>,
+    /// Primitives whose inputs were dropped.
+    renumbered: Vec<bool>,
+}
+
+const fn is_gate(prim: &Primitive) -> bool {
+    matches!(
+        prim,
+        Primitive::AndGate
+            | Primitive::OrGate
+            | Primitive::NotGate
+            | Primitive::NandGate
+            | Primitive::NorGate
+            | Primitive::XorGate
+    )
+}
+
+fn gate(id: usize, prim: &Primitive, in_count: usize) -> PrimitiveComponent {
+    match prim {
+        Primitive::AndGate => PrimitiveComponent::and_gate(id, in_count),
+        Primitive::OrGate => PrimitiveComponent::or_gate(id, in_count),
+        Primitive::NandGate => PrimitiveComponent::nand_gate(id, in_count),
+        Primitive::NorGate => PrimitiveComponent::nor_gate(id, in_count),
+        Primitive::XorGate => PrimitiveComponent::xor_gate(id, in_count),
+        _ => PrimitiveComponent::not_gate(id),
+    }
+}
+
+/// Output of a gate with the given inputs, if it is fixed by the constant
+/// ones.
+fn fixed_output(prim: &Primitive, inputs: &[Option<bool>]) -> Option<bool> {
+    let all = || inputs.iter().copied().collect::<Option<Vec<bool>>>();
+    match prim {
+        Primitive::AndGate | Primitive::NandGate => {
+            let out = if inputs.contains(&Some(false)) {
+                false
+            } else {
+                all()?.into_iter().all(|v| v)
+            };
+            Some(out ^ matches!(prim, Primitive::NandGate))
+        }
+        Primitive::OrGate | Primitive::NorGate => {
+            let out = if inputs.contains(&Some(true)) {
+                true
+            } else {
+                all()?.into_iter().any(|v| v)
+            };
+            Some(out ^ matches!(prim, Primitive::NorGate))
+        }
+        Primitive::XorGate => Some(all()?.into_iter().fold(false, |acc, v| acc ^ v)),
+        Primitive::NotGate => Some(!inputs[0]?),
+        _ => None,
+    }
+}
+
+impl Optimizer {
+    fn new(comp: &mut FlattenComponent) -> Self {
+        let len = comp.components.len();
+        let top = comp.top_level_ids();
+        let top_io = comp
+            .components
+            .iter()
+            .map(|c| top.contains(&c.id) && (c.prim_type.is_input() || c.prim_type.is_output()))
+            .collect();
+        let mut in_loop = vec![false; len];
+        for idx in comp.combinational_loops().into_iter().flatten() {
+            in_loop[idx] = true;
+        }
+
+        Optimizer {
+            comps: std::mem::take(&mut comp.components),
+            conns: comp.connections.concat(),
+            removed: vec![false; len],
+            top_io,
+            in_loop,
+            renumbered: vec![false; len],
+        }
+    }
+
+    /// Whether the primitive is a gate that can be rewritten.
+    fn is_free_gate(&self, idx: usize) -> bool {
+        !self.removed[idx] && !self.in_loop[idx] && is_gate(&self.comps[idx].prim_type)
+    }
+
+    /// Whether the primitive drives anything.
+    fn has_loads(&self, idx: usize) -> bool {
+        self.conns.iter().any(|conn| conn.from.0 == idx)
+    }
+
+    /// Whether the primitive passes its input along: the inputs and outputs
+    /// of nested components.
+    fn is_buffer(&self, idx: usize) -> bool {
+        let prim = &self.comps[idx].prim_type;
+        !self.top_io[idx] && (prim.is_input() || prim.is_output())
+    }
+
+    fn drivers(&self) -> HashMap<PortAddr, PortAddr> {
+        self.conns.iter().map(|conn| (conn.to, conn.from)).collect()
+    }
+
+    /// Source of the value read by an input, seeing through buffers.
+    fn source(&self, drivers: &HashMap<PortAddr, PortAddr>, to: PortAddr) -> Source {
+        let mut to = to;
+        for _ in 0..self.comps.len() {
+            let Some(from) = drivers.get(&to) else {
+                return Source::Low;
+            };
+            if !self.is_buffer(from.0) {
+                return Source::Port(*from);
+            }
+            to = (from.0, 0);
+        }
+        Source::Low
+    }
+
+    fn const_value(&self, source: Source) -> Option<bool> {
+        match source {
+            Source::Low => Some(false),
+            Source::Port((idx, _)) => match self.comps[idx].prim_type {
+                Primitive::Const { value } => Some(value.as_bool()),
+                _ => None,
+            },
+        }
+    }
+
+    fn width(&self, (idx, port): PortAddr) -> usize {
+        self.comps[idx].outputs[port].size
+    }
+
+    /// Connects the loads of `from` to `to`. Returns the amount of
+    /// connections changed.
+    fn redirect(&mut self, from: PortAddr, to: PortAddr) -> usize {
+        let mut count = 0;
+        for conn in self.conns.iter_mut().filter(|conn| conn.from == from) {
+            conn.from = to;
+            count += 1;
+        }
+        count
+    }
+
+    fn constant_propagation(&mut self) -> usize {
+        let drivers = self.drivers();
+        let mut count = 0;
+        for idx in 0..self.comps.len() {
+            if !self.is_free_gate(idx) || !self.has_loads(idx) {
+                continue;
+            }
+            let id = self.comps[idx].id;
+            let prim = self.comps[idx].prim_type.clone();
+            let sources: Vec<Source> = (0..self.comps[idx].inputs.len())
+                .map(|port| self.source(&drivers, (idx, port)))
+                .collect();
+            let values: Vec<Option<bool>> =
+                sources.iter().map(|src| self.const_value(*src)).collect();
+
+            if let Some(value) = fixed_output(&prim, &values) {
+                self.comps[idx] = PrimitiveComponent::const_gate(id, Data::from(value));
+                self.conns.retain(|conn| conn.to.0 != idx);
+                count += 1;
+                continue;
+            }
+
+            // Inputs that don't change the output
+            let neutral = match prim {
+                Primitive::AndGate | Primitive::NandGate => Some(true),
+                Primitive::OrGate | Primitive::NorGate | Primitive::XorGate => Some(false),
+                _ => continue,
+            };
+            let kept: Vec<usize> = (0..values.len())
+                .filter(|port| values[*port] != neutral)
+                .collect();
+            if kept.len() == values.len() {
+                continue;
+            }
+
+            count += 1;
+            self.conns
+                .retain(|conn| conn.to.0 != idx || kept.contains(&conn.to.1));
+            // Single bit input left, wider ones keep the gate
+            let single = match kept.as_slice() {
+                [port] => match sources[*port] {
+                    Source::Port(src) if self.width(src) == 1 => Some(src),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if single.is_some() && matches!(prim, Primitive::NandGate | Primitive::NorGate) {
+                self.comps[idx] = PrimitiveComponent::not_gate(id);
+            } else if let Some(src) = single {
+                // A single input passes along
+                self.redirect((idx, 0), src);
+                self.conns.retain(|conn| conn.to.0 != idx);
+                continue;
+            } else {
+                self.comps[idx] = gate(id, &prim, kept.len());
+            }
+            for conn in self.conns.iter_mut().filter(|conn| conn.to.0 == idx) {
+                conn.to.1 = kept.iter().position(|port| *port == conn.to.1).unwrap();
+            }
+            self.renumbered[idx] = true;
+        }
+        count
+    }
+
+    fn double_negation(&mut self) -> usize {
+        let drivers = self.drivers();
+        let mut count = 0;
+        for idx in 0..self.comps.len() {
+            if !self.is_free_gate(idx) || !matches!(self.comps[idx].prim_type, Primitive::NotGate) {
+                continue;
+            }
+            let Source::Port(inner) = self.source(&drivers, (idx, 0)) else {
+                continue;
+            };
+            if !self.is_free_gate(inner.0)
+                || !matches!(self.comps[inner.0].prim_type, Primitive::NotGate)
+            {
+                continue;
+            }
+            let Source::Port(src) = self.source(&drivers, (inner.0, 0)) else {
+                continue;
+            };
+            if self.redirect((idx, 0), src) > 0 {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn common_subexpressions(&mut self) -> usize {
+        let drivers = self.drivers();
+        let mut seen: HashMap<(&'static str, Vec<Source>), usize> = HashMap::new();
+        let mut count = 0;
+        for idx in 0..self.comps.len() {
+            if !self.is_free_gate(idx) {
+                continue;
+            }
+            let mut sources: Vec<Source> = (0..self.comps[idx].inputs.len())
+                .map(|port| self.source(&drivers, (idx, port)))
+                .collect();
+            sources.sort();
+            let key = (kind(&self.comps[idx].prim_type), sources);
+            match seen.get(&key) {
+                Some(first) => {
+                    if self.redirect((idx, 0), (*first, 0)) > 0 {
+                        count += 1;
+                    }
+                }
+                None => {
+                    seen.insert(key, idx);
+                }
+            }
+        }
+        count
+    }
+
+    fn dead_gates(&mut self) -> usize {
+        let drivers = self.drivers();
+        for idx in 0..self.comps.len() {
+            if !self.removed[idx] && self.is_buffer(idx) {
+                if let Source::Port(src) = self.source(&drivers, (idx, 0)) {
+                    self.redirect((idx, 0), src);
+                }
+            }
+        }
+
+        let mut count = 0;
+        loop {
+            let mut used = vec![false; self.comps.len()];
+            for conn in &self.conns {
+                used[conn.from.0] = true;
+            }
+            let dead: Vec<usize> = (0..self.comps.len())
+                .filter(|idx| !self.removed[*idx] && !self.top_io[*idx] && !used[*idx])
+                .collect();
+            if dead.is_empty() {
+                return count;
+            }
+            for idx in dead {
+                self.removed[idx] = true;
+                count += 1;
+            }
+            self.conns.retain(|conn| !self.removed[conn.to.0]);
+        }
+    }
+
+    /// Builds the optimized component, dropping the removed primitives.
+    fn finish(self, nested_config: NestedConfig) -> FlattenComponent {
+        let mut new_idx = vec![None; self.comps.len()];
+        let mut comps = vec![];
+        for (idx, comp) in self.comps.into_iter().enumerate() {
+            if !self.removed[idx] {
+                new_idx[idx] = Some(comps.len());
+                comps.push(comp);
+            }
+        }
+        let conns = self
+            .conns
+            .iter()
+            .filter_map(|conn| {
+                Some(Conn::new(
+                    new_idx[conn.from.0]?,
+                    conn.from.1,
+                    new_idx[conn.to.0]?,
+                    conn.to.1,
+                ))
+            })
+            .collect();
+
+        let id_to_idx: HashMap<usize, usize> = comps
+            .iter()
+            .enumerate()
+            .map(|(idx, comp)| (comp.id, idx))
+            .collect();
+        let single = |name: String, id: usize| {
+            let idx = *id_to_idx.get(&id)?;
+            let ins = (0..comps[idx].inputs.len()).map(|i| (idx, i)).collect();
+            let outs = (0..comps[idx].outputs.len()).map(|i| (idx, i)).collect();
+            Some(NestedConfig::Single(name, id, ins, outs))
+        };
+        // Addresses of changed primitives no longer hold the same values
+        let remap = |(idx, port): &PortAddr| {
+            Some((new_idx[*idx].filter(|_| !self.renumbered[*idx])?, *port))
+        };
+        let nested_config = match nested_config {
+            NestedConfig::Single(name, id, ins, outs) => {
+                single(name.clone(), id).unwrap_or(NestedConfig::Single(name, id, ins, outs))
+            }
+            NestedConfig::Compose(name, id, subs, ins, outs) => NestedConfig::Compose(
+                name,
+                id,
+                subs.into_iter()
+                    .filter_map(|(sub_id, config)| match config {
+                        NestedConfig::Single(name, id, _, _) => Some((sub_id, single(name, id)?)),
+                        NestedConfig::Compose(..) => None,
+                    })
+                    .collect(),
+                ins.iter().filter_map(remap).collect(),
+                outs.iter().filter_map(remap).collect(),
+            ),
+        };
+
+        FlattenComponent::from_parts(comps, conns, nested_config)
+    }
+}
+
+impl FlattenComponent {
+    /// Runs every optimization pass until the circuit doesn't change.
+    pub fn optimize(&mut self) -> OptimizeReport {
+        self.optimize_with(&Pass::ALL)
+    }
+
+    /// Runs the given optimization passes, in order, until the circuit
+    /// doesn't change.
+    pub fn optimize_with(&mut self, passes: &[Pass]) -> OptimizeReport {
+        let mut opt = Optimizer::new(self);
+        let mut report = OptimizeReport::default();
+        loop {
+            let before = report;
+            for pass in passes {
+                match pass {
+                    Pass::ConstantPropagation => report.constants += opt.constant_propagation(),
+                    Pass::DoubleNegation => report.negations += opt.double_negation(),
+                    Pass::CommonSubexpressions => report.duplicates += opt.common_subexpressions(),
+                    Pass::DeadGates => report.removed += opt.dead_gates(),
+                }
+            }
+            if report == before {
+                break;
+            }
+        }
+
+        let nested_config = std::mem::replace(
+            &mut self.nested_config,
+            NestedConfig::Single(String::new(), 0, vec![], vec![]),
+        );
+        let ports = (
+            std::mem::take(&mut self.input_ports),
+            std::mem::take(&mut self.output_ports),
+        );
+        *self = opt.finish(nested_config);
+        (self.input_ports, self.output_ports) = ports;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        headless::top_level_ports, primitives::primitive::ExtraInfo, test_utils::prim, TruthTable,
+    };
+
+    /// Inverter component, whose input and output are buffers once
+    /// flattened.
+    fn inv(id: usize) -> Component<ExtraInfo> {
+        ComponentBuilder::new(id)
+            .name("Inv".into())
+            .port_count(1, 1)
+            .sub_comps(vec![
+                prim(id + 1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(id + 2, 1, 1, Primitive::NotGate),
+                prim(id + 3, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0), Conn::new(1, 0, 2, 0)])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(2, 0)])
+            .build()
+            .unwrap()
+    }
+
+    /// `x = !!a`, `y = (a & b & 1) | (b & a)` and `z = b ^ (a & 0) ^ c`,
+    /// where `c` is left unconnected.
+    fn main() -> FlattenComponent {
+        let main = ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(2, 3)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                inv(10),
+                inv(20),
+                prim(3, 1, 1, Primitive::Output { bits: 1 }),
+                prim(
+                    4,
+                    0,
+                    1,
+                    Primitive::Const {
+                        value: Data::high(),
+                    },
+                ),
+                prim(5, 3, 1, Primitive::AndGate),
+                prim(6, 2, 1, Primitive::AndGate),
+                prim(7, 2, 1, Primitive::OrGate),
+                prim(8, 1, 1, Primitive::Output { bits: 1 }),
+                prim(9, 2, 1, Primitive::AndGate),
+                prim(30, 3, 1, Primitive::XorGate),
+                prim(31, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(2, 0, 3, 0),
+                Conn::new(3, 0, 4, 0),
+                Conn::new(0, 0, 6, 0),
+                Conn::new(1, 0, 6, 1),
+                Conn::new(5, 0, 6, 2),
+                Conn::new(1, 0, 7, 0),
+                Conn::new(0, 0, 7, 1),
+                Conn::new(6, 0, 8, 0),
+                Conn::new(7, 0, 8, 1),
+                Conn::new(8, 0, 9, 0),
+                Conn::new(0, 0, 10, 0),
+                Conn::new(1, 0, 11, 0),
+                Conn::new(10, 0, 11, 1),
+                Conn::new(11, 0, 12, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(4, 0), (9, 0), (12, 0)])
+            .build()
+            .unwrap();
+        FlattenComponent::new(main).unwrap()
+    }
+
+    fn table(comp: FlattenComponent) -> TruthTable {
+        let (inputs, outputs) = top_level_ports(&comp);
+        TruthTable::generate(comp, &inputs, &outputs).unwrap()
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut comp = main();
+        let count = comp.components.len();
+        let expected = table(main());
+
+        let report = comp.optimize();
+        assert_eq!(report.negations, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.constants, 3);
+        assert_eq!(
+            count - comp.components.len(),
+            report.removed,
+            "{:?}",
+            comp.components
+        );
+
+        // `x = a`, `y = (a & b) | (a & b)` and `z = b`
+        let kinds: Vec<&str> = comp.components.iter().map(|c| kind(&c.prim_type)).collect();
+        assert_eq!(
+            kinds,
+            vec!["input", "input", "output", "and", "or", "output", "output"]
+        );
+        assert_eq!(comp.components[3].inputs.len(), 2);
+        assert_eq!(table(comp), expected);
+    }
+
+    #[test]
+    fn test_single_passes() {
+        let mut comp = main();
+        let report = comp.optimize_with(&[Pass::DoubleNegation]);
+        assert_eq!(report.negations, 1);
+        // Nothing is removed without the dead gates pass
+        assert_eq!(comp.components.len(), main().components.len());
+        assert_eq!(table(comp), table(main()));
+    }
+
+    #[test]
+    fn test_wide_nand_is_not_inverted() {
+        // `x = !(a & 1)` with `a` four bits wide
+        let main = ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(1, 1)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 4 }),
+                prim(
+                    2,
+                    0,
+                    1,
+                    Primitive::Const {
+                        value: Data::high(),
+                    },
+                ),
+                prim(3, 2, 1, Primitive::NandGate),
+                prim(4, 1, 1, Primitive::Output { bits: 4 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 2, 1),
+                Conn::new(2, 0, 3, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(3, 0)])
+            .build()
+            .unwrap();
+        let mut comp = FlattenComponent::new(main).unwrap();
+
+        let report = comp.optimize_with(&[Pass::ConstantPropagation]);
+        assert_eq!(report.constants, 1);
+        let nand = comp
+            .components
+            .iter()
+            .find(|c| {
+                !matches!(
+                    c.prim_type,
+                    Primitive::Input { .. } | Primitive::Output { .. } | Primitive::Const { .. }
+                )
+            })
+            .unwrap();
+        assert_eq!(kind(&nand.prim_type), "nand");
+        assert_eq!(nand.inputs.len(), 1);
+    }
+}
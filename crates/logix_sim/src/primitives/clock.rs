@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+/// Waveform of a clock, with its times in nanoseconds of simulation time.
+///
+/// Each cycle starts low and the clock rises once the low part of the
+/// period is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredClock")]
+pub struct ClockConfig {
+    /// Time between two rising edges.
+    pub period: u128,
+    /// Percentage of the period the clock is high.
+    pub duty: u8,
+    /// Time the waveform is delayed by. Unlike `delay`, the clock already
+    /// runs before it.
+    pub phase: u128,
+    /// Time the clock stays low before its first cycle starts.
+    pub delay: u128,
+    /// Whether the clock has an enable input. The clock stays low while it
+    /// is disabled.
+    pub enable: bool,
+}
+
+impl ClockConfig {
+    /// Clock with a 50% duty cycle that starts at time 0.
+    pub const fn new(period: u128) -> Self {
+        ClockConfig {
+            period,
+            duty: 50,
+            phase: 0,
+            delay: 0,
+            enable: false,
+        }
+    }
+
+    /// Time the clock is high in each period. Clocks are never constant, so
+    /// a period of at least 2ns has both a high and a low part.
+    pub fn high_time(&self) -> u128 {
+        let high = self.period * u128::from(self.duty) / 100;
+        high.clamp(1, self.period.saturating_sub(1).max(1))
+    }
+
+    /// Time the clock is low in each period.
+    pub fn low_time(&self) -> u128 {
+        self.period.saturating_sub(self.high_time())
+    }
+
+    /// Value of the (enabled) clock at the given time.
+    pub fn value_at(&self, time: u128) -> bool {
+        time >= self.delay && self.position(time - self.delay) >= self.low_time()
+    }
+
+    /// Next time after `time` at which the clock changes its value.
+    pub fn next_edge(&self, time: u128) -> u128 {
+        if time < self.delay {
+            if self.value_at(self.delay) {
+                return self.delay;
+            }
+            return self.delay + self.low_time() - self.position(0);
+        }
+        let elapsed = time - self.delay;
+        let position = self.position(elapsed);
+        let low = self.low_time();
+        let wait = if position < low {
+            low - position
+        } else {
+            self.period.max(1) - position
+        };
+        time + wait
+    }
+
+    /// Time of the n-th (starting at 1) rising or falling edge of the
+    /// (enabled) clock.
+    pub fn nth_edge(&self, n: u128, rising: bool) -> u128 {
+        let mut time = self.next_edge(0);
+        if self.value_at(time) != rising {
+            time = self.next_edge(time);
+        }
+        time + n.saturating_sub(1) * self.period
+    }
+
+    /// Time at which the n-th cycle ends, that is, the falling edge that
+    /// follows the n-th rising edge.
+    pub fn cycle_end(&self, n: u128) -> u128 {
+        if n == 0 {
+            return 0;
+        }
+        self.nth_edge(n, true) + self.high_time()
+    }
+
+    /// Position within the cycle after running for `elapsed` nanoseconds.
+    fn position(&self, elapsed: u128) -> u128 {
+        let period = self.period.max(1);
+        (elapsed + period - self.phase % period) % period
+    }
+}
+
+/// Clock as stored in board files.
+///
+/// Files written before clocks had a duty cycle stored the half period in
+/// `period`.
+#[derive(Deserialize)]
+struct StoredClock {
+    period: u128,
+    duty: Option<u8>,
+    #[serde(default)]
+    phase: u128,
+    #[serde(default)]
+    delay: u128,
+    #[serde(default)]
+    enable: bool,
+}
+
+impl From<StoredClock> for ClockConfig {
+    fn from(clock: StoredClock) -> Self {
+        let Some(duty) = clock.duty else {
+            return ClockConfig::new(clock.period * 2);
+        };
+        ClockConfig {
+            period: clock.period,
+            duty,
+            phase: clock.phase,
+            delay: clock.delay,
+            enable: clock.enable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Times of the changes up to `end`.
+    fn edges(clock: &ClockConfig, end: u128) -> Vec<(u128, bool)> {
+        let mut edges = vec![];
+        let mut time = 0;
+        loop {
+            time = clock.next_edge(time);
+            if time > end {
+                return edges;
+            }
+            edges.push((time, clock.value_at(time)));
+        }
+    }
+
+    #[test]
+    fn test_waveforms() {
+        let clock = ClockConfig::new(10);
+        assert!(!clock.value_at(0));
+        assert_eq!(
+            edges(&clock, 20),
+            vec![(5, true), (10, false), (15, true), (20, false)]
+        );
+
+        let clock = ClockConfig {
+            duty: 25,
+            ..ClockConfig::new(8)
+        };
+        assert_eq!(
+            edges(&clock, 16),
+            vec![(6, true), (8, false), (14, true), (16, false)]
+        );
+
+        let clock = ClockConfig {
+            phase: 7,
+            ..ClockConfig::new(10)
+        };
+        assert_eq!(
+            edges(&clock, 20),
+            vec![(2, true), (7, false), (12, true), (17, false)]
+        );
+
+        // Shifted 3ns, so it is already high at the start
+        let clock = ClockConfig {
+            phase: 3,
+            ..ClockConfig::new(10)
+        };
+        assert!(clock.value_at(0));
+        assert_eq!(edges(&clock, 14), vec![(3, false), (8, true), (13, false)]);
+
+        let clock = ClockConfig {
+            delay: 12,
+            ..ClockConfig::new(10)
+        };
+        assert!(!clock.value_at(16));
+        assert_eq!(edges(&clock, 30), vec![(17, true), (22, false), (27, true)]);
+    }
+
+    #[test]
+    fn test_nth_edges() {
+        let clock = ClockConfig {
+            duty: 25,
+            delay: 1000,
+            ..ClockConfig::new(100)
+        };
+        assert_eq!(clock.nth_edge(1, true), 1075);
+        assert_eq!(clock.nth_edge(1, false), 1100);
+        assert_eq!(clock.nth_edge(3, true), 1275);
+        assert_eq!(clock.cycle_end(2), 1200);
+        assert_eq!(clock.cycle_end(0), 0);
+
+        // Already high at the start, so it falls before its first rise
+        let clock = ClockConfig {
+            phase: 3,
+            ..ClockConfig::new(10)
+        };
+        assert_eq!(clock.nth_edge(1, false), 3);
+        assert_eq!(clock.nth_edge(1, true), 8);
+        assert_eq!(clock.cycle_end(1), 13);
+    }
+
+    #[test]
+    fn test_legacy_files() {
+        let clock: ClockConfig = serde_json::from_str(r#"{"period":5}"#).unwrap();
+        assert_eq!(clock, ClockConfig::new(10));
+
+        let clock = ClockConfig {
+            duty: 30,
+            enable: true,
+            ..ClockConfig::new(10)
+        };
+        let json = serde_json::to_string(&clock).unwrap();
+        assert_eq!(serde_json::from_str::<ClockConfig>(&json).unwrap(), clock);
+    }
+}
@@ -1,5 +1,7 @@
+pub mod clock;
 pub mod primitive;
 
 pub mod prelude {
+    pub use crate::primitives::clock::ClockConfig;
     pub use crate::primitives::primitive::*;
 }
@@ -1,8 +1,11 @@
 use std::fmt::{Display, Formatter};
 
-use asmhdl::{AsmComponent, AsmProgramState, Data};
+use asmhdl::{AsmComponent, AsmProgramState, AsmProgramUpdateType, Data};
+use logix_core::component::PortDesc;
 use serde::{Deserialize, Serialize};
 
+use super::clock::ClockConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Primitive {
     AndGate,
@@ -24,9 +27,7 @@ pub enum Primitive {
     Joiner {
         bits: usize,
     },
-    Clock {
-        period: u128,
-    },
+    Clock(ClockConfig),
     Const {
         value: Data,
     },
@@ -50,8 +51,9 @@ impl Primitive {
             | Primitive::XorGate => Some(Data::low()),
             Primitive::Output { bits: b } => Some(Data::new(0, b)),
             Primitive::Splitter { bits: b } => Some(Data::new(0, b)),
+            Primitive::Clock(clock) if clock.enable => Some(Data::low()),
             Primitive::Input { bits: _ } => None,
-            Primitive::Clock { period: _ } => None,
+            Primitive::Clock(_) => None,
             Primitive::Const { value: _ } => None,
         }
     }
@@ -64,7 +66,7 @@ impl Primitive {
             | Primitive::NandGate
             | Primitive::NorGate
             | Primitive::Switch
-            | Primitive::Clock { period: _ }
+            | Primitive::Clock(_)
             | Primitive::Splitter { bits: _ }
             | Primitive::Custom { .. }
             | Primitive::XorGate => Some(Data::low()),
@@ -75,6 +77,50 @@ impl Primitive {
         }
     }
 
+    /// Descriptors of the ports of a component of this primitive with
+    /// `in_count` inputs and `out_count` outputs.
+    ///
+    /// Ports are named `in{i}` and `out{i}`, except the bits of splitters and
+    /// joiners, named after their index, and the ports of custom components.
+    pub fn port_descs(&self, in_count: usize, out_count: usize) -> (Vec<PortDesc>, Vec<PortDesc>) {
+        let named = |widths: Vec<usize>, prefix: &str| {
+            widths
+                .into_iter()
+                .enumerate()
+                .map(|(i, width)| (format!("{prefix}{i}"), width))
+                .collect::<Vec<_>>()
+        };
+        let (ins, outs) = match self {
+            Primitive::Input { bits } | Primitive::Output { bits } => (
+                named(vec![*bits; in_count], "in"),
+                named(vec![*bits; out_count], "out"),
+            ),
+            Primitive::Splitter { bits } => (named(vec![*bits], "in"), named(vec![1; *bits], "")),
+            Primitive::Joiner { bits } => (named(vec![1; *bits], ""), named(vec![*bits], "out")),
+            Primitive::Const { value } => (vec![], named(vec![value.size], "out")),
+            Primitive::Clock(_) => (
+                vec![("en".to_string(), 1); in_count],
+                named(vec![1; out_count], "out"),
+            ),
+            Primitive::Custom { comp, .. } => (
+                comp.inputs.iter().map(|(n, w)| (n.clone(), *w)).collect(),
+                comp.outputs.iter().map(|(n, w)| (n.clone(), *w)).collect(),
+            ),
+            _ => (
+                named(vec![1; in_count], "in"),
+                named(vec![1; out_count], "out"),
+            ),
+        };
+        (
+            ins.into_iter()
+                .map(|(name, width)| PortDesc::input(name, width))
+                .collect(),
+            outs.into_iter()
+                .map(|(name, width)| PortDesc::output(name, width))
+                .collect(),
+        )
+    }
+
     pub fn is_input(&self) -> bool {
         matches!(self, Primitive::Input { bits: _ })
     }
@@ -84,7 +130,7 @@ impl Primitive {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PrimitiveComponent {
     pub id: usize,
     pub name: String,
@@ -98,6 +144,20 @@ impl PrimitiveComponent {
         self.inputs[index] = value;
     }
 
+    /// Next time after `time` at which the outputs of a component that
+    /// depends on the time may change, or `None` for the other components.
+    pub fn next_event(&self, time: u128) -> Option<u128> {
+        match &self.prim_type {
+            Primitive::Clock(clock) => Some(clock.next_edge(time)),
+            Primitive::Custom { comp, .. }
+                if matches!(comp.update_type, AsmProgramUpdateType::Always) =>
+            {
+                Some(time + 1)
+            }
+            _ => None,
+        }
+    }
+
     pub fn update(&mut self, time: u128) {
         match &mut self.prim_type {
             Primitive::AndGate => {
@@ -146,8 +206,9 @@ impl PrimitiveComponent {
                         .fold(0, |acc, (i, x)| acc | ((x.as_bool() as usize) << i)),
                 );
             }
-            Primitive::Clock { period } => {
-                self.outputs[0].set_from_bool((time % (*period * 2)) > *period);
+            Primitive::Clock(clock) => {
+                let enabled = !clock.enable || self.inputs[0].as_bool();
+                self.outputs[0].set_from_bool(enabled && clock.value_at(time));
             }
             Primitive::Const { value: _v } => (),
             Primitive::Custom { comp, state } => {
@@ -298,12 +359,12 @@ impl PrimitiveComponent {
         }
     }
 
-    pub fn clock(id: usize, period: u128) -> Self {
+    pub fn clock(id: usize, clock: ClockConfig) -> Self {
         PrimitiveComponent {
             id,
-            name: format!("Clock({})", 1_000_000_000.0 / period as f64),
-            prim_type: Primitive::Clock { period },
-            inputs: vec![],
+            name: format!("Clock({})", 1_000_000_000.0 / clock.period as f64),
+            prim_type: Primitive::Clock(clock),
+            inputs: vec![Data::low(); usize::from(clock.enable)],
             outputs: vec![Data::low()],
         }
     }
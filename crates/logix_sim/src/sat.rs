@@ -0,0 +1,342 @@
+//! Small CDCL SAT solver used to prove the equivalence of circuits.
+//!
+//! Clauses are watched by two literals, conflicts are analysed down to their
+//! first unique implication point and the learnt clauses are kept. Variables
+//! are picked by activity and the search restarts after a growing number of
+//! conflicts.
+
+/// Literal: a variable index shifted left once, with the lowest bit set when
+/// it is negated.
+pub(crate) type Lit = u32;
+
+pub(crate) const fn lit(var: usize, negated: bool) -> Lit {
+    ((var as u32) << 1) | negated as u32
+}
+
+pub(crate) const fn neg(lit: Lit) -> Lit {
+    lit ^ 1
+}
+
+const fn var(lit: Lit) -> usize {
+    (lit >> 1) as usize
+}
+
+#[derive(Default)]
+pub(crate) struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses watching each literal, visited when it becomes false.
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    var_inc: f64,
+    trail: Vec<Lit>,
+    /// Length of the trail when each decision was taken.
+    trail_lim: Vec<usize>,
+    queue_head: usize,
+    /// Whether no empty clause has been added.
+    ok: bool,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver {
+            var_inc: 1.0,
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_var(&mut self) -> usize {
+        self.assigns.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.activity.push(0.0);
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        self.assigns.len() - 1
+    }
+
+    /// Value of a literal, if its variable is assigned.
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[var(lit)].map(|v| v ^ (lit & 1 == 1))
+    }
+
+    /// Value of a literal in the last solution found.
+    pub fn model(&self, lit: Lit) -> bool {
+        self.value(lit).unwrap_or(false)
+    }
+
+    /// Adds a clause. Must be called before solving.
+    pub fn add_clause(&mut self, lits: &[Lit]) {
+        let mut lits = lits.to_vec();
+        lits.sort_unstable();
+        lits.dedup();
+        if lits.windows(2).any(|w| w[0] == neg(w[1])) {
+            // Always true
+            return;
+        }
+        match lits.as_slice() {
+            [] => self.ok = false,
+            [unit] => match self.value(*unit) {
+                Some(true) => (),
+                Some(false) => self.ok = false,
+                None => self.enqueue(*unit, None),
+            },
+            _ => {
+                self.watch(lits);
+            }
+        }
+    }
+
+    fn watch(&mut self, lits: Vec<Lit>) -> usize {
+        let idx = self.clauses.len();
+        self.watches[lits[0] as usize].push(idx);
+        self.watches[lits[1] as usize].push(idx);
+        self.clauses.push(lits);
+        idx
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let v = var(lit);
+        self.assigns[v] = Some(lit & 1 == 0);
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Assigns the literals implied by the trail. Returns the conflicting
+    /// clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = neg(self.trail[self.queue_head]);
+            self.queue_head += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_lit as usize]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &ci) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+
+                let clause = &mut self.clauses[ci];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if self.assigns[var(first)].map(|v| v ^ (first & 1 == 1)) == Some(true) {
+                    kept.push(ci);
+                    continue;
+                }
+
+                // Look for a new literal to watch
+                let clause = &self.clauses[ci];
+                let new_watch = (2..clause.len()).find(|k| self.value(clause[*k]) != Some(false));
+                if let Some(k) = new_watch {
+                    self.clauses[ci].swap(1, k);
+                    let lit = self.clauses[ci][1];
+                    self.watches[lit as usize].push(ci);
+                    continue;
+                }
+
+                kept.push(ci);
+                match self.value(first) {
+                    Some(false) => conflict = Some(ci),
+                    _ => self.enqueue(first, Some(ci)),
+                }
+            }
+            self.watches[false_lit as usize] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Learns a clause from a conflict. Returns it, with the asserting
+    /// literal first, and the level to go back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.assigns.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut implied: Option<Lit> = None;
+        let mut idx = self.trail.len();
+        let mut clause = conflict;
+
+        loop {
+            let skip = usize::from(implied.is_some());
+            for k in skip..self.clauses[clause].len() {
+                let q = self.clauses[clause][k];
+                let v = var(q);
+                if seen[v] || self.level[v] == 0 {
+                    continue;
+                }
+                seen[v] = true;
+                self.bump(v);
+                if self.level[v] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learnt.push(q);
+                }
+            }
+
+            // Next literal of the current level in the trail
+            loop {
+                idx -= 1;
+                if seen[var(self.trail[idx])] {
+                    break;
+                }
+            }
+            let p = self.trail[idx];
+            seen[var(p)] = false;
+            implied = Some(p);
+            pending -= 1;
+            if pending == 0 {
+                break;
+            }
+            clause = self.reason[var(p)].expect("Implied literals have a reason");
+        }
+        learnt[0] = neg(implied.unwrap());
+
+        let mut back_level = 0;
+        if learnt.len() > 1 {
+            let max = (1..learnt.len())
+                .max_by_key(|k| self.level[var(learnt[*k])])
+                .unwrap();
+            learnt.swap(1, max);
+            back_level = self.level[var(learnt[1])];
+        }
+        (learnt, back_level)
+    }
+
+    fn bump(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            for act in &mut self.activity {
+                *act *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_lim[level];
+        for lit in self.trail.drain(start..) {
+            self.assigns[var(lit)] = None;
+            self.reason[var(lit)] = None;
+        }
+        self.trail_lim.truncate(level);
+        self.queue_head = start;
+    }
+
+    fn pick_branch(&self) -> Option<usize> {
+        (0..self.assigns.len())
+            .filter(|v| self.assigns[*v].is_none())
+            .max_by(|a, b| self.activity[*a].total_cmp(&self.activity[*b]))
+    }
+
+    /// Returns whether the clauses can be satisfied. If they can, the
+    /// solution is read with [`Solver::model`].
+    pub fn solve(&mut self) -> bool {
+        if !self.ok {
+            return false;
+        }
+        // Clauses added after the units were assigned are checked again
+        self.queue_head = 0;
+
+        let mut conflicts = 0;
+        let mut restart_limit = 100.0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    return false;
+                }
+                conflicts += 1;
+                let (learnt, back_level) = self.analyze(conflict);
+                self.backtrack(back_level);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let ci = self.watch(learnt);
+                    self.enqueue(asserting, Some(ci));
+                }
+                self.var_inc /= 0.95;
+                continue;
+            }
+
+            if conflicts as f64 >= restart_limit {
+                conflicts = 0;
+                restart_limit *= 1.5;
+                self.backtrack(0);
+                continue;
+            }
+
+            let Some(v) = self.pick_branch() else {
+                return true;
+            };
+            self.trail_lim.push(self.trail.len());
+            self.enqueue(lit(v, true), None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pigeons into holes, each pigeon in some hole and no hole with two
+    /// pigeons.
+    fn pigeonhole(pigeons: usize, holes: usize) -> Solver {
+        let mut solver = Solver::new();
+        let vars: Vec<Vec<usize>> = (0..pigeons)
+            .map(|_| (0..holes).map(|_| solver.new_var()).collect())
+            .collect();
+        for p in &vars {
+            let lits: Vec<Lit> = p.iter().map(|v| lit(*v, false)).collect();
+            solver.add_clause(&lits);
+        }
+        for h in 0..holes {
+            for (a, first) in vars.iter().enumerate() {
+                for second in &vars[a + 1..] {
+                    solver.add_clause(&[lit(first[h], true), lit(second[h], true)]);
+                }
+            }
+        }
+        solver
+    }
+
+    #[test]
+    fn test_solve() {
+        assert!(!pigeonhole(5, 4).solve());
+
+        let mut solver = pigeonhole(4, 4);
+        assert!(solver.solve());
+        for h in 0..4 {
+            let used = (0..4)
+                .filter(|p| solver.model(lit(p * 4 + h, false)))
+                .count();
+            assert!(used <= 1);
+        }
+
+        let mut solver = Solver::new();
+        let (a, b) = (solver.new_var(), solver.new_var());
+        solver.add_clause(&[lit(a, true)]);
+        solver.add_clause(&[lit(a, false), lit(b, false)]);
+        assert!(solver.solve());
+        assert!(solver.model(lit(a, true)) && solver.model(lit(b, false)));
+        solver.add_clause(&[lit(b, true)]);
+        assert!(!solver.solve());
+    }
+}
@@ -1,193 +1,776 @@
-use crate::{flatten::FlattenComponent, primitives::primitive::Primitive};
-use asmhdl::AsmProgramUpdateType;
+use crate::{
+    delta::DeltaCycles,
+    errors::{ComponentRequestError, DataRequestError},
+    flatten::{FlattenComponent, NestedConfig},
+    primitives::primitive::{Primitive, PrimitiveComponent},
+};
+use asmhdl::Data;
 use log::debug;
+use logix_core::prelude::*;
 use rand::seq::SliceRandom;
 use std::{
-    sync::{Arc, Mutex},
-    thread,
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
-pub struct SimState {
-    pub comp: FlattenComponent,
-    pub to_upd: Vec<usize>,
+/// Minimum number of components updated in a delta cycle for the cycle to
+/// be split between threads. Smaller cycles are cheaper to run on a single
+/// thread than to hand out.
+const MIN_PARALLEL_UPDATES: usize = 256;
+
+/// Minimum number of components of a partition. Circuits too small to give
+/// every thread this many components use fewer threads, or run serially.
+const MIN_PARTITION_LEN: usize = 1024;
+
+/// Time between two published snapshots while the simulation is running.
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Time the simulation thread waits for commands while paused or stable.
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// How fast the simulation time advances relative to the wall time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedMode {
+    /// One simulated nanosecond per nanosecond of wall time.
+    #[default]
+    RealTime,
+    /// The simulation time runs the given number of times slower than the
+    /// wall time.
+    Slower(u32),
+    /// The time moves to the next event as soon as the circuit is stable.
+    AsFastAsPossible,
+    /// The time moves to the next clock edge each time
+    /// [`Simulator::step`] is called.
+    ClockStep,
+}
+
+/// Progress of a simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SimStats {
+    /// Simulation time in nanoseconds.
+    pub time: u128,
+    /// Number of component updates since the start.
+    pub events: u64,
+    /// Component updates per second of wall time, measured since the
+    /// previous snapshot.
+    pub events_per_sec: f64,
     pub running: bool,
-    pub end: bool,
+    pub speed: SpeedMode,
+}
+
+/// Values of every primitive at some point of a simulation.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub stats: SimStats,
+    nested_config: Arc<NestedConfig>,
+    inputs: Vec<Vec<Data>>,
+    outputs: Vec<Vec<Data>>,
+}
+
+impl Snapshot {
+    fn new(
+        stats: SimStats,
+        nested_config: Arc<NestedConfig>,
+        components: &[PrimitiveComponent],
+    ) -> Self {
+        Snapshot {
+            stats,
+            nested_config,
+            inputs: components.iter().map(|c| c.inputs.clone()).collect(),
+            outputs: components.iter().map(|c| c.outputs.clone()).collect(),
+        }
+    }
+
+    /// Input and output values of the component found following the ids of
+    /// `comp_path` and then `at`.
+    pub fn status(
+        &self,
+        comp_path: &[usize],
+        at: Option<usize>,
+    ) -> Result<(Vec<Data>, Vec<Data>), DataRequestError> {
+        let (ins, outs) = self.nested_config.ports_at(comp_path, at)?;
+        let in_bits = ins
+            .iter()
+            .map(|(idx, port)| self.inputs[*idx][*port])
+            .collect();
+        let out_bits = outs
+            .iter()
+            .map(|(idx, port)| self.outputs[*idx][*port])
+            .collect();
+        Ok((in_bits, out_bits))
+    }
+}
+
+enum Command {
+    SetInput(usize, usize, Data),
+    SetOutput(usize, usize, Data),
+    SetSpeed(SpeedMode),
+    Step,
+    PauseResume,
+    Stop,
 }
 
+/// Simulator that runs on its own thread, advancing the simulation time
+/// along with the wall time as set by its [`SpeedMode`].
+///
+/// The values computed by the simulation are read through snapshots, which
+/// are published periodically, so readers never wait for a delta cycle to
+/// finish.
 pub struct Simulator {
-    state: Arc<Mutex<SimState>>,
+    nested_config: Arc<NestedConfig>,
+    id_to_idx: HashMap<usize, usize>,
+    /// Number of input and output ports of each primitive.
+    port_counts: Vec<(usize, usize)>,
+    snapshot: Arc<Mutex<Arc<Snapshot>>>,
+    commands: Sender<Command>,
+    /// Engine and command receiver, until the simulation is started.
+    engine: Option<(Engine, Receiver<Command>)>,
+    handle: Option<JoinHandle<()>>,
 }
 
 impl Simulator {
     pub fn new(comp: FlattenComponent) -> Self {
-        let to_upd = comp
-            .components
-            .iter()
-            .enumerate()
-            .filter_map(|(i, c)| {
-                match &c.prim_type {
-                    Primitive::Const { value: _v } => {
-                        return Some(i);
-                    }
-                    Primitive::Clock { period: _p } => {
-                        return Some(i);
-                    }
-                    Primitive::Custom { comp, state: _ } => {
-                        if let AsmProgramUpdateType::Always = comp.update_type {
-                            return Some(i);
-                        }
-                    }
-
-                    _ => {}
-                }
-                if let Primitive::Clock { period: _p } = c.prim_type {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<usize>>();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let nested_config = Arc::new(comp.nested_config.clone());
+        let stats = SimStats {
+            running: true,
+            ..Default::default()
+        };
+        let snapshot = Snapshot::new(stats, nested_config.clone(), &comp.components);
+        let (commands, receiver) = mpsc::channel();
 
         Simulator {
-            state: Arc::new(Mutex::new(SimState {
-                comp,
-                to_upd,
-                running: false,
-                end: false,
-            })),
+            nested_config,
+            id_to_idx: comp.id_to_idx.clone(),
+            port_counts: comp
+                .components
+                .iter()
+                .map(|c| (c.inputs.len(), c.outputs.len()))
+                .collect(),
+            snapshot: Arc::new(Mutex::new(Arc::new(snapshot))),
+            commands,
+            engine: Some((Engine::new(comp, threads), receiver)),
+            handle: None,
         }
     }
 
-    pub fn state<T>(&mut self, on_locked: impl FnOnce(&mut SimState) -> T) -> T {
-        let mut state = self.state.lock().unwrap();
-        on_locked(&mut state)
+    /// Sets the number of threads used to evaluate the circuit. Has no
+    /// effect once the simulation is started.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        if let Some((engine, _)) = self.engine.as_mut() {
+            engine.partition(threads.max(1));
+        }
+        self
+    }
+
+    /// Latest published values of the simulation.
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.lock().unwrap().clone()
     }
 
-    pub fn try_state<T: Default>(&mut self, on_locked: impl FnOnce(&mut SimState) -> T) -> T {
-        let state = self.state.try_lock();
-        if let Ok(mut state) = state {
-            return on_locked(&mut state);
+    /// Sets an input of the primitive found following the ids of
+    /// `comp_path` and then `at`.
+    pub fn set_input(
+        &self,
+        comp_path: &[usize],
+        at: Option<usize>,
+        port: usize,
+        value: Data,
+    ) -> Result<(), DataRequestError> {
+        let idx = self.prim_idx(comp_path, at)?;
+        if port >= self.port_counts[idx].0 {
+            return Err(DataRequestError::InvalidInputPortIndex(port));
         }
-        T::default()
+        self.send(Command::SetInput(idx, port, value));
+        Ok(())
+    }
+
+    /// Sets an output of the primitive found following the ids of
+    /// `comp_path` and then `at`.
+    pub fn set_output(
+        &self,
+        comp_path: &[usize],
+        at: Option<usize>,
+        port: usize,
+        value: Data,
+    ) -> Result<(), DataRequestError> {
+        let idx = self.prim_idx(comp_path, at)?;
+        if port >= self.port_counts[idx].1 {
+            return Err(DataRequestError::InvalidOutputPortIndex(port));
+        }
+        self.send(Command::SetOutput(idx, port, value));
+        Ok(())
+    }
+
+    /// Changes how fast the simulation time advances.
+    pub fn set_speed(&self, speed: SpeedMode) {
+        self.send(Command::SetSpeed(speed));
+    }
+
+    /// Moves the simulation to the next clock edge when running in
+    /// [`SpeedMode::ClockStep`].
+    pub fn step(&self) {
+        self.send(Command::Step);
     }
 
     pub fn pause_resume(&mut self) {
-        let mut state = self.state.lock().unwrap();
-        state.running = !state.running;
+        self.send(Command::PauseResume);
     }
 
     pub fn stop(&mut self) {
-        let mut state = self.state.lock().unwrap();
-        state.end = true;
+        self.send(Command::Stop);
     }
 
     /// Starts the simulation.
+    ///
+    /// If `keep_running` is false, the simulation ends once the circuit is
+    /// stable.
     pub fn start(&mut self, keep_running: bool) {
-        let state_arc = self.state.clone();
+        let Some((engine, receiver)) = self.engine.take() else {
+            return;
+        };
+        let nested_config = self.nested_config.clone();
+        let snapshot = self.snapshot.clone();
+        self.handle = Some(thread::spawn(move || {
+            engine.run(receiver, keep_running, |stats, components| {
+                let new = Snapshot::new(stats, nested_config.clone(), components);
+                *snapshot.lock().unwrap() = Arc::new(new);
+            });
+        }));
+    }
 
-        thread::spawn(move || {
-            let mut state = state_arc.lock().unwrap();
-            state.running = true;
-            let start = Instant::now();
-            let mut local_upd_list: Vec<usize> = (0..state.comp.components.len()).collect();
-            let mut local_next_upd_list: Vec<usize> = state.to_upd.clone();
-            let mut to_upd_len = state.to_upd.len();
+    /// Waits for the simulation thread to end.
+    pub fn wait(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Simulation thread panicked");
+        }
+    }
 
-            {
-                let _x = state;
-            }
+    fn prim_idx(&self, comp_path: &[usize], at: Option<usize>) -> Result<usize, DataRequestError> {
+        let id = self.nested_config.prim_id_at(comp_path, at)?;
+        Ok(*self
+            .id_to_idx
+            .get(&id)
+            .ok_or(ComponentRequestError::InvalidComponentId(id))?)
+    }
+
+    fn send(&self, command: Command) {
+        // The simulation thread may have already ended
+        let _ = self.commands.send(command);
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        self.send(Command::Stop);
+    }
+}
+
+/// State of a running simulation.
+///
+/// Components are split into partitions of contiguous indices. Flattening
+/// places the primitives of each nested component together, so most
+/// connections stay inside a partition. In each delta cycle, partitions are
+/// updated on their own threads, writing to their own components right away
+/// and collecting the values sent to other partitions, which are applied
+/// once every partition is done.
+///
+/// Threads are only spawned for delta cycles that update enough components
+/// of a large enough circuit (see [`MIN_PARALLEL_UPDATES`] and
+/// [`MIN_PARTITION_LEN`]), so that the work makes up for starting them.
+struct Engine {
+    core: DeltaCycles,
+    /// First index of each partition, followed by the number of components.
+    bounds: Vec<usize>,
+    running: bool,
+    speed: SpeedMode,
+    /// Clock edges requested in [`SpeedMode::ClockStep`].
+    steps: usize,
+    /// Wall and simulation times the speed is measured from.
+    pace: (Instant, u128),
+    events: u64,
+}
+
+impl Engine {
+    fn new(comp: FlattenComponent, threads: usize) -> Self {
+        let mut engine = Engine {
+            core: DeltaCycles::new(comp),
+            bounds: vec![],
+            running: true,
+            speed: SpeedMode::default(),
+            steps: 0,
+            pace: (Instant::now(), 0),
+            events: 0,
+        };
+        engine.partition(threads);
+        engine
+    }
+
+    fn partition(&mut self, parts: usize) {
+        let len = self.core.comp.components.len();
+        let parts = parts.min(len / MIN_PARTITION_LEN).max(1);
+        self.bounds = (0..=parts).map(|p| p * len / parts).collect();
+    }
 
-            while to_upd_len > 0 || keep_running {
-                // debug!("Update list: {:?}", self.to_upd);
+    fn run(
+        mut self,
+        commands: Receiver<Command>,
+        keep_running: bool,
+        mut publish: impl FnMut(SimStats, &[PrimitiveComponent]),
+    ) {
+        self.pace = (Instant::now(), self.core.time);
+        let mut last_publish = (Instant::now(), self.events);
+        let mut dirty = true;
 
-                let mut state = state_arc.lock().unwrap();
-                if state.end {
-                    {
-                        let _x = state;
+        'sim: loop {
+            let wait = if !self.running {
+                Some(IDLE_WAIT)
+            } else if !self.core.is_stable() {
+                None
+            } else if !keep_running {
+                break;
+            } else {
+                self.advance()
+            };
+
+            if let Some(wait) = wait {
+                if dirty {
+                    publish(self.stats(last_publish), &self.core.comp.components);
+                    last_publish = (Instant::now(), self.events);
+                    dirty = false;
+                }
+                match commands.recv_timeout(wait) {
+                    Ok(Command::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                    Ok(command) => {
+                        self.apply(command);
+                        dirty = true;
                     }
-                    break;
+                    Err(RecvTimeoutError::Timeout) => (),
                 }
+            }
 
-                if !state.running {
-                    {
-                        let _x = state;
+            loop {
+                match commands.try_recv() {
+                    Ok(Command::Stop) | Err(TryRecvError::Disconnected) => break 'sim,
+                    Ok(command) => {
+                        self.apply(command);
+                        dirty = true;
                     }
-                    thread::sleep(Duration::from_millis(100));
-                    continue;
+                    Err(TryRecvError::Empty) => break,
                 }
+            }
+
+            if self.running && !self.core.is_stable() {
+                self.step();
+                dirty = true;
+            }
 
-                let time = start.elapsed().as_nanos();
-
-                for idx in local_upd_list.iter() {
-                    debug!(
-                        "To Update: {:?}",
-                        local_upd_list
-                            .iter()
-                            .map(|x| format!("{} {}", *x, state.comp.components[*x].name.clone()))
-                            .collect::<Vec<String>>()
-                    );
-
-                    let comp_idx = *idx;
-                    let comp_i = &mut state.comp.components[comp_idx];
-                    debug!("Updating component: {} {:?}", comp_idx, comp_i.prim_type);
-                    debug!("  Old inputs: {:?}", comp_i.inputs);
-                    debug!("  Old outputs: {:?}", comp_i.outputs);
-
-                    comp_i.update(time);
-
-                    debug!("  New outputs: {:?}", comp_i.outputs);
-
-                    match &comp_i.prim_type {
-                        Primitive::Clock { period: _p } => {}
-                        Primitive::Custom { comp, state: _ } => {
-                            if let AsmProgramUpdateType::InputChanges = comp.update_type {
-                                state.to_upd.retain(|&x| x != comp_idx);
-                            }
-                        }
-                        _ => {
-                            state.to_upd.retain(|&x| x != comp_idx);
-                        }
+            if (dirty || self.running) && last_publish.0.elapsed() >= PUBLISH_INTERVAL {
+                publish(self.stats(last_publish), &self.core.comp.components);
+                last_publish = (Instant::now(), self.events);
+                dirty = false;
+            }
+        }
+
+        publish(self.stats(last_publish), &self.core.comp.components);
+    }
+
+    fn stats(&self, (since, events): (Instant, u64)) -> SimStats {
+        let secs = since.elapsed().as_secs_f64();
+        SimStats {
+            time: self.core.time,
+            events: self.events,
+            events_per_sec: if secs > 0.0 {
+                (self.events - events) as f64 / secs
+            } else {
+                0.0
+            },
+            running: self.running,
+            speed: self.speed,
+        }
+    }
+
+    fn apply(&mut self, command: Command) {
+        match command {
+            Command::SetInput(idx, port, value) => {
+                self.core.comp.components[idx].inputs[port] = value;
+                self.core.enqueue(idx);
+            }
+            Command::SetOutput(idx, port, value) => {
+                self.core.comp.components[idx].outputs[port] = value;
+                self.core.enqueue(idx);
+            }
+            Command::SetSpeed(speed) => {
+                self.speed = speed;
+                self.steps = 0;
+                self.pace = (Instant::now(), self.core.time);
+            }
+            Command::Step => self.steps += 1,
+            Command::PauseResume => {
+                self.running = !self.running;
+                self.pace = (Instant::now(), self.core.time);
+            }
+            Command::Stop => (),
+        }
+    }
+
+    /// Moves the time forward, once the circuit is stable, as far as the
+    /// speed mode allows.
+    ///
+    /// Returns how long to wait if the next event is not due yet.
+    fn advance(&mut self) -> Option<Duration> {
+        let next = match self.speed {
+            SpeedMode::AsFastAsPossible => self.next_event(false),
+            SpeedMode::ClockStep if self.steps == 0 => return Some(IDLE_WAIT),
+            SpeedMode::ClockStep => {
+                self.steps -= 1;
+                self.next_event(true).or_else(|| self.next_event(false))
+            }
+            SpeedMode::RealTime | SpeedMode::Slower(_) => {
+                let factor = match self.speed {
+                    SpeedMode::Slower(factor) => u128::from(factor.max(1)),
+                    _ => 1,
+                };
+                let target = self.pace.1 + self.pace.0.elapsed().as_nanos() / factor;
+                match self.next_event(false) {
+                    Some(next) if next <= target => Some(next),
+                    next => {
+                        self.core.time = self.core.time.max(target);
+                        let wait = next.map_or(IDLE_WAIT, |next| {
+                            let nanos = (next - self.core.time) * factor;
+                            Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+                        });
+                        return Some(wait.min(IDLE_WAIT));
                     }
+                }
+            }
+        };
 
-                    let mut rand_conns =
-                        (0..state.comp.connections[comp_idx].len()).collect::<Vec<usize>>();
+        let Some(next) = next else {
+            return Some(IDLE_WAIT);
+        };
+        self.core.advance_to(next);
+        None
+    }
 
-                    rand_conns.shuffle(&mut rand::thread_rng());
+    /// Time of the next change that does not come from the user, only
+    /// looking at the clocks if `clocks_only` is set.
+    fn next_event(&self, clocks_only: bool) -> Option<u128> {
+        self.core
+            .next_event(|c| !clocks_only || matches!(c.prim_type, Primitive::Clock(_)))
+    }
 
-                    for idx in rand_conns.iter() {
-                        let conn = state.comp.connections[comp_idx][*idx];
-                        let val = state.comp.components[comp_idx].outputs[conn.from.1];
+    /// Runs a delta cycle.
+    fn step(&mut self) {
+        let to_upd = self.core.take_pending();
+        self.events += to_upd.len() as u64;
+        let core = &mut self.core;
+        let time = core.time;
 
-                        // Do not update if the value is the same
-                        if val == state.comp.components[conn.to.0].inputs[conn.to.1] {
-                            continue;
-                        }
+        let parts = self.bounds.len() - 1;
+        let results = if parts == 1 || to_upd.len() < MIN_PARALLEL_UPDATES {
+            vec![update_partition(
+                &mut core.comp.components,
+                0,
+                &core.comp.connections,
+                &to_upd,
+                time,
+            )]
+        } else {
+            let mut work = vec![vec![]; parts];
+            for idx in to_upd {
+                let part = self.bounds.partition_point(|start| *start <= idx) - 1;
+                work[part].push(idx);
+            }
 
-                        debug!("  Connection: {:?}", conn);
-                        debug!(
-                            "New comp to update: {} {:?}",
-                            conn.to.0, state.comp.components[conn.to.0].prim_type
-                        );
+            let mut chunks = Vec::with_capacity(parts);
+            let mut rest = core.comp.components.as_mut_slice();
+            for part in 0..parts {
+                let (chunk, tail) = rest.split_at_mut(self.bounds[part + 1] - self.bounds[part]);
+                chunks.push(chunk);
+                rest = tail;
+            }
 
-                        // Update the value
-                        state.comp.components[conn.to.0].inputs[conn.to.1] = val;
+            let (bounds, connections) = (&self.bounds, &core.comp.connections);
+            thread::scope(|s| {
+                let handles: Vec<_> = chunks
+                    .into_iter()
+                    .zip(work)
+                    .enumerate()
+                    .map(|(part, (chunk, work))| {
+                        s.spawn(move || {
+                            update_partition(chunk, bounds[part], connections, &work, time)
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| h.join().expect("Partition update panicked"))
+                    .collect()
+            })
+        };
 
-                        if !local_next_upd_list.contains(&conn.to.0) {
-                            // stats.clk_cycle_ended = false;
-                            local_next_upd_list.push(conn.to.0);
-                        }
-                    }
-                }
+        for (changed, sent) in results {
+            for idx in changed {
+                core.enqueue(idx);
+            }
+            for (addr, val) in sent {
+                core.set_input(addr, val);
+            }
+        }
+    }
+}
 
-                local_upd_list = local_next_upd_list;
-                local_next_upd_list = state.to_upd.clone();
+/// Updates the given components of a partition starting at index `start`.
+///
+/// Returns the components of the partition whose inputs changed, and the
+/// values sent to components of other partitions.
+fn update_partition(
+    comps: &mut [PrimitiveComponent],
+    start: usize,
+    connections: &[Vec<Conn>],
+    to_upd: &[usize],
+    time: u128,
+) -> (Vec<usize>, Vec<(PortAddr, Data)>) {
+    let end = start + comps.len();
+    let mut changed = vec![];
+    let mut sent = vec![];
+    let mut order = vec![];
+    let mut rng = rand::thread_rng();
 
-                to_upd_len = state.to_upd.len();
-                {
-                    let _x = state;
-                }
+    for &idx in to_upd {
+        let comp = &mut comps[idx - start];
+        comp.update(time);
+        debug!("Updated component: {} {:?}", idx, comp.outputs);
+
+        order.clear();
+        order.extend(0..connections[idx].len());
+        order.shuffle(&mut rng);
+
+        for conn_idx in &order {
+            let conn = connections[idx][*conn_idx];
+            let val = comps[idx - start].outputs[conn.from.1];
+            if !(start..end).contains(&conn.to.0) {
+                sent.push((conn.to, val));
+                continue;
+            }
+
+            // Do not update if the value is the same
+            let dest = &mut comps[conn.to.0 - start].inputs[conn.to.1];
+            if *dest != val {
+                *dest = val;
+                changed.push(conn.to.0);
+            }
+        }
+    }
+    (changed, sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{primitives::clock::ClockConfig, test_utils::prim, HeadlessSimulator};
+
+    /// `chains` independent chains of `len` not gates, each one driven by
+    /// an input and ending in an output.
+    fn not_chains(chains: usize, len: usize) -> FlattenComponent {
+        let mut subs = vec![];
+        let mut conns = vec![];
+        let mut id = 1;
+        for _ in 0..chains {
+            let first = subs.len();
+            subs.push(prim(id, 1, 1, Primitive::Input { bits: 1 }));
+            for _ in 0..len {
+                id += 1;
+                subs.push(prim(id, 1, 1, Primitive::NotGate));
+            }
+            id += 1;
+            subs.push(prim(id, 1, 1, Primitive::Output { bits: 1 }));
+            id += 1;
+            for i in first..subs.len() - 1 {
+                conns.push(Conn::new(i, 0, i + 1, 0));
             }
-        });
+        }
+        let comp = ComponentBuilder::new(0)
+            .port_count(0, 0)
+            .sub_comps(subs)
+            .connections(conns)
+            .build()
+            .unwrap();
+        FlattenComponent::new(comp).unwrap()
+    }
+
+    fn outputs(comp: &FlattenComponent, snapshot: &Snapshot) -> Vec<Data> {
+        comp.components
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.prim_type.is_output())
+            .map(|(idx, _)| snapshot.inputs[idx][0])
+            .collect()
+    }
+
+    /// Polls the outputs until they are the expected ones or a timeout.
+    fn wait_for(sim: &Simulator, comp: &FlattenComponent, expected: &[Data]) -> Vec<Data> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let values = outputs(comp, &sim.snapshot());
+            if values == expected || Instant::now() > deadline {
+                return values;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_headless() {
+        let comp = not_chains(8, 101);
+
+        let mut headless = HeadlessSimulator::new(comp.clone());
+        headless.settle().unwrap();
+        let expected: Vec<Data> = comp
+            .components
+            .iter()
+            .filter(|c| c.prim_type.is_output())
+            .map(|c| headless.output(c.id).unwrap())
+            .collect();
+        assert!(expected.iter().all(|d| *d == Data::high()));
+
+        for threads in [1, 4] {
+            let mut sim = Simulator::new(comp.clone()).with_threads(threads);
+            sim.start(false);
+            sim.wait();
+            assert_eq!(outputs(&comp, &sim.snapshot()), expected);
+        }
+    }
+
+    /// Runs delta cycles until no component is waiting to be updated.
+    fn settle(engine: &mut Engine) {
+        while !engine.core.is_stable() {
+            engine.step();
+        }
+    }
+
+    #[test]
+    fn test_serial_matches_parallel() {
+        let comp = not_chains(512, 9);
+        let mut serial = Engine::new(comp.clone(), 1);
+        let mut parallel = Engine::new(comp, 4);
+        assert_eq!(serial.bounds.len() - 1, 1);
+        assert_eq!(parallel.bounds.len() - 1, 4);
+        // Small circuits are not split
+        assert_eq!(Engine::new(not_chains(8, 101), 4).bounds.len() - 1, 1);
+
+        settle(&mut serial);
+        settle(&mut parallel);
+        let values = |engine: &Engine| -> Vec<(Vec<Data>, Vec<Data>)> {
+            engine
+                .core
+                .comp
+                .components
+                .iter()
+                .map(|c| (c.inputs.clone(), c.outputs.clone()))
+                .collect()
+        };
+        assert_eq!(values(&serial), values(&parallel));
+        assert_eq!(serial.events, parallel.events);
+    }
+
+    #[test]
+    fn test_set_input_while_running() {
+        let comp = not_chains(4, 3);
+        let input_id = comp
+            .components
+            .iter()
+            .find(|c| matches!(c.prim_type, Primitive::Input { .. }))
+            .unwrap()
+            .id;
+        let mut sim = Simulator::new(comp.clone()).with_threads(2);
+        sim.start(true);
+
+        let high = vec![Data::high(); 4];
+        assert_eq!(wait_for(&sim, &comp, &high), high);
+
+        sim.set_input(&[], Some(input_id), 0, Data::high()).unwrap();
+        assert!(matches!(
+            sim.set_input(&[], Some(input_id), 1, Data::high()),
+            Err(DataRequestError::InvalidInputPortIndex(1))
+        ));
+
+        let expected = vec![Data::low(), Data::high(), Data::high(), Data::high()];
+        assert_eq!(wait_for(&sim, &comp, &expected), expected);
+
+        sim.stop();
+        sim.wait();
+    }
+
+    /// Clock with a period of 10ns driving an output.
+    fn clock_comp() -> FlattenComponent {
+        let comp = ComponentBuilder::new(0)
+            .port_count(0, 0)
+            .sub_comps(vec![
+                prim(1, 0, 1, Primitive::Clock(ClockConfig::new(10))),
+                prim(2, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0)])
+            .build()
+            .unwrap();
+        FlattenComponent::new(comp).unwrap()
+    }
+
+    /// Polls the snapshots until one satisfies `done` or a timeout.
+    fn wait_until(sim: &Simulator, done: impl Fn(&Snapshot) -> bool) -> Arc<Snapshot> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let snapshot = sim.snapshot();
+            if done(&snapshot) || Instant::now() > deadline {
+                return snapshot;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_clock_step_mode() {
+        let comp = clock_comp();
+        let mut sim = Simulator::new(comp.clone());
+        sim.set_speed(SpeedMode::ClockStep);
+        sim.start(true);
+
+        for (time, value) in [(5, Data::high()), (10, Data::low()), (15, Data::high())] {
+            sim.step();
+            let snapshot = wait_until(&sim, |s| s.stats.time == time);
+            assert_eq!(snapshot.stats.time, time);
+            assert_eq!(outputs(&comp, &snapshot), vec![value]);
+        }
+
+        // Without steps the time does not move
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(sim.snapshot().stats.time, 15);
+    }
+
+    #[test]
+    fn test_speed_modes() {
+        let mut sim = Simulator::new(clock_comp());
+        sim.set_speed(SpeedMode::AsFastAsPossible);
+        sim.start(true);
+        let snapshot = wait_until(&sim, |s| s.stats.time >= 1_000_000);
+        assert!(snapshot.stats.time >= 1_000_000);
+        assert!(snapshot.stats.events >= 100_000);
+
+        for (speed, factor) in [(SpeedMode::RealTime, 1), (SpeedMode::Slower(1000), 1000)] {
+            let mut sim = Simulator::new(clock_comp());
+            sim.set_speed(speed);
+            let start = Instant::now();
+            sim.start(true);
+            thread::sleep(Duration::from_millis(50));
+            let stats = sim.snapshot().stats;
+            assert_eq!(stats.speed, speed);
+            assert!(stats.time <= start.elapsed().as_nanos() / factor);
+        }
     }
 }
@@ -0,0 +1,302 @@
+//! Size and complexity statistics of circuits.
+//!
+//! Statistics are computed over the flattened circuit: primitives are counted
+//! by kind, both in total and by the hierarchy level they are nested at.
+//! Transistor counts are estimates for static CMOS gates, where `n` is the
+//! amount of inputs and every bit of a multi-bit gate counts as a gate:
+//!
+//! | Kind        | Transistors  |
+//! |-------------|--------------|
+//! | not         | 2            |
+//! | nand, nor   | 2n           |
+//! | and, or     | 2n + 2       |
+//! | xor         | 12 (n - 1)   |
+//! | switch      | 2            |
+//!
+//! Wiring primitives (inputs, outputs, splitters and joiners), clocks,
+//! constants and custom components are not counted.
+//!
+//! The logic depth is the amount of gates of the longest combinational path.
+//! Paths start at inputs, clocks, constants and custom components, which
+//! hold the state of registers. Feedback loops between gates, as the ones of
+//! latches, are cut where they close.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Formatter},
+};
+
+use logix_core::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    errors::FlattenComponentError,
+    flatten::{FlattenComponent, NestedConfig},
+    netlist::kind,
+    primitives::{prelude::Primitive, primitive::ExtraInfo},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DesignStats {
+    /// Amount of primitives of each kind.
+    pub primitives: BTreeMap<String, usize>,
+
+    /// Amount of primitives of each kind at each hierarchy level.
+    ///
+    /// The first level holds the primitives placed directly in the top
+    /// component, the second one the primitives of its sub-components, and
+    /// so on.
+    pub levels: Vec<BTreeMap<String, usize>>,
+
+    /// Estimated amount of transistors.
+    pub transistors: usize,
+
+    /// Maximum amount of inputs driven by a single output.
+    pub max_fan_out: usize,
+
+    /// Amount of gates of the longest combinational path.
+    pub logic_depth: usize,
+}
+
+impl DesignStats {
+    /// Computes the statistics of a component by flattening it.
+    pub fn from_component(comp: &Component<ExtraInfo>) -> Result<Self, FlattenComponentError> {
+        Ok(FlattenComponent::new(comp.clone())?.stats())
+    }
+}
+
+impl Display for DesignStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let total: usize = self.primitives.values().sum();
+        writeln!(f, "Primitives: {total}")?;
+        for (kind, count) in &self.primitives {
+            writeln!(f, "  {kind}: {count}")?;
+        }
+        for (level, counts) in self.levels.iter().enumerate() {
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|(kind, count)| format!("{kind}: {count}"))
+                .collect();
+            writeln!(f, "Level {level}: {}", counts.join(", "))?;
+        }
+        writeln!(f, "Transistors: {}", self.transistors)?;
+        writeln!(f, "Max fan-out: {}", self.max_fan_out)?;
+        write!(f, "Logic depth: {}", self.logic_depth)
+    }
+}
+
+/// Amount of gates the primitive adds to a combinational path.
+const fn gate_delay(prim: &Primitive) -> usize {
+    match prim {
+        Primitive::AndGate
+        | Primitive::OrGate
+        | Primitive::NotGate
+        | Primitive::NandGate
+        | Primitive::NorGate
+        | Primitive::XorGate
+        | Primitive::Switch => 1,
+        _ => 0,
+    }
+}
+
+/// Whether combinational paths start at the primitive, regardless of what
+/// drives it.
+const fn starts_path(prim: &Primitive) -> bool {
+    matches!(
+        prim,
+        Primitive::Clock(_) | Primitive::Const { .. } | Primitive::Custom { .. }
+    )
+}
+
+/// Estimated amount of transistors of a gate with `n` inputs of one bit.
+const fn transistors(prim: &Primitive, n: usize) -> usize {
+    match prim {
+        Primitive::NotGate | Primitive::Switch => 2,
+        Primitive::NandGate | Primitive::NorGate => 2 * n,
+        Primitive::AndGate | Primitive::OrGate => 2 * n + 2,
+        Primitive::XorGate => 12 * n.saturating_sub(1),
+        _ => 0,
+    }
+}
+
+impl FlattenComponent {
+    /// Computes the size and complexity statistics of the circuit.
+    pub fn stats(&self) -> DesignStats {
+        let mut primitives = BTreeMap::new();
+        let mut transistor_count = 0;
+        for comp in &self.components {
+            *primitives
+                .entry(kind(&comp.prim_type).to_string())
+                .or_default() += 1;
+            let width = comp.outputs.first().map_or(1, |data| data.size);
+            transistor_count += width * transistors(&comp.prim_type, comp.inputs.len());
+        }
+
+        let mut levels = vec![];
+        self.count_levels(&self.nested_config, 0, &mut levels);
+
+        let max_fan_out = self
+            .connections
+            .iter()
+            .flat_map(|conns| {
+                let mut fan_out: HashMap<usize, usize> = HashMap::new();
+                for conn in conns {
+                    *fan_out.entry(addr_of(conn.from)).or_default() += 1;
+                }
+                fan_out.into_values()
+            })
+            .max()
+            .unwrap_or(0);
+
+        DesignStats {
+            primitives,
+            levels,
+            transistors: transistor_count,
+            max_fan_out,
+            logic_depth: self.logic_depth(),
+        }
+    }
+
+    fn count_levels(
+        &self,
+        config: &NestedConfig,
+        level: usize,
+        levels: &mut Vec<BTreeMap<String, usize>>,
+    ) {
+        match config {
+            NestedConfig::Single(_, id, _, _) => {
+                let Some(idx) = self.id_to_idx.get(id) else {
+                    return;
+                };
+                if levels.len() <= level {
+                    levels.resize(level + 1, BTreeMap::new());
+                }
+                *levels[level]
+                    .entry(kind(&self.components[*idx].prim_type).to_string())
+                    .or_default() += 1;
+            }
+            NestedConfig::Compose(_, _, subs, _, _) => {
+                let mut ids: Vec<&usize> = subs.keys().collect();
+                ids.sort();
+                for id in ids {
+                    let next = match subs[id] {
+                        NestedConfig::Single(..) => level,
+                        NestedConfig::Compose(..) => level + 1,
+                    };
+                    self.count_levels(&subs[id], next, levels);
+                }
+            }
+        }
+    }
+
+    /// Amount of gates of the longest combinational path.
+    fn logic_depth(&self) -> usize {
+        let count = self.components.len();
+        let mut depths: Vec<Option<usize>> = vec![None; count];
+        let mut on_stack = vec![false; count];
+
+        for root in 0..count {
+            if depths[root].is_some() {
+                continue;
+            }
+            // Depth first over the drivers, with the next driver to visit
+            let mut stack = vec![(root, 0)];
+            on_stack[root] = true;
+            while let Some((idx, next)) = stack.last_mut() {
+                let idx = *idx;
+                let prim = &self.components[idx].prim_type;
+                let drivers: &[usize] = if starts_path(prim) {
+                    &[]
+                } else {
+                    &self.deps[idx]
+                };
+
+                if let Some(driver) = drivers.get(*next).copied() {
+                    *next += 1;
+                    // Drivers on the stack close a feedback loop
+                    if depths[driver].is_none() && !on_stack[driver] {
+                        on_stack[driver] = true;
+                        stack.push((driver, 0));
+                    }
+                    continue;
+                }
+
+                let max_driver = drivers.iter().filter_map(|d| depths[*d]).max();
+                depths[idx] = Some(gate_delay(prim) + max_driver.unwrap_or(0));
+                on_stack[idx] = false;
+                stack.pop();
+            }
+        }
+
+        depths.into_iter().flatten().max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prim;
+
+    /// Main with `q = !a & a` where the inverter is inside a sub-component,
+    /// and an SR latch made of two nor gates.
+    fn main() -> Component<ExtraInfo> {
+        let inv = ComponentBuilder::new(10)
+            .name("Inv".into())
+            .port_count(1, 1)
+            .sub_comps(vec![
+                prim(11, 1, 1, Primitive::Input { bits: 1 }),
+                prim(12, 1, 1, Primitive::NotGate),
+                prim(13, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0), Conn::new(1, 0, 2, 0)])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(2, 0)])
+            .build()
+            .unwrap();
+        ComponentBuilder::new(0)
+            .name("Main".into())
+            .port_count(1, 2)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                inv,
+                prim(2, 2, 1, Primitive::AndGate),
+                prim(3, 1, 1, Primitive::Output { bits: 1 }),
+                prim(4, 2, 1, Primitive::NorGate),
+                prim(5, 2, 1, Primitive::NorGate),
+                prim(6, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 1, 0),
+                Conn::new(0, 0, 2, 1),
+                Conn::new(1, 0, 2, 0),
+                Conn::new(2, 0, 3, 0),
+                Conn::new(0, 0, 4, 0),
+                Conn::new(0, 0, 5, 1),
+                Conn::new(4, 0, 5, 0),
+                Conn::new(5, 0, 4, 1),
+                Conn::new(5, 0, 6, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(3, 0), (6, 0)])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = DesignStats::from_component(&main()).unwrap();
+        assert_eq!(stats.primitives["input"], 2);
+        assert_eq!(stats.primitives["nor"], 2);
+        assert_eq!(stats.levels.len(), 2);
+        assert_eq!(stats.levels[0]["output"], 2);
+        assert_eq!(stats.levels[1]["not"], 1);
+        assert!(!stats.levels[1].contains_key("and"));
+        assert_eq!(stats.transistors, 2 + 6 + 4 + 4);
+        assert_eq!(stats.max_fan_out, 4);
+        assert_eq!(stats.logic_depth, 2);
+
+        let report = stats.to_string();
+        assert!(report.starts_with("Primitives: 9\n"));
+        assert!(report.ends_with("Logic depth: 2"));
+    }
+}
@@ -0,0 +1,18 @@
+//! Helpers shared by the tests of the crate.
+
+use logix_core::prelude::*;
+
+use crate::{
+    netlist::kind,
+    primitives::primitive::{ExtraInfo, Primitive},
+};
+
+/// Primitive component named after its kind (e.g. `and`).
+pub fn prim(id: usize, ins: usize, outs: usize, prim: Primitive) -> Component<ExtraInfo> {
+    ComponentBuilder::new(id)
+        .name(kind(&prim).into())
+        .port_count(ins, outs)
+        .extra(ExtraInfo::from_primitive(id, prim))
+        .build()
+        .unwrap()
+}
@@ -0,0 +1,460 @@
+//! Testbenches (`.lgxt` files) drive the inputs of a circuit and check its
+//! outputs at given times.
+//!
+//! ```text
+//! # Circuit under test, relative to the testbench file
+//! design "counter.lgx"
+//!
+//! # Drives the `clk` input with a 20ns clock (rising at 10ns, 30ns, ...)
+//! clock clk 20ns
+//!
+//! at 0ns     set rst=1
+//! at 5ns     set rst=0
+//! at rise 1  expect q=0b01
+//! at fall 2  expect q=0b10 carry=0
+//! ```
+//!
+//! Times are in nanoseconds unless a unit (`ns`, `us`, `ms`, `s`) is given.
+//! `rise n` and `fall n` refer to the n-th edge (starting at 1) of the
+//! testbench clock or, if there is none, of the fastest clock in the circuit,
+//! taking its duty cycle, phase and delay into account. Clocks with an
+//! enable input are not used, since their edges depend on the circuit.
+//! At any given time the inputs are set first, then the circuit settles and
+//! finally the expectations are checked.
+
+use std::fmt::{Display, Formatter};
+
+use asmhdl::Data;
+
+use crate::{
+    errors::TestbenchError,
+    flatten::FlattenComponent,
+    headless::{HeadlessSimulator, Port},
+    primitives::clock::ClockConfig,
+};
+
+/// Parses a time like `20`, `20ns` or `1.5us` into nanoseconds.
+pub fn parse_time(time: &str) -> Option<u128> {
+    let split = time
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(time.len());
+    let (value, unit) = time.split_at(split);
+    let scale = match unit {
+        "" | "ns" => 1.0,
+        "us" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        _ => return None,
+    };
+    let ns = value.parse::<f64>().ok()? * scale;
+    ns.is_finite().then(|| ns.round() as u128)
+}
+
+/// Parses a decimal, `0x` hex or `0b` binary number.
+pub fn parse_value(value: &str) -> Option<usize> {
+    if let Some(hex) = value.strip_prefix("0x") {
+        usize::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = value.strip_prefix("0b") {
+        usize::from_str_radix(bin, 2).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum When {
+    /// Absolute time in nanoseconds.
+    Time(u128),
+    /// n-th rising edge of the clock.
+    Rise(u128),
+    /// n-th falling edge of the clock.
+    Fall(u128),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Set(Vec<(String, usize)>),
+    Expect(Vec<(String, usize)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub line: usize,
+    pub when: When,
+    pub action: Action,
+}
+
+/// Input driven as a clock by the testbench.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestClock {
+    pub input: String,
+    /// Full cycle in nanoseconds.
+    pub period: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Testbench {
+    /// Path of the circuit under test as written in the file.
+    pub design: Option<String>,
+    pub clock: Option<TestClock>,
+    pub steps: Vec<Step>,
+}
+
+/// Expectation that did not hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub line: usize,
+    pub time: u128,
+    pub signal: String,
+    pub expected: usize,
+    pub actual: Data,
+    /// Values of every input and output at the time of the failure.
+    pub values: Vec<(String, Data)>,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|(name, data)| format!("{}={:0width$b}", name, data.value, width = data.size))
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "line {} at {}ns: expected {}={:0width$b}, got {:0width$b} ({})",
+            self.line,
+            self.time,
+            self.signal,
+            self.expected,
+            self.actual.value,
+            values.join(" "),
+            width = self.actual.size,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TestReport {
+    /// Number of expectations checked.
+    pub checks: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+fn parse_assigns(line: usize, words: &[&str]) -> Result<Vec<(String, usize)>, TestbenchError> {
+    words
+        .iter()
+        .map(|word| {
+            let (name, value) = word
+                .split_once('=')
+                .filter(|(name, _)| !name.is_empty())
+                .ok_or_else(|| TestbenchError::InvalidAssignment(line, word.to_string()))?;
+            let value = parse_value(value)
+                .ok_or_else(|| TestbenchError::InvalidValue(line, value.to_string()))?;
+            Ok((name.to_string(), value))
+        })
+        .collect()
+}
+
+fn parse_time_at(line: usize, time: &str) -> Result<u128, TestbenchError> {
+    parse_time(time).ok_or_else(|| TestbenchError::InvalidTime(line, time.to_string()))
+}
+
+fn parse_edge(line: usize, edge: Option<&str>) -> Result<u128, TestbenchError> {
+    edge.and_then(|n| n.parse().ok())
+        .filter(|n| *n > 0)
+        .ok_or_else(|| TestbenchError::InvalidEdge(line, edge.unwrap_or_default().to_string()))
+}
+
+impl Testbench {
+    pub fn parse(src: &str) -> Result<Self, TestbenchError> {
+        let mut tb = Testbench::default();
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => (),
+                ["design", path] => {
+                    tb.design = Some(path.trim_matches('"').to_string());
+                }
+                ["clock", input, period] => {
+                    let period = parse_time_at(line_no, period)?;
+                    if period < 2 {
+                        return Err(TestbenchError::InvalidTime(line_no, words[2].to_string()));
+                    }
+                    tb.clock = Some(TestClock {
+                        input: input.to_string(),
+                        period,
+                    });
+                }
+                ["at", rest @ ..] => {
+                    let (when, rest) = match rest {
+                        ["rise", n, rest @ ..] => (When::Rise(parse_edge(line_no, Some(n))?), rest),
+                        ["fall", n, rest @ ..] => (When::Fall(parse_edge(line_no, Some(n))?), rest),
+                        ["rise" | "fall"] => return Err(parse_edge(line_no, None).unwrap_err()),
+                        [time, rest @ ..] => (When::Time(parse_time_at(line_no, time)?), rest),
+                        [] => return Err(TestbenchError::InvalidTime(line_no, String::new())),
+                    };
+                    let action = match rest {
+                        ["set", assigns @ ..] => Action::Set(parse_assigns(line_no, assigns)?),
+                        ["expect", assigns @ ..] => {
+                            Action::Expect(parse_assigns(line_no, assigns)?)
+                        }
+                        _ => {
+                            return Err(TestbenchError::InvalidCommand(line_no, line.trim().into()))
+                        }
+                    };
+                    tb.steps.push(Step {
+                        line: line_no,
+                        when,
+                        action,
+                    });
+                }
+                _ => return Err(TestbenchError::InvalidCommand(line_no, line.trim().into())),
+            }
+        }
+        Ok(tb)
+    }
+
+    /// Runs the testbench on a circuit whose ports are named as in the
+    /// testbench.
+    pub fn run(
+        &self,
+        comp: FlattenComponent,
+        inputs: &[Port],
+        outputs: &[Port],
+    ) -> Result<TestReport, TestbenchError> {
+        let mut sim = HeadlessSimulator::new(comp);
+        let input = |name: &str| {
+            inputs
+                .iter()
+                .find(|port| port.name == name)
+                .ok_or_else(|| TestbenchError::UnknownInput(name.to_string()))
+        };
+
+        // Testbench clocks rise at half the cycle and fall at the end of it
+        let clock = match &self.clock {
+            Some(clock) => Some(ClockConfig::new(clock.period)),
+            None => sim.clock(),
+        };
+        let time_of = |step: &Step| match (step.when, clock) {
+            (When::Time(time), _) => Ok(time),
+            (When::Rise(n), Some(clock)) => Ok(clock.nth_edge(n, true)),
+            (When::Fall(n), Some(clock)) => Ok(clock.nth_edge(n, false)),
+            (_, None) => Err(TestbenchError::NoClock(step.line)),
+        };
+        let mut steps = self
+            .steps
+            .iter()
+            .map(|step| Ok((time_of(step)?, step)))
+            .collect::<Result<Vec<_>, TestbenchError>>()?;
+        // Stable, so steps at the same time keep the file order
+        steps.sort_by_key(|(time, _)| *time);
+        let end = steps.last().map_or(0, |(time, _)| *time);
+
+        // Toggles of the testbench clock
+        let mut clock_edges = vec![];
+        if let (Some(test_clock), Some(clock)) = (&self.clock, clock) {
+            let port = input(&test_clock.input)?;
+            sim.set_input(port.id, Data::low())?;
+            let mut time = clock.next_edge(0);
+            while time <= end {
+                clock_edges.push((time, clock.value_at(time)));
+                time = clock.next_edge(time);
+            }
+        }
+
+        let mut times: Vec<u128> = steps
+            .iter()
+            .map(|(time, _)| *time)
+            .chain(clock_edges.iter().map(|(time, _)| *time))
+            .collect();
+        times.sort_unstable();
+        times.dedup();
+
+        let mut report = TestReport::default();
+        for time in times {
+            sim.run_until(time)?;
+            if let (Some(clock), Some((_, high))) = (
+                &self.clock,
+                clock_edges.iter().find(|(edge, _)| *edge == time),
+            ) {
+                sim.set_input(input(&clock.input)?.id, Data::from(*high))?;
+            }
+            for (_, step) in steps.iter().filter(|(t, _)| *t == time) {
+                if let Action::Set(assigns) = &step.action {
+                    for (name, value) in assigns {
+                        let port = input(name)?;
+                        sim.set_input(port.id, Data::new(*value, port.bits))?;
+                    }
+                }
+            }
+            sim.settle()?;
+
+            for (_, step) in steps.iter().filter(|(t, _)| *t == time) {
+                let Action::Expect(assigns) = &step.action else {
+                    continue;
+                };
+                for (name, expected) in assigns {
+                    let port = outputs
+                        .iter()
+                        .find(|port| port.name == *name)
+                        .ok_or_else(|| TestbenchError::UnknownOutput(name.clone()))?;
+                    let actual = sim.output(port.id)?;
+                    report.checks += 1;
+                    if actual.value != *expected {
+                        let values = inputs
+                            .iter()
+                            .chain(outputs)
+                            .map(|port| {
+                                let comp = sim.comp().comp_by_id(port.id);
+                                (port.name.clone(), comp.inputs[0])
+                            })
+                            .collect();
+                        report.failures.push(Failure {
+                            line: step.line,
+                            time,
+                            signal: name.clone(),
+                            expected: *expected,
+                            actual,
+                            values,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::top_level_ports, primitives::primitive::*, test_utils::prim};
+    use logix_core::prelude::*;
+
+    /// `y = a & clk`, with `clk` as an input.
+    fn gated_clock() -> (FlattenComponent, Vec<Port>, Vec<Port>) {
+        let comp = ComponentBuilder::new(0)
+            .port_count(2, 1)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                prim(3, 2, 1, Primitive::AndGate),
+                prim(4, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 2, 1),
+                Conn::new(2, 0, 3, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(3, 0)])
+            .build()
+            .unwrap();
+        let comp = FlattenComponent::new(comp).unwrap();
+        let (mut inputs, mut outputs) = top_level_ports(&comp);
+        inputs[0].name = "a".into();
+        inputs[1].name = "clk".into();
+        outputs[0].name = "y".into();
+        (comp, inputs, outputs)
+    }
+
+    #[test]
+    fn test_parse() {
+        let tb = Testbench::parse(
+            "design \"gate.lgx\"\n\
+             clock clk 20ns # comment\n\
+             at 0 set a=1\n\
+             at rise 2 expect y=0b1\n",
+        )
+        .unwrap();
+        assert_eq!(tb.design.as_deref(), Some("gate.lgx"));
+        assert_eq!(
+            tb.clock,
+            Some(TestClock {
+                input: "clk".into(),
+                period: 20
+            })
+        );
+        assert_eq!(tb.steps[1].when, When::Rise(2));
+        assert_eq!(tb.steps[1].action, Action::Expect(vec![("y".into(), 1)]));
+
+        assert!(matches!(
+            Testbench::parse("at 1ps set a=1"),
+            Err(TestbenchError::InvalidTime(1, _))
+        ));
+        assert!(matches!(
+            Testbench::parse("\nat rise 0 set a=1"),
+            Err(TestbenchError::InvalidEdge(2, _))
+        ));
+        assert!(matches!(
+            Testbench::parse("at 0 check a=1"),
+            Err(TestbenchError::InvalidCommand(1, _))
+        ));
+    }
+
+    #[test]
+    fn test_run() {
+        let (comp, inputs, outputs) = gated_clock();
+        let tb = Testbench::parse(
+            "clock clk 20ns\n\
+             at 0 set a=1\n\
+             at 5 expect y=0\n\
+             at rise 1 expect y=1\n\
+             at fall 1 expect y=0\n\
+             at 25ns set a=0\n\
+             at rise 2 expect y=1\n",
+        )
+        .unwrap();
+        let report = tb.run(comp, &inputs, &outputs).unwrap();
+        assert_eq!(report.checks, 4);
+        assert_eq!(report.failures.len(), 1);
+
+        let failure = &report.failures[0];
+        assert_eq!((failure.line, failure.time), (7, 30));
+        assert_eq!(
+            failure.to_string(),
+            "line 7 at 30ns: expected y=1, got 0 (a=0 clk=1 y=0)"
+        );
+    }
+
+    #[test]
+    fn test_circuit_clock_edges() {
+        let clock = ClockConfig {
+            duty: 25,
+            delay: 1000,
+            ..ClockConfig::new(100)
+        };
+        let comp = ComponentBuilder::new(0)
+            .port_count(0, 1)
+            .sub_comps(vec![
+                prim(1, 0, 1, Primitive::Clock(clock)),
+                prim(2, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![Conn::new(0, 0, 1, 0)])
+            .out_addrs(vec![(1, 0)])
+            .build()
+            .unwrap();
+        let comp = FlattenComponent::new(comp).unwrap();
+        let (inputs, mut outputs) = top_level_ports(&comp);
+        outputs[0].name = "c".into();
+
+        let tb = Testbench::parse(
+            "at 1074 expect c=0\n\
+             at rise 1 expect c=1\n\
+             at fall 1 expect c=0\n\
+             at rise 3 expect c=1\n",
+        )
+        .unwrap();
+        let report = tb.run(comp, &inputs, &outputs).unwrap();
+        assert_eq!(report.checks, 4);
+        assert!(report.passed(), "{:?}", report.failures);
+    }
+}
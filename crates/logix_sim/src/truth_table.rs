@@ -0,0 +1,397 @@
+//! Truth tables of combinational circuits.
+//!
+//! A table is generated by driving every combination of the top-level inputs
+//! and collecting the outputs once the circuit settles. Tables can be written
+//! and read as CSV (values in binary) or JSON, and compared against an
+//! expected table.
+
+use std::fmt::{Display, Formatter};
+
+use asmhdl::Data;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::TruthTableError,
+    flatten::FlattenComponent,
+    headless::{HeadlessSimulator, Port},
+};
+
+/// Maximum number of input bits (the table has `2^bits` rows).
+pub const MAX_INPUT_BITS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    pub bits: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruthRow {
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruthTable {
+    pub inputs: Vec<Column>,
+    pub outputs: Vec<Column>,
+    pub rows: Vec<TruthRow>,
+}
+
+/// Row of an expected table whose outputs differ from the actual ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub inputs: Vec<usize>,
+    pub expected: Vec<usize>,
+    pub actual: Vec<usize>,
+}
+
+fn columns(ports: &[Port]) -> Vec<Column> {
+    ports
+        .iter()
+        .map(|port| Column {
+            name: port.name.clone(),
+            bits: port.bits,
+        })
+        .collect()
+}
+
+fn format_values(columns: &[Column], values: &[usize]) -> String {
+    columns
+        .iter()
+        .zip(values)
+        .map(|(col, value)| format!("{}={:0width$b}", col.name, value, width = col.bits))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl TruthTable {
+    /// Enumerates every combination of `inputs` and records the values of
+    /// `outputs` once the circuit settles.
+    ///
+    /// The first input is the most significant one, so rows are sorted as
+    /// in a handwritten table.
+    pub fn generate(
+        comp: FlattenComponent,
+        inputs: &[Port],
+        outputs: &[Port],
+    ) -> Result<Self, TruthTableError> {
+        let total_bits: usize = inputs.iter().map(|port| port.bits).sum();
+        if total_bits > MAX_INPUT_BITS {
+            return Err(TruthTableError::TooManyInputs(total_bits));
+        }
+
+        let mut sim = HeadlessSimulator::new(comp);
+        if sim.next_event().is_some() {
+            return Err(TruthTableError::NotCombinational);
+        }
+
+        let mut rows = Vec::with_capacity(1 << total_bits);
+        for combination in 0..1usize << total_bits {
+            let mut shift = total_bits;
+            let mut values = Vec::with_capacity(inputs.len());
+            for port in inputs {
+                shift -= port.bits;
+                let value = (combination >> shift) & ((1 << port.bits) - 1);
+                sim.set_input(port.id, Data::new(value, port.bits))?;
+                values.push(value);
+            }
+            sim.settle()?;
+            let outs = outputs
+                .iter()
+                .map(|port| sim.output(port.id).map(|data| data.value))
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(TruthRow {
+                inputs: values,
+                outputs: outs,
+            });
+        }
+
+        Ok(TruthTable {
+            inputs: columns(inputs),
+            outputs: columns(outputs),
+            rows,
+        })
+    }
+
+    /// Rows of `expected` whose outputs differ from this table. Expected
+    /// tables may list only some of the rows and some of the outputs, but
+    /// they must have the same inputs.
+    pub fn compare(&self, expected: &TruthTable) -> Result<Vec<Mismatch>, TruthTableError> {
+        if expected.inputs != self.inputs {
+            return Err(TruthTableError::ColumnMismatch(
+                expected.inputs.iter().map(|col| col.name.clone()).collect(),
+            ));
+        }
+        let out_idxs = expected
+            .outputs
+            .iter()
+            .map(|col| {
+                self.outputs
+                    .iter()
+                    .position(|own| own == col)
+                    .ok_or_else(|| TruthTableError::UnknownColumn(col.name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut mismatches = vec![];
+        for row in &expected.rows {
+            let actual_row = self
+                .rows
+                .iter()
+                .find(|own| own.inputs == row.inputs)
+                .ok_or_else(|| {
+                    TruthTableError::UnknownRow(format_values(&self.inputs, &row.inputs))
+                })?;
+            let actual: Vec<usize> = out_idxs.iter().map(|i| actual_row.outputs[*i]).collect();
+            if actual != row.outputs {
+                mismatches.push(Mismatch {
+                    inputs: row.inputs.clone(),
+                    expected: row.outputs.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Describes a mismatch using the column names of `expected`.
+    pub fn describe(expected: &TruthTable, mismatch: &Mismatch) -> String {
+        format!(
+            "{}: expected {}, got {}",
+            format_values(&expected.inputs, &mismatch.inputs),
+            format_values(&expected.outputs, &mismatch.expected),
+            format_values(&expected.outputs, &mismatch.actual),
+        )
+    }
+
+    /// Writes the table as CSV. The header holds `name:bits` for every
+    /// multi-bit column and the values are written in binary.
+    pub fn to_csv(&self) -> String {
+        let header = |col: &Column| match col.bits {
+            1 => col.name.clone(),
+            bits => format!("{}:{}", col.name, bits),
+        };
+        let mut csv = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(header)
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+        for row in &self.rows {
+            let values = self
+                .inputs
+                .iter()
+                .zip(&row.inputs)
+                .chain(self.outputs.iter().zip(&row.outputs))
+                .map(|(col, value)| format!("{:0width$b}", value, width = col.bits))
+                .collect::<Vec<_>>();
+            csv.push_str(&values.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Reads a CSV table. The input columns are given by `input_count`
+    /// since the header does not tell inputs and outputs apart.
+    pub fn from_csv(src: &str, input_count: usize) -> Result<Self, TruthTableError> {
+        let mut lines = src
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().ok_or(TruthTableError::EmptyCsv)?;
+        let mut columns = header
+            .split(',')
+            .map(|name| {
+                let name = name.trim();
+                match name.split_once(':') {
+                    Some((name, bits)) => bits
+                        .parse()
+                        .map(|bits| Column {
+                            name: name.to_string(),
+                            bits,
+                        })
+                        .map_err(|_| TruthTableError::InvalidCsv(1, header.to_string())),
+                    None => Ok(Column {
+                        name: name.to_string(),
+                        bits: 1,
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if input_count > columns.len() {
+            return Err(TruthTableError::InvalidCsv(1, header.to_string()));
+        }
+        let outputs = columns.split_off(input_count);
+        let inputs = columns;
+
+        let mut rows = vec![];
+        for (i, line) in lines {
+            let invalid = || TruthTableError::InvalidCsv(i + 1, line.to_string());
+            let mut values = line
+                .split(',')
+                .map(|value| usize::from_str_radix(value.trim(), 2).map_err(|_| invalid()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.len() != inputs.len() + outputs.len() {
+                return Err(invalid());
+            }
+            let outputs = values.split_off(input_count);
+            rows.push(TruthRow {
+                inputs: values,
+                outputs,
+            });
+        }
+        Ok(TruthTable {
+            inputs,
+            outputs,
+            rows,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Truth tables are always serializable")
+    }
+
+    pub fn from_json(src: &str) -> Result<Self, TruthTableError> {
+        Ok(serde_json::from_str(src)?)
+    }
+}
+
+impl Display for TruthTable {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let width = |col: &Column| col.name.len().max(col.bits);
+        let cells = |cols: &[Column], values: Vec<String>| {
+            cols.iter()
+                .zip(values)
+                .map(|(col, value)| format!("{:>w$}", value, w = width(col)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let names = |cols: &[Column]| cols.iter().map(|col| col.name.clone()).collect();
+        writeln!(
+            f,
+            "{} | {}",
+            cells(&self.inputs, names(&self.inputs)),
+            cells(&self.outputs, names(&self.outputs))
+        )?;
+
+        let binary = |cols: &[Column], values: &[usize]| {
+            cols.iter()
+                .zip(values)
+                .map(|(col, value)| format!("{:0width$b}", value, width = col.bits))
+                .collect()
+        };
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{} | {}",
+                cells(&self.inputs, binary(&self.inputs, &row.inputs)),
+                cells(&self.outputs, binary(&self.outputs, &row.outputs))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::top_level_ports, primitives::primitive::*, test_utils::prim};
+    use logix_core::prelude::*;
+
+    /// Half adder with named ports.
+    fn half_adder() -> (FlattenComponent, Vec<Port>, Vec<Port>) {
+        let comp = ComponentBuilder::new(0)
+            .port_count(2, 2)
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                prim(3, 2, 1, Primitive::XorGate),
+                prim(4, 2, 1, Primitive::AndGate),
+                prim(5, 1, 1, Primitive::Output { bits: 1 }),
+                prim(6, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 2, 1),
+                Conn::new(0, 0, 3, 0),
+                Conn::new(1, 0, 3, 1),
+                Conn::new(2, 0, 4, 0),
+                Conn::new(3, 0, 5, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(4, 0), (5, 0)])
+            .build()
+            .unwrap();
+        let comp = FlattenComponent::new(comp).unwrap();
+        let (mut inputs, mut outputs) = top_level_ports(&comp);
+        for (port, name) in inputs
+            .iter_mut()
+            .chain(&mut outputs)
+            .zip(["a", "b", "s", "c"])
+        {
+            port.name = name.to_string();
+        }
+        (comp, inputs, outputs)
+    }
+
+    #[test]
+    fn test_generate_and_formats() {
+        let (comp, inputs, outputs) = half_adder();
+        let table = TruthTable::generate(comp, &inputs, &outputs).unwrap();
+        let rows: Vec<(Vec<usize>, Vec<usize>)> = table
+            .rows
+            .iter()
+            .map(|row| (row.inputs.clone(), row.outputs.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (vec![0, 0], vec![0, 0]),
+                (vec![0, 1], vec![1, 0]),
+                (vec![1, 0], vec![1, 0]),
+                (vec![1, 1], vec![0, 1]),
+            ]
+        );
+
+        assert_eq!(table.to_string().lines().nth(4), Some("1 1 | 0 1"));
+        let csv = table.to_csv();
+        assert!(csv.starts_with("a,b,s,c\n0,0,0,0\n"));
+        assert_eq!(TruthTable::from_csv(&csv, 2).unwrap(), table);
+        assert_eq!(TruthTable::from_json(&table.to_json()).unwrap(), table);
+    }
+
+    #[test]
+    fn test_compare() {
+        let (comp, inputs, outputs) = half_adder();
+        let table = TruthTable::generate(comp, &inputs, &outputs).unwrap();
+
+        // Only the carry of two rows, one of them wrong
+        let expected = TruthTable::from_csv("a,b,c\n1,1,1\n0,1,1\n", 2).unwrap();
+        let mismatches = table.compare(&expected).unwrap();
+        assert_eq!(
+            mismatches,
+            vec![Mismatch {
+                inputs: vec![0, 1],
+                expected: vec![1],
+                actual: vec![0],
+            }]
+        );
+        assert_eq!(
+            TruthTable::describe(&expected, &mismatches[0]),
+            "a=0 b=1: expected c=1, got c=0"
+        );
+
+        let other_inputs = TruthTable::from_csv("x,b,c\n1,1,1\n", 2).unwrap();
+        assert!(matches!(
+            table.compare(&other_inputs),
+            Err(TruthTableError::ColumnMismatch(_))
+        ));
+        assert!(matches!(
+            TruthTable::from_csv("a,b,c\n1,2,1\n", 2),
+            Err(TruthTableError::InvalidCsv(2, _))
+        ));
+    }
+}
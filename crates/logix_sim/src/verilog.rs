@@ -0,0 +1,849 @@
+//! Export of components as structural Verilog.
+//!
+//! Every composite component becomes a module. Gates are written as Verilog
+//! gate primitives, splitters, joiners and constants as `assign`s and custom
+//! components as behavioral modules translated from their AsmHDL code when
+//! possible (programs that only jump forward).
+
+use std::collections::{HashMap, HashSet};
+
+use asmhdl::{AsmCommand, AsmComponent, AsmExpr, AsmProgramUpdateType, Data};
+use logix_core::component::{Component, PortAddr, PortDesc, SubComponent};
+
+use crate::{
+    errors::VerilogError,
+    primitives::{
+        clock::ClockConfig,
+        primitive::{ExtraInfo, Primitive},
+    },
+};
+
+/// Reserved words of Verilog.
+const KEYWORDS: &str =
+    "always and assign begin buf bufif0 bufif1 case casex casez default defparam disable \
+    edge else end endcase endfunction endgenerate endmodule endtask event for force \
+    forever fork function generate genvar if initial inout input integer join localparam \
+    module nand negedge nor not notif0 notif1 or output parameter posedge pulldown \
+    pullup real reg release repeat signed specify supply0 supply1 task time tri wait \
+    while";
+
+/// Writes a composite component and every component it uses as Verilog
+/// modules, the given component last.
+///
+/// Ports are named after their descriptors and instances after their
+/// instance names, or after their type if they have none.
+pub fn to_verilog(comp: &Component<ExtraInfo>) -> Result<String, VerilogError> {
+    let mut writer = VerilogWriter::new();
+    writer.write_composite(comp)?;
+    Ok(format!(
+        "`timescale 1ns / 1ps\n\n{}",
+        writer.modules.join("\n")
+    ))
+}
+
+/// Turns a name into a valid Verilog identifier.
+fn ident(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    if KEYWORDS.split_whitespace().any(|keyword| keyword == id) {
+        id.push('_');
+    }
+    id
+}
+
+fn unique(name: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.to_string();
+    let mut i = 1;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{name}_{i}");
+        i += 1;
+    }
+    candidate
+}
+
+fn range(bits: usize) -> String {
+    match bits {
+        0 | 1 => String::new(),
+        _ => format!("[{}:0] ", bits - 1),
+    }
+}
+
+fn literal(data: Data) -> String {
+    let bits = data.size.max(1);
+    format!("{}'b{:0bits$b}", bits, data.value)
+}
+
+/// Statements driving the register `net` with the waveform of a clock.
+fn clock_lines(net: &str, clock: &ClockConfig) -> Vec<String> {
+    let (high, low) = (clock.high_time(), clock.low_time());
+    let first = clock.next_edge(0);
+    if high == low && first == low && !clock.value_at(0) {
+        return vec![
+            format!("    initial {net} = 1'b0;"),
+            format!("    always #{high} {net} = ~{net};"),
+        ];
+    }
+
+    // Values after the first edge, which may be a falling one
+    let (start, first_wait, second_wait) = if clock.value_at(first) {
+        (1, high, low)
+    } else {
+        (0, low, high)
+    };
+    vec![
+        "    initial begin".into(),
+        format!("        {net} = 1'b{};", 1 - start),
+        format!("        #{first};"),
+        "        forever begin".into(),
+        format!("            {net} = 1'b{start};"),
+        format!("            #{first_wait};"),
+        format!("            {net} = 1'b{};", 1 - start),
+        format!("            #{second_wait};"),
+        "        end".into(),
+        "    end".into(),
+    ]
+}
+
+fn primitive(comp: &Component<ExtraInfo>) -> Option<&Primitive> {
+    comp.extra.primitive.as_ref()
+}
+
+fn in_width(comp: &Component<ExtraInfo>, port: usize) -> usize {
+    match (primitive(comp), &comp.sub) {
+        (Some(Primitive::Splitter { bits } | Primitive::Output { bits }), _) => *bits,
+        (Some(Primitive::Custom { comp, .. }), _) => comp.inputs[port],
+        (Some(_), _) => 1,
+        (None, Some(sub)) => {
+            input_prim(sub, port).map_or(1, |idx| match primitive(&sub.components[idx]) {
+                Some(Primitive::Input { bits }) => *bits,
+                _ => 1,
+            })
+        }
+        (None, None) => 1,
+    }
+}
+
+fn out_width(comp: &Component<ExtraInfo>, port: usize) -> usize {
+    match (primitive(comp), &comp.sub) {
+        (
+            Some(
+                Primitive::Input { bits } | Primitive::Output { bits } | Primitive::Joiner { bits },
+            ),
+            _,
+        ) => *bits,
+        (Some(Primitive::Const { value }), _) => value.size,
+        (Some(Primitive::Custom { comp, .. }), _) => comp.outputs[port],
+        (Some(_), _) => 1,
+        (None, Some(sub)) => sub
+            .out_addrs
+            .get(port)
+            .map_or(1, |(idx, port)| out_width(&sub.components[*idx], *port)),
+        (None, None) => 1,
+    }
+}
+
+/// Index of the `Input` primitive of the i-th input of a composite component.
+///
+/// Input addresses point either to the primitive itself or to the ports it
+/// is connected to.
+fn input_prim(sub: &SubComponent<ExtraInfo>, i: usize) -> Option<usize> {
+    let is_input = |idx: usize| primitive(&sub.components[idx]).is_some_and(Primitive::is_input);
+    sub.in_addrs
+        .iter()
+        .filter(|(in_idx, _)| *in_idx == i)
+        .find_map(|(_, addr)| {
+            if is_input(addr.0) {
+                return Some(addr.0);
+            }
+            sub.connections
+                .iter()
+                .find(|conn| conn.to == *addr && is_input(conn.from.0))
+                .map(|conn| conn.from.0)
+        })
+}
+
+/// Index of the `Output` primitive of the i-th output of a composite
+/// component.
+fn output_prim(sub: &SubComponent<ExtraInfo>, i: usize) -> Option<usize> {
+    let is_output = |idx: usize| primitive(&sub.components[idx]).is_some_and(Primitive::is_output);
+    let addr = sub.out_addrs.get(i)?;
+    if is_output(addr.0) {
+        return Some(addr.0);
+    }
+    sub.connections
+        .iter()
+        .find(|conn| conn.from == *addr && is_output(conn.to.0))
+        .map(|conn| conn.to.0)
+}
+
+fn kind(comp: &Component<ExtraInfo>) -> String {
+    match primitive(comp) {
+        Some(Primitive::AndGate) => "and".into(),
+        Some(Primitive::OrGate) => "or".into(),
+        Some(Primitive::NotGate) => "not".into(),
+        Some(Primitive::NandGate) => "nand".into(),
+        Some(Primitive::NorGate) => "nor".into(),
+        Some(Primitive::XorGate) => "xor".into(),
+        Some(Primitive::Switch) => "switch".into(),
+        Some(Primitive::Input { .. }) => "in".into(),
+        Some(Primitive::Output { .. }) => "out".into(),
+        Some(Primitive::Splitter { .. }) => "splitter".into(),
+        Some(Primitive::Joiner { .. }) => "joiner".into(),
+        Some(Primitive::Clock(_)) => "clock".into(),
+        Some(Primitive::Const { .. }) => "const".into(),
+        Some(Primitive::Custom { comp, .. }) => ident(&comp.name).to_lowercase(),
+        None => ident(comp.name.as_deref().unwrap_or("comp")).to_lowercase(),
+    }
+}
+
+/// Name and port names of a written module.
+#[derive(Clone)]
+struct Module {
+    name: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+struct VerilogWriter {
+    modules: Vec<String>,
+    module_names: HashSet<String>,
+    composites: HashMap<String, Module>,
+    asm: Vec<(String, Module)>,
+}
+
+impl VerilogWriter {
+    fn new() -> Self {
+        Self {
+            modules: vec![],
+            module_names: HashSet::new(),
+            composites: HashMap::new(),
+            asm: vec![],
+        }
+    }
+
+    fn write_composite(&mut self, comp: &Component<ExtraInfo>) -> Result<Module, VerilogError> {
+        let comp_name = comp.name.clone().unwrap_or_else(|| "Main".into());
+        if let Some(module) = self.composites.get(&comp_name) {
+            return Ok(module.clone());
+        }
+        let sub = comp
+            .sub
+            .as_ref()
+            .ok_or_else(|| VerilogError::NotComposite(comp_name.clone()))?;
+
+        // Modules used by this one are written first
+        let mut sub_modules = vec![];
+        for sub_comp in &sub.components {
+            sub_modules.push(match primitive(sub_comp) {
+                None => Some(self.write_composite(sub_comp)?),
+                Some(Primitive::Custom { comp, .. }) => Some(self.write_asm(comp)),
+                Some(_) => None,
+            });
+        }
+
+        let mut used = HashSet::new();
+        let mut inst_names = vec![String::new(); sub.components.len()];
+        let mut port_names =
+            |descs: &[PortDesc], prefix: &str, prims: &dyn Fn(usize) -> Option<usize>| {
+                (0..descs.len())
+                    .map(|i| {
+                        let prim = prims(i);
+                        let name = Some(descs[i].name.as_str())
+                            .filter(|name| !name.is_empty())
+                            .map_or_else(|| format!("{prefix}{i}"), ident);
+                        let name = unique(&name, &mut used);
+                        if let Some(idx) = prim {
+                            inst_names[idx].clone_from(&name);
+                        }
+                        name
+                    })
+                    .collect::<Vec<_>>()
+            };
+        let inputs = port_names(&comp.input_ports, "in", &|i| input_prim(sub, i));
+        let outputs = port_names(&comp.output_ports, "out", &|i| output_prim(sub, i));
+
+        for (j, sub_comp) in sub.components.iter().enumerate() {
+            if inst_names[j].is_empty() {
+                let name = sub_comp
+                    .instance
+                    .as_deref()
+                    .filter(|name| !name.is_empty())
+                    .map_or_else(|| format!("{}{}", kind(sub_comp), j), ident);
+                inst_names[j] = unique(&name, &mut used);
+            }
+        }
+
+        // A net for every output port, inputs are already named by the port
+        let mut decls = vec![];
+        let nets: Vec<Vec<String>> = sub
+            .components
+            .iter()
+            .enumerate()
+            .map(|(j, sub_comp)| match primitive(sub_comp) {
+                Some(Primitive::Input { .. }) => vec![inst_names[j].clone()],
+                Some(Primitive::Output { .. }) => vec![],
+                prim => (0..sub_comp.outputs)
+                    .map(|p| {
+                        let net = unique(&format!("{}_o{}", inst_names[j], p), &mut used);
+                        let decl = match prim {
+                            Some(Primitive::Clock(clock)) if !clock.enable => "reg",
+                            _ => "wire",
+                        };
+                        decls.push(format!(
+                            "    {decl} {}{net};",
+                            range(out_width(sub_comp, p))
+                        ));
+                        net
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let driver = |addr: PortAddr| {
+            sub.connections
+                .iter()
+                .find(|conn| conn.to == addr)
+                .and_then(|conn| nets[conn.from.0].get(conn.from.1).cloned())
+                .unwrap_or_else(|| literal(Data::new(0, in_width(&sub.components[addr.0], addr.1))))
+        };
+
+        let mut body = vec![];
+        for (j, sub_comp) in sub.components.iter().enumerate() {
+            let inst = &inst_names[j];
+            let ins: Vec<String> = (0..sub_comp.inputs).map(|q| driver((j, q))).collect();
+            let outs = &nets[j];
+            let gate = |name: &str| format!("    {name} {inst} ({}, {});", outs[0], ins.join(", "));
+            match primitive(sub_comp) {
+                Some(Primitive::AndGate) => body.push(gate("and")),
+                Some(Primitive::OrGate) => body.push(gate("or")),
+                Some(Primitive::NotGate) => body.push(gate("not")),
+                Some(Primitive::NandGate) => body.push(gate("nand")),
+                Some(Primitive::NorGate) => body.push(gate("nor")),
+                Some(Primitive::XorGate) => body.push(gate("xor")),
+                Some(Primitive::Input { .. }) => (),
+                Some(Primitive::Output { .. }) => {
+                    body.push(format!("    assign {inst} = {};", ins[0]));
+                }
+                Some(Primitive::Switch) => {
+                    body.push(format!("    assign {} = 1'b0; // Switch", outs[0]));
+                }
+                Some(Primitive::Splitter { .. }) => {
+                    for (bit, out) in outs.iter().enumerate() {
+                        body.push(format!("    assign {out} = {}[{bit}];", ins[0]));
+                    }
+                }
+                Some(Primitive::Joiner { .. }) => {
+                    let bits: Vec<&str> = ins.iter().rev().map(String::as_str).collect();
+                    body.push(format!("    assign {} = {{{}}};", outs[0], bits.join(", ")));
+                }
+                Some(Primitive::Const { value }) => {
+                    body.push(format!("    assign {} = {};", outs[0], literal(*value)));
+                }
+                Some(Primitive::Clock(clock)) if clock.enable => {
+                    let wave = format!("{}_wave", outs[0]);
+                    decls.push(format!("    reg {wave};"));
+                    body.extend(clock_lines(&wave, clock));
+                    body.push(format!("    assign {} = {wave} & {};", outs[0], ins[0]));
+                }
+                Some(Primitive::Clock(clock)) => body.extend(clock_lines(&outs[0], clock)),
+                Some(Primitive::Custom { .. }) | None => {
+                    let module = sub_modules[j].as_ref().expect("Module written above");
+                    let ports: Vec<String> = module
+                        .inputs
+                        .iter()
+                        .zip(&ins)
+                        .chain(module.outputs.iter().zip(outs))
+                        .map(|(port, net)| format!(".{port}({net})"))
+                        .collect();
+                    body.push(format!(
+                        "    {} {inst} ({});",
+                        module.name,
+                        ports.join(", ")
+                    ));
+                }
+            }
+        }
+
+        let mut ports: Vec<String> = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("    input {}{name}", range(in_width(comp, i))))
+            .collect();
+        ports.extend(
+            outputs
+                .iter()
+                .enumerate()
+                .map(|(i, name)| format!("    output {}{name}", range(out_width(comp, i)))),
+        );
+
+        let module = Module {
+            name: unique(&ident(&comp_name), &mut self.module_names),
+            inputs,
+            outputs,
+        };
+        let mut lines = vec![
+            format!("module {} (", module.name),
+            ports.join(",\n"),
+            ");".into(),
+        ];
+        if !decls.is_empty() {
+            lines.extend(decls);
+            lines.push(String::new());
+        }
+        lines.extend(body);
+        lines.push("endmodule\n".into());
+        self.modules.push(lines.join("\n"));
+        self.composites.insert(comp_name, module.clone());
+        Ok(module)
+    }
+
+    fn write_asm(&mut self, comp: &AsmComponent) -> Module {
+        let code = comp.to_code();
+        if let Some((_, module)) = self.asm.iter().find(|(c, _)| *c == code) {
+            return module.clone();
+        }
+        let module = Module {
+            name: unique(&ident(&comp.name), &mut self.module_names),
+            inputs: comp.inputs.keys().map(|name| ident(name)).collect(),
+            outputs: comp.outputs.keys().map(|name| ident(name)).collect(),
+        };
+
+        let mut lines = vec![];
+        if let Some(desc) = &comp.description {
+            lines.push(format!("// {desc}"));
+        }
+        lines.push(format!("module {} (", module.name));
+        let behavior = AsmTranslation::new(comp).and_then(|asm| asm.always_block());
+        let out_kind = if behavior.is_ok() {
+            "output reg"
+        } else {
+            "output"
+        };
+        let mut ports: Vec<String> = comp
+            .inputs
+            .iter()
+            .map(|(name, bits)| format!("    input {}{}", range(*bits), ident(name)))
+            .collect();
+        ports.extend(comp.outputs.iter().map(|(name, bits)| {
+            let init = if behavior.is_ok() {
+                format!(" = {}", literal(Data::new(0, *bits)))
+            } else {
+                String::new()
+            };
+            format!("    {out_kind} {}{}{init}", range(*bits), ident(name))
+        }));
+        lines.push(ports.join(",\n"));
+        lines.push(");".into());
+        match behavior {
+            Ok(body) => lines.extend(body),
+            Err(reason) => lines.push(format!(
+                "    // The behavior of this component could not be translated: {reason}"
+            )),
+        }
+        lines.push("endmodule\n".into());
+
+        self.modules.push(lines.join("\n"));
+        self.asm.push((code, module.clone()));
+        module
+    }
+}
+
+/// Translation of an AsmHDL program into an `always` block.
+///
+/// Forward jumps are written as `disable` statements of named blocks that
+/// end right before the target label. All of these blocks start with the
+/// program, so they are always nested.
+struct AsmTranslation<'a> {
+    comp: &'a AsmComponent,
+    widths: HashMap<String, usize>,
+    regs: Vec<(String, Data)>,
+    flags: (String, String),
+}
+
+impl<'a> AsmTranslation<'a> {
+    fn new(comp: &'a AsmComponent) -> Result<Self, String> {
+        let mut widths: HashMap<String, usize> = comp
+            .inputs
+            .iter()
+            .chain(&comp.outputs)
+            .map(|(name, bits)| (name.clone(), *bits))
+            .collect();
+        let mut defaults: Vec<_> = comp.defaults.iter().collect();
+        defaults.sort_by(|a, b| a.0.cmp(b.0));
+        let mut regs = vec![];
+        for (name, value) in defaults {
+            if !widths.contains_key(name) {
+                widths.insert(name.clone(), value.size);
+                regs.push((name.clone(), *value));
+            }
+        }
+
+        let mut translation = Self {
+            comp,
+            widths,
+            regs,
+            flags: (String::new(), String::new()),
+        };
+        for cmd in &comp.cmds {
+            match cmd {
+                AsmCommand::Mov { name, .. } if comp.inputs.contains_key(name) => {
+                    return Err(format!("it writes to the input {name}"));
+                }
+                AsmCommand::Mov { name, value } if !translation.widths.contains_key(name) => {
+                    let bits = translation.width(value)?;
+                    translation.widths.insert(name.clone(), bits);
+                    translation.regs.push((name.clone(), Data::new(0, bits)));
+                }
+                AsmCommand::Mov { value, .. } => {
+                    translation.width(value)?;
+                }
+                AsmCommand::Cmp { v1, v2 } => {
+                    translation.width(v1)?;
+                    translation.width(v2)?;
+                }
+                _ => (),
+            }
+        }
+
+        let mut used: HashSet<String> = translation.widths.keys().map(|n| ident(n)).collect();
+        translation.flags = (unique("flag_eq", &mut used), unique("flag_lt", &mut used));
+        Ok(translation)
+    }
+
+    fn width(&self, expr: &AsmExpr) -> Result<usize, String> {
+        match expr {
+            AsmExpr::Not(expr) => self.width(expr),
+            AsmExpr::And(exprs)
+            | AsmExpr::Or(exprs)
+            | AsmExpr::Nand(exprs)
+            | AsmExpr::Nor(exprs)
+            | AsmExpr::Xor(exprs) => exprs
+                .iter()
+                .map(|expr| self.width(expr))
+                .try_fold(0, |acc, bits| Ok(bits?.max(acc))),
+            AsmExpr::BitVec(exprs) => {
+                for expr in exprs {
+                    self.width(expr)?;
+                }
+                Ok(exprs.len())
+            }
+            AsmExpr::Var(name) => self
+                .widths
+                .get(name)
+                .copied()
+                .ok_or_else(|| format!("the variable {name} is used before being set")),
+            AsmExpr::Const(value) => Ok(value.size.max(1)),
+        }
+    }
+
+    fn expr(&self, expr: &AsmExpr) -> String {
+        let join = |op: &str, exprs: &[AsmExpr]| {
+            let items: Vec<String> = exprs.iter().map(|e| self.expr(e)).collect();
+            format!("({})", items.join(&format!(" {op} ")))
+        };
+        match expr {
+            AsmExpr::Not(expr) => format!("~{}", self.expr(expr)),
+            AsmExpr::And(exprs) => join("&", exprs),
+            AsmExpr::Or(exprs) => join("|", exprs),
+            AsmExpr::Nand(exprs) => format!("~{}", join("&", exprs)),
+            AsmExpr::Nor(exprs) => format!("~{}", join("|", exprs)),
+            AsmExpr::Xor(exprs) => join("^", exprs),
+            AsmExpr::BitVec(exprs) => {
+                let items: Vec<String> = exprs
+                    .iter()
+                    .map(|e| match self.width(e) {
+                        Ok(1) => self.expr(e),
+                        _ => format!("(|{})", self.expr(e)),
+                    })
+                    .collect();
+                format!("{{{}}}", items.join(", "))
+            }
+            AsmExpr::Var(name) => ident(name),
+            AsmExpr::Const(value) => literal(*value),
+        }
+    }
+
+    fn always_block(&self) -> Result<Vec<String>, String> {
+        let cmds = &self.comp.cmds;
+        let labels: HashMap<&str, usize> = cmds
+            .iter()
+            .enumerate()
+            .filter_map(|(i, cmd)| match cmd {
+                AsmCommand::Label { name } => Some((name.as_str(), i)),
+                _ => None,
+            })
+            .collect();
+
+        // Labels that are jumped to, in order
+        let mut targets = vec![];
+        for (i, cmd) in cmds.iter().enumerate() {
+            let label = match cmd {
+                AsmCommand::Goto { label }
+                | AsmCommand::Je { label }
+                | AsmCommand::Jne { label }
+                | AsmCommand::Jg { label }
+                | AsmCommand::Jl { label }
+                | AsmCommand::Jge { label }
+                | AsmCommand::Jle { label } => label,
+                AsmCommand::Wait { .. } => match self.comp.update_type {
+                    AsmProgramUpdateType::Always => continue,
+                    AsmProgramUpdateType::InputChanges => {
+                        return Err("it waits while updating on input changes".into())
+                    }
+                },
+                _ => continue,
+            };
+            let pos = *labels
+                .get(label.as_str())
+                .ok_or_else(|| format!("the label {label} is not defined"))?;
+            if pos <= i {
+                return Err(format!("it jumps backwards to {label}"));
+            }
+            targets.push((pos, label.as_str()));
+        }
+        targets.sort_unstable();
+        targets.dedup();
+
+        let (eq, lt) = &self.flags;
+        let mut lines = vec![];
+        for (name, value) in &self.regs {
+            lines.push(format!(
+                "    reg {}{} = {};",
+                range(value.size),
+                ident(name),
+                literal(*value)
+            ));
+        }
+        lines.push(format!("    reg {eq} = 1'b0;"));
+        lines.push(format!("    reg {lt} = 1'b0;"));
+        lines.push(String::new());
+
+        lines.push(match self.comp.update_type {
+            AsmProgramUpdateType::InputChanges => {
+                let ins: Vec<String> = self.comp.inputs.keys().map(|n| ident(n)).collect();
+                format!("    always @({}) begin", ins.join(" or "))
+            }
+            AsmProgramUpdateType::Always => "    always begin".into(),
+        });
+        let block = |label: &str| format!("lbl_{}", ident(label));
+        let mut depth = targets.len() + 2;
+        for (i, (_, label)) in targets.iter().enumerate().rev() {
+            lines.push(format!(
+                "{}begin : {}",
+                "    ".repeat(targets.len() + 1 - i),
+                block(label)
+            ));
+        }
+        let mut next_target = 0;
+        for (i, cmd) in cmds.iter().enumerate() {
+            while targets.get(next_target).is_some_and(|(pos, _)| *pos == i) {
+                depth -= 1;
+                lines.push(format!("{}end", "    ".repeat(depth)));
+                next_target += 1;
+            }
+            let indent = "    ".repeat(depth);
+            let jump =
+                |cond: &str, label: &str| format!("{indent}if ({cond}) disable {};", block(label));
+            lines.push(match cmd {
+                AsmCommand::Mov { name, value } => {
+                    format!("{indent}{} = {};", ident(name), self.expr(value))
+                }
+                AsmCommand::Label { name } => format!("{indent}// {name}:"),
+                AsmCommand::Goto { label } => format!("{indent}disable {};", block(label)),
+                AsmCommand::Cmp { v1, v2 } => {
+                    let (v1, v2) = (self.expr(v1), self.expr(v2));
+                    format!("{indent}{eq} = {v1} == {v2};\n{indent}{lt} = {v1} < {v2};")
+                }
+                AsmCommand::Je { label } => jump(eq, label),
+                AsmCommand::Jne { label } => jump(&format!("!{eq}"), label),
+                AsmCommand::Jg { label } => jump(&format!("!{lt}"), label),
+                AsmCommand::Jl { label } => jump(lt, label),
+                AsmCommand::Jge { label } => jump(&format!("{eq} || !{lt}"), label),
+                AsmCommand::Jle { label } => jump(&format!("{eq} || {lt}"), label),
+                AsmCommand::Wait { time } => format!("{indent}#{time};"),
+            });
+        }
+        if let AsmProgramUpdateType::Always = self.comp.update_type {
+            lines.push("        #1;".into());
+        }
+        lines.push("    end".into());
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prim;
+    use logix_core::prelude::*;
+
+    const JKFF: &str = "
+_info:
+name JKFF
+update input_changes
+
+_inputs:
+J 1
+CLK 1
+K 1
+
+_outputs:
+Q 1
+
+_defaults:
+last_clk 0
+
+_start:
+mov edge (!CLK and last_clk)
+mov last_clk CLK
+cmp edge 0b1
+jne end
+cmp [J K] 0b01
+je reset
+cmp [J K] 0b10
+je set
+cmp [J K] 0b11
+jne end
+mov Q !Q
+goto end
+reset:
+mov Q 0b0
+goto end
+set:
+mov Q 0b1
+end:
+";
+
+    #[test]
+    fn test_structural_verilog() {
+        let half_adder = ComponentBuilder::new(10)
+            .name("Half Adder".into())
+            .instance("ha".into())
+            .input_ports(vec![PortDesc::input("a", 1), PortDesc::input("b", 1)])
+            .output_ports(vec![PortDesc::output("s", 1), PortDesc::output("c", 1)])
+            .sub_comps(vec![
+                prim(1, 1, 1, Primitive::Input { bits: 1 }),
+                prim(2, 1, 1, Primitive::Input { bits: 1 }),
+                prim(3, 2, 1, Primitive::XorGate),
+                prim(4, 2, 1, Primitive::AndGate),
+                prim(5, 1, 1, Primitive::Output { bits: 1 }),
+                prim(6, 1, 1, Primitive::Output { bits: 1 }),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 2, 0),
+                Conn::new(1, 0, 2, 1),
+                Conn::new(0, 0, 3, 0),
+                Conn::new(1, 0, 3, 1),
+                Conn::new(2, 0, 4, 0),
+                Conn::new(3, 0, 5, 0),
+            ])
+            .in_addrs(vec![(0, (0, 0)), (1, (1, 0))])
+            .out_addrs(vec![(4, 0), (5, 0)])
+            .build()
+            .unwrap();
+        let jkff = AsmComponent::from_code(JKFF);
+        let main = ComponentBuilder::new(20)
+            .input_ports(vec![PortDesc::input("x", 2)])
+            .output_ports(vec![PortDesc::output("sum", 2)])
+            .sub_comps(vec![
+                prim(11, 1, 1, Primitive::Input { bits: 2 }),
+                prim(12, 1, 2, Primitive::Splitter { bits: 2 }),
+                half_adder,
+                prim(13, 2, 1, Primitive::Joiner { bits: 2 }),
+                prim(14, 1, 1, Primitive::Output { bits: 2 }),
+                prim(15, 0, 1, Primitive::Clock(ClockConfig::new(10))),
+                prim(
+                    16,
+                    3,
+                    1,
+                    Primitive::Custom {
+                        state: jkff.new_program_state(),
+                        comp: jkff,
+                    },
+                ),
+            ])
+            .connections(vec![
+                Conn::new(0, 0, 1, 0),
+                Conn::new(1, 0, 2, 0),
+                Conn::new(1, 1, 2, 1),
+                Conn::new(2, 0, 3, 0),
+                Conn::new(2, 1, 3, 1),
+                Conn::new(3, 0, 4, 0),
+                Conn::new(5, 0, 6, 1),
+            ])
+            .in_addrs(vec![(0, (0, 0))])
+            .out_addrs(vec![(3, 0)])
+            .build()
+            .unwrap();
+        let verilog = to_verilog(&main).unwrap();
+        let expected_lines = [
+            "module Half_Adder (",
+            "    input a,",
+            "    output c",
+            "    xor xor2 (xor2_o0, a, b);",
+            "    assign s = xor2_o0;",
+            "module Main (",
+            "    input [1:0] x,",
+            "    output [1:0] sum",
+            "    assign splitter1_o1 = x[1];",
+            "    Half_Adder ha (.a(splitter1_o0), .b(splitter1_o1), .s(ha_o0), .c(ha_o1));",
+            "    assign joiner3_o0 = {ha_o1, ha_o0};",
+            "    always #5 clock5_o0 = ~clock5_o0;",
+            "    JKFF jkff6 (.J(1'b0), .CLK(clock5_o0), .K(1'b0), .Q(jkff6_o0));",
+            "    output reg Q = 1'b0",
+            "    always @(J or CLK or K) begin",
+            "        begin : lbl_end_",
+            "                    if (!flag_eq) disable lbl_end_;",
+            "                    if (flag_eq) disable lbl_reset;",
+            "                Q = 1'b0;",
+        ];
+        for line in expected_lines {
+            assert!(
+                verilog.lines().any(|l| l == line),
+                "Missing {line:?}\n{verilog}"
+            );
+        }
+        // Dependencies first
+        assert!(verilog.find("module Half_Adder").unwrap() < verilog.find("module Main").unwrap());
+    }
+
+    #[test]
+    fn test_untranslatable_asm() {
+        let comp = AsmComponent::from_code(
+            "_info:\nname Loop\n\n_inputs:\na 1\n\n_outputs:\nb 1\n\n_start:\nstart:\nmov b a\ngoto start\n",
+        );
+        let mut writer = VerilogWriter::new();
+        writer.write_asm(&comp);
+        assert!(writer.modules[0].contains("    output b\n"));
+        assert!(writer.modules[0].contains("could not be translated: it jumps backwards to start"));
+    }
+
+    #[test]
+    fn test_clock_waveforms() {
+        assert_eq!(
+            clock_lines("clk", &ClockConfig::new(10)),
+            vec!["    initial clk = 1'b0;", "    always #5 clk = ~clk;"]
+        );
+
+        let clock = ClockConfig {
+            duty: 25,
+            delay: 3,
+            ..ClockConfig::new(8)
+        };
+        assert_eq!(
+            clock_lines("clk", &clock)[1..6],
+            [
+                "        clk = 1'b0;",
+                "        #9;",
+                "        forever begin",
+                "            clk = 1'b1;",
+                "            #2;",
+            ]
+        );
+    }
+}